@@ -2,14 +2,17 @@
 //!
 //! Provides a multi-tier denoising architecture:
 //! - Tier 1: RNNoise (fast, CPU-based)
-//! - Tier 2: Deep Learning (ONNX-based, GPU-accelerated)
+//! - Tier 2: Deep Learning (user-supplied ONNX model, see [`crate::onnx_denoiser`])
 //! - Tier 3: Spectral (Wiener filter, precision enhancement)
 
 #![allow(dead_code)] // Complete denoising API with multiple backends
 
+use crate::onnx_denoiser::{OnnxDenoiser, OnnxModelConfig};
 use anyhow::Result;
 use num_complex::Complex32;
 use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 
 /// Denoising modes available in the advanced system
@@ -38,12 +41,16 @@ pub enum DenoiserTier {
 }
 
 /// Performance metrics for denoising
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DenoisingMetrics {
     pub latency_ms: f32,
     pub cpu_usage_percent: f32,
     pub memory_usage_mb: f32,
     pub quality_score: f32, // 0.0 to 1.0
+    /// Per-tier share of the buffer duration, in percent
+    pub rnnoise_load_percent: f32,
+    pub deep_learning_load_percent: f32,
+    pub spectral_load_percent: f32,
 }
 
 /// Trait for advanced denoising implementations
@@ -74,6 +81,46 @@ pub trait AdvancedDenoiser: Send + Sync {
 
     /// Check if the denoiser is enabled
     fn is_enabled(&self) -> bool;
+
+    /// Set the sample rate of the audio passed to `process_frame`
+    fn set_sample_rate(&mut self, sample_rate: u32);
+
+    /// Set how many interleaved channels each `process_frame` buffer holds,
+    /// so load is measured against the buffer's real duration
+    fn set_input_channels(&mut self, channels: usize);
+
+    /// Delay added to the signal, in samples, for delay compensation
+    fn latency_samples(&self) -> usize;
+
+    /// Capture `seconds` of room tone and freeze it as the spectral noise profile
+    fn start_noise_learning(&mut self, seconds: f32) -> Result<()>;
+
+    /// Learning progress from 0.0 to 1.0, or None when not learning
+    fn noise_learning_progress(&self) -> Option<f32>;
+
+    /// The learned or loaded noise profile, if one is frozen
+    fn get_noise_profile(&self) -> Option<NoiseProfile>;
+
+    /// Load a previously captured noise profile and freeze it
+    fn set_noise_profile(&mut self, profile: &NoiseProfile) -> Result<()>;
+
+    /// Forget the frozen profile and return to the default noise floor
+    fn clear_noise_profile(&mut self);
+
+    /// Load (or with `None`, unload) the model used by the Deep Learning tier.
+    /// The previous model stays in use if loading fails.
+    fn set_deep_learning_model(&mut self, model: Option<OnnxModelConfig>) -> Result<()>;
+
+    /// Run an instance of a model that is already loaded (or with `None`, no
+    /// model) in the Deep Learning tier
+    fn use_deep_learning_model(&mut self, model: Option<OnnxDenoiser>);
+
+    /// Information about the loaded deep-learning model, if any
+    fn deep_learning_model_info(&self) -> Option<ModelInfo>;
+
+    /// Report the audio callback's overall DSP load, in percent of its budget,
+    /// so adaptive mode can back off before the whole callback overruns
+    fn set_callback_load(&mut self, load_percent: f32);
 }
 
 /// Configuration for the advanced denoising system
@@ -87,6 +134,12 @@ pub struct AdvancedDenoisingConfig {
     pub quality_preference: f32, // 0.0 = speed, 1.0 = quality
     pub gpu_acceleration: bool,
     pub adaptive_mode: bool, // Automatically adjust based on performance
+    /// STFT hop of the spectral stage; must divide its FFT size with ≥50% overlap
+    pub spectral_hop_size: usize,
+    /// Model for the Deep Learning tier; the tier is skipped when unset
+    pub deep_learning_model: Option<OnnxModelConfig>,
+    /// Interleaved channels in each processed buffer
+    pub input_channels: usize,
 }
 
 impl Default for AdvancedDenoisingConfig {
@@ -100,6 +153,9 @@ impl Default for AdvancedDenoisingConfig {
             quality_preference: 0.7,
             gpu_acceleration: true,
             adaptive_mode: true,
+            spectral_hop_size: 256, // 50% overlap of a 512-point FFT
+            deep_learning_model: None,
+            input_channels: 1,
         }
     }
 }
@@ -107,6 +163,9 @@ impl Default for AdvancedDenoisingConfig {
 /// Main advanced denoising system
 pub struct AdvancedDenoisingSystem {
     config: AdvancedDenoisingConfig,
+    /// The mode last chosen by the user; adaptive mode never upgrades past it
+    requested_mode: DenoisingMode,
+    callback_load_percent: f32,
     rnnoise_denoiser: Option<crate::rnnoise::Rnnoise>,
     deep_learning_denoiser: Option<Box<dyn DeepLearningDenoiser>>,
     spectral_denoiser: Option<Box<dyn SpectralDenoiser>>,
@@ -126,8 +185,16 @@ pub trait DeepLearningDenoiser: Send + Sync {
 /// Trait for spectral-based denoisers
 pub trait SpectralDenoiser: Send + Sync {
     fn process(&mut self, input: &[f32]) -> Result<Vec<f32>>;
-    fn set_noise_profile(&mut self, profile: &[f32]);
+    fn set_noise_profile(&mut self, profile: &NoiseProfile);
+    fn get_noise_profile(&self) -> Option<NoiseProfile>;
+    fn clear_noise_profile(&mut self);
     fn get_noise_reduction_db(&self) -> f32;
+    /// Delay added by the filter, in samples
+    fn latency_samples(&self) -> usize;
+    fn start_noise_learning(&mut self, seconds: f32);
+    /// Feed room tone to an active learning pass without filtering it
+    fn observe_noise(&mut self, input: &[f32]) -> Result<()>;
+    fn learning_progress(&self) -> Option<f32>;
 }
 
 /// Information about a loaded model
@@ -140,108 +207,289 @@ pub struct ModelInfo {
     pub supported_sample_rates: Vec<u32>,
 }
 
+/// A captured noise spectrum that can be saved and re-applied later.
+///
+/// Magnitudes are normalized by the analysis window's RMS gain, so a profile
+/// captured at one FFT size can be applied to a denoiser using another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoiseProfile {
+    pub sample_rate: u32,
+    pub fft_size: usize,
+    /// Noise magnitude per FFT bin, `fft_size / 2 + 1` entries
+    pub magnitudes: Vec<f32>,
+}
+
+impl NoiseProfile {
+    /// Frequency in Hz of bin `index`
+    pub fn bin_frequency(&self, index: usize) -> f32 {
+        index as f32 * self.sample_rate as f32 / self.fft_size.max(1) as f32
+    }
+
+    /// Noise magnitude at `frequency`, linearly interpolated between bins
+    pub fn magnitude_at(&self, frequency: f32) -> f32 {
+        let Some(&last) = self.magnitudes.last() else {
+            return 0.0;
+        };
+        let pos = frequency * self.fft_size as f32 / self.sample_rate.max(1) as f32;
+        if pos <= 0.0 {
+            return self.magnitudes[0];
+        }
+        let idx = pos.floor() as usize;
+        if idx + 1 >= self.magnitudes.len() {
+            return last;
+        }
+        let frac = pos - idx as f32;
+        self.magnitudes[idx] + (self.magnitudes[idx + 1] - self.magnitudes[idx]) * frac
+    }
+
+    /// Per-bin level in dBFS for display
+    pub fn magnitudes_db(&self) -> Vec<f32> {
+        self.magnitudes
+            .iter()
+            .map(|&m| 20.0 * m.max(1e-9).log10())
+            .collect()
+    }
+}
+
+/// Accumulates windowed power spectra of room tone while learning
+struct NoiseLearner {
+    frames_needed: usize,
+    frames_seen: usize,
+    power_sum: Vec<f64>,
+    pending: Vec<f32>,
+}
+
 // ============================================================================
 // Wiener Filter Spectral Denoiser Implementation
 // ============================================================================
 
+/// Callback load above which adaptive mode sheds denoising tiers
+const CALLBACK_BUDGET_PERCENT: f32 = 80.0;
+
+/// Floor for the a-priori SNR estimate (-25 dB), limits musical noise
+const MIN_PRIORI_SNR: f32 = 0.003_162;
+
+/// Mean a-posteriori SNR below which a frame is treated as noise only (3 dB)
+const NOISE_ONLY_SNR: f32 = 2.0;
+
+/// Audio assumed to be noise only while the adaptive estimate initializes
+const NOISE_INIT_SECONDS: f32 = 0.25;
+
 /// Wiener filter-based spectral denoiser
 /// Provides fine-grained noise reduction in frequency domain
+///
+/// Runs a streaming STFT: input is buffered into hops, each analysis frame is
+/// windowed, filtered and overlap-added back with a normalized synthesis
+/// window, so the output is delayed by [`WienerDenoiser::latency_samples`].
 pub struct WienerDenoiser {
-    frame_size: usize,
     sample_rate: u32,
+    fft_size: usize,
+    hop_size: usize,
     // FFT processing
     fft_planner: RealFftPlanner<f32>,
     fft_forward: Arc<dyn RealToComplex<f32>>,
     fft_inverse: Arc<dyn ComplexToReal<f32>>,
-    // Processing buffers
+    // Streaming buffers
+    input_fifo: VecDeque<f32>,
+    output_fifo: VecDeque<f32>,
+    /// The most recent `fft_size` input samples
+    analysis_frame: Vec<f32>,
     input_buffer: Vec<f32>,
     output_buffer: Vec<f32>,
     spectrum: Vec<Complex32>,
     // Noise profile
     noise_profile: Vec<f32>,
     noise_floor: f32,
+    /// Frames averaged into the startup noise estimate so far
+    noise_init_frames: usize,
+    /// Set once a profile has been learned or loaded; stops adaptive updates
+    profile_frozen: bool,
+    learner: Option<NoiseLearner>,
+    learn_frame: Vec<f32>,
+    learn_spectrum: Vec<Complex32>,
     // Wiener filter parameters
     smoothing_factor: f32,
+    /// Decision-directed weight of the previous frame's clean speech estimate
+    priori_smoothing: f32,
+    /// Lowest gain applied to any bin, keeps residual noise smooth
+    min_gain: f32,
+    gains: Vec<f32>,
+    prev_clean_power: Vec<f32>,
     noise_reduction_db: f32,
-    // Overlap-add for smooth transitions
+    // Overlap-add with a synthesis window normalized for the hop
     overlap_buffer: Vec<f32>,
     window: Vec<f32>,
+    synthesis_window: Vec<f32>,
 }
 
 impl WienerDenoiser {
     pub fn new(sample_rate: u32, frame_size: usize) -> Result<Self> {
         let fft_size = frame_size.next_power_of_two();
+        Self::with_hop(sample_rate, fft_size, fft_size / 2)
+    }
+
+    /// Create a denoiser with an explicit FFT size and hop.
+    ///
+    /// `fft_size` must be even and `hop_size` must divide it with at least
+    /// 50% overlap.
+    pub fn with_hop(sample_rate: u32, fft_size: usize, hop_size: usize) -> Result<Self> {
+        if fft_size < 4 || !fft_size.is_multiple_of(2) {
+            anyhow::bail!("FFT size must be even and at least 4, got {}", fft_size);
+        }
+        if hop_size == 0 || hop_size > fft_size / 2 || !fft_size.is_multiple_of(hop_size) {
+            anyhow::bail!(
+                "Hop size {} must divide FFT size {} with at least 50% overlap",
+                hop_size,
+                fft_size
+            );
+        }
 
         let mut fft_planner = RealFftPlanner::new();
         let fft_forward = fft_planner.plan_fft_forward(fft_size);
         let fft_inverse = fft_planner.plan_fft_inverse(fft_size);
 
-        // Create Hann window for overlap-add
+        // Periodic square-root Hann for both analysis and synthesis
         let window: Vec<f32> = (0..fft_size)
             .map(|i| {
-                let x = std::f32::consts::PI * i as f32 / (fft_size - 1) as f32;
-                0.5 * (1.0 - x.cos())
+                let x = 2.0 * std::f32::consts::PI * i as f32 / fft_size as f32;
+                (0.5 * (1.0 - x.cos())).sqrt()
             })
             .collect();
 
+        // Divide out the summed analysis × synthesis overlap so that an
+        // all-pass filter reconstructs the input exactly at any valid hop
+        let mut overlap_sum = vec![0.0f32; hop_size];
+        for (i, w) in window.iter().enumerate() {
+            overlap_sum[i % hop_size] += w * w;
+        }
+        let synthesis_window = window
+            .iter()
+            .enumerate()
+            .map(|(i, w)| w / overlap_sum[i % hop_size].max(f32::EPSILON))
+            .collect();
+
+        let bins = fft_size / 2 + 1;
+        let mut output_fifo = VecDeque::with_capacity(fft_size * 2);
+        // One hop of silence so a full hop is always ready for the caller
+        output_fifo.extend(std::iter::repeat_n(0.0, hop_size));
+
         Ok(Self {
-            frame_size,
             sample_rate,
+            fft_size,
+            hop_size,
             fft_planner,
             fft_forward,
             fft_inverse,
+            input_fifo: VecDeque::with_capacity(fft_size * 2),
+            output_fifo,
+            analysis_frame: vec![0.0; fft_size],
             input_buffer: vec![0.0; fft_size],
             output_buffer: vec![0.0; fft_size],
-            spectrum: vec![Complex32::new(0.0, 0.0); fft_size / 2 + 1],
-            noise_profile: vec![0.001; fft_size / 2 + 1], // Default noise floor
-            noise_floor: -60.0,                           // dB
+            spectrum: vec![Complex32::new(0.0, 0.0); bins],
+            noise_profile: vec![0.001; bins], // Default noise floor
+            noise_floor: -60.0,               // dB
+            noise_init_frames: 0,
+            profile_frozen: false,
+            learner: None,
+            learn_frame: vec![0.0; fft_size],
+            learn_spectrum: vec![Complex32::new(0.0, 0.0); bins],
             smoothing_factor: 0.98,
+            priori_smoothing: 0.98,
+            min_gain: 0.056, // -25 dB
+            gains: vec![1.0; bins],
+            prev_clean_power: vec![0.0; bins],
             noise_reduction_db: 0.0,
             overlap_buffer: vec![0.0; fft_size],
             window,
+            synthesis_window,
         })
     }
 
+    /// Analysis hop in samples
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
     /// Update noise profile from current spectrum
-    fn update_noise_profile(&mut self, spectrum: &[Complex32]) {
-        for (i, &s) in spectrum.iter().enumerate() {
-            let magnitude = s.norm();
-            // Exponential smoothing
-            self.noise_profile[i] = self.smoothing_factor * self.noise_profile[i]
-                + (1.0 - self.smoothing_factor) * magnitude;
+    ///
+    /// The first [`NOISE_INIT_SECONDS`] are averaged as-is; afterwards only
+    /// frames that look like noise alone are blended in.
+    fn update_noise_profile(&mut self) {
+        if self.profile_frozen {
+            return;
+        }
+
+        let init_frames =
+            (NOISE_INIT_SECONDS * self.sample_rate as f32 / self.hop_size as f32) as usize;
+        let weight = if self.noise_init_frames < init_frames.max(1) {
+            self.noise_init_frames += 1;
+            1.0 - 1.0 / self.noise_init_frames as f32
+        } else {
+            let mean_snr = self
+                .spectrum
+                .iter()
+                .zip(&self.noise_profile)
+                .map(|(s, n)| s.norm_sqr() / (n * n).max(1e-12))
+                .sum::<f32>()
+                / self.spectrum.len() as f32;
+            if mean_snr > NOISE_ONLY_SNR {
+                return;
+            }
+            self.smoothing_factor
+        };
+
+        for (noise, s) in self.noise_profile.iter_mut().zip(&self.spectrum) {
+            let power = weight * *noise * *noise + (1.0 - weight) * s.norm_sqr();
+            *noise = power.sqrt().max(1e-9);
         }
     }
 
-    /// Apply Wiener filter to spectrum
-    fn apply_wiener_filter(&self, spectrum: &mut [Complex32]) {
-        for (i, s) in spectrum.iter_mut().enumerate() {
-            let signal_power = s.norm_sqr();
-            let noise_power = self.noise_profile[i].powi(2);
+    /// Compute per-bin Wiener gains from a decision-directed a-priori SNR
+    fn update_gains(&mut self) {
+        for (i, s) in self.spectrum.iter().enumerate() {
+            let noise_power = self.noise_profile[i].powi(2).max(1e-12);
+            let posteriori = s.norm_sqr() / noise_power;
+            let priori = self.priori_smoothing * self.prev_clean_power[i] / noise_power
+                + (1.0 - self.priori_smoothing) * (posteriori - 1.0).max(0.0);
+            let priori = priori.max(MIN_PRIORI_SNR);
+            self.gains[i] = priori / (1.0 + priori);
+        }
 
-            // Wiener filter gain: H(w) = max(1 - noise/signal, floor)
-            let gain = if signal_power > noise_power * 1.1 {
-                ((signal_power - noise_power) / signal_power).sqrt()
-            } else {
-                0.01 // Minimum gain to avoid complete nulling
-            };
+        // Smooth isolated gain spikes across neighbouring bins; these are what
+        // turn residual noise into "musical" tones
+        let mut prev = self.gains[0];
+        let last = self.gains.len() - 1;
+        for i in 0..=last {
+            let current = self.gains[i];
+            let next = self.gains[(i + 1).min(last)];
+            self.gains[i] = (0.25 * prev + 0.5 * current + 0.25 * next).max(self.min_gain);
+            prev = current;
+        }
 
-            *s *= gain;
+        for (i, s) in self.spectrum.iter().enumerate() {
+            self.prev_clean_power[i] = self.gains[i].powi(2) * s.norm_sqr();
         }
     }
-}
 
-impl SpectralDenoiser for WienerDenoiser {
-    fn process(&mut self, input: &[f32]) -> Result<Vec<f32>> {
-        let fft_size = self.input_buffer.len();
-        let input_len = input.len().min(fft_size);
+    /// Analyze, filter and overlap-add one hop taken from the input FIFO
+    fn process_hop(&mut self) -> Result<()> {
+        let fft_size = self.fft_size;
+        let hop_size = self.hop_size;
 
-        // Zero-pad input and apply window
-        self.input_buffer[..input_len].copy_from_slice(&input[..input_len]);
-        for i in input_len..fft_size {
-            self.input_buffer[i] = 0.0;
+        self.analysis_frame.copy_within(hop_size.., 0);
+        for (dst, src) in self.analysis_frame[fft_size - hop_size..]
+            .iter_mut()
+            .zip(self.input_fifo.drain(..hop_size))
+        {
+            *dst = src;
         }
-        for i in 0..fft_size {
-            self.input_buffer[i] *= self.window[i];
+
+        for (dst, (&x, &w)) in self
+            .input_buffer
+            .iter_mut()
+            .zip(self.analysis_frame.iter().zip(&self.window))
+        {
+            *dst = x * w;
         }
 
         // Forward FFT
@@ -249,73 +497,173 @@ impl SpectralDenoiser for WienerDenoiser {
             .process(&mut self.input_buffer, &mut self.spectrum)
             .map_err(|e| anyhow::anyhow!("FFT forward error: {:?}", e))?;
 
-        // Apply Wiener filter inline (avoiding borrow issue)
-        for (i, s) in self.spectrum.iter_mut().enumerate() {
-            let signal_power = s.norm_sqr();
-            let noise_power = self.noise_profile[i].powi(2);
-
-            // Wiener filter gain: H(w) = max(1 - noise/signal, floor)
-            let gain = if signal_power > noise_power * 1.1 {
-                ((signal_power - noise_power) / signal_power).sqrt()
-            } else {
-                0.01 // Minimum gain to avoid complete nulling
-            };
-
+        self.update_noise_profile();
+        self.update_gains();
+        for (s, &gain) in self.spectrum.iter_mut().zip(&self.gains) {
             *s *= gain;
         }
 
-        // Calculate noise reduction achieved
-        let input_energy: f32 = input.iter().map(|x| x * x).sum();
-        let output_energy: f32 = self.spectrum.iter().map(|s| s.norm_sqr()).sum();
-        if input_energy > 0.0 && output_energy > 0.0 {
-            self.noise_reduction_db = 10.0 * (input_energy / output_energy).log10();
-        }
-
         // Inverse FFT
         self.fft_inverse
             .process(&mut self.spectrum, &mut self.output_buffer)
             .map_err(|e| anyhow::anyhow!("FFT inverse error: {:?}", e))?;
 
-        // Normalize and apply window
+        // Normalize, apply the synthesis window and overlap-add
         let norm = 1.0 / fft_size as f32;
         for i in 0..fft_size {
-            self.output_buffer[i] *= norm * self.window[i];
+            self.overlap_buffer[i] += self.output_buffer[i] * norm * self.synthesis_window[i];
         }
 
-        // Overlap-add
-        let mut output = vec![0.0; input_len];
-        for (i, out) in output.iter_mut().enumerate().take(input_len) {
-            *out = self.output_buffer[i] + self.overlap_buffer[i];
+        // The first hop is now complete
+        self.output_fifo
+            .extend(self.overlap_buffer.drain(..hop_size));
+        self.overlap_buffer.resize(fft_size, 0.0);
+
+        Ok(())
+    }
+
+    /// RMS gain of the analysis window, used to make profiles FFT-size independent
+    fn window_rms_gain(&self) -> f32 {
+        self.window.iter().map(|w| w * w).sum::<f32>().sqrt()
+    }
+
+    /// Average the accumulated power spectra into the profile and freeze it
+    fn finish_learning(&mut self, learner: NoiseLearner) {
+        let frames = learner.frames_seen.max(1) as f64;
+        for (bin, &power) in self.noise_profile.iter_mut().zip(&learner.power_sum) {
+            *bin = ((power / frames).sqrt() as f32).max(1e-9);
         }
+        self.profile_frozen = true;
+        log::info!(
+            "Noise profile learned from {} frames ({} bins)",
+            learner.frames_seen,
+            self.noise_profile.len()
+        );
+    }
+}
 
-        // Store overlap for next frame
-        let hop_size = fft_size / 2;
-        for i in 0..hop_size {
-            self.overlap_buffer[i] = if i + hop_size < fft_size {
-                self.output_buffer[i + hop_size]
-            } else {
-                0.0
-            };
+impl SpectralDenoiser for WienerDenoiser {
+    fn process(&mut self, input: &[f32]) -> Result<Vec<f32>> {
+        if self.learner.is_some() {
+            self.observe_noise(input)?;
+        }
+
+        self.input_fifo.extend(input.iter().copied());
+        while self.input_fifo.len() >= self.hop_size {
+            self.process_hop()?;
+        }
+
+        let available = self.output_fifo.len().min(input.len());
+        let mut output: Vec<f32> = self.output_fifo.drain(..available).collect();
+        output.resize(input.len(), 0.0);
+
+        // Calculate noise reduction achieved
+        let input_energy: f32 = input.iter().map(|x| x * x).sum();
+        let output_energy: f32 = output.iter().map(|x| x * x).sum();
+        if input_energy > 0.0 && output_energy > 0.0 {
+            self.noise_reduction_db = 10.0 * (input_energy / output_energy).log10();
         }
 
         Ok(output)
     }
 
-    fn set_noise_profile(&mut self, profile: &[f32]) {
-        let len = profile.len().min(self.noise_profile.len());
-        self.noise_profile[..len].copy_from_slice(&profile[..len]);
+    fn set_noise_profile(&mut self, profile: &NoiseProfile) {
+        let gain = self.window_rms_gain();
+        for (i, bin) in self.noise_profile.iter_mut().enumerate() {
+            let frequency = i as f32 * self.sample_rate as f32 / self.fft_size as f32;
+            *bin = (profile.magnitude_at(frequency) * gain).max(1e-9);
+        }
+        self.learner = None;
+        self.profile_frozen = true;
+    }
+
+    fn get_noise_profile(&self) -> Option<NoiseProfile> {
+        if !self.profile_frozen {
+            return None;
+        }
+        let gain = self.window_rms_gain();
+        Some(NoiseProfile {
+            sample_rate: self.sample_rate,
+            fft_size: self.fft_size,
+            magnitudes: self.noise_profile.iter().map(|m| m / gain).collect(),
+        })
+    }
+
+    fn clear_noise_profile(&mut self) {
+        self.noise_profile.fill(0.001);
+        self.noise_init_frames = 0;
+        self.learner = None;
+        self.profile_frozen = false;
     }
 
     fn get_noise_reduction_db(&self) -> f32 {
         self.noise_reduction_db
     }
+
+    /// One frame of analysis history less a hop, plus the hop of silence
+    /// queued ahead of the first output
+    fn latency_samples(&self) -> usize {
+        self.fft_size
+    }
+
+    fn start_noise_learning(&mut self, seconds: f32) {
+        let samples = (seconds.max(0.0) * self.sample_rate as f32) as usize;
+        self.learner = Some(NoiseLearner {
+            frames_needed: (samples / self.hop_size).max(1),
+            frames_seen: 0,
+            power_sum: vec![0.0; self.noise_profile.len()],
+            pending: Vec::with_capacity(self.fft_size * 2),
+        });
+        log::info!("Learning noise profile for {:.1}s", seconds);
+    }
+
+    fn observe_noise(&mut self, input: &[f32]) -> Result<()> {
+        let Some(mut learner) = self.learner.take() else {
+            return Ok(());
+        };
+
+        let fft_size = self.fft_size;
+        learner.pending.extend_from_slice(input);
+
+        // Same window and hop as the analysis in `process`
+        while learner.pending.len() >= fft_size && learner.frames_seen < learner.frames_needed {
+            for (dst, (&x, &w)) in self
+                .learn_frame
+                .iter_mut()
+                .zip(learner.pending.iter().zip(&self.window))
+            {
+                *dst = x * w;
+            }
+            self.fft_forward
+                .process(&mut self.learn_frame, &mut self.learn_spectrum)
+                .map_err(|e| anyhow::anyhow!("FFT forward error: {:?}", e))?;
+            for (sum, s) in learner.power_sum.iter_mut().zip(&self.learn_spectrum) {
+                *sum += s.norm_sqr() as f64;
+            }
+            learner.frames_seen += 1;
+            learner.pending.drain(..self.hop_size);
+        }
+
+        if learner.frames_seen >= learner.frames_needed {
+            self.finish_learning(learner);
+        } else {
+            self.learner = Some(learner);
+        }
+        Ok(())
+    }
+
+    fn learning_progress(&self) -> Option<f32> {
+        self.learner
+            .as_ref()
+            .map(|l| l.frames_seen as f32 / l.frames_needed as f32)
+    }
 }
 
 /// Performance monitoring for adaptive mode
 struct PerformanceMonitor {
     cpu_history: Vec<f32>,
     latency_history: Vec<f32>,
-    last_update: std::time::Instant,
+    last_adaptation: std::time::Instant,
 }
 
 impl PerformanceMonitor {
@@ -323,7 +671,7 @@ impl PerformanceMonitor {
         Self {
             cpu_history: Vec::with_capacity(100),
             latency_history: Vec::with_capacity(100),
-            last_update: std::time::Instant::now(),
+            last_adaptation: std::time::Instant::now(),
         }
     }
 
@@ -338,8 +686,6 @@ impl PerformanceMonitor {
         if self.latency_history.len() > 100 {
             self.latency_history.remove(0);
         }
-
-        self.last_update = std::time::Instant::now();
     }
 
     fn average_cpu(&self) -> f32 {
@@ -359,24 +705,32 @@ impl PerformanceMonitor {
     }
 
     fn should_adapt(&self) -> bool {
-        self.last_update.elapsed().as_secs() >= 5 // Adapt every 5 seconds
+        // Adapt every 5 seconds
+        !self.cpu_history.is_empty() && self.last_adaptation.elapsed().as_secs() >= 5
+    }
+
+    /// Start the next adaptation interval; after a mode change the old
+    /// measurements describe the wrong tiers, so they are dropped
+    fn mark_adapted(&mut self, mode_changed: bool) {
+        self.last_adaptation = std::time::Instant::now();
+        if mode_changed {
+            self.cpu_history.clear();
+            self.latency_history.clear();
+        }
     }
 }
 
 impl AdvancedDenoisingSystem {
     pub fn new(config: AdvancedDenoisingConfig) -> Result<Self> {
         let mut system = Self {
+            requested_mode: config.mode.clone(),
+            callback_load_percent: 0.0,
             config,
             rnnoise_denoiser: None,
             deep_learning_denoiser: None,
             spectral_denoiser: None,
             enabled: false,
-            metrics: DenoisingMetrics {
-                latency_ms: 0.0,
-                cpu_usage_percent: 0.0,
-                memory_usage_mb: 0.0,
-                quality_score: 0.0,
-            },
+            metrics: DenoisingMetrics::default(),
             performance_monitor: PerformanceMonitor::new(),
         };
 
@@ -386,12 +740,22 @@ impl AdvancedDenoisingSystem {
 
     fn initialize_denoisers(&mut self) -> Result<()> {
         // Initialize RNNoise denoiser
-        let mut rnnoise = crate::rnnoise::Rnnoise::new();
+        let mut rnnoise = crate::rnnoise::Rnnoise::with_sample_rate(self.config.sample_rate);
         rnnoise.enable();
         self.rnnoise_denoiser = Some(rnnoise);
 
+        // Initialize the deep-learning tier if a model is configured
+        if let Err(e) = self.initialize_deep_learning() {
+            log::warn!("Deep learning denoiser unavailable: {}", e);
+        }
+
         // Initialize spectral Wiener denoiser
-        match WienerDenoiser::new(self.config.sample_rate, self.config.frame_size) {
+        let fft_size = self.config.frame_size.next_power_of_two();
+        match WienerDenoiser::with_hop(
+            self.config.sample_rate,
+            fft_size,
+            self.config.spectral_hop_size,
+        ) {
             Ok(wiener) => {
                 log::info!("Spectral Wiener denoiser initialized");
                 self.spectral_denoiser = Some(Box::new(wiener));
@@ -404,6 +768,30 @@ impl AdvancedDenoisingSystem {
         Ok(())
     }
 
+    /// (Re)load the configured deep-learning model for the current sample rate
+    fn initialize_deep_learning(&mut self) -> Result<()> {
+        self.deep_learning_denoiser = None;
+        let Some(ref model) = self.config.deep_learning_model else {
+            return Ok(());
+        };
+
+        if model.sample_rate != self.config.sample_rate {
+            anyhow::bail!(
+                "model runs at {} Hz but the stream runs at {} Hz",
+                model.sample_rate,
+                self.config.sample_rate
+            );
+        }
+
+        let denoiser = OnnxDenoiser::load(model.clone())?;
+        log::info!(
+            "Deep learning denoiser initialized: {}",
+            denoiser.get_model_info().name
+        );
+        self.deep_learning_denoiser = Some(Box::new(denoiser));
+        Ok(())
+    }
+
     fn adaptive_mode_adjustment(&mut self) {
         if !self.config.adaptive_mode || !self.performance_monitor.should_adapt() {
             return;
@@ -411,24 +799,39 @@ impl AdvancedDenoisingSystem {
 
         let avg_cpu = self.performance_monitor.average_cpu();
         let avg_latency = self.performance_monitor.average_latency();
+        let callback_load = self.callback_load_percent;
+        let previous_mode = self.config.mode.clone();
 
         // If we're exceeding limits, downgrade mode
-        if avg_cpu > self.config.max_cpu_percent || avg_latency > self.config.max_latency_ms {
+        if avg_cpu > self.config.max_cpu_percent
+            || avg_latency > self.config.max_latency_ms
+            || callback_load > CALLBACK_BUDGET_PERCENT
+        {
             match self.config.mode {
                 DenoisingMode::Maximum => {
                     self.config.mode = DenoisingMode::Enhanced;
-                    log::info!("Adaptive mode: Downgraded to Enhanced due to performance");
+                    log::info!(
+                        "Adaptive mode: Downgraded to Enhanced (denoiser {:.0}%, callback {:.0}%)",
+                        avg_cpu,
+                        callback_load
+                    );
                 }
                 DenoisingMode::Enhanced => {
                     self.config.mode = DenoisingMode::Basic;
-                    log::info!("Adaptive mode: Downgraded to Basic due to performance");
+                    log::info!(
+                        "Adaptive mode: Downgraded to Basic (denoiser {:.0}%, callback {:.0}%)",
+                        avg_cpu,
+                        callback_load
+                    );
                 }
                 _ => {}
             }
         }
-        // If we have headroom, try upgrading
+        // If we have headroom, step back up towards the mode the user asked for
         else if avg_cpu < self.config.max_cpu_percent * 0.7
             && avg_latency < self.config.max_latency_ms * 0.7
+            && callback_load < CALLBACK_BUDGET_PERCENT * 0.7
+            && self.config.mode != self.requested_mode
         {
             match self.config.mode {
                 DenoisingMode::Basic => {
@@ -442,6 +845,9 @@ impl AdvancedDenoisingSystem {
                 _ => {}
             }
         }
+
+        self.performance_monitor
+            .mark_adapted(self.config.mode != previous_mode);
     }
 }
 
@@ -454,67 +860,63 @@ impl AdvancedDenoiser for AdvancedDenoisingSystem {
         let start_time = std::time::Instant::now();
         let mut output = input.to_vec();
 
-        // Process based on current mode
-        match &self.config.mode {
-            DenoisingMode::Basic => {
-                if let Some(ref rnnoise) = self.rnnoise_denoiser {
-                    output = rnnoise.process(&output);
-                }
-            }
-            DenoisingMode::Enhanced => {
-                // First pass: RNNoise
-                if let Some(ref rnnoise) = self.rnnoise_denoiser {
-                    output = rnnoise.process(&output);
-                }
-
-                // Second pass: Deep learning (when available)
-                if let Some(ref mut deep_learning) = self.deep_learning_denoiser {
-                    output = deep_learning.process(&output)?;
-                }
-            }
-            DenoisingMode::Maximum => {
-                // First pass: RNNoise
-                if let Some(ref rnnoise) = self.rnnoise_denoiser {
-                    output = rnnoise.process(&output);
-                }
+        // Run the tiers the current mode enables, timing each one
+        let mut tier_time = [std::time::Duration::ZERO; 3];
 
-                // Second pass: Deep learning (when available)
-                if let Some(ref mut deep_learning) = self.deep_learning_denoiser {
-                    output = deep_learning.process(&output)?;
-                }
+        // First pass: RNNoise
+        if self.runs_rnnoise()
+            && let Some(ref rnnoise) = self.rnnoise_denoiser
+        {
+            let tier_start = std::time::Instant::now();
+            output = rnnoise.process(&output);
+            tier_time[0] = tier_start.elapsed();
+        }
 
-                // Third pass: Spectral enhancement (when available)
-                if let Some(ref mut spectral) = self.spectral_denoiser {
-                    output = spectral.process(&output)?;
-                }
-            }
-            DenoisingMode::Custom {
-                use_rnnoise,
-                use_deep_learning,
-                use_spectral,
-            } => {
-                if *use_rnnoise && let Some(ref rnnoise) = self.rnnoise_denoiser {
-                    output = rnnoise.process(&output);
-                }
+        // Second pass: Deep learning (when available)
+        if self.runs_deep_learning()
+            && let Some(ref mut deep_learning) = self.deep_learning_denoiser
+        {
+            let tier_start = std::time::Instant::now();
+            output = deep_learning.process(&output)?;
+            tier_time[1] = tier_start.elapsed();
+        }
 
-                if *use_deep_learning
-                    && let Some(ref mut deep_learning) = self.deep_learning_denoiser
-                {
-                    output = deep_learning.process(&output)?;
-                }
+        // Third pass: Spectral enhancement (when available)
+        if self.runs_spectral()
+            && let Some(ref mut spectral) = self.spectral_denoiser
+        {
+            let tier_start = std::time::Instant::now();
+            output = spectral.process(&output)?;
+            tier_time[2] = tier_start.elapsed();
+        }
 
-                if *use_spectral && let Some(ref mut spectral) = self.spectral_denoiser {
-                    output = spectral.process(&output)?;
-                }
-            }
+        // Keep a learning pass going even when the mode skips the spectral stage
+        if !self.runs_spectral()
+            && let Some(ref mut spectral) = self.spectral_denoiser
+            && spectral.learning_progress().is_some()
+        {
+            spectral.observe_noise(&output)?;
         }
 
-        // Update performance metrics
-        let processing_time = start_time.elapsed().as_secs_f32() * 1000.0; // Convert to ms
-        let cpu_usage = self.estimate_cpu_usage(processing_time);
+        // Update performance metrics from the real share of the buffer's duration
+        let elapsed = start_time.elapsed();
+        let processing_time = elapsed.as_secs_f32() * 1000.0; // Convert to ms
+        let frames = input.len() / self.config.input_channels.max(1);
+        let buffer = crate::dsp_load::buffer_duration(frames, self.config.sample_rate);
+        let load = |time: std::time::Duration| {
+            if buffer.is_zero() {
+                0.0
+            } else {
+                time.as_secs_f32() / buffer.as_secs_f32() * 100.0
+            }
+        };
+        let cpu_usage = load(elapsed);
 
         self.metrics.latency_ms = processing_time;
         self.metrics.cpu_usage_percent = cpu_usage;
+        self.metrics.rnnoise_load_percent = load(tier_time[0]);
+        self.metrics.deep_learning_load_percent = load(tier_time[1]);
+        self.metrics.spectral_load_percent = load(tier_time[2]);
 
         self.performance_monitor.update(cpu_usage, processing_time);
 
@@ -525,6 +927,7 @@ impl AdvancedDenoiser for AdvancedDenoisingSystem {
     }
 
     fn set_mode(&mut self, mode: DenoisingMode) -> Result<()> {
+        self.requested_mode = mode.clone();
         self.config.mode = mode;
         Ok(())
     }
@@ -556,14 +959,149 @@ impl AdvancedDenoiser for AdvancedDenoisingSystem {
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        let changed = self.config.sample_rate != sample_rate;
+        self.config.sample_rate = sample_rate;
+        if let Some(ref mut rnnoise) = self.rnnoise_denoiser {
+            rnnoise.set_sample_rate(sample_rate);
+        }
+        if changed
+            && self.config.deep_learning_model.is_some()
+            && let Err(e) = self.initialize_deep_learning()
+        {
+            log::warn!("Deep learning denoiser unavailable: {}", e);
+        }
+    }
+
+    fn latency_samples(&self) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+
+        let rnnoise_latency = match self.rnnoise_denoiser {
+            Some(ref rnnoise) if self.runs_rnnoise() => rnnoise.latency_samples(),
+            _ => 0,
+        };
+        let deep_learning_latency = match self.deep_learning_denoiser {
+            Some(ref deep_learning) if self.runs_deep_learning() => {
+                (deep_learning.get_latency() * self.config.sample_rate as f32 / 1000.0).round()
+                    as usize
+            }
+            _ => 0,
+        };
+        let spectral_latency = match self.spectral_denoiser {
+            Some(ref spectral) if self.runs_spectral() => spectral.latency_samples(),
+            _ => 0,
+        };
+
+        rnnoise_latency + deep_learning_latency + spectral_latency
+    }
+
+    fn start_noise_learning(&mut self, seconds: f32) -> Result<()> {
+        if !self.enabled {
+            anyhow::bail!("Advanced denoising is disabled");
+        }
+        if seconds.is_nan() || seconds <= 0.0 {
+            anyhow::bail!("Learning duration must be positive, got {}", seconds);
+        }
+        let spectral = self
+            .spectral_denoiser
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Spectral denoiser is not available"))?;
+        spectral.start_noise_learning(seconds);
+        Ok(())
+    }
+
+    fn noise_learning_progress(&self) -> Option<f32> {
+        self.spectral_denoiser
+            .as_ref()
+            .and_then(|spectral| spectral.learning_progress())
+    }
+
+    fn get_noise_profile(&self) -> Option<NoiseProfile> {
+        self.spectral_denoiser
+            .as_ref()
+            .and_then(|spectral| spectral.get_noise_profile())
+    }
+
+    fn set_noise_profile(&mut self, profile: &NoiseProfile) -> Result<()> {
+        if profile.magnitudes.is_empty() || profile.fft_size == 0 || profile.sample_rate == 0 {
+            anyhow::bail!("Noise profile is empty");
+        }
+        let spectral = self
+            .spectral_denoiser
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Spectral denoiser is not available"))?;
+        spectral.set_noise_profile(profile);
+        Ok(())
+    }
+
+    fn clear_noise_profile(&mut self) {
+        if let Some(ref mut spectral) = self.spectral_denoiser {
+            spectral.clear_noise_profile();
+        }
+    }
+
+    fn set_deep_learning_model(&mut self, model: Option<OnnxModelConfig>) -> Result<()> {
+        let previous_model = std::mem::replace(&mut self.config.deep_learning_model, model);
+        let previous_denoiser = self.deep_learning_denoiser.take();
+        let result = self.initialize_deep_learning();
+        if result.is_err() {
+            self.config.deep_learning_model = previous_model;
+            self.deep_learning_denoiser = previous_denoiser;
+        }
+        result
+    }
+
+    fn use_deep_learning_model(&mut self, model: Option<OnnxDenoiser>) {
+        self.config.deep_learning_model = model.as_ref().map(|model| model.config().clone());
+        self.deep_learning_denoiser =
+            model.map(|model| Box::new(model) as Box<dyn DeepLearningDenoiser>);
+    }
+
+    fn deep_learning_model_info(&self) -> Option<ModelInfo> {
+        self.deep_learning_denoiser
+            .as_ref()
+            .map(|deep_learning| deep_learning.get_model_info())
+    }
+
+    fn set_callback_load(&mut self, load_percent: f32) {
+        self.callback_load_percent = load_percent;
+    }
+
+    fn set_input_channels(&mut self, channels: usize) {
+        self.config.input_channels = channels.max(1);
+    }
 }
 
 impl AdvancedDenoisingSystem {
-    /// Estimate CPU usage based on processing time
-    fn estimate_cpu_usage(&self, processing_time_ms: f32) -> f32 {
-        let frame_duration_ms =
-            (self.config.frame_size as f32 / self.config.sample_rate as f32) * 1000.0;
-        (processing_time_ms / frame_duration_ms) * 100.0
+    /// Whether the current mode runs RNNoise
+    fn runs_rnnoise(&self) -> bool {
+        match &self.config.mode {
+            DenoisingMode::Custom { use_rnnoise, .. } => *use_rnnoise,
+            _ => true,
+        }
+    }
+
+    /// Whether the current mode runs the deep-learning stage
+    fn runs_deep_learning(&self) -> bool {
+        match &self.config.mode {
+            DenoisingMode::Enhanced | DenoisingMode::Maximum => true,
+            DenoisingMode::Custom {
+                use_deep_learning, ..
+            } => *use_deep_learning,
+            DenoisingMode::Basic => false,
+        }
+    }
+
+    /// Whether the current mode runs the spectral stage
+    fn runs_spectral(&self) -> bool {
+        match &self.config.mode {
+            DenoisingMode::Maximum => true,
+            DenoisingMode::Custom { use_spectral, .. } => *use_spectral,
+            _ => false,
+        }
     }
 
     /// Get available denoising modes based on system capabilities
@@ -583,10 +1121,14 @@ impl AdvancedDenoisingSystem {
 
     /// Update configuration
     pub fn update_config(&mut self, config: AdvancedDenoisingConfig) -> Result<()> {
+        let model_changed = config.deep_learning_model != self.config.deep_learning_model;
+        self.requested_mode = config.mode.clone();
         self.config = config;
         // Re-initialize if needed
         if !self.is_ready() {
             self.initialize_denoisers()?;
+        } else if model_changed {
+            self.initialize_deep_learning()?;
         }
         Ok(())
     }
@@ -600,3 +1142,255 @@ pub fn create_advanced_denoiser(config: AdvancedDenoisingConfig) -> Result<Share
     let system = AdvancedDenoisingSystem::new(config)?;
     Ok(Arc::new(Mutex::new(system)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic white noise in ±amplitude
+    fn white_noise(len: usize, amplitude: f32, seed: &mut u32) -> Vec<f32> {
+        (0..len)
+            .map(|_| {
+                *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (*seed >> 8) as f32 / (1u32 << 24) as f32 * 2.0 * amplitude - amplitude
+            })
+            .collect()
+    }
+
+    fn tone(len: usize, frequency: f32, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|n| {
+                amplitude * (2.0 * std::f32::consts::PI * frequency * n as f32 / 48000.0).sin()
+            })
+            .collect()
+    }
+
+    /// Run `input` through the denoiser in callback-sized chunks
+    fn process_chunked(wiener: &mut WienerDenoiser, input: &[f32], chunk: usize) -> Vec<f32> {
+        input
+            .chunks(chunk)
+            .flat_map(|c| wiener.process(c).unwrap())
+            .collect()
+    }
+
+    /// SNR of `processed` against `clean`, after removing the filter delay
+    fn snr_db(clean: &[f32], processed: &[f32], delay: usize, skip: usize) -> f32 {
+        let (signal, error) = clean[skip..clean.len() - delay]
+            .iter()
+            .zip(&processed[skip + delay..])
+            .fold((0.0f64, 0.0f64), |(s, e), (&c, &p)| {
+                (s + (c * c) as f64, e + ((p - c) * (p - c)) as f64)
+            });
+        (10.0 * (signal / error).log10()) as f32
+    }
+
+    #[test]
+    fn test_stft_reconstructs_input_at_every_hop() {
+        let quiet = NoiseProfile {
+            sample_rate: 48000,
+            fft_size: 512,
+            magnitudes: vec![0.0; 257],
+        };
+        let mut seed = 3;
+        let input = white_noise(48000 / 4, 0.5, &mut seed);
+
+        for hop in [64, 128, 256] {
+            let mut wiener = WienerDenoiser::with_hop(48000, 512, hop).unwrap();
+            wiener.set_noise_profile(&quiet);
+            let output = process_chunked(&mut wiener, &input, 333);
+            assert_eq!(output.len(), input.len());
+
+            let delay = wiener.latency_samples();
+            for n in 0..input.len() - delay {
+                assert!(
+                    (output[n + delay] - input[n]).abs() < 1e-4,
+                    "hop {} sample {}: {} vs {}",
+                    hop,
+                    n,
+                    output[n + delay],
+                    input[n]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_hop_is_rejected() {
+        assert!(WienerDenoiser::with_hop(48000, 512, 0).is_err());
+        assert!(WienerDenoiser::with_hop(48000, 512, 384).is_err());
+        assert!(WienerDenoiser::with_hop(48000, 512, 100).is_err());
+        assert!(WienerDenoiser::with_hop(48000, 512, 512).is_err());
+    }
+
+    #[test]
+    fn test_snr_improves_with_learned_profile() {
+        let mut seed = 11;
+        let mut wiener = WienerDenoiser::new(48000, 480).unwrap();
+        wiener.start_noise_learning(1.0);
+        while wiener.learning_progress().is_some() {
+            wiener
+                .observe_noise(&white_noise(480, 0.1, &mut seed))
+                .unwrap();
+        }
+
+        let clean = tone(96000, 1000.0, 0.3);
+        let noise = white_noise(clean.len(), 0.1, &mut seed);
+        let noisy: Vec<f32> = clean.iter().zip(&noise).map(|(c, n)| c + n).collect();
+        let output = process_chunked(&mut wiener, &noisy, 480);
+
+        let delay = wiener.latency_samples();
+        let before = snr_db(&clean, &noisy, 0, 48000);
+        let after = snr_db(&clean, &output, delay, 48000);
+        assert!(
+            after - before > 10.0,
+            "SNR {:.1} dB -> {:.1} dB",
+            before,
+            after
+        );
+    }
+
+    #[test]
+    fn test_snr_improves_with_adaptive_estimate() {
+        let mut seed = 5;
+        let mut wiener = WienerDenoiser::new(48000, 480).unwrap();
+
+        // Half a second of room tone before the tone starts
+        let mut clean = vec![0.0; 24000];
+        clean.extend(tone(96000, 440.0, 0.3));
+        let noise = white_noise(clean.len(), 0.1, &mut seed);
+        let noisy: Vec<f32> = clean.iter().zip(&noise).map(|(c, n)| c + n).collect();
+        let output = process_chunked(&mut wiener, &noisy, 256);
+
+        let delay = wiener.latency_samples();
+        let before = snr_db(&clean, &noisy, 0, 72000);
+        let after = snr_db(&clean, &output, delay, 72000);
+        assert!(
+            after - before > 10.0,
+            "SNR {:.1} dB -> {:.1} dB",
+            before,
+            after
+        );
+        assert!(wiener.get_noise_reduction_db() > 0.0);
+    }
+
+    #[test]
+    fn test_missing_deep_learning_model_leaves_tier_empty() {
+        let config = AdvancedDenoisingConfig {
+            deep_learning_model: Some(OnnxModelConfig::new("/nonexistent.onnx", 480, 48000)),
+            ..Default::default()
+        };
+        let mut system = AdvancedDenoisingSystem::new(config).unwrap();
+        assert!(system.deep_learning_model_info().is_none());
+        assert!(
+            !system
+                .get_available_modes()
+                .contains(&DenoisingMode::Enhanced)
+        );
+
+        // A failed load leaves the previous model in place
+        system.set_deep_learning_model(None).unwrap();
+        let model = OnnxModelConfig::new("/nonexistent.onnx", 480, 48000);
+        assert!(system.set_deep_learning_model(Some(model)).is_err());
+        assert!(system.config.deep_learning_model.is_none());
+    }
+
+    #[test]
+    fn test_noise_learning_freezes_profile() {
+        let mut wiener = WienerDenoiser::new(48000, 480).unwrap();
+        assert!(wiener.get_noise_profile().is_none());
+
+        wiener.start_noise_learning(0.5);
+        let mut seed = 1;
+        while wiener.learning_progress().is_some() {
+            wiener
+                .observe_noise(&white_noise(480, 0.1, &mut seed))
+                .unwrap();
+        }
+
+        let profile = wiener
+            .get_noise_profile()
+            .expect("profile should be frozen");
+        assert_eq!(profile.magnitudes.len(), 512 / 2 + 1);
+        // Uniform noise of ±0.1 has an RMS of 0.1 / sqrt(3) in every bin
+        let mid = profile.magnitudes[64..192].iter().sum::<f32>() / 128.0;
+        assert!(
+            (mid - 0.1 / 3f32.sqrt()).abs() < 0.01,
+            "mid-band level {}",
+            mid
+        );
+
+        wiener.clear_noise_profile();
+        assert!(wiener.get_noise_profile().is_none());
+    }
+
+    #[test]
+    fn test_noise_profile_survives_fft_size_change() {
+        let profile = NoiseProfile {
+            sample_rate: 48000,
+            fft_size: 256,
+            magnitudes: (0..129).map(|i| 0.01 + i as f32 * 1e-4).collect(),
+        };
+
+        let mut wiener = WienerDenoiser::new(48000, 480).unwrap();
+        wiener.set_noise_profile(&profile);
+        let applied = wiener.get_noise_profile().unwrap();
+
+        assert_eq!(applied.fft_size, 512);
+        for freq in [0.0, 1000.0, 5000.0, 20000.0] {
+            let expected = profile.magnitude_at(freq);
+            assert!((applied.magnitude_at(freq) - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_system_learns_outside_spectral_mode() {
+        let config = AdvancedDenoisingConfig {
+            mode: DenoisingMode::Basic,
+            adaptive_mode: false,
+            ..Default::default()
+        };
+        let mut system = AdvancedDenoisingSystem::new(config).unwrap();
+        assert!(system.start_noise_learning(0.2).is_err());
+
+        system.set_enabled(true);
+        system.start_noise_learning(0.2).unwrap();
+        let mut seed = 7;
+        for _ in 0..100 {
+            system
+                .process_frame(&white_noise(480, 0.05, &mut seed))
+                .unwrap();
+        }
+
+        assert!(system.noise_learning_progress().is_none());
+        assert!(system.get_noise_profile().is_some());
+    }
+
+    #[test]
+    fn test_adaptive_mode_follows_callback_budget() {
+        let config = AdvancedDenoisingConfig {
+            mode: DenoisingMode::Enhanced,
+            adaptive_mode: true,
+            ..Default::default()
+        };
+        let mut system = AdvancedDenoisingSystem::new(config).unwrap();
+
+        // Pretend the adaptation interval has elapsed with a cheap denoiser
+        let check = |system: &mut AdvancedDenoisingSystem| {
+            system.performance_monitor.last_adaptation -= std::time::Duration::from_secs(6);
+            system.performance_monitor.update(1.0, 0.1);
+            system.adaptive_mode_adjustment();
+        };
+
+        // The denoiser itself is cheap, but the callback as a whole is over budget
+        system.set_callback_load(95.0);
+        check(&mut system);
+        assert_eq!(system.get_mode(), DenoisingMode::Basic);
+
+        // With headroom it returns to the requested mode and no further
+        system.set_callback_load(10.0);
+        check(&mut system);
+        assert_eq!(system.get_mode(), DenoisingMode::Enhanced);
+        check(&mut system);
+        assert_eq!(system.get_mode(), DenoisingMode::Enhanced);
+    }
+}
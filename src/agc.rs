@@ -0,0 +1,348 @@
+//! Automatic loudness normalisation for output buses.
+//!
+//! A broadcast-style AGC: the bus is measured with the BS.1770 momentary
+//! loudness and its gain is eased toward the target over several seconds,
+//! within a maximum boost and cut. The gain only moves while a simple
+//! energy-based voice activity detector hears speech, so pauses and room
+//! tone are never pumped up. A lookahead true-peak limiter finishes the
+//! chain so the added gain cannot push the bus over its ceiling.
+
+#![allow(dead_code)] // Complete AGC API, used incrementally by the GUI and IPC
+
+use crate::audio_effects::Limiter;
+use crate::loudness::LoudnessMeter;
+use serde::{Deserialize, Serialize};
+
+/// Voice activity is decided on 10 ms frames
+const VAD_FRAME_MS: f32 = 10.0;
+
+/// Frames this far above the noise floor count as speech
+const VAD_THRESHOLD_DB: f32 = 9.0;
+
+/// Nothing quieter than this is speech, whatever the floor
+const VAD_MIN_LEVEL_DB: f32 = -60.0;
+
+/// The noise floor follows quiet frames at once but climbs this slowly
+const VAD_FLOOR_RISE_DB_PER_SEC: f32 = 1.0;
+
+/// Speech stays active this long after the last loud frame
+const VAD_HANGOVER_MS: f32 = 300.0;
+
+/// AGC settings for one bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgcConfig {
+    pub enabled: bool,
+    /// Loudness the bus is steered toward
+    pub target_lufs: f32,
+    /// Most gain the AGC may add
+    pub max_boost_db: f32,
+    /// Most gain the AGC may take away
+    pub max_cut_db: f32,
+    /// Time constant of the gain, in seconds
+    pub response_secs: f32,
+    /// True-peak ceiling of the final limiter
+    pub ceiling_dbtp: f32,
+}
+
+impl Default for AgcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_lufs: -16.0,
+            max_boost_db: 12.0,
+            max_cut_db: 12.0,
+            response_secs: 4.0,
+            ceiling_dbtp: -1.0,
+        }
+    }
+}
+
+/// Live state of one bus's AGC
+#[derive(Debug, Clone, Serialize)]
+pub struct AgcStatus {
+    pub bus: String,
+    pub enabled: bool,
+    pub gain_db: f32,
+    pub voice_active: bool,
+    pub limiter_reduction_db: f32,
+    pub latency_samples: usize,
+}
+
+/// Energy-based voice activity detector with an adaptive noise floor
+#[derive(Debug, Clone)]
+pub struct VoiceActivityDetector {
+    frame_len: usize,
+    frame_pos: usize,
+    frame_energy: f64,
+    floor_db: f32,
+    floor_rise_per_frame: f32,
+    hangover_frames: usize,
+    hangover: usize,
+}
+
+impl VoiceActivityDetector {
+    pub fn new(sample_rate: u32) -> Self {
+        let frame_len = ((sample_rate as f32 * VAD_FRAME_MS / 1000.0) as usize).max(1);
+        Self {
+            frame_len,
+            frame_pos: 0,
+            frame_energy: 0.0,
+            floor_db: VAD_MIN_LEVEL_DB,
+            floor_rise_per_frame: VAD_FLOOR_RISE_DB_PER_SEC * VAD_FRAME_MS / 1000.0,
+            hangover_frames: (VAD_HANGOVER_MS / VAD_FRAME_MS) as usize,
+            hangover: 0,
+        }
+    }
+
+    /// Feed interleaved frames; the loudest channel decides
+    pub fn process_interleaved(&mut self, samples: &[f32], channels: usize) {
+        for frame in samples.chunks_exact(channels.max(1)) {
+            let peak_power = frame.iter().map(|s| s * s).fold(0.0_f32, f32::max);
+            self.frame_energy += peak_power as f64;
+            self.frame_pos += 1;
+            if self.frame_pos == self.frame_len {
+                self.finish_frame();
+            }
+        }
+    }
+
+    fn finish_frame(&mut self) {
+        let mean = self.frame_energy / self.frame_len as f64;
+        let level_db = 10.0 * mean.max(1e-12).log10() as f32;
+        self.frame_pos = 0;
+        self.frame_energy = 0.0;
+
+        if level_db > VAD_MIN_LEVEL_DB && level_db > self.floor_db + VAD_THRESHOLD_DB {
+            self.hangover = self.hangover_frames;
+        } else {
+            self.hangover = self.hangover.saturating_sub(1);
+        }
+
+        if level_db < self.floor_db {
+            self.floor_db = level_db;
+        } else {
+            self.floor_db += self.floor_rise_per_frame;
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.hangover > 0
+    }
+
+    pub fn reset(&mut self) {
+        self.frame_pos = 0;
+        self.frame_energy = 0.0;
+        self.floor_db = VAD_MIN_LEVEL_DB;
+        self.hangover = 0;
+    }
+}
+
+/// Slow loudness AGC followed by a true-peak limiter, for one bus
+pub struct BusAgc {
+    config: AgcConfig,
+    sample_rate: u32,
+    channels: usize,
+    meter: LoudnessMeter,
+    vad: VoiceActivityDetector,
+    gain_db: f32,
+    limiter: Limiter,
+}
+
+impl BusAgc {
+    pub fn new(config: AgcConfig, sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        let mut agc = Self {
+            config: AgcConfig::default(),
+            sample_rate,
+            channels,
+            meter: LoudnessMeter::new(sample_rate, channels),
+            vad: VoiceActivityDetector::new(sample_rate),
+            gain_db: 0.0,
+            limiter: Limiter::new(sample_rate as f32, channels),
+        };
+        // A bus can afford the longest lookahead and the gentlest ramp
+        agc.limiter.set_lookahead(5.0);
+        agc.limiter.set_release(100.0);
+        agc.set_config(config);
+        agc
+    }
+
+    pub fn config(&self) -> &AgcConfig {
+        &self.config
+    }
+
+    /// Apply new settings; switching the AGC on starts again from unity gain
+    pub fn set_config(&mut self, config: AgcConfig) {
+        if config.enabled && !self.config.enabled {
+            self.reset();
+        }
+        self.limiter.set_ceiling(config.ceiling_dbtp);
+        self.limiter.set_enabled(config.enabled);
+        self.config = config;
+    }
+
+    /// Normalise and limit a buffer of interleaved samples in place
+    pub fn process_interleaved(&mut self, samples: &mut [f32]) {
+        if !self.config.enabled || samples.is_empty() {
+            return;
+        }
+
+        self.meter.process_interleaved(samples);
+        self.vad.process_interleaved(samples, self.channels);
+
+        let previous_db = self.gain_db;
+        if self.vad.is_active()
+            && let Some(loudness) = self.meter.momentary_lufs()
+        {
+            let wanted = (self.config.target_lufs - loudness)
+                .clamp(-self.config.max_cut_db, self.config.max_boost_db);
+            let buffer_secs = (samples.len() / self.channels) as f32 / self.sample_rate as f32;
+            let coeff = 1.0 - (-buffer_secs / self.config.response_secs.max(0.1)).exp();
+            self.gain_db += (wanted - self.gain_db) * coeff;
+        }
+
+        // Ramp across the buffer so gain changes never step
+        let from = 10.0_f32.powf(previous_db / 20.0);
+        let to = 10.0_f32.powf(self.gain_db / 20.0);
+        let frames = samples.len() / self.channels;
+        for (i, frame) in samples.chunks_exact_mut(self.channels).enumerate() {
+            let gain = from + (to - from) * (i + 1) as f32 / frames as f32;
+            frame.iter_mut().for_each(|s| *s *= gain);
+        }
+
+        self.limiter.process(samples);
+    }
+
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    pub fn is_voice_active(&self) -> bool {
+        self.vad.is_active()
+    }
+
+    /// Frames of delay added by the limiter while the AGC is on
+    pub fn latency_samples(&self) -> usize {
+        self.limiter.latency_samples()
+    }
+
+    pub fn status(&self, bus: &str) -> AgcStatus {
+        AgcStatus {
+            bus: bus.to_string(),
+            enabled: self.config.enabled,
+            gain_db: self.gain_db,
+            voice_active: self.vad.is_active(),
+            limiter_reduction_db: self.limiter.get_gain_reduction(),
+            latency_samples: self.latency_samples(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.meter.reset();
+        self.vad.reset();
+        self.gain_db = 0.0;
+        self.limiter.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    /// One second of 1 kHz tone followed by half a second of faint noise,
+    /// a rough stand-in for speech with pauses
+    fn talk(seconds: usize, amplitude: f32) -> Vec<f32> {
+        let mut seed = 1u32;
+        (0..seconds * RATE as usize)
+            .map(|i| {
+                let t = i % (RATE as usize * 3 / 2);
+                if t < RATE as usize {
+                    amplitude * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / RATE as f32).sin()
+                } else {
+                    seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                    (seed as f32 / u32::MAX as f32 - 0.5) * 2e-4
+                }
+            })
+            .collect()
+    }
+
+    fn run(agc: &mut BusAgc, samples: &mut [f32]) {
+        for block in samples.chunks_mut(256) {
+            agc.process_interleaved(block);
+        }
+    }
+
+    fn enabled_config() -> AgcConfig {
+        AgcConfig {
+            enabled: true,
+            ..AgcConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_agc_reaches_target() {
+        let mut agc = BusAgc::new(enabled_config(), RATE, 1);
+        let mut warmup = talk(30, 0.1);
+        run(&mut agc, &mut warmup);
+
+        let mut settled = talk(15, 0.1);
+        run(&mut agc, &mut settled);
+        let mut meter = LoudnessMeter::new(RATE, 1);
+        meter.process_interleaved(&settled);
+        let loudness = meter.integrated_lufs().unwrap();
+        assert!(
+            (loudness + 16.0).abs() < 1.5,
+            "expected about -16 LUFS, got {}",
+            loudness
+        );
+
+        // Boost never exceeds the limit, however quiet the source
+        let mut whisper = talk(30, 0.001);
+        run(&mut agc, &mut whisper);
+        assert!(agc.gain_db() <= 12.0 + 1e-3);
+    }
+
+    #[test]
+    fn test_agc_freezes_in_silence() {
+        let mut agc = BusAgc::new(enabled_config(), RATE, 1);
+        let mut speech = talk(10, 0.05);
+        run(&mut agc, &mut speech);
+        let gain = agc.gain_db();
+        assert!(gain > 3.0);
+
+        // Room tone alone must not drive the gain up
+        let mut room_tone: Vec<f32> = talk(10, 0.0);
+        run(&mut agc, &mut room_tone);
+        assert!(!agc.is_voice_active());
+        assert!((agc.gain_db() - gain).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_agc_output_stays_under_ceiling() {
+        let config = AgcConfig {
+            target_lufs: -6.0,
+            ceiling_dbtp: -1.0,
+            ..enabled_config()
+        };
+        let mut agc = BusAgc::new(config, RATE, 1);
+        let mut loud = talk(20, 0.5);
+        run(&mut agc, &mut loud);
+
+        let ceiling = 10.0_f32.powf(-1.0 / 20.0);
+        assert!(loud.iter().all(|s| s.abs() <= ceiling));
+        assert!(agc.status("master").limiter_reduction_db >= 0.0);
+    }
+
+    #[test]
+    fn test_disabled_agc_is_transparent() {
+        let mut agc = BusAgc::new(AgcConfig::default(), RATE, 2);
+        let original = talk(1, 0.5);
+        let mut samples = original.clone();
+        run(&mut agc, &mut samples);
+        assert_eq!(samples, original);
+        assert_eq!(agc.latency_samples(), 0);
+    }
+}
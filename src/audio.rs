@@ -1,30 +1,148 @@
 use crate::advanced_denoising::{
-    AdvancedDenoisingConfig, DenoisingMode, SharedAdvancedDenoiser, create_advanced_denoiser,
+    AdvancedDenoiser, AdvancedDenoisingConfig, AdvancedDenoisingSystem, DenoisingMetrics,
+    DenoisingMode, ModelInfo, NoiseProfile,
 };
+use crate::agc::{AgcConfig, AgcStatus, BusAgc};
 use crate::audio_effects::{ChannelEffects, ChannelEffectsConfig};
+use crate::calibration::LatencyCalibration;
+use crate::dsp_load::{ChannelDspLoad, DspLoadReport, DspStage, LoadMeter, buffer_duration};
 use crate::ghostwave_integration::{
     GhostWaveIntegration, PhantomLinkProfile, detect_nvidia_driver,
 };
 use crate::gui::visualizer::{SpectrumAnalyzer, VUMeter};
+use crate::latency::{BusLatency, ChannelLatency, DelayLine, LatencyReport, compensation_for};
+use crate::loudness::{BusLoudness, LoudnessMeter};
+use crate::onnx_denoiser::{OnnxDenoiser, OnnxModelConfig};
 use crate::phantomlink::AudioRouter;
+use crate::plugin_bridge::{BridgedProcessor, SandboxConfig};
+use crate::plugin_host::{
+    self, ChannelLayout, ParameterChange, PluginFormat, PluginProcessor, PluginScene, ProgramList,
+    SavedPlugin,
+};
+use crate::recorder::{
+    RecordBlock, Recorder, RecorderConfig, RecorderStatus, RecordingSummary, ReplaySummary,
+    StreamLayout,
+};
 use crate::rnnoise::Rnnoise;
-use crate::vst_host::VstProcessor;
+use crate::signal_generator::{SignalGenerator, SignalGeneratorConfig};
+use crate::transport;
+use crate::vst_host::{ParameterInfo, VstPluginInfo};
 use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Stream, StreamConfig};
 use crossbeam_channel::{Receiver, Sender};
 use std::collections::VecDeque;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 const BUFFER_SIZE: usize = 1024;
 const CHANNEL_COUNT: usize = 4;
 const DEFAULT_SAMPLE_RATE: f32 = 48000.0;
 
+/// Output buses the channels are mixed into, in routing order
+pub const OUTPUT_BUSES: &[&str] = &["master"];
+
+/// Buses carry the strips' output: stereo frames, interleaved
+pub const BUS_CHANNELS: usize = 2;
+
+/// Denoiser a channel runs ahead of its VST and dynamics chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChannelDenoiser {
+    /// No noise suppression
+    #[default]
+    Off,
+    /// Legacy RNNoise only
+    Rnnoise,
+    /// Multi-stage advanced denoising (mode is selectable per channel)
+    Advanced,
+    /// GhostWave AI denoising (RTX-accelerated when available)
+    GhostWave,
+}
+
+impl ChannelDenoiser {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChannelDenoiser::Off => "Off",
+            ChannelDenoiser::Rnnoise => "RNNoise",
+            ChannelDenoiser::Advanced => "Advanced",
+            ChannelDenoiser::GhostWave => "GhostWave",
+        }
+    }
+
+    /// Parse a name as used by IPC and config files (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn all() -> &'static [ChannelDenoiser] {
+        &[
+            ChannelDenoiser::Off,
+            ChannelDenoiser::Rnnoise,
+            ChannelDenoiser::Advanced,
+            ChannelDenoiser::GhostWave,
+        ]
+    }
+}
+
+/// What a channel needs to create a denoiser instance, taken from the
+/// channel so the instance can be built without holding the mixer lock
+pub struct DenoiserSetup {
+    denoiser: ChannelDenoiser,
+    /// Set when the advanced denoiser has to be created
+    advanced: Option<AdvancedDenoisingConfig>,
+    advanced_enabled: bool,
+    noise_profile: Option<NoiseProfile>,
+    /// GhostWave has to be created
+    ghostwave: bool,
+}
+
+impl DenoiserSetup {
+    /// Create the instances; slow for the advanced denoiser and GhostWave
+    pub fn build(self) -> Result<PreparedDenoiser> {
+        let advanced = match self.advanced {
+            Some(config) => {
+                let mut system = AdvancedDenoisingSystem::new(config)?;
+                system.set_enabled(self.advanced_enabled);
+                if let Some(ref profile) = self.noise_profile {
+                    system.set_noise_profile(profile)?;
+                }
+                Some(Box::new(system) as Box<dyn AdvancedDenoiser>)
+            }
+            None => None,
+        };
+        let ghostwave = if self.ghostwave {
+            Some(GhostWaveIntegration::new()?)
+        } else {
+            None
+        };
+        Ok(PreparedDenoiser {
+            denoiser: self.denoiser,
+            advanced,
+            ghostwave,
+        })
+    }
+}
+
+/// A denoiser selection with its instances built, ready to swap into a channel
+pub struct PreparedDenoiser {
+    denoiser: ChannelDenoiser,
+    advanced: Option<Box<dyn AdvancedDenoiser>>,
+    ghostwave: Option<GhostWaveIntegration>,
+}
+
 /// Audio channel processor with volume, effects, and metering
 pub struct ChannelProcessor {
     pub volume: f32,
     pub muted: bool,
-    pub vst_processor: Option<VstProcessor>,
+    /// Effect plugin of any supported format
+    pub plugin: Option<Box<dyn PluginProcessor>>,
+    /// Catalogue entry the plugin was loaded from, kept to save it
+    plugin_info: Option<VstPluginInfo>,
+    /// Channel whose input feeds the plugin's sidechain ports
+    sidechain_source: Option<usize>,
     pub gain: f32,
     pub pan: f32,
     #[allow(dead_code)] // Solo functionality for future mixer implementation
@@ -35,6 +153,29 @@ pub struct ChannelProcessor {
     last_levels: [f32; 2], // Store last peak/rms levels
     /// Dynamics effects chain (Gate -> Compressor -> Limiter)
     pub effects: ChannelEffects,
+    // Each channel owns its denoiser instances so the recurrent and
+    // noise-profile state of one source never leaks into another
+    denoiser: ChannelDenoiser,
+    denoising_mode: DenoisingMode,
+    advanced_enabled: bool,
+    rnnoise: Rnnoise,
+    advanced_denoiser: Option<Box<dyn AdvancedDenoiser>>,
+    ghostwave: Option<GhostWaveIntegration>,
+    sample_rate: u32,
+    /// Interleaved channels in the buffers passed to `process`
+    input_channels: usize,
+    denoise_metrics: DenoisingMetrics,
+    /// Loaded noise profile, re-applied whenever the advanced denoiser is recreated
+    noise_profile: Option<NoiseProfile>,
+    /// Model for the advanced denoiser's Deep Learning tier
+    deep_learning_model: Option<OnnxModelConfig>,
+    /// Let the advanced denoiser change mode with measured load
+    adaptive_denoising: bool,
+    dsp_load: ChannelDspLoad,
+    /// Delay that lines this channel up with the slowest one on the bus
+    compensation: DelayLine,
+    /// Test signal used in place of the captured input
+    generator: Option<SignalGenerator>,
 }
 
 impl ChannelProcessor {
@@ -42,7 +183,9 @@ impl ChannelProcessor {
         Self {
             volume: 0.8,
             muted: false,
-            vst_processor: None,
+            plugin: None,
+            plugin_info: None,
+            sidechain_source: None,
             gain: 0.0,
             pan: 0.0,
             solo: false,
@@ -50,147 +193,398 @@ impl ChannelProcessor {
             vu_meter: VUMeter::new(128),
             last_levels: [0.0, 0.0],
             effects: ChannelEffects::new(DEFAULT_SAMPLE_RATE),
+            denoiser: ChannelDenoiser::Off,
+            denoising_mode: DenoisingMode::Basic,
+            advanced_enabled: true,
+            rnnoise: Rnnoise::new(),
+            advanced_denoiser: None,
+            ghostwave: None,
+            sample_rate: DEFAULT_SAMPLE_RATE as u32,
+            input_channels: 1,
+            denoise_metrics: DenoisingMetrics::default(),
+            noise_profile: None,
+            deep_learning_model: None,
+            adaptive_denoising: true,
+            dsp_load: ChannelDspLoad::default(),
+            compensation: DelayLine::new(),
+            generator: None,
+        }
+    }
+
+    /// Feed this channel from a test signal instead of the input, or None
+    /// to go back to the input
+    pub fn set_generator(&mut self, config: Option<SignalGeneratorConfig>) {
+        match (config, self.generator.as_mut()) {
+            (Some(config), Some(generator)) => generator.set_config(config),
+            (Some(config), None) => {
+                self.generator = Some(SignalGenerator::new(config, self.sample_rate));
+            }
+            (None, _) => self.generator = None,
         }
     }
 
+    pub fn generator(&self) -> Option<&SignalGeneratorConfig> {
+        self.generator.as_ref().map(|g| g.config())
+    }
+
+    /// The generator's signal in the input's layout, when one is selected
+    pub fn generate_input(&mut self, len: usize, channels: usize) -> Option<Vec<f32>> {
+        let generator = self.generator.as_mut()?;
+        let mut samples = vec![0.0; len];
+        generator.fill_interleaved(&mut samples, channels);
+        Some(samples)
+    }
+
     /// Configure effects chain
     #[allow(dead_code)]
     pub fn configure_effects(&mut self, config: &ChannelEffectsConfig) {
         self.effects.apply_config(config);
     }
 
-    pub fn process(&mut self, input: &[f32], rnnoise: &Rnnoise, dt: f32) -> (Vec<f32>, [f32; 2]) {
-        if self.muted {
-            return (vec![0.0; input.len()], [0.0, 0.0]);
+    /// Select this channel's denoiser, creating its instance if needed.
+    /// Instances for the other denoisers are released.
+    pub fn set_denoiser(&mut self, denoiser: ChannelDenoiser) -> Result<()> {
+        let prepared = self.denoiser_setup(denoiser).build()?;
+        self.install_denoiser(prepared);
+        Ok(())
+    }
+
+    /// Settings for creating the instance `denoiser` needs; the current
+    /// instance is kept when the same denoiser is selected again
+    pub fn denoiser_setup(&self, denoiser: ChannelDenoiser) -> DenoiserSetup {
+        let advanced = (denoiser == ChannelDenoiser::Advanced && self.advanced_denoiser.is_none())
+            .then(|| AdvancedDenoisingConfig {
+                mode: self.denoising_mode.clone(),
+                sample_rate: self.sample_rate,
+                deep_learning_model: self.deep_learning_model.clone(),
+                adaptive_mode: self.adaptive_denoising,
+                ..Default::default()
+            });
+        DenoiserSetup {
+            denoiser,
+            advanced,
+            advanced_enabled: self.advanced_enabled,
+            noise_profile: self.noise_profile.clone(),
+            ghostwave: denoiser == ChannelDenoiser::GhostWave && self.ghostwave.is_none(),
         }
+    }
 
-        let mut output = input.to_vec();
+    /// Swap in a denoiser built from [`ChannelProcessor::denoiser_setup`].
+    /// Instances for the other denoisers are released.
+    pub fn install_denoiser(&mut self, prepared: PreparedDenoiser) {
+        let denoiser = prepared.denoiser;
+        if denoiser == ChannelDenoiser::Rnnoise && !self.rnnoise.is_enabled() {
+            self.rnnoise.set_sample_rate(self.sample_rate);
+            self.rnnoise.enable();
+        }
+        if let Some(mut advanced) = prepared.advanced {
+            // The stream may have restarted while it was being built
+            advanced.set_sample_rate(self.sample_rate);
+            self.advanced_denoiser = Some(advanced);
+        }
+        if let Some(ghostwave) = prepared.ghostwave {
+            self.ghostwave = Some(ghostwave);
+        }
 
-        // Apply gain
-        let gain_linear = if self.gain >= 0.0 {
-            1.0 + self.gain / 20.0
-        } else {
-            10.0_f32.powf(self.gain / 20.0)
-        };
+        if denoiser != ChannelDenoiser::Rnnoise {
+            self.rnnoise.disable();
+        }
+        if denoiser != ChannelDenoiser::Advanced {
+            self.advanced_denoiser = None;
+        }
+        if denoiser != ChannelDenoiser::GhostWave {
+            self.ghostwave = None;
+        }
 
-        for sample in &mut output {
-            *sample *= gain_linear;
+        self.denoiser = denoiser;
+        self.denoise_metrics = DenoisingMetrics::default();
+    }
+
+    /// Currently selected denoiser
+    pub fn denoiser(&self) -> ChannelDenoiser {
+        self.denoiser
+    }
+
+    /// Set the mode used when this channel runs the advanced denoiser
+    pub fn set_denoising_mode(&mut self, mode: DenoisingMode) -> Result<()> {
+        if let Some(ref mut denoiser) = self.advanced_denoiser {
+            denoiser.set_mode(mode.clone())?;
         }
+        self.denoising_mode = mode;
+        Ok(())
+    }
 
-        // Apply noise reduction if enabled (fallback to legacy RNNoise)
-        if rnnoise.is_enabled() {
-            output = rnnoise.process(&output);
+    /// Mode of the advanced denoiser (reflects adaptive downgrades)
+    pub fn denoising_mode(&self) -> DenoisingMode {
+        match self.advanced_denoiser {
+            Some(ref denoiser) => denoiser.get_mode(),
+            None => self.denoising_mode.clone(),
         }
+    }
+
+    /// Allow the advanced denoiser to trade quality for load. Offline processing
+    /// turns this off so results do not depend on machine speed; the setting
+    /// applies when the advanced denoiser is next created.
+    pub fn set_adaptive_denoising(&mut self, enabled: bool) {
+        self.adaptive_denoising = enabled;
+    }
 
-        // Apply VST processing
-        if let Some(ref mut vst) = self.vst_processor {
-            output = vst.process(&output);
+    /// Enable or disable the advanced denoiser without releasing it
+    pub fn set_advanced_denoising_enabled(&mut self, enabled: bool) {
+        self.advanced_enabled = enabled;
+        if let Some(ref mut denoiser) = self.advanced_denoiser {
+            denoiser.set_enabled(enabled);
         }
+    }
 
-        // Apply dynamics effects chain (Gate -> Compressor -> Limiter)
-        self.effects.process(&mut output);
+    /// Check if advanced denoising is enabled for this channel
+    pub fn is_advanced_denoising_enabled(&self) -> bool {
+        self.advanced_enabled
+    }
 
-        // Apply volume
-        for sample in &mut output {
-            *sample *= self.volume;
+    /// Tell the denoisers the stream rate so they can resample and report latency
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        if let Some(ref mut generator) = self.generator {
+            generator.set_sample_rate(sample_rate);
+        }
+        self.effects.set_sample_rate(sample_rate as f32);
+        self.rnnoise.set_sample_rate(sample_rate);
+        self.rnnoise.reset();
+        if let Some(ref mut denoiser) = self.advanced_denoiser {
+            denoiser.set_sample_rate(sample_rate);
         }
+        if let Some(ref mut plugin) = self.plugin
+            && let Err(e) = plugin.set_sample_rate(sample_rate as f32)
+        {
+            log::warn!(
+                "{} cannot run at {} Hz: {:#}",
+                plugin.get_plugin_name(),
+                sample_rate,
+                e
+            );
+        }
+    }
 
-        // Apply panning (assuming stereo output)
-        let mut stereo_output = Vec::with_capacity(output.len() * 2);
-        for &sample in &output {
-            let left_gain = if self.pan <= 0.0 { 1.0 } else { 1.0 - self.pan };
-            let right_gain = if self.pan >= 0.0 { 1.0 } else { 1.0 + self.pan };
+    /// Delay added by this channel's denoiser, in samples
+    pub fn denoiser_latency_samples(&self) -> usize {
+        match self.denoiser {
+            ChannelDenoiser::Off => 0,
+            ChannelDenoiser::GhostWave => {
+                self.ghostwave.as_ref().map_or(0, |g| g.latency_samples())
+            }
+            ChannelDenoiser::Rnnoise => self.rnnoise.latency_samples(),
+            ChannelDenoiser::Advanced => self
+                .advanced_denoiser
+                .as_ref()
+                .map_or(0, |d| d.latency_samples()),
+        }
+    }
 
-            stereo_output.push(sample * left_gain);
-            stereo_output.push(sample * right_gain);
+    /// Delay of each stage of this strip, including its compensation
+    pub fn latency(&self) -> ChannelLatency {
+        ChannelLatency {
+            denoiser: self.denoiser_latency_samples(),
+            vst: self
+                .plugin
+                .as_ref()
+                .map_or(0, |plugin| plugin.latency_samples()),
+            limiter: self.effects.latency_samples(),
+            compensation: self.compensation.delay(),
         }
+    }
 
-        // Update VU meter and get levels
-        let (peak, rms) = self.vu_meter.process(&stereo_output, dt);
-        let levels = [peak, rms];
+    /// Delay from this strip's processing, before compensation
+    pub fn latency_samples(&self) -> usize {
+        self.latency().processing()
+    }
 
-        // Store levels for GUI access
-        self.last_levels = levels;
+    /// Extra delay to line this channel up with slower ones
+    pub fn set_compensation(&mut self, samples: usize) {
+        // The delay line runs on interleaved frames after the plugin
+        self.compensation.set_delay(samples * self.strip_channels());
+    }
 
-        (stereo_output, levels)
+    /// Channels after the plugin slot: 2 once a stereo plugin is loaded
+    pub fn strip_channels(&self) -> usize {
+        self.plugin
+            .as_ref()
+            .map_or(1, |plugin| plugin.channel_layout().strip_channels())
     }
 
-    // New method for processing with advanced denoiser
-    pub fn process_advanced(
-        &mut self,
-        input: &[f32],
-        advanced_denoiser: Option<&SharedAdvancedDenoiser>,
-        dt: f32,
-    ) -> (Vec<f32>, [f32; 2]) {
-        if self.muted {
-            return (vec![0.0; input.len()], [0.0, 0.0]);
+    pub fn sidechain_source(&self) -> Option<usize> {
+        self.sidechain_source
+    }
+
+    pub fn set_sidechain_source(&mut self, source: Option<usize>) {
+        self.sidechain_source = source;
+    }
+
+    /// Delay of the limiter's lookahead, in milliseconds
+    pub fn limiter_latency_ms(&self) -> f32 {
+        self.effects.latency_samples() as f32 * 1000.0 / self.sample_rate as f32
+    }
+
+    /// Deepest limiter gain reduction during the last buffer, in dB
+    pub fn limiter_gain_reduction_db(&self) -> f32 {
+        self.effects.limiter.get_gain_reduction()
+    }
+
+    /// Metrics for this channel's denoiser from the most recent buffer
+    pub fn denoise_metrics(&self) -> &DenoisingMetrics {
+        &self.denoise_metrics
+    }
+
+    /// Start capturing room tone into the advanced denoiser's noise profile
+    pub fn start_noise_learning(&mut self, seconds: f32) -> Result<()> {
+        match self.advanced_denoiser {
+            Some(ref mut denoiser) if self.denoiser == ChannelDenoiser::Advanced => {
+                denoiser.start_noise_learning(seconds)
+            }
+            _ => anyhow::bail!("Noise learning requires the Advanced denoiser"),
         }
+    }
 
-        let mut output = input.to_vec();
+    /// Learning progress from 0.0 to 1.0, or None when not learning
+    pub fn noise_learning_progress(&self) -> Option<f32> {
+        self.advanced_denoiser
+            .as_ref()
+            .and_then(|d| d.noise_learning_progress())
+    }
 
-        // Apply gain
-        let gain_linear = if self.gain >= 0.0 {
-            1.0 + self.gain / 20.0
-        } else {
-            10.0_f32.powf(self.gain / 20.0)
-        };
+    /// The noise profile currently frozen in the advanced denoiser
+    pub fn noise_profile(&self) -> Option<NoiseProfile> {
+        match self.advanced_denoiser {
+            Some(ref denoiser) => denoiser.get_noise_profile(),
+            None => self.noise_profile.clone(),
+        }
+    }
 
-        for sample in &mut output {
-            *sample *= gain_linear;
+    /// Load a saved noise profile; it is kept across denoiser changes
+    pub fn set_noise_profile(&mut self, profile: NoiseProfile) -> Result<()> {
+        if let Some(ref mut denoiser) = self.advanced_denoiser {
+            denoiser.set_noise_profile(&profile)?;
         }
+        self.noise_profile = Some(profile);
+        Ok(())
+    }
 
-        // Apply advanced noise reduction if available
-        if let Some(denoiser) = advanced_denoiser
-            && let Ok(mut d) = denoiser.lock()
-            && d.is_enabled()
-            && let Ok(processed) = d.process_frame(&output)
-        {
-            output = processed;
+    /// Drop any loaded or learned noise profile
+    pub fn clear_noise_profile(&mut self) {
+        self.noise_profile = None;
+        if let Some(ref mut denoiser) = self.advanced_denoiser {
+            denoiser.clear_noise_profile();
         }
+    }
+
+    /// Information about the loaded deep-learning model, if any
+    pub fn deep_learning_model_info(&self) -> Option<ModelInfo> {
+        self.advanced_denoiser
+            .as_ref()
+            .and_then(|d| d.deep_learning_model_info())
+    }
 
-        // Apply VST processing
-        if let Some(ref mut vst) = self.vst_processor {
-            output = vst.process(&output);
+    /// Load (or with `None`, unload) the ONNX model for the Deep Learning tier.
+    /// The model is kept for when the advanced denoiser is next created.
+    pub fn set_deep_learning_model(&mut self, model: Option<OnnxModelConfig>) -> Result<()> {
+        if let Some(ref mut denoiser) = self.advanced_denoiser {
+            denoiser.set_deep_learning_model(model.clone())?;
         }
+        self.deep_learning_model = model;
+        Ok(())
+    }
 
-        // Apply volume
-        for sample in &mut output {
-            *sample *= self.volume;
+    /// Run an instance of an already loaded model (or with `None`, none) in
+    /// the Deep Learning tier
+    pub fn use_deep_learning_model(&mut self, model: Option<&OnnxDenoiser>) {
+        self.deep_learning_model = model.map(|model| model.config().clone());
+        if let Some(ref mut denoiser) = self.advanced_denoiser {
+            denoiser.use_deep_learning_model(model.map(OnnxDenoiser::instance));
         }
+    }
 
-        // Apply panning (assuming stereo output)
-        let mut stereo_output = Vec::with_capacity(output.len() * 2);
-        for &sample in &output {
-            let left_gain = if self.pan <= 0.0 { 1.0 } else { 1.0 - self.pan };
-            let right_gain = if self.pan >= 0.0 { 1.0 } else { 1.0 + self.pan };
+    /// This channel's GhostWave instance, if it runs one
+    pub fn ghostwave(&self) -> Option<&GhostWaveIntegration> {
+        self.ghostwave.as_ref()
+    }
+
+    /// Mutable access to this channel's GhostWave instance for settings sync
+    pub fn ghostwave_mut(&mut self) -> Option<&mut GhostWaveIntegration> {
+        self.ghostwave.as_mut()
+    }
+
+    /// Tell the channel how many interleaved channels its input buffers hold,
+    /// so DSP load is measured against their real duration
+    pub fn set_input_channels(&mut self, channels: usize) {
+        self.input_channels = channels.max(1);
+    }
+
+    /// Frames in a buffer of `samples` interleaved input samples
+    fn frames(&self, samples: usize) -> usize {
+        samples / self.input_channels
+    }
 
-            stereo_output.push(sample * left_gain);
-            stereo_output.push(sample * right_gain);
+    /// The strip's signal from a device buffer, as interleaved samples and
+    /// their channel count: the first two device channels for a plugin with
+    /// stereo inputs, otherwise a mono downmix
+    fn strip_source(&self, input: &[f32]) -> (Vec<f32>, usize) {
+        let stereo_plugin = self
+            .plugin
+            .as_ref()
+            .is_some_and(|plugin| plugin.channel_layout().inputs >= 2);
+        if self.input_channels >= 2 && stereo_plugin {
+            let stereo = input
+                .chunks_exact(self.input_channels)
+                .flat_map(|frame| [frame[0], frame[1]])
+                .collect();
+            (stereo, 2)
+        } else {
+            (downmix(input, self.input_channels), 1)
         }
+    }
 
-        // Update VU meter and get levels
-        let (peak, rms) = self.vu_meter.process(&stereo_output, dt);
-        let levels = [peak, rms];
+    /// Time spent in this strip per buffer, in total and per stage
+    pub fn dsp_load(&self) -> &ChannelDspLoad {
+        &self.dsp_load
+    }
 
-        // Store levels for GUI access
-        self.last_levels = levels;
+    /// Tell the advanced denoiser how much of the callback budget is in use
+    pub fn set_callback_load(&mut self, load_percent: f32) {
+        if let Some(ref mut denoiser) = self.advanced_denoiser {
+            denoiser.set_callback_load(load_percent);
+        }
+    }
 
-        (stereo_output, levels)
+    pub fn process(&mut self, input: &[f32], dt: f32) -> (Vec<f32>, [f32; 2]) {
+        self.process_with_sidechain(input, None, dt)
     }
 
-    /// Process audio with GhostWave AI denoising (GhostWave -> VST chain order)
-    /// This is the recommended pipeline for NVIDIA Broadcast-quality processing
-    pub fn process_with_ghostwave(
+    /// Process one buffer of interleaved device channels, giving the plugin
+    /// `sidechain` (another channel's input) as its key signal. Output is
+    /// interleaved stereo, two samples per input frame.
+    pub fn process_with_sidechain(
         &mut self,
         input: &[f32],
-        ghostwave: Option<&mut GhostWaveIntegration>,
+        sidechain: Option<&[f32]>,
         dt: f32,
     ) -> (Vec<f32>, [f32; 2]) {
+        let frames = self.frames(input.len());
+        let buffer = buffer_duration(frames, self.sample_rate);
+        let start = std::time::Instant::now();
+
         if self.muted {
-            return (vec![0.0; input.len()], [0.0, 0.0]);
+            self.dsp_load.total.record(start.elapsed(), buffer);
+            return (vec![0.0; frames * 2], [0.0, 0.0]);
         }
 
-        let mut output = input.to_vec();
+        let mut stage_start = start;
+        let mut end_stage = |load: &mut ChannelDspLoad, stage: DspStage| {
+            let now = std::time::Instant::now();
+            load.record_stage(stage, now - stage_start, buffer);
+            stage_start = now;
+        };
+
+        let (mut output, mut channels) = self.strip_source(input);
 
         // 1. Apply input gain (pre-processing)
         let gain_linear = if self.gain >= 0.0 {
@@ -202,36 +596,58 @@ impl ChannelProcessor {
         for sample in &mut output {
             *sample *= gain_linear;
         }
+        end_stage(&mut self.dsp_load, DspStage::Gain);
 
-        // 2. Apply GhostWave AI denoising (RTX-accelerated)
+        // 2. Apply this channel's denoiser
         //    This runs BEFORE VST to give clean audio to subsequent effects
-        if let Some(gw) = ghostwave
-            && gw.is_enabled()
-            && let Err(e) = gw.process(&mut output)
-        {
-            log::trace!("GhostWave processing error: {}", e);
+        self.apply_denoiser(&mut output, channels);
+        end_stage(&mut self.dsp_load, DspStage::Denoiser);
+
+        // 3. Apply plugin processing chain (post-denoising). The plugin gets
+        //    the strip's channels planar; a stereo plugin makes the rest of
+        //    the strip stereo. Samples stay interleaved frames of `channels`
+        if self.plugin.is_some() {
+            let key = sidechain.map(|key| downmix(key, self.input_channels));
+            let planar = deinterleave(&output, channels);
+            let inputs: Vec<&[f32]> = planar.iter().map(Vec::as_slice).collect();
+            if let Some(ref mut plugin) = self.plugin {
+                let processed =
+                    plugin_host::process_strip(plugin.as_mut(), &inputs, key.as_deref());
+                channels = processed.len();
+                output = interleave(&processed);
+            }
         }
+        self.dsp_load.plugin_xruns = self.plugin.as_ref().map_or(0, |plugin| plugin.xruns());
+        end_stage(&mut self.dsp_load, DspStage::Vst);
 
-        // 3. Apply VST processing chain (post-denoising)
-        //    VST effects receive clean, denoised audio
-        if let Some(ref mut vst) = self.vst_processor {
-            output = vst.process(&output);
-        }
+        // 4. Apply dynamics effects chain (Gate -> Compressor -> Limiter)
+        self.effects.set_channels(channels);
+        self.effects.process(&mut output);
+        end_stage(&mut self.dsp_load, DspStage::Effects);
+
+        // Delay compensation against the slowest channel
+        self.compensation.process(&mut output);
 
-        // 4. Apply output volume
+        // 5. Apply output volume
         for sample in &mut output {
             *sample *= self.volume;
         }
 
-        // 5. Apply stereo panning
-        let mut stereo_output = Vec::with_capacity(output.len() * 2);
-        for &sample in &output {
-            let left_gain = if self.pan <= 0.0 { 1.0 } else { 1.0 - self.pan };
-            let right_gain = if self.pan >= 0.0 { 1.0 } else { 1.0 + self.pan };
-
-            stereo_output.push(sample * left_gain);
-            stereo_output.push(sample * right_gain);
-        }
+        // 6. Apply stereo panning; a stereo signal is balanced rather than
+        //    panned, so each side keeps its own content
+        let left_gain = if self.pan <= 0.0 { 1.0 } else { 1.0 - self.pan };
+        let right_gain = if self.pan >= 0.0 { 1.0 } else { 1.0 + self.pan };
+        let stereo_output: Vec<f32> = if channels == 2 {
+            output
+                .chunks_exact(2)
+                .flat_map(|frame| [frame[0] * left_gain, frame[1] * right_gain])
+                .collect()
+        } else {
+            output
+                .iter()
+                .flat_map(|&sample| [sample * left_gain, sample * right_gain])
+                .collect()
+        };
 
         // Update VU meter and get levels
         let (peak, rms) = self.vu_meter.process(&stereo_output, dt);
@@ -239,9 +655,101 @@ impl ChannelProcessor {
 
         // Store levels for GUI access
         self.last_levels = levels;
+        end_stage(&mut self.dsp_load, DspStage::Output);
+        self.dsp_load.total.record(start.elapsed(), buffer);
 
         (stereo_output, levels)
     }
+
+    /// Run the selected denoiser in place on the strip's `channels`
+    /// interleaved channels and record its cost for this buffer
+    fn apply_denoiser(&mut self, output: &mut Vec<f32>, channels: usize) {
+        if output.is_empty() {
+            return;
+        }
+
+        let start = std::time::Instant::now();
+
+        match self.denoiser {
+            ChannelDenoiser::Off => return,
+            ChannelDenoiser::Rnnoise => {
+                if self.rnnoise.is_enabled() {
+                    *output = self.rnnoise.process(output);
+                }
+            }
+            ChannelDenoiser::Advanced => {
+                if let Some(ref mut denoiser) = self.advanced_denoiser
+                    && denoiser.is_enabled()
+                {
+                    denoiser.set_input_channels(channels);
+                    if let Ok(processed) = denoiser.process_frame(output) {
+                        *output = processed;
+                    }
+                }
+            }
+            ChannelDenoiser::GhostWave => {
+                if let Some(ref mut gw) = self.ghostwave
+                    && gw.is_enabled()
+                    && let Err(e) = gw.process(output)
+                {
+                    log::trace!("GhostWave processing error: {}", e);
+                }
+            }
+        }
+
+        let processing_ms = start.elapsed().as_secs_f32() * 1000.0;
+        let buffer_ms = (output.len() / channels.max(1)) as f32 * 1000.0 / self.sample_rate as f32;
+        let delay_ms = self.denoiser_latency_samples() as f32 * 1000.0 / self.sample_rate as f32;
+
+        if let Some(ref denoiser) = self.advanced_denoiser {
+            let inner = denoiser.get_metrics();
+            self.denoise_metrics.memory_usage_mb = inner.memory_usage_mb;
+            self.denoise_metrics.quality_score = inner.quality_score;
+        }
+        self.denoise_metrics.latency_ms = delay_ms + processing_ms;
+        self.denoise_metrics.cpu_usage_percent = processing_ms / buffer_ms * 100.0;
+    }
+}
+
+/// Mono mix of interleaved frames
+fn downmix(samples: &[f32], channels: usize) -> Vec<f32> {
+    match channels {
+        0 | 1 => samples.to_vec(),
+        _ => samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect(),
+    }
+}
+
+/// Interleaved frames to planar channels
+fn deinterleave(samples: &[f32], channels: usize) -> Vec<Vec<f32>> {
+    if channels <= 1 {
+        return vec![samples.to_vec()];
+    }
+    (0..channels)
+        .map(|channel| {
+            samples
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+/// Planar channels to interleaved frames
+fn interleave(planar: &[Vec<f32>]) -> Vec<f32> {
+    match planar {
+        [mono] => mono.clone(),
+        _ => {
+            let frames = planar.first().map_or(0, Vec::len);
+            (0..frames)
+                .flat_map(|frame| planar.iter().map(move |channel| channel[frame]))
+                .collect()
+        }
+    }
 }
 
 /// Main audio processing engine with denoising and GhostWave integration
@@ -249,15 +757,14 @@ pub struct AudioEngine {
     input_stream: Option<Stream>,
     output_stream: Option<Stream>,
     channels: Arc<Mutex<Vec<ChannelProcessor>>>,
-    rnnoise: Arc<Mutex<Rnnoise>>, // Keep for backward compatibility
-    advanced_denoiser: Option<SharedAdvancedDenoiser>,
     spectrum_analyzer: Arc<Mutex<SpectrumAnalyzer>>,
     spectrum_data: Arc<Mutex<Vec<f32>>>,
     #[allow(dead_code)] // For future inter-thread audio routing
     audio_sender: Option<Sender<Vec<f32>>>,
     #[allow(dead_code)] // For future inter-thread audio routing
     audio_receiver: Option<Receiver<Vec<f32>>>,
-    // GhostWave Integration
+    // GhostWave Integration: this shared instance holds the user-facing settings
+    // and aggregate status; each channel processes through its own instance
     #[allow(dead_code)] // GhostWave integration for RTX denoising
     ghostwave: Option<Arc<Mutex<GhostWaveIntegration>>>,
     #[allow(dead_code)] // GhostWave enable flag
@@ -268,35 +775,31 @@ pub struct AudioEngine {
     buffer_size: usize,
     /// Audio routing matrix (channel → output with gain)
     router: Arc<Mutex<AudioRouter>>,
+    /// Load of the whole input callback
+    dsp_load: Arc<Mutex<LoadMeter>>,
+    /// Loudness AGC and true-peak limiter per output bus, in `OUTPUT_BUSES` order
+    bus_agc: Arc<Mutex<Vec<BusAgc>>>,
+    /// EBU R128 meter per output bus, in `OUTPUT_BUSES` order
+    loudness: Arc<Mutex<Vec<LoudnessMeter>>>,
+    /// Multitrack recorder fed from the input callback
+    recorder: Recorder,
+    /// Layout of the running input stream
+    stream_layout: Option<StreamLayout>,
+    /// Replay buffer settings, applied whenever the engine starts
+    replay_config: Option<RecorderConfig>,
+    /// Measured hardware round trip, when the user has calibrated
+    latency_calibration: Option<LatencyCalibration>,
+    /// Whether channel plugins load in child processes
+    plugin_sandbox: SandboxConfig,
 }
 
 impl AudioEngine {
     pub fn new() -> Self {
-        let mut channels_vec = Vec::with_capacity(CHANNEL_COUNT);
-        for _ in 0..CHANNEL_COUNT {
-            channels_vec.push(ChannelProcessor::new());
-        }
-        let channels = Arc::new(Mutex::new(channels_vec));
-        let rnnoise = Arc::new(Mutex::new(Rnnoise::new()));
         let spectrum_analyzer = Arc::new(Mutex::new(SpectrumAnalyzer::new(48000.0)));
         let spectrum_data = Arc::new(Mutex::new(vec![0.0; 512]));
 
         let (audio_sender, audio_receiver) = crossbeam_channel::bounded(1024);
 
-        // Initialize advanced denoising system
-        let advanced_denoiser = match create_advanced_denoiser(AdvancedDenoisingConfig::default()) {
-            Ok(denoiser) => {
-                if let Ok(mut d) = denoiser.lock() {
-                    d.set_enabled(true);
-                }
-                Some(denoiser)
-            }
-            Err(e) => {
-                eprintln!("Failed to initialize advanced denoiser: {}", e);
-                None
-            }
-        };
-
         // Initialize GhostWave integration
         let ghostwave = match GhostWaveIntegration::new() {
             Ok(gw) => {
@@ -312,12 +815,36 @@ impl AudioEngine {
             }
         };
 
+        // Priority: GhostWave (RTX) -> Advanced Denoiser -> no denoising
+        let mut channels_vec = Vec::with_capacity(CHANNEL_COUNT);
+        for idx in 0..CHANNEL_COUNT {
+            let mut channel = ChannelProcessor::new();
+            let preferred = if ghostwave.is_some() {
+                ChannelDenoiser::GhostWave
+            } else {
+                ChannelDenoiser::Advanced
+            };
+            if let Err(e) = channel.set_denoiser(preferred) {
+                log::warn!(
+                    "Failed to initialize {} denoiser for channel {}: {}",
+                    preferred.name(),
+                    idx,
+                    e
+                );
+                if preferred == ChannelDenoiser::GhostWave
+                    && let Err(e) = channel.set_denoiser(ChannelDenoiser::Advanced)
+                {
+                    log::warn!("Failed to initialize advanced denoiser: {}", e);
+                }
+            }
+            channels_vec.push(channel);
+        }
+        let channels = Arc::new(Mutex::new(channels_vec));
+
         Self {
             input_stream: None,
             output_stream: None,
             channels,
-            rnnoise,
-            advanced_denoiser,
             spectrum_analyzer,
             spectrum_data,
             audio_sender: Some(audio_sender),
@@ -327,6 +854,30 @@ impl AudioEngine {
             current_profile: PhantomLinkProfile::Balanced,
             buffer_size: BUFFER_SIZE,
             router: Arc::new(Mutex::new(AudioRouter::new())),
+            dsp_load: Arc::new(Mutex::new(LoadMeter::default())),
+            bus_agc: Arc::new(Mutex::new(
+                OUTPUT_BUSES
+                    .iter()
+                    .map(|_| {
+                        BusAgc::new(
+                            AgcConfig::default(),
+                            DEFAULT_SAMPLE_RATE as u32,
+                            BUS_CHANNELS,
+                        )
+                    })
+                    .collect(),
+            )),
+            loudness: Arc::new(Mutex::new(
+                OUTPUT_BUSES
+                    .iter()
+                    .map(|_| LoudnessMeter::new(DEFAULT_SAMPLE_RATE as u32, BUS_CHANNELS))
+                    .collect(),
+            )),
+            recorder: Recorder::new(),
+            stream_layout: None,
+            replay_config: None,
+            latency_calibration: None,
+            plugin_sandbox: SandboxConfig::default(),
         }
     }
 
@@ -341,14 +892,18 @@ impl AudioEngine {
         }
     }
 
-    /// Enable or disable legacy RNNoise denoising
+    /// Enable or disable legacy RNNoise on every channel that is not
+    /// already running another denoiser
     #[allow(dead_code)] // API for noise suppression toggle
     pub fn set_rnnoise_enabled(&self, enabled: bool) {
-        if let Ok(mut rnnoise) = self.rnnoise.lock() {
-            if enabled {
-                rnnoise.enable();
-            } else {
-                rnnoise.disable();
+        if let Ok(mut channels) = self.channels.lock() {
+            for channel in channels.iter_mut() {
+                let target = match (enabled, channel.denoiser()) {
+                    (true, ChannelDenoiser::Off) => ChannelDenoiser::Rnnoise,
+                    (false, ChannelDenoiser::Rnnoise) => ChannelDenoiser::Off,
+                    _ => continue,
+                };
+                let _ = channel.set_denoiser(target);
             }
         }
     }
@@ -356,70 +911,319 @@ impl AudioEngine {
     // Advanced denoising methods
     #[allow(dead_code)] // API for denoising settings UI
     pub fn set_denoising_mode(&self, mode: DenoisingMode) -> Result<()> {
-        if let Some(ref denoiser) = self.advanced_denoiser
-            && let Ok(mut d) = denoiser.lock()
-        {
-            d.set_mode(mode)?;
+        if let Ok(mut channels) = self.channels.lock() {
+            for channel in channels.iter_mut() {
+                channel.set_denoising_mode(mode.clone())?;
+            }
         }
         Ok(())
     }
 
-    /// Get current denoising mode
+    /// Get current denoising mode (of the first channel)
     #[allow(dead_code)] // API for denoising settings UI
     pub fn get_denoising_mode(&self) -> Option<DenoisingMode> {
-        if let Some(ref denoiser) = self.advanced_denoiser
-            && let Ok(d) = denoiser.lock()
-        {
-            return Some(d.get_mode());
-        }
-        None
+        self.get_channel_denoising_mode(0)
     }
 
     /// Enable or disable advanced denoising
     pub fn set_advanced_denoising_enabled(&self, enabled: bool) {
-        if let Some(ref denoiser) = self.advanced_denoiser
-            && let Ok(mut d) = denoiser.lock()
-        {
-            d.set_enabled(enabled);
+        if let Ok(mut channels) = self.channels.lock() {
+            for channel in channels.iter_mut() {
+                channel.set_advanced_denoising_enabled(enabled);
+            }
         }
     }
 
-    /// Check if advanced denoising is currently active
+    /// Check if advanced denoising is currently active on any channel
     #[allow(dead_code)] // API for status display
     pub fn is_advanced_denoising_enabled(&self) -> bool {
-        if let Some(ref denoiser) = self.advanced_denoiser
-            && let Ok(d) = denoiser.lock()
-        {
-            return d.is_enabled();
-        }
-        false
+        self.channels
+            .lock()
+            .is_ok_and(|channels| channels.iter().any(|c| c.is_advanced_denoising_enabled()))
     }
 
-    /// Get denoising metrics for display
+    /// Get denoising metrics summed over all channels for display
     #[allow(dead_code)] // API for metrics panel
-    pub fn get_denoising_metrics(&self) -> Option<crate::advanced_denoising::DenoisingMetrics> {
-        if let Some(ref denoiser) = self.advanced_denoiser
-            && let Ok(d) = denoiser.lock()
-        {
-            return Some(d.get_metrics());
+    pub fn get_denoising_metrics(&self) -> Option<DenoisingMetrics> {
+        let channels = self.channels.lock().ok()?;
+        let active: Vec<&DenoisingMetrics> = channels
+            .iter()
+            .filter(|c| c.denoiser() != ChannelDenoiser::Off)
+            .map(|c| c.denoise_metrics())
+            .collect();
+        if active.is_empty() {
+            return None;
         }
-        None
+
+        Some(DenoisingMetrics {
+            latency_ms: active.iter().map(|m| m.latency_ms).fold(0.0, f32::max),
+            cpu_usage_percent: active.iter().map(|m| m.cpu_usage_percent).sum(),
+            memory_usage_mb: active.iter().map(|m| m.memory_usage_mb).sum(),
+            quality_score: active.iter().map(|m| m.quality_score).sum::<f32>()
+                / active.len() as f32,
+            rnnoise_load_percent: active.iter().map(|m| m.rnnoise_load_percent).sum(),
+            deep_learning_load_percent: active.iter().map(|m| m.deep_learning_load_percent).sum(),
+            spectral_load_percent: active.iter().map(|m| m.spectral_load_percent).sum(),
+        })
+    }
+
+    /// Get the DSP load of one channel strip
+    pub fn get_channel_dsp_load(&self, channel_idx: usize) -> Option<ChannelDspLoad> {
+        let channels = self.channels.lock().ok()?;
+        channels.get(channel_idx).map(|c| c.dsp_load().clone())
+    }
+
+    /// Get the load of the input callback and of each channel strip
+    pub fn get_dsp_load(&self) -> DspLoadReport {
+        DspLoadReport {
+            callback: self
+                .dsp_load
+                .lock()
+                .map_or_else(|_| LoadMeter::default(), |m| *m),
+            channels: self.channels.lock().map_or_else(
+                |_| Vec::new(),
+                |channels| channels.iter().map(|c| c.dsp_load().clone()).collect(),
+            ),
+        }
+    }
+
+    /// Get the delay added by the slowest channel denoiser, in samples at the input rate
+    #[allow(dead_code)] // API for delay compensation
+    pub fn get_denoiser_latency_samples(&self) -> usize {
+        self.channels.lock().map_or(0, |channels| {
+            channels
+                .iter()
+                .map(|c| c.denoiser_latency_samples())
+                .max()
+                .unwrap_or(0)
+        })
+    }
+
+    /// Get the delay of every channel strip, in samples at the input rate
+    pub fn get_channel_latency_samples(&self) -> Vec<usize> {
+        self.channels.lock().map_or_else(
+            |_| Vec::new(),
+            |channels| channels.iter().map(|c| c.latency_samples()).collect(),
+        )
+    }
+
+    /// Input-to-output latency, stage by stage
+    pub fn get_latency_report(&self) -> LatencyReport {
+        let channels = self.channels.lock().map_or_else(
+            |_| Vec::new(),
+            |channels| channels.iter().map(|c| c.latency()).collect(),
+        );
+        let buses = self.bus_agc.lock().map_or_else(
+            |_| Vec::new(),
+            |agcs| {
+                OUTPUT_BUSES
+                    .iter()
+                    .zip(agcs.iter())
+                    .map(|(bus, agc)| BusLatency {
+                        bus: bus.to_string(),
+                        samples: agc.latency_samples(),
+                    })
+                    .collect()
+            },
+        );
+        let sample_rate = self
+            .stream_layout
+            .map_or(DEFAULT_SAMPLE_RATE as u32, |layout| layout.sample_rate);
+        let measured = self
+            .latency_calibration
+            .map(|calibration| calibration.samples_at(sample_rate));
+        LatencyReport::new(sample_rate, self.buffer_size, channels, buses, measured)
+    }
+
+    /// Use a measured round trip for latency reports and recorder markers
+    pub fn set_latency_calibration(&mut self, calibration: Option<LatencyCalibration>) {
+        self.latency_calibration = calibration;
+        self.apply_marker_offset();
+    }
+
+    pub fn get_latency_calibration(&self) -> Option<LatencyCalibration> {
+        self.latency_calibration
+    }
+
+    fn apply_marker_offset(&mut self) {
+        let offset = match (self.latency_calibration, self.stream_layout) {
+            (Some(calibration), Some(layout)) => calibration.samples_at(layout.sample_rate),
+            _ => 0,
+        };
+        self.recorder.set_marker_offset(offset as u64);
+    }
+
+    /// Get the limiter lookahead delay of every channel, in milliseconds
+    pub fn get_limiter_latency_ms(&self) -> Vec<f32> {
+        self.channels.lock().map_or_else(
+            |_| Vec::new(),
+            |channels| channels.iter().map(|c| c.limiter_latency_ms()).collect(),
+        )
+    }
+
+    /// Get the limiter gain reduction of every channel, in dB
+    pub fn get_limiter_gain_reduction(&self) -> Vec<f32> {
+        self.channels.lock().map_or_else(
+            |_| Vec::new(),
+            |channels| {
+                channels
+                    .iter()
+                    .map(|c| c.limiter_gain_reduction_db())
+                    .collect()
+            },
+        )
     }
 
     /// Get list of available denoising modes
     #[allow(dead_code)] // API for mode selector UI
     pub fn get_available_denoising_modes(&self) -> Vec<DenoisingMode> {
-        if let Some(ref denoiser) = self.advanced_denoiser
-            && denoiser.lock().is_ok()
+        vec![
+            DenoisingMode::Basic,
+            DenoisingMode::Enhanced,
+            DenoisingMode::Maximum,
+        ]
+    }
+
+    // Per-channel denoising methods
+
+    /// Select the denoiser for one channel
+    pub fn set_channel_denoiser(
+        &self,
+        channel_idx: usize,
+        denoiser: ChannelDenoiser,
+    ) -> Result<()> {
+        let setup = self
+            .channels
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Channel state lock poisoned"))?
+            .get(channel_idx)
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel index: {}", channel_idx))?
+            .denoiser_setup(denoiser);
+        // Creating a denoiser can take a while; keep the callback running meanwhile
+        let prepared = setup.build()?;
+        let mut channels = self
+            .channels
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Channel state lock poisoned"))?;
+        let channel = channels
+            .get_mut(channel_idx)
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel index: {}", channel_idx))?;
+        channel.install_denoiser(prepared);
+        Ok(())
+    }
+
+    /// Get the denoiser selected for one channel
+    pub fn get_channel_denoiser(&self, channel_idx: usize) -> Option<ChannelDenoiser> {
+        let channels = self.channels.lock().ok()?;
+        channels.get(channel_idx).map(|c| c.denoiser())
+    }
+
+    /// Set the advanced denoising mode for one channel
+    pub fn set_channel_denoising_mode(
+        &self,
+        channel_idx: usize,
+        mode: DenoisingMode,
+    ) -> Result<()> {
+        let mut channels = self
+            .channels
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Channel state lock poisoned"))?;
+        let channel = channels
+            .get_mut(channel_idx)
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel index: {}", channel_idx))?;
+        channel.set_denoising_mode(mode)
+    }
+
+    /// Get the advanced denoising mode for one channel
+    pub fn get_channel_denoising_mode(&self, channel_idx: usize) -> Option<DenoisingMode> {
+        let channels = self.channels.lock().ok()?;
+        channels.get(channel_idx).map(|c| c.denoising_mode())
+    }
+
+    /// Get denoiser metrics for one channel
+    pub fn get_channel_denoising_metrics(&self, channel_idx: usize) -> Option<DenoisingMetrics> {
+        let channels = self.channels.lock().ok()?;
+        channels
+            .get(channel_idx)
+            .map(|c| c.denoise_metrics().clone())
+    }
+
+    /// Load an ONNX model into every channel's Deep Learning tier, or unload it
+    /// with `None`. Channels keep their previous model if loading fails.
+    pub fn set_deep_learning_model(&self, model: Option<OnnxModelConfig>) -> Result<()> {
+        // Loading takes a while; do it once, without holding up the callback
+        let loaded = model.map(OnnxDenoiser::load).transpose()?;
+        let mut channels = self
+            .channels
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Channel state lock poisoned"))?;
+        if let Some(ref loaded) = loaded
+            && let Some(channel) = channels
+                .iter()
+                .find(|channel| channel.sample_rate != loaded.config().sample_rate)
         {
-            // Return basic modes since we can't downcast the trait object
-            return vec![
-                DenoisingMode::Basic,
-                DenoisingMode::Enhanced,
-                DenoisingMode::Maximum,
-            ];
+            anyhow::bail!(
+                "model runs at {} Hz but the stream runs at {} Hz",
+                loaded.config().sample_rate,
+                channel.sample_rate
+            );
+        }
+        for channel in channels.iter_mut() {
+            channel.use_deep_learning_model(loaded.as_ref());
+        }
+        Ok(())
+    }
+
+    /// Information about the deep-learning model loaded on a channel
+    pub fn get_channel_deep_learning_model_info(&self, channel_idx: usize) -> Option<ModelInfo> {
+        let channels = self.channels.lock().ok()?;
+        channels.get(channel_idx)?.deep_learning_model_info()
+    }
+
+    /// Capture `seconds` of room tone on one channel as its noise profile
+    pub fn start_channel_noise_learning(&self, channel_idx: usize, seconds: f32) -> Result<()> {
+        let mut channels = self
+            .channels
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Channel state lock poisoned"))?;
+        let channel = channels
+            .get_mut(channel_idx)
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel index: {}", channel_idx))?;
+        channel.start_noise_learning(seconds)
+    }
+
+    /// Noise learning progress for one channel (0.0-1.0), None when idle
+    pub fn get_channel_noise_learning_progress(&self, channel_idx: usize) -> Option<f32> {
+        let channels = self.channels.lock().ok()?;
+        channels.get(channel_idx)?.noise_learning_progress()
+    }
+
+    /// Get the learned or loaded noise profile for one channel
+    pub fn get_channel_noise_profile(&self, channel_idx: usize) -> Option<NoiseProfile> {
+        let channels = self.channels.lock().ok()?;
+        channels.get(channel_idx)?.noise_profile()
+    }
+
+    /// Load a saved noise profile into one channel, or clear it with `None`
+    pub fn set_channel_noise_profile(
+        &self,
+        channel_idx: usize,
+        profile: Option<NoiseProfile>,
+    ) -> Result<()> {
+        let mut channels = self
+            .channels
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Channel state lock poisoned"))?;
+        let channel = channels
+            .get_mut(channel_idx)
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel index: {}", channel_idx))?;
+        match profile {
+            Some(profile) => channel.set_noise_profile(profile),
+            None => {
+                channel.clear_noise_profile();
+                Ok(())
+            }
         }
-        vec![DenoisingMode::Basic] // Fallback to basic mode
     }
 
     /// Get a reference to the audio router for configuration
@@ -461,26 +1265,58 @@ impl AudioEngine {
         println!("Input config: {:?}", input_config);
         println!("Output config: {:?}", output_config);
 
+        // Denoisers buffer internally, so they need the real device rate to
+        // resample correctly and start from a clean FIFO on every (re)start
+        if let Ok(mut channels) = self.channels.lock() {
+            for channel in channels.iter_mut() {
+                channel.set_sample_rate(input_config.sample_rate);
+                channel.set_input_channels(input_config.channels as usize);
+            }
+        }
+
         // Create shared audio buffer for routing between input and output
         let audio_buffer = Arc::new(Mutex::new(VecDeque::<f32>::with_capacity(BUFFER_SIZE * 4)));
         let audio_buffer_out = Arc::clone(&audio_buffer);
 
         let channels = Arc::clone(&self.channels);
-        let rnnoise = Arc::clone(&self.rnnoise);
-        let advanced_denoiser = self.advanced_denoiser.clone();
         let ghostwave = self.ghostwave.clone();
         let use_ghostwave = self.use_ghostwave;
         let spectrum_analyzer: Arc<Mutex<SpectrumAnalyzer>> = Arc::clone(&self.spectrum_analyzer);
         let spectrum_data: Arc<Mutex<Vec<f32>>> = Arc::clone(&self.spectrum_data);
         let router = Arc::clone(&self.router);
+        let dsp_load = Arc::clone(&self.dsp_load);
+        if let Ok(mut meter) = dsp_load.lock() {
+            *meter = LoadMeter::default();
+        }
+        let input_channels = (input_config.channels as usize).max(1);
+        let input_rate = input_config.sample_rate;
+        let recorder_tap = self.recorder.tap();
+        let bus_agc = Arc::clone(&self.bus_agc);
+        if let Ok(mut agcs) = bus_agc.lock() {
+            for agc in agcs.iter_mut() {
+                *agc = BusAgc::new(agc.config().clone(), input_rate, BUS_CHANNELS);
+            }
+        }
+        let loudness = Arc::clone(&self.loudness);
+        if let Ok(mut meters) = loudness.lock() {
+            for meter in meters.iter_mut() {
+                *meter = LoudnessMeter::new(input_rate, BUS_CHANNELS);
+            }
+        }
 
         // Input stream: capture and process audio
         let input_stream = input_device.build_input_stream(
             &input_config,
             move |data: &[f32], _| {
-                // Process input through all channels and mix
-                let mut mixed_output = vec![0.0; data.len()];
+                let callback_start = std::time::Instant::now();
+                let callback_load = dsp_load.lock().map_or(0.0, |m| m.average_percent);
+
+                // Process input through all channels and mix into stereo frames
+                let frames = data.len() / input_channels;
+                let mut mixed_output = vec![0.0; frames * BUS_CHANNELS];
                 let mut total_levels = [0.0f32; 2];
+                // Per-channel processed audio for routing and recording
+                let mut channel_outputs: Vec<Vec<f32>> = Vec::with_capacity(CHANNEL_COUNT);
 
                 // Get GhostWave lock outside the channel loop for efficiency
                 let mut gw_guard = if use_ghostwave {
@@ -490,20 +1326,38 @@ impl AudioEngine {
                 };
 
                 if let Ok(mut channels) = channels.lock() {
-                    // Collect per-channel processed audio for routing
-                    let mut channel_outputs: Vec<Vec<f32>> = Vec::with_capacity(channels.len());
-
-                    for channel in channels.iter_mut() {
-                        // Priority: GhostWave (RTX) -> Advanced Denoiser -> Legacy RNNoise
-                        let (processed, levels) = if let Some(ref mut gw) = gw_guard {
-                            channel.process_with_ghostwave(data, Some(gw), 0.02)
-                        } else if advanced_denoiser.is_some() {
-                            channel.process_advanced(data, advanced_denoiser.as_ref(), 0.02)
-                        } else if let Ok(rnnoise) = rnnoise.lock() {
-                            channel.process(data, &rnnoise, 0.02)
-                        } else {
-                            channel.process_advanced(data, None, 0.02)
-                        };
+                    // Line all channels up with the slowest before they are mixed
+                    let processing: Vec<usize> =
+                        channels.iter().map(|c| c.latency_samples()).collect();
+                    for (channel, samples) in channels.iter_mut().zip(compensation_for(&processing))
+                    {
+                        channel.set_compensation(samples);
+                    }
+
+                    // Inputs are gathered first so any channel can key another's plugin
+                    let generated: Vec<Option<Vec<f32>>> = channels
+                        .iter_mut()
+                        .map(|channel| channel.generate_input(data.len(), input_channels))
+                        .collect();
+                    let inputs: Vec<&[f32]> = generated
+                        .iter()
+                        .map(|generated| generated.as_deref().unwrap_or(data))
+                        .collect();
+
+                    for (index, channel) in channels.iter_mut().enumerate() {
+                        // Per-channel GhostWave instances follow the shared settings
+                        if let Some(ref control) = gw_guard
+                            && let Some(gw) = channel.ghostwave_mut()
+                        {
+                            gw.sync_settings_from(control);
+                        }
+
+                        channel.set_callback_load(callback_load);
+                        let sidechain = channel
+                            .sidechain_source()
+                            .and_then(|source| inputs.get(source).copied());
+                        let (processed, levels) =
+                            channel.process_with_sidechain(inputs[index], sidechain, 0.02);
 
                         channel_outputs.push(processed);
 
@@ -512,6 +1366,16 @@ impl AudioEngine {
                         total_levels[1] += levels[1] / CHANNEL_COUNT as f32; // RMS average
                     }
 
+                    // Report aggregate GhostWave metrics through the shared instance
+                    if let Some(ref mut control) = gw_guard {
+                        control.aggregate_metrics(
+                            channels
+                                .iter()
+                                .filter_map(|c| c.ghostwave())
+                                .map(|g| g.get_metrics()),
+                        );
+                    }
+
                     // Mix channels: use AudioRouter if routes are configured, otherwise average
                     let use_router = router.lock().is_ok_and(|r| !r.get_routes().is_empty());
 
@@ -521,7 +1385,8 @@ impl AudioEngine {
                                 (0..channel_outputs.len())
                                     .map(|i| (format!("ch{}", i), i))
                                     .collect();
-                            let output_names = vec!["master".to_string()];
+                            let output_names: Vec<String> =
+                                OUTPUT_BUSES.iter().map(|b| b.to_string()).collect();
                             let routed = r.apply_routing(&channel_outputs, &channel_map, &output_names);
                             if let Some(master) = routed.first() {
                                 let copy_len = mixed_output.len().min(master.len());
@@ -540,6 +1405,28 @@ impl AudioEngine {
                     }
                 }
 
+                // Master is the only bus mixed so far
+                if let Ok(mut agcs) = bus_agc.lock() {
+                    for (agc, output) in agcs.iter_mut().zip([&mut mixed_output]) {
+                        agc.process_interleaved(output);
+                    }
+                }
+                let bus_outputs: [&[f32]; 1] = [&mixed_output];
+
+                if let Ok(mut meters) = loudness.lock() {
+                    for (meter, output) in meters.iter_mut().zip(bus_outputs) {
+                        meter.process_interleaved(output);
+                    }
+                }
+
+                if recorder_tap.is_active() {
+                    recorder_tap.push(RecordBlock {
+                        input: data.to_vec(),
+                        channels: channel_outputs,
+                        buses: bus_outputs.iter().map(|b| b.to_vec()).collect(),
+                    });
+                }
+
                 // Update spectrum analyzer
                 if let Ok(mut analyzer) = spectrum_analyzer.lock() {
                     let spectrum = analyzer.process(&mixed_output);
@@ -561,6 +1448,15 @@ impl AudioEngine {
                         }
                     }
                 }
+
+                transport::advance(frames, input_rate as f64);
+
+                if let Ok(mut meter) = dsp_load.lock() {
+                    meter.record(
+                        callback_start.elapsed(),
+                        buffer_duration(frames, input_rate),
+                    );
+                }
             },
             move |err| {
                 eprintln!("Input stream error: {}", err);
@@ -597,12 +1493,27 @@ impl AudioEngine {
         // Store streams to keep them alive
         self.input_stream = Some(input_stream);
         self.output_stream = Some(output_stream);
+        self.stream_layout = Some(StreamLayout {
+            sample_rate: input_rate,
+            input_channels: input_channels as u16,
+        });
+        if let Err(e) = self.apply_replay_buffer() {
+            log::warn!("Failed to start replay buffer: {}", e);
+        }
+        self.apply_marker_offset();
 
         println!("Audio engine started successfully!");
         Ok(())
     }
 
     pub fn stop(&mut self) {
+        if self.recorder.is_recording()
+            && let Err(e) = self.recorder.stop()
+        {
+            log::error!("Failed to finish recording: {}", e);
+        }
+        self.recorder.stop_replay();
+        self.stream_layout = None;
         if let Some(input_stream) = self.input_stream.take() {
             let _ = input_stream.pause();
         }
@@ -612,6 +1523,122 @@ impl AudioEngine {
         println!("Audio engine stopped");
     }
 
+    /// Momentary, short-term and integrated loudness, loudness range and
+    /// true peak of every output bus
+    pub fn get_bus_loudness(&self) -> Vec<BusLoudness> {
+        self.loudness
+            .lock()
+            .map(|meters| {
+                OUTPUT_BUSES
+                    .iter()
+                    .zip(meters.iter())
+                    .map(|(bus, meter)| BusLoudness {
+                        bus: bus.to_string(),
+                        reading: meter.reading(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Restart integrated loudness, range and peak measurement on every bus
+    pub fn reset_loudness(&self) {
+        if let Ok(mut meters) = self.loudness.lock() {
+            for meter in meters.iter_mut() {
+                meter.reset();
+            }
+        }
+    }
+
+    /// Configure the loudness AGC of an output bus
+    pub fn set_bus_agc(&self, bus: &str, config: AgcConfig) -> Result<()> {
+        let Some(index) = OUTPUT_BUSES.iter().position(|b| *b == bus) else {
+            anyhow::bail!("Unknown output bus: {}", bus);
+        };
+        let mut agcs = self
+            .bus_agc
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Failed to lock bus AGC"))?;
+        agcs[index].set_config(config);
+        Ok(())
+    }
+
+    pub fn get_bus_agc(&self, bus: &str) -> Option<AgcConfig> {
+        let index = OUTPUT_BUSES.iter().position(|b| *b == bus)?;
+        self.bus_agc
+            .lock()
+            .ok()
+            .map(|agcs| agcs[index].config().clone())
+    }
+
+    /// Gain, voice activity and limiting of every bus AGC
+    pub fn get_agc_status(&self) -> Vec<AgcStatus> {
+        self.bus_agc
+            .lock()
+            .map(|agcs| {
+                OUTPUT_BUSES
+                    .iter()
+                    .zip(agcs.iter())
+                    .map(|(bus, agc)| agc.status(bus))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Start recording the armed channels and buses, returning the new
+    /// session directory
+    pub fn start_recording(&mut self, config: &RecorderConfig) -> Result<PathBuf> {
+        let layout = self
+            .stream_layout
+            .ok_or_else(|| anyhow::anyhow!("Start the audio engine before recording"))?;
+        self.recorder.start(config, layout, OUTPUT_BUSES)
+    }
+
+    pub fn stop_recording(&mut self) -> Result<RecordingSummary> {
+        self.recorder.stop()
+    }
+
+    /// Drop a marker at the current recording position
+    pub fn add_recording_marker(&self, label: Option<String>) -> Result<()> {
+        self.recorder.add_marker(label)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_recording()
+    }
+
+    pub fn get_recorder_status(&self) -> RecorderStatus {
+        self.recorder.status()
+    }
+
+    /// Keep the last `replay_seconds` of the master bus and replay channel in
+    /// memory whenever the engine runs, if `replay_enabled` is set
+    pub fn set_replay_buffer(&mut self, config: &RecorderConfig) -> Result<()> {
+        self.replay_config = config.replay_enabled.then(|| config.clone());
+        self.apply_replay_buffer()
+    }
+
+    fn apply_replay_buffer(&mut self) -> Result<()> {
+        match (&self.replay_config, self.stream_layout) {
+            (Some(config), Some(layout)) => {
+                self.recorder.start_replay(config, layout, OUTPUT_BUSES)
+            }
+            _ => {
+                self.recorder.stop_replay();
+                Ok(())
+            }
+        }
+    }
+
+    pub fn is_replay_buffer_enabled(&self) -> bool {
+        self.recorder.is_replay_enabled()
+    }
+
+    /// Write the replay buffer to disk ("save the last N seconds")
+    pub fn save_replay(&self) -> Result<ReplaySummary> {
+        self.recorder.save_replay()
+    }
+
     /// Check if audio streams are running
     #[allow(dead_code)] // API for status display
     pub fn is_running(&self) -> bool {
@@ -629,6 +1656,21 @@ impl AudioEngine {
         {
             g.set_enabled(enabled);
         }
+
+        // Move channels between GhostWave and the advanced denoiser as a group;
+        // channels explicitly set to Off or RNNoise are left alone
+        if let Ok(mut channels) = self.channels.lock() {
+            for channel in channels.iter_mut() {
+                let target = match (self.use_ghostwave, channel.denoiser()) {
+                    (true, ChannelDenoiser::Advanced) => ChannelDenoiser::GhostWave,
+                    (false, ChannelDenoiser::GhostWave) => ChannelDenoiser::Advanced,
+                    _ => continue,
+                };
+                if let Err(e) = channel.set_denoiser(target) {
+                    log::warn!("Failed to switch channel denoiser: {}", e);
+                }
+            }
+        }
     }
 
     /// Check if GhostWave is enabled
@@ -732,14 +1774,267 @@ impl AudioEngine {
         None
     }
 
-    /// Set VST plugin for a channel
-    #[allow(dead_code)] // API for VST plugin management
-    pub fn set_channel_vst(&self, channel_idx: usize, vst_processor: Option<VstProcessor>) {
+    /// Feed a channel from the signal generator, or None for its input
+    pub fn set_channel_generator(
+        &self,
+        channel_idx: usize,
+        config: Option<SignalGeneratorConfig>,
+    ) -> Result<()> {
+        let mut channels = self
+            .channels
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Channel state lock poisoned"))?;
+        let channel = channels
+            .get_mut(channel_idx)
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel index: {}", channel_idx))?;
+        channel.set_generator(config);
+        Ok(())
+    }
+
+    /// Generator settings of a channel, None when it plays its input
+    pub fn get_channel_generator(&self, channel_idx: usize) -> Option<SignalGeneratorConfig> {
+        let channels = self.channels.lock().ok()?;
+        channels.get(channel_idx)?.generator().cloned()
+    }
+
+    /// Set the effect plugin for a channel
+    #[allow(dead_code)] // API for plugin management
+    pub fn set_channel_plugin(&self, channel_idx: usize, plugin: Option<Box<dyn PluginProcessor>>) {
+        if let Ok(mut channels) = self.channels.lock()
+            && let Some(channel) = channels.get_mut(channel_idx)
+        {
+            channel.plugin = plugin;
+            channel.plugin_info = None;
+        }
+    }
+
+    /// Load a catalogue entry into a channel, or clear it. The plugin is
+    /// loaded before the channel lock is taken, sandboxed if enabled.
+    pub fn load_channel_plugin(
+        &self,
+        channel_idx: usize,
+        info: Option<&VstPluginInfo>,
+    ) -> Result<()> {
+        let Some(sample_rate) = self
+            .channels
+            .lock()
+            .ok()
+            .and_then(|channels| Some(channels.get(channel_idx)?.sample_rate as f32))
+        else {
+            anyhow::bail!("Invalid channel index: {}", channel_idx);
+        };
+        let sandbox = self.plugin_sandbox;
+        let plugin = info
+            .map(|info| -> Result<Box<dyn PluginProcessor>> {
+                if sandbox.enabled {
+                    Ok(Box::new(BridgedProcessor::load(
+                        info,
+                        sandbox.on_crash,
+                        sample_rate,
+                    )?))
+                } else {
+                    plugin_host::load_plugin(info, sample_rate)
+                }
+            })
+            .transpose()?;
         if let Ok(mut channels) = self.channels.lock()
             && let Some(channel) = channels.get_mut(channel_idx)
         {
-            channel.vst_processor = vst_processor;
+            channel.plugin = plugin;
+            channel.plugin_info = info.cloned();
+        }
+        Ok(())
+    }
+
+    /// A channel's plugin with its current state, for the saved config
+    pub fn get_saved_plugin(&self, channel_idx: usize) -> Option<SavedPlugin> {
+        let channels = self.channels.lock().ok()?;
+        let channel = channels.get(channel_idx)?;
+        let plugin = channel.plugin.as_ref()?;
+        let state = plugin.save_state().unwrap_or_else(|e| {
+            log::warn!(
+                "Could not save state of {}: {:#}",
+                plugin.get_plugin_name(),
+                e
+            );
+            Vec::new()
+        });
+        Some(SavedPlugin {
+            info: channel.plugin_info.clone()?,
+            state,
+        })
+    }
+
+    /// Load a saved plugin into a channel and restore its state
+    pub fn restore_channel_plugin(&self, channel_idx: usize, saved: &SavedPlugin) -> Result<()> {
+        self.load_channel_plugin(channel_idx, Some(&saved.info))?;
+        if !saved.state.is_empty() {
+            self.with_channel_plugin(channel_idx, |plugin| plugin.load_state(&saved.state))?;
+        }
+        Ok(())
+    }
+
+    /// Ports of a channel's plugin
+    pub fn channel_plugin_layout(&self, channel_idx: usize) -> Option<ChannelLayout> {
+        let channels = self.channels.lock().ok()?;
+        let plugin = channels.get(channel_idx)?.plugin.as_ref()?;
+        Some(plugin.channel_layout())
+    }
+
+    /// Feed another channel's input to a channel plugin's sidechain ports
+    pub fn set_channel_sidechain(&self, channel_idx: usize, source: Option<usize>) -> Result<()> {
+        let mut channels = self
+            .channels
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Channel state lock poisoned"))?;
+        if let Some(source) = source
+            && source >= channels.len()
+        {
+            anyhow::bail!("Invalid sidechain source: {}", source);
         }
+        channels
+            .get_mut(channel_idx)
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel index: {}", channel_idx))?
+            .set_sidechain_source(source);
+        Ok(())
+    }
+
+    pub fn get_channel_sidechain(&self, channel_idx: usize) -> Option<usize> {
+        let channels = self.channels.lock().ok()?;
+        channels.get(channel_idx)?.sidechain_source()
+    }
+
+    /// Programs of a channel's plugin
+    pub fn channel_plugin_programs(&self, channel_idx: usize) -> Result<ProgramList> {
+        self.with_channel_plugin(channel_idx, |plugin| Ok(plugin.programs()))
+    }
+
+    pub fn set_channel_program(&self, channel_idx: usize, index: usize) -> Result<()> {
+        self.with_channel_plugin(channel_idx, |plugin| plugin.set_program(index))
+    }
+
+    /// FXP of the current program, or FXB of the whole bank
+    pub fn export_channel_preset(&self, channel_idx: usize, bank: bool) -> Result<Vec<u8>> {
+        self.with_channel_plugin(channel_idx, |plugin| plugin.export_preset(bank))
+    }
+
+    /// Load an FXP or FXB file into a channel's plugin
+    pub fn import_channel_preset(&self, channel_idx: usize, data: &[u8]) -> Result<()> {
+        self.with_channel_plugin(channel_idx, |plugin| plugin.import_preset(data))
+    }
+
+    /// Catalogue entry of a channel's plugin
+    pub fn channel_plugin_info(&self, channel_idx: usize) -> Option<VstPluginInfo> {
+        let channels = self.channels.lock().ok()?;
+        channels.get(channel_idx)?.plugin_info.clone()
+    }
+
+    /// Name, unit and display text of every parameter of a channel's plugin.
+    /// Parameters the plugin cannot describe get a generic name.
+    pub fn channel_plugin_parameters(&self, channel_idx: usize) -> Result<Vec<ParameterInfo>> {
+        // Waiting on the plugin's thread happens after the lock is released
+        let query = self.with_channel_plugin(channel_idx, |plugin| Ok(plugin.parameter_query()))?;
+        Ok(query())
+    }
+
+    /// One parameter of a channel's plugin, as the plugin displays it now
+    pub fn channel_plugin_parameter(
+        &self,
+        channel_idx: usize,
+        index: i32,
+    ) -> Result<Option<ParameterInfo>> {
+        self.with_channel_plugin(channel_idx, |plugin| Ok(plugin.get_parameter_info(index)))
+    }
+
+    /// Set a normalised (0..1) parameter of a channel's plugin
+    pub fn set_channel_plugin_parameter(
+        &self,
+        channel_idx: usize,
+        index: i32,
+        value: f32,
+    ) -> Result<()> {
+        self.with_channel_plugin(channel_idx, |plugin| {
+            if !(0..plugin.get_parameter_count()).contains(&index) {
+                anyhow::bail!("{} has no parameter {}", plugin.get_plugin_name(), index);
+            }
+            plugin.set_parameter(index, value);
+            Ok(())
+        })
+    }
+
+    /// Parameter changes channel plugins made themselves since the last
+    /// call, with their channel
+    pub fn take_plugin_automation(&self) -> Vec<(usize, ParameterChange)> {
+        let Ok(mut channels) = self.channels.lock() else {
+            return Vec::new();
+        };
+        channels
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, channel)| Some((i, channel.plugin.as_mut()?)))
+            .flat_map(|(i, plugin)| {
+                plugin
+                    .take_automation()
+                    .into_iter()
+                    .map(move |change| (i, change))
+            })
+            .collect()
+    }
+
+    /// Apply a scene's values to the channel plugins, skipping channels that
+    /// hold another plugin now. Returns how many were applied.
+    pub fn recall_plugin_scene(&self, scene: &PluginScene) -> usize {
+        let Ok(mut channels) = self.channels.lock() else {
+            return 0;
+        };
+        let mut applied = 0;
+        for parameter in &scene.parameters {
+            if let Some(plugin) = channels
+                .get_mut(parameter.channel)
+                .and_then(|channel| channel.plugin.as_mut())
+                && plugin.get_plugin_name() == parameter.plugin
+                && (0..plugin.get_parameter_count()).contains(&parameter.index)
+            {
+                plugin.set_parameter(parameter.index, parameter.value);
+                applied += 1;
+            }
+        }
+        applied
+    }
+
+    fn with_channel_plugin<T>(
+        &self,
+        channel_idx: usize,
+        f: impl FnOnce(&mut dyn PluginProcessor) -> Result<T>,
+    ) -> Result<T> {
+        let mut channels = self
+            .channels
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Channel state lock poisoned"))?;
+        let channel = channels
+            .get_mut(channel_idx)
+            .ok_or_else(|| anyhow::anyhow!("Invalid channel index: {}", channel_idx))?;
+        let plugin = channel
+            .plugin
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Channel {} has no plugin", channel_idx + 1))?;
+        f(plugin.as_mut())
+    }
+
+    /// Applies to plugins loaded from now on
+    pub fn set_plugin_sandbox(&mut self, sandbox: SandboxConfig) {
+        self.plugin_sandbox = sandbox;
+    }
+
+    pub fn get_plugin_sandbox(&self) -> SandboxConfig {
+        self.plugin_sandbox
+    }
+
+    /// Name and format of a channel's plugin
+    pub fn get_channel_plugin(&self, channel_idx: usize) -> Option<(String, PluginFormat)> {
+        let channels = self.channels.lock().ok()?;
+        let plugin = channels.get(channel_idx)?.plugin.as_ref()?;
+        Some((plugin.get_plugin_name(), plugin.format()))
     }
 
     /// Get shared spectrum data for visualization
@@ -836,9 +2131,8 @@ mod tests {
     fn test_channel_processor_muted_returns_silence() {
         let mut proc = ChannelProcessor::new();
         proc.muted = true;
-        let rnnoise = Rnnoise::new();
         let input = vec![0.5; 480];
-        let (output, levels) = proc.process(&input, &rnnoise, 0.016);
+        let (output, levels) = proc.process(&input, 0.016);
         assert!(output.iter().all(|&s| s == 0.0));
         assert_eq!(levels, [0.0, 0.0]);
     }
@@ -849,9 +2143,8 @@ mod tests {
         proc.gain = 0.0;
         proc.volume = 1.0;
         proc.pan = 0.0;
-        let rnnoise = Rnnoise::new();
         let input = vec![0.5; 10];
-        let (output, _) = proc.process(&input, &rnnoise, 0.016);
+        let (output, _) = proc.process(&input, 0.016);
         // Mono input → stereo output (2x samples)
         assert_eq!(output.len(), input.len() * 2);
     }
@@ -863,9 +2156,8 @@ mod tests {
         proc.gain = 0.0;
         proc.volume = 1.0;
         proc.pan = -1.0; // Full left
-        let rnnoise = Rnnoise::new();
         let input = vec![1.0; 1];
-        let (output, _) = proc.process(&input, &rnnoise, 0.016);
+        let (output, _) = proc.process(&input, 0.016);
         // Left channel should be full, right should be zero
         assert_eq!(output.len(), 2);
         assert!((output[0] - 1.0).abs() < 0.001);
@@ -879,14 +2171,230 @@ mod tests {
         proc.gain = 0.0;
         proc.volume = 1.0;
         proc.pan = 1.0; // Full right
-        let rnnoise = Rnnoise::new();
         let input = vec![1.0; 1];
-        let (output, _) = proc.process(&input, &rnnoise, 0.016);
+        let (output, _) = proc.process(&input, 0.016);
         assert_eq!(output.len(), 2);
         assert!((output[0]).abs() < 0.001);
         assert!((output[1] - 1.0).abs() < 0.001);
     }
 
+    /// Mono in, stereo out: the input on the left, inverted on the right
+    struct Widener;
+
+    impl PluginProcessor for Widener {
+        fn format(&self) -> PluginFormat {
+            PluginFormat::Lv2
+        }
+        fn channel_layout(&self) -> ChannelLayout {
+            ChannelLayout::new(1, 2)
+        }
+        fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+            vec![inputs[0].to_vec(), inputs[0].iter().map(|s| -s).collect()]
+        }
+        fn get_plugin_name(&self) -> String {
+            "Widener".to_string()
+        }
+        fn set_parameter(&mut self, _index: i32, _value: f32) {}
+        fn get_parameter(&self, _index: i32) -> f32 {
+            0.0
+        }
+        fn get_parameter_info(&self, _index: i32) -> Option<crate::vst_host::ParameterInfo> {
+            None
+        }
+        fn get_parameter_count(&self) -> i32 {
+            0
+        }
+        fn set_enabled(&mut self, _enabled: bool) {}
+        fn is_enabled(&self) -> bool {
+            true
+        }
+        fn latency_samples(&self) -> usize {
+            0
+        }
+    }
+
+    /// Stereo in, stereo out: doubles the left side and halves the right
+    struct StereoGain;
+
+    impl PluginProcessor for StereoGain {
+        fn format(&self) -> PluginFormat {
+            PluginFormat::Lv2
+        }
+        fn channel_layout(&self) -> ChannelLayout {
+            ChannelLayout::new(2, 2)
+        }
+        fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+            vec![
+                inputs[0].iter().map(|s| s * 2.0).collect(),
+                inputs[1].iter().map(|s| s * 0.5).collect(),
+            ]
+        }
+        fn get_plugin_name(&self) -> String {
+            "Stereo Gain".to_string()
+        }
+        fn set_parameter(&mut self, _index: i32, _value: f32) {}
+        fn get_parameter(&self, _index: i32) -> f32 {
+            0.0
+        }
+        fn get_parameter_info(&self, _index: i32) -> Option<crate::vst_host::ParameterInfo> {
+            None
+        }
+        fn get_parameter_count(&self) -> i32 {
+            0
+        }
+        fn set_enabled(&mut self, _enabled: bool) {}
+        fn is_enabled(&self) -> bool {
+            true
+        }
+        fn latency_samples(&self) -> usize {
+            0
+        }
+    }
+
+    /// Two parameters, and a queue of changes it pretends to make itself
+    struct Knobs {
+        values: [f32; 2],
+        automation: Vec<ParameterChange>,
+    }
+
+    impl PluginProcessor for Knobs {
+        fn format(&self) -> PluginFormat {
+            PluginFormat::Clap
+        }
+        fn channel_layout(&self) -> ChannelLayout {
+            ChannelLayout::new(1, 1)
+        }
+        fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+            vec![inputs[0].to_vec()]
+        }
+        fn get_plugin_name(&self) -> String {
+            "Knobs".to_string()
+        }
+        fn set_parameter(&mut self, index: i32, value: f32) {
+            self.values[index as usize] = value;
+        }
+        fn get_parameter(&self, index: i32) -> f32 {
+            self.values[index as usize]
+        }
+        fn get_parameter_info(&self, _index: i32) -> Option<crate::vst_host::ParameterInfo> {
+            None
+        }
+        fn get_parameter_count(&self) -> i32 {
+            2
+        }
+        fn set_enabled(&mut self, _enabled: bool) {}
+        fn is_enabled(&self) -> bool {
+            true
+        }
+        fn latency_samples(&self) -> usize {
+            0
+        }
+        fn take_automation(&mut self) -> Vec<ParameterChange> {
+            for change in &self.automation {
+                self.values[change.index as usize] = change.value;
+            }
+            std::mem::take(&mut self.automation)
+        }
+    }
+
+    #[test]
+    fn test_plugin_parameters_automation_and_scenes() {
+        let engine = AudioEngine::new();
+        let change = ParameterChange {
+            index: 1,
+            value: 0.75,
+        };
+        engine.channels.lock().unwrap()[2].plugin = Some(Box::new(Knobs {
+            values: [0.0; 2],
+            automation: vec![change],
+        }));
+
+        assert_eq!(engine.take_plugin_automation(), vec![(2, change)]);
+        assert!(engine.take_plugin_automation().is_empty());
+        let parameters = engine.channel_plugin_parameters(2).unwrap();
+        assert_eq!(parameters[1].name, "Parameter 2");
+        assert_eq!(parameters[1].value, 0.75);
+        assert!(engine.set_channel_plugin_parameter(2, 2, 0.5).is_err());
+        assert!(engine.channel_plugin_parameters(0).is_err());
+
+        // Only values recorded from the same plugin come back
+        let mut scene = PluginScene::new("Verse");
+        scene.record(
+            2,
+            "Knobs",
+            ParameterChange {
+                index: 0,
+                value: 0.5,
+            },
+        );
+        scene.record(
+            0,
+            "Knobs",
+            ParameterChange {
+                index: 0,
+                value: 0.5,
+            },
+        );
+        scene.record(
+            3,
+            "Other",
+            ParameterChange {
+                index: 0,
+                value: 0.5,
+            },
+        );
+        assert_eq!(engine.recall_plugin_scene(&scene), 1);
+        assert_eq!(engine.channel_plugin_parameters(2).unwrap()[0].value, 0.5);
+    }
+
+    #[test]
+    fn test_stereo_plugin_feeds_pan_stage() {
+        let mut proc = ChannelProcessor::new();
+        proc.effects.limiter.set_enabled(false); // Disable limiter for math test
+        proc.volume = 1.0;
+        proc.plugin = Some(Box::new(Widener));
+        assert_eq!(proc.strip_channels(), 2);
+
+        let (output, _) = proc.process(&[0.5, 0.25], 0.016);
+        assert_eq!(output, vec![0.5, -0.5, 0.25, -0.25]);
+
+        // Panning a stereo strip balances it: the right side fades out
+        // instead of being folded into the left
+        proc.pan = -1.0;
+        let (output, _) = proc.process(&[0.5], 0.016);
+        assert_eq!(output, vec![0.5, 0.0]);
+    }
+
+    #[test]
+    fn test_stereo_device_input_reaches_plugin_sides() {
+        let mut proc = ChannelProcessor::new();
+        proc.effects.limiter.set_enabled(false); // Disable limiter for math test
+        proc.volume = 1.0;
+        proc.set_input_channels(2);
+        // Interleaved L/R with different content on each side
+        let input = [0.1, -0.4, 0.2, -0.2, 0.3, -0.1];
+
+        // Stereo to stereo: each plugin port gets its own side
+        proc.plugin = Some(Box::new(StereoGain));
+        let (output, _) = proc.process(&input, 0.016);
+        assert_eq!(output.len(), 3 * 2);
+        let left: Vec<f32> = output.iter().step_by(2).copied().collect();
+        let right: Vec<f32> = output.iter().skip(1).step_by(2).copied().collect();
+        assert_eq!(left, vec![0.2, 0.4, 0.6]);
+        assert_eq!(right, vec![-0.2, -0.1, -0.05]);
+
+        // A mono plugin gets the downmix; without a plugin the strip is
+        // mono and panned, and muted it is silence of the same length
+        proc.plugin = Some(Box::new(Widener));
+        let (output, _) = proc.process(&input, 0.016);
+        assert_eq!(output.len(), 3 * 2);
+        assert!((output[0] + 0.15).abs() < 1e-6 && (output[1] - 0.15).abs() < 1e-6);
+        proc.plugin = None;
+        assert_eq!(proc.process(&input, 0.016).0.len(), 3 * 2);
+        proc.muted = true;
+        assert_eq!(proc.process(&input, 0.016).0, vec![0.0; 3 * 2]);
+    }
+
     #[test]
     fn test_channel_processor_gain_positive() {
         let mut proc = ChannelProcessor::new();
@@ -894,9 +2402,8 @@ mod tests {
         proc.gain = 20.0; // +20dB → gain_linear = 2.0
         proc.volume = 1.0;
         proc.pan = 0.0;
-        let rnnoise = Rnnoise::new();
         let input = vec![0.25; 1];
-        let (output, _) = proc.process(&input, &rnnoise, 0.016);
+        let (output, _) = proc.process(&input, 0.016);
         // With +20dB gain_linear = 1.0 + 20.0/20.0 = 2.0
         // So output should be 0.25 * 2.0 = 0.5 per channel
         assert!((output[0] - 0.5).abs() < 0.01);
@@ -910,13 +2417,36 @@ mod tests {
         proc.gain = 0.0;
         proc.volume = 0.5;
         proc.pan = 0.0;
-        let rnnoise = Rnnoise::new();
         let input = vec![1.0; 1];
-        let (output, _) = proc.process(&input, &rnnoise, 0.016);
+        let (output, _) = proc.process(&input, 0.016);
         assert!((output[0] - 0.5).abs() < 0.01);
         assert!((output[1] - 0.5).abs() < 0.01);
     }
 
+    #[test]
+    fn test_channel_generator_replaces_input() {
+        let engine = AudioEngine::new();
+        let config = SignalGeneratorConfig {
+            waveform: crate::signal_generator::Waveform::Reference,
+            ..Default::default()
+        };
+        engine
+            .set_channel_generator(2, Some(config.clone()))
+            .unwrap();
+        assert_eq!(engine.get_channel_generator(2), Some(config));
+        assert_eq!(engine.get_channel_generator(0), None);
+        assert!(engine.set_channel_generator(99, None).is_err());
+
+        let mut channels = engine.channels.lock().unwrap();
+        assert!(channels[0].generate_input(64, 2).is_none());
+        let tone = channels[2].generate_input(4800, 2).unwrap();
+        let peak = tone.iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+        assert!((20.0 * peak.log10() + 18.0).abs() < 0.1);
+
+        channels[2].set_generator(None);
+        assert!(channels[2].generate_input(64, 2).is_none());
+    }
+
     #[test]
     fn test_audio_engine_defaults() {
         let engine = AudioEngine::new();
@@ -1101,10 +2631,9 @@ mod tests {
         // Enable limiter (it's part of effects chain)
         proc.effects.limiter.set_enabled(true);
 
-        let rnnoise = Rnnoise::new();
         // Input a very loud signal that would clip
         let input = vec![2.0; 10];
-        let (output, _) = proc.process(&input, &rnnoise, 0.016);
+        let (output, _) = proc.process(&input, 0.016);
 
         // Limiter should prevent any sample from exceeding ~1.0
         for &sample in &output {
@@ -1112,6 +2641,103 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_channel_noise_profile_survives_denoiser_switch() {
+        let mut proc = ChannelProcessor::new();
+        assert!(proc.start_noise_learning(1.0).is_err());
+
+        let profile = NoiseProfile {
+            sample_rate: 48000,
+            fft_size: 512,
+            magnitudes: vec![0.02; 257],
+        };
+        proc.set_denoiser(ChannelDenoiser::Advanced).unwrap();
+        proc.set_noise_profile(profile).unwrap();
+        proc.set_denoiser(ChannelDenoiser::Rnnoise).unwrap();
+        proc.set_denoiser(ChannelDenoiser::Advanced).unwrap();
+
+        let restored = proc.noise_profile().expect("profile should be re-applied");
+        assert!(restored.magnitudes.iter().all(|&m| (m - 0.02).abs() < 1e-4));
+    }
+
+    #[test]
+    fn test_channel_denoisers_keep_their_own_state() {
+        let engine = AudioEngine::new();
+        engine
+            .set_channel_denoiser(0, ChannelDenoiser::Advanced)
+            .unwrap();
+        engine
+            .set_channel_denoising_mode(0, DenoisingMode::Maximum)
+            .unwrap();
+        engine
+            .set_channel_denoiser(1, ChannelDenoiser::Advanced)
+            .unwrap();
+        engine
+            .set_channel_denoising_mode(1, DenoisingMode::Basic)
+            .unwrap();
+        engine
+            .set_channel_denoiser(2, ChannelDenoiser::Rnnoise)
+            .unwrap();
+        assert!(
+            engine
+                .set_channel_denoising_mode(99, DenoisingMode::Basic)
+                .is_err()
+        );
+        engine.start_channel_noise_learning(0, 0.1).unwrap();
+
+        // Room tone into channel 0 only, a tone into the others
+        let mut seed = 1u32;
+        {
+            let mut channels = engine.channels.lock().unwrap();
+            for channel in channels.iter_mut() {
+                channel.set_advanced_denoising_enabled(true);
+            }
+            for block in 0..20 {
+                let hiss: Vec<f32> = (0..480)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                        0.02 * (seed as f32 / u32::MAX as f32 * 2.0 - 1.0)
+                    })
+                    .collect();
+                let tone: Vec<f32> = (0..480)
+                    .map(|i| 0.5 * ((block * 480 + i) as f32 * 0.06).sin())
+                    .collect();
+                channels[0].process(&hiss, 0.01);
+                channels[1].process(&tone, 0.01);
+                channels[2].process(&tone, 0.01);
+            }
+        }
+
+        // Only the channel that heard the room tone learned it
+        let channels = engine.channels.lock().unwrap();
+        assert!(channels[0].noise_profile().is_some());
+        assert!(channels[1].noise_profile().is_none());
+        assert!(channels[1].noise_learning_progress().is_none());
+        assert_eq!(channels[0].denoising_mode(), DenoisingMode::Maximum);
+        assert_eq!(channels[1].denoising_mode(), DenoisingMode::Basic);
+
+        // Metrics describe each channel's own denoiser
+        assert!(channels[0].denoiser_latency_samples() > channels[1].denoiser_latency_samples());
+        assert!(channels[0].denoise_metrics().latency_ms > 0.0);
+        assert!(channels[2].denoise_metrics().latency_ms > 0.0);
+        assert_eq!(channels[3].denoise_metrics().latency_ms, 0.0);
+        assert_eq!(channels[3].denoise_metrics().cpu_usage_percent, 0.0);
+    }
+
+    #[test]
+    fn test_failed_model_load_changes_no_channel() {
+        let engine = AudioEngine::new();
+        engine
+            .set_channel_denoiser(0, ChannelDenoiser::Advanced)
+            .unwrap();
+        let model = OnnxModelConfig::new("/nonexistent.onnx", 480, 48000);
+        assert!(engine.set_deep_learning_model(Some(model)).is_err());
+
+        let channels = engine.channels.lock().unwrap();
+        assert!(channels.iter().all(|c| c.deep_learning_model.is_none()));
+        assert!(channels[0].deep_learning_model_info().is_none());
+    }
+
     #[test]
     fn test_audio_engine_spectrum_data() {
         let engine = AudioEngine::new();
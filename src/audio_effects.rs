@@ -3,7 +3,10 @@
 //! Provides professional-grade dynamics processing:
 //! - Noise Gate with hold time
 //! - Compressor with soft knee
-//! - Limiter with lookahead
+//! - Lookahead brickwall limiter with true-peak detection
+
+use crate::loudness::TruePeakDetector;
+use std::collections::VecDeque;
 
 /// Noise gate for cutting audio below threshold
 pub struct NoiseGate {
@@ -12,6 +15,8 @@ pub struct NoiseGate {
     release_ms: f32,
     hold_ms: f32,
     sample_rate: f32,
+    /// Interleaved channels per frame, gated together
+    channels: usize,
     envelope: f32,
     hold_counter: f32,
     enabled: bool,
@@ -25,6 +30,7 @@ impl NoiseGate {
             release_ms: 50.0,
             hold_ms: 20.0,
             sample_rate,
+            channels: 1,
             envelope: 0.0,
             hold_counter: 0.0,
             enabled: false,
@@ -65,8 +71,8 @@ impl NoiseGate {
         let release_coeff = (-1.0 / (self.release_ms * 0.001 * self.sample_rate)).exp();
         let hold_samples = self.hold_ms * 0.001 * self.sample_rate;
 
-        for sample in samples.iter_mut() {
-            let input_level = sample.abs();
+        for frame in samples.chunks_mut(self.channels) {
+            let input_level = frame.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
 
             // Gate detection
             if input_level > threshold_linear {
@@ -81,7 +87,9 @@ impl NoiseGate {
             }
 
             // Apply gate
-            *sample *= self.envelope;
+            for sample in frame {
+                *sample *= self.envelope;
+            }
         }
     }
 
@@ -100,6 +108,8 @@ pub struct Compressor {
     knee_db: f32,
     makeup_db: f32,
     sample_rate: f32,
+    /// Interleaved channels per frame, compressed together
+    channels: usize,
     envelope: f32,
     enabled: bool,
 }
@@ -114,6 +124,7 @@ impl Compressor {
             knee_db: 6.0,
             makeup_db: 0.0,
             sample_rate,
+            channels: 1,
             envelope: 0.0,
             enabled: false,
         }
@@ -183,8 +194,11 @@ impl Compressor {
         let release_coeff = (-1.0 / (self.release_ms * 0.001 * self.sample_rate)).exp();
         let makeup_linear = 10.0_f32.powf(self.makeup_db / 20.0);
 
-        for sample in samples.iter_mut() {
-            let input_abs = sample.abs().max(1e-10);
+        for frame in samples.chunks_mut(self.channels) {
+            let input_abs = frame
+                .iter()
+                .fold(0.0_f32, |peak, s| peak.max(s.abs()))
+                .max(1e-10);
             let input_db = 20.0 * input_abs.log10();
 
             // Compute desired gain reduction
@@ -200,7 +214,9 @@ impl Compressor {
 
             // Apply gain reduction and makeup
             let gain_linear = 10.0_f32.powf(-self.envelope / 20.0) * makeup_linear;
-            *sample *= gain_linear;
+            for sample in frame {
+                *sample *= gain_linear;
+            }
         }
     }
 
@@ -213,32 +229,117 @@ impl Compressor {
     }
 }
 
-/// Hard limiter with ceiling control
+/// Brickwall limiter that sees peaks coming: the signal is delayed by the
+/// lookahead so gain can ramp down before a transient arrives, and peaks
+/// are detected on an oversampled copy so inter-sample overs are caught too
 pub struct Limiter {
     ceiling_db: f32,
     release_ms: f32,
+    lookahead_ms: f32,
     sample_rate: f32,
+    channels: usize,
+    detectors: Vec<TruePeakDetector>,
+    /// Frames of audio delay: detector latency plus the lookahead ramp
+    delay_frames: usize,
+    /// Interleaved delay line holding `delay_frames + 1` frames
+    delay: Vec<f32>,
+    delay_pos: usize,
+    /// Gain needed per frame, falling instantly and recovering at the release rate
     envelope: f32,
+    /// Sliding minimum of the envelope over the lookahead, as (frame, gain)
+    hold: VecDeque<(usize, f32)>,
+    /// Held gains averaged over the lookahead into a smooth ramp
+    ramp: Vec<f32>,
+    ramp_pos: usize,
+    ramp_sum: f64,
+    frame: usize,
+    gain: f32,
+    /// Lowest gain applied during the last buffer, for metering
+    buffer_gain: f32,
     enabled: bool,
 }
 
 impl Limiter {
-    pub fn new(sample_rate: f32) -> Self {
-        Self {
+    pub fn new(sample_rate: f32, channels: usize) -> Self {
+        let mut limiter = Self {
             ceiling_db: -0.3,
             release_ms: 50.0,
+            lookahead_ms: 2.0,
             sample_rate,
-            envelope: 0.0,
+            channels: channels.max(1),
+            detectors: Vec::new(),
+            delay_frames: 0,
+            delay: Vec::new(),
+            delay_pos: 0,
+            envelope: 1.0,
+            hold: VecDeque::new(),
+            ramp: Vec::new(),
+            ramp_pos: 0,
+            ramp_sum: 0.0,
+            frame: 0,
+            gain: 1.0,
+            buffer_gain: 1.0,
             enabled: true, // Limiter typically enabled by default
-        }
+        };
+        limiter.allocate();
+        limiter
     }
 
+    /// Size the delay line and ramp for the current rate and lookahead
+    fn allocate(&mut self) {
+        self.detectors = (0..self.channels)
+            .map(|_| TruePeakDetector::new(self.sample_rate as u32))
+            .collect();
+        let lookahead = self.lookahead_frames();
+        let detector_latency = self.detectors[0].latency_samples();
+        self.delay_frames = detector_latency + lookahead - 1;
+        self.delay = vec![0.0; (self.delay_frames + 1) * self.channels];
+        self.hold = VecDeque::with_capacity(lookahead + 1);
+        self.ramp = vec![1.0; lookahead];
+        self.reset();
+    }
+
+    fn lookahead_frames(&self) -> usize {
+        ((self.lookahead_ms * 0.001 * self.sample_rate).round() as usize).max(1)
+    }
+
+    /// Ceiling in dBTP
     pub fn set_ceiling(&mut self, db: f32) {
         self.ceiling_db = db.clamp(-12.0, 0.0);
     }
 
     pub fn set_release(&mut self, ms: f32) {
-        self.release_ms = ms.clamp(10.0, 500.0);
+        self.release_ms = ms.clamp(10.0, 1000.0);
+    }
+
+    /// Lookahead time; changing it resizes the delay line and resets state
+    pub fn set_lookahead(&mut self, ms: f32) {
+        let ms = ms.clamp(1.0, 5.0);
+        if ms != self.lookahead_ms {
+            self.lookahead_ms = ms;
+            self.allocate();
+        }
+    }
+
+    pub fn lookahead_ms(&self) -> f32 {
+        self.lookahead_ms
+    }
+
+    /// Interleaved channels per frame; changing it resets state
+    pub fn set_channels(&mut self, channels: usize) {
+        let channels = channels.max(1);
+        if channels != self.channels {
+            self.channels = channels;
+            self.allocate();
+        }
+    }
+
+    /// Follow a new stream rate; the lookahead keeps its length in time
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.allocate();
+        }
     }
 
     pub fn set_enabled(&mut self, enabled: bool) {
@@ -249,42 +350,121 @@ impl Limiter {
         self.enabled
     }
 
+    /// Frames of delay the limiter adds while enabled
+    pub fn latency_samples(&self) -> usize {
+        if self.enabled { self.delay_frames } else { 0 }
+    }
+
+    /// Deepest gain reduction during the last buffer, in dB (0 when not
+    /// limiting), so meters polled once per frame still see short hits
+    pub fn get_gain_reduction(&self) -> f32 {
+        -20.0 * self.buffer_gain.max(1e-6).log10()
+    }
+
+    pub fn is_limiting(&self) -> bool {
+        self.gain < 0.99
+    }
+
+    /// Limit a buffer of interleaved frames in place
     pub fn process(&mut self, samples: &mut [f32]) {
         if !self.enabled {
+            self.buffer_gain = 1.0;
             return;
         }
 
-        let ceiling_linear = 10.0_f32.powf(self.ceiling_db / 20.0);
+        self.apply_gain(samples);
+        // The gain already holds the ceiling; the clamp only absorbs rounding
+        let ceiling = 10.0_f32.powf(self.ceiling_db / 20.0);
+        for sample in samples.iter_mut() {
+            *sample = sample.clamp(-ceiling, ceiling);
+        }
+    }
+
+    /// Delay the frames and apply the lookahead gain, without the final clamp
+    fn apply_gain(&mut self, samples: &mut [f32]) {
+        let ceiling = 10.0_f32.powf(self.ceiling_db / 20.0);
         let release_coeff = (-1.0 / (self.release_ms * 0.001 * self.sample_rate)).exp();
+        let lookahead = self.ramp.len();
+        let detector_latency = self.delay_frames + 1 - lookahead;
+        let frames_stored = self.delay_frames + 1;
+        let mut lowest_gain = 1.0_f32;
+
+        for frame in samples.chunks_exact_mut(self.channels) {
+            // Store the incoming frame and track its oversampled peak
+            let write = self.delay_pos * self.channels;
+            let mut peak = 0.0_f32;
+            for ((slot, detector), &sample) in self.delay[write..write + self.channels]
+                .iter_mut()
+                .zip(&mut self.detectors)
+                .zip(frame.iter())
+            {
+                *slot = sample;
+                peak = peak.max(detector.process(sample));
+            }
 
-        for sample in samples.iter_mut() {
-            let input_abs = sample.abs();
+            // The detector reports on the frame it has fully seen; include
+            // that frame's own samples so the sample ceiling always holds
+            let detected = (self.delay_pos + frames_stored - detector_latency) % frames_stored;
+            let detected = detected * self.channels;
+            for &sample in &self.delay[detected..detected + self.channels] {
+                peak = peak.max(sample.abs());
+            }
 
-            // Calculate required attenuation
-            let attenuation = if input_abs > ceiling_linear {
-                ceiling_linear / input_abs
+            let required = if peak > ceiling { ceiling / peak } else { 1.0 };
+            self.envelope = if required < self.envelope {
+                required
             } else {
-                1.0
+                self.envelope * release_coeff + required * (1.0 - release_coeff)
             };
 
-            // Smooth the attenuation (instant attack, smooth release)
-            if attenuation < self.envelope {
-                self.envelope = attenuation; // Instant attack
-            } else {
-                self.envelope = self.envelope * release_coeff + attenuation * (1.0 - release_coeff);
+            // Every gain averaged into the ramp is held at or below the
+            // minimum over the lookahead, so the gain has reached the peak's
+            // requirement by the time that peak leaves the delay line
+            while self.hold.back().is_some_and(|&(_, g)| g >= self.envelope) {
+                self.hold.pop_back();
+            }
+            self.hold.push_back((self.frame, self.envelope));
+            while self
+                .hold
+                .front()
+                .is_some_and(|&(f, _)| f + lookahead <= self.frame)
+            {
+                self.hold.pop_front();
+            }
+            let held = self.hold.front().map_or(1.0, |&(_, g)| g);
+
+            self.ramp_sum += (held - self.ramp[self.ramp_pos]) as f64;
+            self.ramp[self.ramp_pos] = held;
+            self.ramp_pos = (self.ramp_pos + 1) % lookahead;
+            self.gain = (self.ramp_sum / lookahead as f64) as f32;
+            lowest_gain = lowest_gain.min(self.gain);
+            self.frame += 1;
+
+            // Emit the oldest frame
+            self.delay_pos = (self.delay_pos + 1) % frames_stored;
+            let read = self.delay_pos * self.channels;
+            for (out, &delayed) in frame
+                .iter_mut()
+                .zip(&self.delay[read..read + self.channels])
+            {
+                *out = delayed * self.gain;
             }
-
-            // Apply limiting
-            *sample *= self.envelope;
         }
+        self.buffer_gain = lowest_gain;
     }
 
     pub fn reset(&mut self) {
+        self.detectors.iter_mut().for_each(TruePeakDetector::reset);
+        self.delay.iter_mut().for_each(|x| *x = 0.0);
+        self.delay_pos = 0;
         self.envelope = 1.0;
-    }
-
-    pub fn is_limiting(&self) -> bool {
-        self.envelope < 0.99
+        self.hold.clear();
+        self.ramp.iter_mut().for_each(|g| *g = 1.0);
+        self.ramp_pos = 0;
+        self.ramp_sum = self.ramp.len() as f64;
+        self.frame = 0;
+        self.gain = 1.0;
+        self.buffer_gain = 1.0;
     }
 }
 
@@ -298,6 +478,7 @@ pub struct ChannelEffectsConfig {
     pub compressor_ratio: f32,
     pub limiter_enabled: bool,
     pub limiter_ceiling_db: f32,
+    pub limiter_lookahead_ms: f32,
 }
 
 impl Default for ChannelEffectsConfig {
@@ -310,6 +491,7 @@ impl Default for ChannelEffectsConfig {
             compressor_ratio: 4.0,
             limiter_enabled: true,
             limiter_ceiling_db: -0.3,
+            limiter_lookahead_ms: 2.0,
         }
     }
 }
@@ -326,7 +508,7 @@ impl ChannelEffects {
         Self {
             gate: NoiseGate::new(sample_rate),
             compressor: Compressor::new(sample_rate),
-            limiter: Limiter::new(sample_rate),
+            limiter: Limiter::new(sample_rate, 1),
         }
     }
 
@@ -348,6 +530,27 @@ impl ChannelEffects {
 
         self.limiter.set_enabled(config.limiter_enabled);
         self.limiter.set_ceiling(config.limiter_ceiling_db);
+        self.limiter.set_lookahead(config.limiter_lookahead_ms);
+    }
+
+    /// Process `channels` interleaved channels, with gain applied to
+    /// whole frames so the stereo image holds
+    pub fn set_channels(&mut self, channels: usize) {
+        let channels = channels.max(1);
+        self.gate.channels = channels;
+        self.compressor.channels = channels;
+        self.limiter.set_channels(channels);
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.gate.sample_rate = sample_rate;
+        self.compressor.sample_rate = sample_rate;
+        self.limiter.set_sample_rate(sample_rate);
+    }
+
+    /// Samples of delay added by the chain (the limiter's lookahead)
+    pub fn latency_samples(&self) -> usize {
+        self.limiter.latency_samples()
     }
 
     pub fn reset(&mut self) {
@@ -399,7 +602,7 @@ mod tests {
 
     #[test]
     fn test_limiter() {
-        let mut limiter = Limiter::new(48000.0);
+        let mut limiter = Limiter::new(48000.0, 1);
         limiter.set_enabled(true);
         limiter.set_ceiling(-6.0); // -6dB ceiling
 
@@ -412,6 +615,115 @@ mod tests {
         assert!(loud.iter().all(|&s| s.abs() <= ceiling_linear + 0.01));
     }
 
+    #[test]
+    fn test_lookahead_limiter_catches_transients() {
+        let mut limiter = Limiter::new(48000.0, 2);
+        limiter.set_ceiling(-1.0);
+
+        // Quiet bed with sudden full-scale clicks
+        let mut samples: Vec<f32> = (0..9600)
+            .map(|i| {
+                if i % 1000 == 500 {
+                    1.0
+                } else {
+                    0.1 * (i as f32 * 0.3).sin()
+                }
+            })
+            .collect();
+        limiter.apply_gain(&mut samples);
+
+        let ceiling = 10.0_f32.powf(-1.0 / 20.0);
+        assert!(samples.iter().all(|s| s.abs() <= ceiling * (1.0 + 1e-6)));
+
+        // Material under the ceiling only comes out delayed
+        limiter.reset();
+        let quiet: Vec<f32> = (0..4800).map(|i| 0.2 * (i as f32 * 0.01).sin()).collect();
+        let mut delayed = quiet.clone();
+        limiter.process(&mut delayed);
+        let latency = limiter.latency_samples() * 2;
+        for (out, original) in delayed[latency..].iter().zip(&quiet) {
+            assert!((out - original).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_limiter_never_exceeds_ceiling() {
+        let mut seed = 7u32;
+        let mut noise = move || {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+        };
+
+        for &rate in &[44100.0, 48000.0, 96000.0] {
+            for &lookahead in &[1.0, 2.5, 5.0] {
+                for channels in 1..=2 {
+                    let mut limiter = Limiter::new(rate, channels);
+                    limiter.set_ceiling(-1.0);
+                    limiter.set_lookahead(lookahead);
+
+                    // Noise bursts up to +20 dBFS with near-Nyquist tones,
+                    // which hide the largest inter-sample peaks
+                    let mut samples: Vec<f32> = (0..rate as usize / 2 * channels)
+                        .map(|i| {
+                            let burst = if (i / 997) % 3 == 0 { 10.0 } else { 0.3 };
+                            let tone = (i as f32 * 2.9).sin();
+                            burst * (0.6 * noise() + 0.4 * tone)
+                        })
+                        .collect();
+                    for block in samples.chunks_mut(128 * channels) {
+                        limiter.apply_gain(block);
+                    }
+
+                    // The gain alone holds the ceiling, up to rounding
+                    let ceiling = 10.0_f32.powf(-1.0 / 20.0);
+                    assert!(samples.iter().all(|s| s.abs() <= ceiling * (1.0 + 1e-6)));
+
+                    // Inter-sample peaks stay within the interpolation error
+                    let mut detector = TruePeakDetector::new(rate as u32);
+                    let true_peak = samples
+                        .iter()
+                        .step_by(channels)
+                        .map(|&s| detector.process(s))
+                        .fold(0.0_f32, f32::max);
+                    assert!(
+                        20.0 * true_peak.log10() <= -1.0 + 0.02,
+                        "true peak {} dBTP at {} Hz, {} ms",
+                        20.0 * true_peak.log10(),
+                        rate,
+                        lookahead
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_limiter_reports_latency_and_reduction() {
+        let mut limiter = Limiter::new(48000.0, 1);
+        limiter.set_lookahead(5.0);
+        // 5 ms of lookahead plus the oversampling filter's delay
+        assert!(limiter.latency_samples() >= 240);
+        limiter.set_lookahead(1.0);
+        assert!(limiter.latency_samples() >= 48 && limiter.latency_samples() < 240);
+
+        limiter.set_ceiling(-6.0);
+        // Once recovered from the onset, a steady full-scale signal needs
+        // exactly 6 dB
+        for _ in 0..10 {
+            limiter.process(&mut vec![1.0; 4800]);
+        }
+        assert!((limiter.get_gain_reduction() - 6.0).abs() < 0.1);
+
+        // and lets go again once the level drops
+        for _ in 0..10 {
+            limiter.process(&mut vec![0.1; 4800]);
+        }
+        assert!(limiter.get_gain_reduction() < 0.1);
+
+        limiter.set_enabled(false);
+        assert_eq!(limiter.latency_samples(), 0);
+    }
+
     #[test]
     fn test_effects_chain() {
         let mut effects = ChannelEffects::new(48000.0);
@@ -424,6 +736,7 @@ mod tests {
             compressor_ratio: 4.0,
             limiter_enabled: true,
             limiter_ceiling_db: -1.0,
+            limiter_lookahead_ms: 2.0,
         };
         effects.apply_config(&config);
 
@@ -434,4 +747,23 @@ mod tests {
         let ceiling_linear = 10.0_f32.powf(-1.0 / 20.0);
         assert!(samples.iter().all(|&s| s.abs() <= ceiling_linear + 0.01));
     }
+
+    #[test]
+    fn test_stereo_frames_share_gain() {
+        let mut effects = ChannelEffects::new(48000.0);
+        effects.apply_config(&ChannelEffectsConfig {
+            compressor_enabled: true,
+            compressor_threshold_db: -20.0,
+            ..ChannelEffectsConfig::default()
+        });
+        effects.set_channels(2);
+
+        // Loud left, quiet right: the right is turned down by the same
+        // amount, so the balance between them holds
+        let mut frames: Vec<f32> = (0..4800).flat_map(|_| [0.8, 0.2]).collect();
+        effects.process(&mut frames);
+        let (left, right) = (frames[frames.len() - 2], frames[frames.len() - 1]);
+        assert!(left < 0.8);
+        assert!((left / right - 4.0).abs() < 1e-3);
+    }
 }
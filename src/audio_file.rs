@@ -0,0 +1,281 @@
+//! Audio file input and output.
+//!
+//! Reads WAV (integer or float) and FLAC into interleaved `f32` samples in
+//! ±1.0, and writes WAV or FLAC picked from the file extension. Writing is
+//! streaming so long recordings never have to fit in memory.
+
+#![allow(dead_code)] // Complete file I/O API
+
+use crate::flac_encoder::FlacWriter;
+use anyhow::Result;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Container format, chosen by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Wav,
+    Flac,
+}
+
+impl AudioFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "wav" | "wave" => Ok(Self::Wav),
+            "flac" => Ok(Self::Flac),
+            _ => anyhow::bail!(
+                "Unsupported audio file {} (expected .wav or .flac)",
+                path.display()
+            ),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Wav => "WAV",
+            Self::Flac => "FLAC",
+        }
+    }
+
+    /// Whether samples of `bits_per_sample` can be stored
+    pub fn supports_bits(&self, bits_per_sample: u16) -> bool {
+        match self {
+            // 32-bit WAV is written as IEEE float
+            Self::Wav => matches!(bits_per_sample, 16 | 24 | 32),
+            Self::Flac => matches!(bits_per_sample, 16 | 24),
+        }
+    }
+}
+
+/// Layout of a file's samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioSpec {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+}
+
+/// Decoded audio with interleaved samples in ±1.0
+#[derive(Debug, Clone)]
+pub struct AudioData {
+    pub spec: AudioSpec,
+    pub samples: Vec<f32>,
+}
+
+impl AudioData {
+    /// Samples per channel
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.spec.channels.max(1) as usize
+    }
+
+    pub fn duration_secs(&self) -> f32 {
+        self.frames() as f32 / self.spec.sample_rate.max(1) as f32
+    }
+}
+
+/// Scale for integer samples of `bits` so full scale maps to ±1.0
+fn int_scale(bits: u16) -> f32 {
+    1.0 / (1u64 << (bits.clamp(1, 32) - 1)) as f32
+}
+
+/// Read a WAV or FLAC file
+pub fn read_audio_file(path: &Path) -> Result<AudioData> {
+    match AudioFormat::from_path(path)? {
+        AudioFormat::Wav => read_wav(path),
+        AudioFormat::Flac => read_flac(path),
+    }
+}
+
+fn read_wav(path: &Path) -> Result<AudioData> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path.display(), e))?;
+    let wav_spec = reader.spec();
+    let samples = match wav_spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = int_scale(wav_spec.bits_per_sample);
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+
+    Ok(AudioData {
+        spec: AudioSpec {
+            sample_rate: wav_spec.sample_rate,
+            channels: wav_spec.channels,
+            bits_per_sample: wav_spec.bits_per_sample,
+        },
+        samples,
+    })
+}
+
+fn read_flac(path: &Path) -> Result<AudioData> {
+    let mut reader = claxon::FlacReader::open(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read {}: {}", path.display(), e))?;
+    let info = reader.streaminfo();
+    let scale = int_scale(info.bits_per_sample as u16);
+    let samples = reader
+        .samples()
+        .map(|s| s.map(|s| s as f32 * scale))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(AudioData {
+        spec: AudioSpec {
+            sample_rate: info.sample_rate,
+            channels: info.channels as u16,
+            bits_per_sample: info.bits_per_sample as u16,
+        },
+        samples,
+    })
+}
+
+enum WriterKind {
+    Wav(hound::WavWriter<BufWriter<File>>),
+    Flac(FlacWriter),
+}
+
+/// Streaming WAV or FLAC writer for interleaved samples in ±1.0
+pub struct AudioFileWriter {
+    inner: WriterKind,
+    spec: AudioSpec,
+}
+
+impl AudioFileWriter {
+    /// Create `path`, choosing the format from its extension
+    pub fn create(path: &Path, spec: AudioSpec) -> Result<Self> {
+        let format = AudioFormat::from_path(path)?;
+        if !format.supports_bits(spec.bits_per_sample) {
+            anyhow::bail!(
+                "{} output does not support {}-bit samples",
+                format.name(),
+                spec.bits_per_sample
+            );
+        }
+
+        let inner = match format {
+            AudioFormat::Wav => {
+                let wav_spec = hound::WavSpec {
+                    channels: spec.channels,
+                    sample_rate: spec.sample_rate,
+                    bits_per_sample: spec.bits_per_sample,
+                    sample_format: if spec.bits_per_sample == 32 {
+                        hound::SampleFormat::Float
+                    } else {
+                        hound::SampleFormat::Int
+                    },
+                };
+                WriterKind::Wav(
+                    hound::WavWriter::create(path, wav_spec)
+                        .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", path.display(), e))?,
+                )
+            }
+            AudioFormat::Flac => WriterKind::Flac(FlacWriter::create(
+                path,
+                spec.sample_rate,
+                spec.channels,
+                spec.bits_per_sample,
+            )?),
+        };
+
+        Ok(Self { inner, spec })
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    /// Append interleaved samples; values outside ±1.0 are clipped
+    pub fn write(&mut self, samples: &[f32]) -> Result<()> {
+        match self.inner {
+            WriterKind::Wav(ref mut writer) if self.spec.bits_per_sample == 32 => {
+                for &sample in samples {
+                    writer.write_sample(sample)?;
+                }
+            }
+            WriterKind::Wav(ref mut writer) => {
+                let max = ((1i64 << (self.spec.bits_per_sample - 1)) - 1) as f32;
+                for &sample in samples {
+                    writer.write_sample((sample.clamp(-1.0, 1.0) * max).round() as i32)?;
+                }
+            }
+            WriterKind::Flac(ref mut writer) => writer.write_samples(samples)?,
+        }
+        Ok(())
+    }
+
+    /// Write buffered audio to disk and bring the header up to date, so the
+    /// file stays readable if the process dies before `finalize`
+    pub fn flush(&mut self) -> Result<()> {
+        match self.inner {
+            WriterKind::Wav(ref mut writer) => writer.flush()?,
+            WriterKind::Flac(ref mut writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+
+    /// Flush buffered audio and complete the file header
+    pub fn finalize(self) -> Result<()> {
+        match self.inner {
+            WriterKind::Wav(writer) => writer.finalize()?,
+            WriterKind::Flac(writer) => writer.finalize()?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_and_flac_round_trip() {
+        let dir = std::env::temp_dir().join("phantomlink_test_audio_file");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let samples: Vec<f32> = (0..9600).map(|i| 0.5 * (i as f32 * 0.05).sin()).collect();
+        for (name, bits) in [("out.wav", 16), ("out.wav", 32), ("out.flac", 24)] {
+            let path = dir.join(name);
+            let spec = AudioSpec {
+                sample_rate: 48000,
+                channels: 2,
+                bits_per_sample: bits,
+            };
+            let mut writer = AudioFileWriter::create(&path, spec).unwrap();
+            writer.write(&samples).unwrap();
+            writer.finalize().unwrap();
+
+            let data = read_audio_file(&path).unwrap();
+            assert_eq!(data.spec, spec);
+            assert_eq!(data.frames(), 4800);
+            let tolerance = 2.0 * int_scale(bits);
+            for (a, b) in data.samples.iter().zip(&samples) {
+                assert!(
+                    (a - b).abs() <= tolerance,
+                    "{}-bit {}: {} vs {}",
+                    bits,
+                    name,
+                    a,
+                    b
+                );
+            }
+        }
+
+        assert!(AudioFormat::from_path(Path::new("take.mp3")).is_err());
+        let spec = AudioSpec {
+            sample_rate: 48000,
+            channels: 1,
+            bits_per_sample: 32,
+        };
+        assert!(AudioFileWriter::create(&dir.join("float.flac"), spec).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
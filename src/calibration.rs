@@ -0,0 +1,390 @@
+//! Round-trip latency calibration.
+//!
+//! A test signal (a maximum-length sequence or a single click) is played on
+//! the default output while the default input records. With a loopback
+//! cable, or a mic near the speakers, the recording contains the signal
+//! again; cross-correlating it against what was played finds the delay to
+//! the sample. Both streams note when their first callback ran, so the
+//! result is the time from the app producing a sample to the app reading it
+//! back: device buffers, driver and converter delay together.
+//!
+//! An MLS spreads its energy over the whole sequence, so it survives room
+//! noise and quiet levels far better than a click and gives a sharp
+//! correlation peak without being loud.
+
+#![allow(dead_code)] // Complete calibration API, used by the GUI and IPC
+
+use anyhow::{Context, Result, bail};
+use cpal::StreamConfig;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use realfft::RealFftPlanner;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 2^15 - 1 samples, about 0.7 s at 48 kHz
+const MLS_ORDER: u32 = 15;
+
+/// Silence played before the signal so both streams have settled
+const LEAD_IN_SECS: f32 = 0.25;
+
+/// Longest round trip looked for after the signal
+const MAX_ROUND_TRIP_SECS: f32 = 1.0;
+
+/// Correlation peak over its RMS below which the signal counts as missing
+const MIN_CONFIDENCE: f32 = 8.0;
+
+/// Default level of the test signal, -12 dBFS
+pub const DEFAULT_LEVEL: f32 = 0.25;
+
+/// Signal played to find the round trip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TestSignal {
+    /// A single full-amplitude sample
+    Impulse,
+    /// Maximum-length sequence, robust against noise
+    #[default]
+    Mls,
+}
+
+impl TestSignal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Impulse => "impulse",
+            Self::Mls => "mls",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "impulse" => Some(Self::Impulse),
+            "mls" => Some(Self::Mls),
+            _ => None,
+        }
+    }
+
+    /// The signal at `level`, before lead-in
+    pub fn samples(&self, level: f32) -> Vec<f32> {
+        match self {
+            Self::Impulse => vec![level],
+            Self::Mls => mls(MLS_ORDER).into_iter().map(|s| s * level).collect(),
+        }
+    }
+}
+
+/// Maximum-length sequence of ±1 from a Fibonacci LFSR, 2^order - 1 long
+pub fn mls(order: u32) -> Vec<f32> {
+    // Primitive polynomial taps (1-based bit positions) per order
+    let taps: &[u32] = match order {
+        10 => &[10, 7],
+        12 => &[12, 6, 4, 1],
+        14 => &[14, 5, 3, 1],
+        16 => &[16, 15, 13, 4],
+        _ => &[15, 14],
+    };
+    let order = taps[0];
+    let len = (1usize << order) - 1;
+
+    let mut state: u32 = 1;
+    (0..len)
+        .map(|_| {
+            let out = state & 1;
+            let feedback = taps
+                .iter()
+                .fold(0, |acc, &tap| acc ^ (state >> (order - tap)));
+            state = (state >> 1) | ((feedback & 1) << (order - 1));
+            if out == 1 { 1.0 } else { -1.0 }
+        })
+        .collect()
+}
+
+/// Where `reference` starts in `recorded`, with the correlation peak over
+/// its RMS as confidence. None when the recording is silent.
+pub fn find_delay(reference: &[f32], recorded: &[f32]) -> Option<(usize, f32)> {
+    if reference.is_empty() || recorded.len() < reference.len() {
+        return None;
+    }
+
+    let size = (reference.len() + recorded.len()).next_power_of_two();
+    let mut planner = RealFftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(size);
+    let inverse = planner.plan_fft_inverse(size);
+
+    let spectrum = |signal: &[f32]| {
+        let mut padded = vec![0.0; size];
+        padded[..signal.len()].copy_from_slice(signal);
+        let mut out = forward.make_output_vec();
+        forward.process(&mut padded, &mut out).ok()?;
+        Some(out)
+    };
+    let recorded_spectrum = spectrum(recorded)?;
+    let reference_spectrum = spectrum(reference)?;
+
+    // Cross-correlation: recorded times the conjugate of the reference
+    let mut product: Vec<_> = recorded_spectrum
+        .iter()
+        .zip(&reference_spectrum)
+        .map(|(r, s)| r * s.conj())
+        .collect();
+    let mut correlation = vec![0.0; size];
+    inverse.process(&mut product, &mut correlation).ok()?;
+
+    // Only lags where the whole reference fits in the recording
+    let lags = &correlation[..=recorded.len() - reference.len()];
+    let (lag, peak) = lags
+        .iter()
+        .map(|c| c.abs())
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let rms = (lags.iter().map(|c| c * c).sum::<f32>() / lags.len() as f32).sqrt();
+    if peak <= 0.0 || rms <= 0.0 {
+        return None;
+    }
+    Some((lag, peak / rms))
+}
+
+/// Result of one calibration run
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundTripMeasurement {
+    pub signal: TestSignal,
+    pub sample_rate: u32,
+    pub round_trip_samples: usize,
+    pub round_trip_ms: f32,
+    /// What the configured buffers promise, for comparison
+    pub expected_ms: f32,
+    /// Measured minus expected; the driver and converters' share
+    pub difference_ms: f32,
+    pub confidence: f32,
+}
+
+impl RoundTripMeasurement {
+    pub fn calibration(&self) -> LatencyCalibration {
+        LatencyCalibration {
+            round_trip_samples: self.round_trip_samples,
+            sample_rate: self.sample_rate,
+        }
+    }
+}
+
+/// A stored measurement, used in place of the buffer estimate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyCalibration {
+    pub round_trip_samples: usize,
+    pub sample_rate: u32,
+}
+
+impl LatencyCalibration {
+    /// The round trip at another stream rate
+    pub fn samples_at(&self, sample_rate: u32) -> usize {
+        (self.round_trip_samples as u64 * sample_rate as u64 / self.sample_rate.max(1) as u64)
+            as usize
+    }
+
+    pub fn round_trip_ms(&self) -> f32 {
+        self.round_trip_samples as f32 * 1000.0 / self.sample_rate.max(1) as f32
+    }
+}
+
+/// Play `signal` on the default output, record the default input and
+/// measure the round trip. `expected_ms` is the configured round trip the
+/// measurement is compared to. Blocks for about two seconds; the engine
+/// must not hold the devices.
+pub fn measure_round_trip(
+    signal: TestSignal,
+    level: f32,
+    expected_ms: f32,
+) -> Result<RoundTripMeasurement> {
+    let host = cpal::default_host();
+    let output_device = host
+        .default_output_device()
+        .context("No output device available")?;
+    let input_device = host
+        .default_input_device()
+        .context("No input device available")?;
+    let output_config: StreamConfig = output_device.default_output_config()?.into();
+    let input_config: StreamConfig = input_device.default_input_config()?.into();
+    if output_config.sample_rate != input_config.sample_rate {
+        bail!(
+            "Input runs at {} Hz and output at {} Hz; set both to the same rate",
+            input_config.sample_rate,
+            output_config.sample_rate
+        );
+    }
+    let sample_rate = output_config.sample_rate;
+
+    let reference = signal.samples(level.clamp(0.01, 1.0));
+    let lead_in = (LEAD_IN_SECS * sample_rate as f32) as usize;
+    let capture_len =
+        lead_in + reference.len() + (MAX_ROUND_TRIP_SECS * sample_rate as f32) as usize;
+
+    let output_started: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+    let input_started: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+    let recorded = Arc::new(Mutex::new(Vec::with_capacity(capture_len)));
+    let (done_tx, done_rx) = crossbeam_channel::bounded::<()>(1);
+
+    let output_channels = (output_config.channels as usize).max(1);
+    let played = reference.clone();
+    let started = Arc::clone(&output_started);
+    let mut position = 0usize;
+    let output_stream = output_device.build_output_stream(
+        &output_config,
+        move |data: &mut [f32], _| {
+            if let Ok(mut started) = started.lock() {
+                started.get_or_insert_with(Instant::now);
+            }
+            for frame in data.chunks_mut(output_channels) {
+                let sample = position
+                    .checked_sub(lead_in)
+                    .and_then(|i| played.get(i))
+                    .copied()
+                    .unwrap_or(0.0);
+                frame.iter_mut().for_each(|s| *s = sample);
+                position += 1;
+            }
+        },
+        |err| log::warn!("Calibration output error: {}", err),
+        None,
+    )?;
+
+    let input_channels = (input_config.channels as usize).max(1);
+    let started = Arc::clone(&input_started);
+    let capture = Arc::clone(&recorded);
+    let input_stream = input_device.build_input_stream(
+        &input_config,
+        move |data: &[f32], _| {
+            if let Ok(mut started) = started.lock() {
+                started.get_or_insert_with(Instant::now);
+            }
+            if let Ok(mut capture) = capture.lock() {
+                // A loopback may come back on any input, so sum them all
+                for frame in data.chunks(input_channels) {
+                    if capture.len() < capture_len {
+                        capture.push(frame.iter().sum::<f32>());
+                    }
+                }
+                if capture.len() >= capture_len {
+                    let _ = done_tx.try_send(());
+                }
+            }
+        },
+        |err| log::warn!("Calibration input error: {}", err),
+        None,
+    )?;
+
+    input_stream.play()?;
+    output_stream.play()?;
+    let timeout = Duration::from_secs_f32(capture_len as f32 / sample_rate as f32 + 3.0);
+    let finished = done_rx.recv_timeout(timeout);
+    drop(output_stream);
+    drop(input_stream);
+    if finished.is_err() {
+        bail!("No audio arrived from the input device");
+    }
+
+    let recorded = recorded
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Calibration capture lock poisoned"))?
+        .clone();
+    let (Some(output_start), Some(input_start)) = (
+        *output_started.lock().unwrap_or_else(|e| e.into_inner()),
+        *input_started.lock().unwrap_or_else(|e| e.into_inner()),
+    ) else {
+        bail!("Audio streams did not start");
+    };
+
+    let Some((lag, confidence)) =
+        find_delay(&reference, &recorded).filter(|&(_, confidence)| confidence >= MIN_CONFIDENCE)
+    else {
+        bail!("Test signal not found in the input; connect a loopback cable or raise the level");
+    };
+
+    // The signal left at output frame `lead_in` and arrived at input frame
+    // `lag`; the streams' clocks differ by when their first callbacks ran
+    let clock_offset = input_start
+        .saturating_duration_since(output_start)
+        .as_secs_f64()
+        - output_start
+            .saturating_duration_since(input_start)
+            .as_secs_f64();
+    let round_trip_secs = clock_offset + (lag as f64 - lead_in as f64) / sample_rate as f64;
+    if round_trip_secs < 0.0 {
+        bail!("Measured a negative round trip; the input may be monitoring the output directly");
+    }
+
+    let round_trip_samples = (round_trip_secs * sample_rate as f64).round() as usize;
+    let round_trip_ms = round_trip_secs as f32 * 1000.0;
+    Ok(RoundTripMeasurement {
+        signal,
+        sample_rate,
+        round_trip_samples,
+        round_trip_ms,
+        expected_ms,
+        difference_ms: round_trip_ms - expected_ms,
+        confidence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mls_is_maximal_and_balanced() {
+        let sequence = mls(MLS_ORDER);
+        assert_eq!(sequence.len(), (1 << MLS_ORDER) - 1);
+        // An m-sequence has exactly one more +1 than -1
+        assert_eq!(sequence.iter().sum::<f32>(), 1.0);
+
+        // Its circular autocorrelation is flat away from lag zero
+        let n = sequence.len();
+        for lag in [1, 17, 1000] {
+            let c: f32 = (0..n).map(|i| sequence[i] * sequence[(i + lag) % n]).sum();
+            assert_eq!(c, -1.0);
+        }
+    }
+
+    #[test]
+    fn test_find_delay_through_noise() {
+        let reference = TestSignal::Mls.samples(0.25);
+        let delay = 12_345;
+        let mut seed = 3u32;
+        let recorded: Vec<f32> = (0..delay + reference.len() + 4800)
+            .map(|i| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                let noise = (seed as f32 / u32::MAX as f32 - 0.5) * 0.1;
+                // Attenuated return, 20 dB down, buried in noise
+                let echo = i
+                    .checked_sub(delay)
+                    .and_then(|j| reference.get(j))
+                    .map_or(0.0, |s| s * 0.1);
+                echo + noise
+            })
+            .collect();
+
+        let (lag, confidence) = find_delay(&reference, &recorded).unwrap();
+        assert_eq!(lag, delay);
+        assert!(confidence >= MIN_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_find_delay_of_impulse_and_silence() {
+        let reference = TestSignal::Impulse.samples(0.5);
+        let mut recorded = vec![0.0; 9600];
+        recorded[777] = 0.4;
+        let (lag, confidence) = find_delay(&reference, &recorded).unwrap();
+        assert_eq!(lag, 777);
+        assert!(confidence >= MIN_CONFIDENCE);
+
+        assert!(find_delay(&reference, &vec![0.0; 9600]).is_none());
+    }
+
+    #[test]
+    fn test_calibration_scales_with_rate() {
+        let calibration = LatencyCalibration {
+            round_trip_samples: 960,
+            sample_rate: 48000,
+        };
+        assert_eq!(calibration.samples_at(96000), 1920);
+        assert!((calibration.round_trip_ms() - 20.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,1649 @@
+//! CLAP plugin hosting.
+//!
+//! Provides CLAP effect hosting alongside the VST2 host with:
+//! - Scanning `$CLAP_PATH`, `~/.clap` and `/usr/lib/clap` for `.clap` bundles
+//! - One processing thread per instance, driven by messages like the VST2 host
+//! - Planar buffers for every audio port; the main ports carry the strip and
+//!   the first other input port the sidechain
+//! - Parameter enumeration and changes through CLAP parameter events
+//! - State save and restore through the `clap.state` extension
+//!
+//! The instance never leaves its thread, which acts as both the CLAP main
+//! thread and audio thread; the plugin is activated once when loaded.
+
+#![allow(dead_code)] // Complete CLAP hosting API for plugin management
+
+use crate::plugin_bridge;
+use crate::plugin_host::{
+    self, BlockPipeline, ChannelLayout, ParameterChange, ParameterQuery, PluginFormat,
+    PluginProcessor,
+};
+use crate::transport::{self, TransportInfo};
+use crate::vst_host::{ParameterInfo, VstCategory, VstPluginInfo};
+use anyhow::{Context, Result, bail};
+use clap_sys::audio_buffer::clap_audio_buffer;
+use clap_sys::entry::clap_plugin_entry;
+use clap_sys::events::{
+    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_VALUE, CLAP_EVENT_TRANSPORT,
+    CLAP_TRANSPORT_HAS_BEATS_TIMELINE, CLAP_TRANSPORT_HAS_SECONDS_TIMELINE,
+    CLAP_TRANSPORT_HAS_TEMPO, CLAP_TRANSPORT_HAS_TIME_SIGNATURE, CLAP_TRANSPORT_IS_PLAYING,
+    clap_event_header, clap_event_param_value, clap_event_transport, clap_input_events,
+    clap_output_events,
+};
+use clap_sys::ext::audio_ports::{
+    CLAP_AUDIO_PORT_IS_MAIN, CLAP_EXT_AUDIO_PORTS, clap_audio_port_info, clap_plugin_audio_ports,
+};
+use clap_sys::ext::latency::{CLAP_EXT_LATENCY, clap_plugin_latency};
+use clap_sys::ext::params::{
+    CLAP_EXT_PARAMS, CLAP_PARAM_IS_HIDDEN, clap_param_info, clap_plugin_params,
+};
+use clap_sys::ext::state::{CLAP_EXT_STATE, clap_plugin_state};
+use clap_sys::factory::plugin_factory::{CLAP_PLUGIN_FACTORY_ID, clap_plugin_factory};
+use clap_sys::fixedpoint::{CLAP_BEATTIME_FACTOR, CLAP_SECTIME_FACTOR};
+use clap_sys::host::clap_host;
+use clap_sys::id::clap_id;
+use clap_sys::plugin::clap_plugin;
+use clap_sys::process::{CLAP_PROCESS_ERROR, clap_process};
+use clap_sys::stream::{clap_istream, clap_ostream};
+use clap_sys::version::{CLAP_VERSION, clap_version_is_compatible};
+use crossbeam_channel::{Receiver, Sender, bounded};
+use libloading::Library;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Largest block handed to the plugin; longer buffers are split
+const MAX_FRAMES: usize = 8192;
+
+const HOST_NAME: &CStr = c"PhantomLink";
+const HOST_VENDOR: &CStr = c"CK Technology LLC";
+const HOST_URL: &CStr = c"https://github.com/ghostkellz/phantomlink";
+const HOST_VERSION: &CStr = c"0.4.0";
+
+/// An opened `.clap` file; `deinit` runs before it is unloaded
+struct ClapLibrary {
+    entry: *const clap_plugin_entry,
+    // Dropped after `entry` is deinitialised
+    _library: Library,
+}
+
+impl ClapLibrary {
+    fn open(path: &Path) -> Result<Self> {
+        // Safety: loading a plugin runs its initialisers; that is the point
+        let library = unsafe { Library::new(path) }
+            .with_context(|| format!("Failed to load {}", path.display()))?;
+        let entry = unsafe {
+            *library
+                .get::<*const clap_plugin_entry>(b"clap_entry\0")
+                .context("Not a CLAP plugin: no clap_entry symbol")?
+        };
+        if entry.is_null() {
+            bail!("CLAP entry point is null");
+        }
+
+        let entry_ref = unsafe { &*entry };
+        if !clap_version_is_compatible(entry_ref.clap_version) {
+            bail!(
+                "Unsupported CLAP version {}.{}",
+                entry_ref.clap_version.major,
+                entry_ref.clap_version.minor
+            );
+        }
+        let c_path = CString::new(path.to_string_lossy().as_bytes())?;
+        let init = entry_ref.init.context("CLAP entry has no init")?;
+        if !unsafe { init(c_path.as_ptr()) } {
+            bail!("CLAP entry init failed");
+        }
+
+        Ok(Self {
+            entry,
+            _library: library,
+        })
+    }
+
+    fn factory(&self) -> Result<&clap_plugin_factory> {
+        let get_factory = unsafe { (*self.entry).get_factory }.context("No get_factory")?;
+        let factory =
+            unsafe { get_factory(CLAP_PLUGIN_FACTORY_ID.as_ptr()) } as *const clap_plugin_factory;
+        if factory.is_null() {
+            bail!("Bundle has no plugin factory");
+        }
+        Ok(unsafe { &*factory })
+    }
+
+    fn descriptors(&self) -> Result<Vec<ClapDescriptor>> {
+        let factory = self.factory()?;
+        let (Some(count), Some(get)) = (factory.get_plugin_count, factory.get_plugin_descriptor)
+        else {
+            bail!("Incomplete plugin factory");
+        };
+
+        let mut descriptors = Vec::new();
+        for index in 0..unsafe { count(factory) } {
+            let desc = unsafe { get(factory, index) };
+            if desc.is_null() {
+                continue;
+            }
+            let desc = unsafe { &*desc };
+            let mut features = Vec::new();
+            if !desc.features.is_null() {
+                let mut feature = desc.features;
+                while !unsafe { *feature }.is_null() {
+                    features.push(unsafe { c_string(*feature) });
+                    feature = unsafe { feature.add(1) };
+                }
+            }
+            descriptors.push(ClapDescriptor {
+                id: unsafe { c_string(desc.id) },
+                name: unsafe { c_string(desc.name) },
+                vendor: unsafe { c_string(desc.vendor) },
+                features,
+            });
+        }
+        Ok(descriptors)
+    }
+}
+
+impl Drop for ClapLibrary {
+    fn drop(&mut self) {
+        if let Some(deinit) = unsafe { (*self.entry).deinit } {
+            unsafe { deinit() };
+        }
+    }
+}
+
+/// What a bundle's factory says about one of its plugins
+#[derive(Debug, Clone)]
+struct ClapDescriptor {
+    id: String,
+    name: String,
+    vendor: String,
+    features: Vec<String>,
+}
+
+/// Copy a C string the plugin owns; null becomes empty
+unsafe fn c_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Fixed-size name buffer from a CLAP info struct
+fn c_array_string(chars: &[c_char]) -> String {
+    let bytes: Vec<u8> = chars
+        .iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Map CLAP feature tags onto the catalogue's categories
+pub fn category_from_features<S: AsRef<str>>(features: &[S]) -> VstCategory {
+    let has = |tag: &str| features.iter().any(|f| f.as_ref() == tag);
+    if has("instrument") {
+        VstCategory::Synth
+    } else if has("analyzer") {
+        VstCategory::Analysis
+    } else if has("mastering") {
+        VstCategory::Mastering
+    } else if has("restoration") {
+        VstCategory::Restoration
+    } else if has("reverb") {
+        VstCategory::RoomFx
+    } else if has("surround") || has("ambisonic") {
+        VstCategory::SurroundFx
+    } else if has("audio-effect") {
+        VstCategory::Effect
+    } else {
+        VstCategory::Unknown
+    }
+}
+
+// ===== Host callbacks =====
+//
+// The host offers no extensions; plugins must cope with that. Restart and
+// callback requests are noted and serviced between messages.
+
+#[derive(Debug, Default)]
+struct HostRequests {
+    restart: std::sync::atomic::AtomicBool,
+    callback: std::sync::atomic::AtomicBool,
+}
+
+unsafe fn host_requests<'a>(host: *const clap_host) -> Option<&'a HostRequests> {
+    unsafe { ((*host).host_data as *const HostRequests).as_ref() }
+}
+
+unsafe extern "C" fn host_get_extension(
+    _host: *const clap_host,
+    _extension_id: *const c_char,
+) -> *const c_void {
+    std::ptr::null()
+}
+
+unsafe extern "C" fn host_request_restart(host: *const clap_host) {
+    if let Some(requests) = unsafe { host_requests(host) } {
+        requests
+            .restart
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+unsafe extern "C" fn host_request_process(_host: *const clap_host) {
+    // Processing never stops while the plugin is loaded
+}
+
+unsafe extern "C" fn host_request_callback(host: *const clap_host) {
+    if let Some(requests) = unsafe { host_requests(host) } {
+        requests
+            .callback
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+// ===== Event lists and streams =====
+
+unsafe extern "C" fn input_events_size(list: *const clap_input_events) -> u32 {
+    let events = unsafe { &*((*list).ctx as *const Vec<clap_event_param_value>) };
+    events.len() as u32
+}
+
+unsafe extern "C" fn input_events_get(
+    list: *const clap_input_events,
+    index: u32,
+) -> *const clap_event_header {
+    let events = unsafe { &*((*list).ctx as *const Vec<clap_event_param_value>) };
+    events
+        .get(index as usize)
+        .map_or(std::ptr::null(), |event| &event.header as *const _)
+}
+
+/// The transport event passed with each process call
+fn transport_event(info: &TransportInfo) -> clap_event_transport {
+    let beats = |quarters: f64| (quarters * CLAP_BEATTIME_FACTOR as f64).round() as i64;
+    let mut flags = CLAP_TRANSPORT_HAS_TEMPO
+        | CLAP_TRANSPORT_HAS_BEATS_TIMELINE
+        | CLAP_TRANSPORT_HAS_SECONDS_TIMELINE
+        | CLAP_TRANSPORT_HAS_TIME_SIGNATURE;
+    if info.playing {
+        flags |= CLAP_TRANSPORT_IS_PLAYING;
+    }
+    clap_event_transport {
+        header: clap_event_header {
+            size: std::mem::size_of::<clap_event_transport>() as u32,
+            time: 0,
+            space_id: CLAP_CORE_EVENT_SPACE_ID,
+            type_: CLAP_EVENT_TRANSPORT,
+            flags: 0,
+        },
+        flags,
+        song_pos_beats: beats(info.quarter_notes()),
+        song_pos_seconds: (info.seconds() * CLAP_SECTIME_FACTOR as f64).round() as i64,
+        tempo: info.tempo,
+        tempo_inc: 0.0,
+        loop_start_beats: 0,
+        loop_end_beats: 0,
+        loop_start_seconds: 0,
+        loop_end_seconds: 0,
+        bar_start: beats(info.bar_start()),
+        bar_number: info.bar() as i32,
+        tsig_num: info.beats_per_bar as u16,
+        tsig_denom: info.beat_unit as u16,
+    }
+}
+
+/// Collects the parameter values a plugin sets itself, as (id, plain
+/// value); gestures and other events are ignored
+unsafe extern "C" fn output_events_try_push(
+    list: *const clap_output_events,
+    event: *const clap_event_header,
+) -> bool {
+    let values = unsafe { &mut *((*list).ctx as *mut Vec<(clap_id, f64)>) };
+    let header = unsafe { &*event };
+    if header.space_id == CLAP_CORE_EVENT_SPACE_ID && header.type_ == CLAP_EVENT_PARAM_VALUE {
+        let event = unsafe { &*(event as *const clap_event_param_value) };
+        values.push((event.param_id, event.value));
+    }
+    true
+}
+
+unsafe extern "C" fn ostream_write(
+    stream: *const clap_ostream,
+    buffer: *const c_void,
+    size: u64,
+) -> i64 {
+    let data = unsafe { &mut *((*stream).ctx as *mut Vec<u8>) };
+    let bytes = unsafe { std::slice::from_raw_parts(buffer as *const u8, size as usize) };
+    data.extend_from_slice(bytes);
+    size as i64
+}
+
+/// Reader over a byte slice for `clap_istream`
+struct ReadCursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+unsafe extern "C" fn istream_read(
+    stream: *const clap_istream,
+    buffer: *mut c_void,
+    size: u64,
+) -> i64 {
+    let cursor = unsafe { &mut *((*stream).ctx as *mut ReadCursor) };
+    let remaining = &cursor.data[cursor.pos..];
+    let count = remaining.len().min(size as usize);
+    unsafe { std::ptr::copy_nonoverlapping(remaining.as_ptr(), buffer as *mut u8, count) };
+    cursor.pos += count;
+    count as i64
+}
+
+// ===== Plugin instance =====
+
+/// A visible parameter, in the order the slot API indexes them
+#[derive(Debug, Clone)]
+struct ClapParam {
+    id: clap_id,
+    cookie: *mut c_void,
+    name: String,
+    min: f64,
+    max: f64,
+    default: f64,
+}
+
+impl ClapParam {
+    fn normalize(&self, value: f64) -> f32 {
+        if self.max > self.min {
+            ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0) as f32
+        } else {
+            0.0
+        }
+    }
+
+    fn denormalize(&self, value: f32) -> f64 {
+        self.min + value.clamp(0.0, 1.0) as f64 * (self.max - self.min)
+    }
+}
+
+/// Channel count of each input or output port
+#[derive(Debug, Default)]
+struct AudioPorts {
+    channels: Vec<u32>,
+    /// Port the strip's signal goes through
+    main: Option<usize>,
+}
+
+impl AudioPorts {
+    fn main_channels(&self) -> usize {
+        self.main.map_or(0, |main| self.channels[main] as usize)
+    }
+
+    /// First non-main port with channels, as (port, channels)
+    fn sidechain(&self) -> Option<(usize, usize)> {
+        self.channels
+            .iter()
+            .enumerate()
+            .find(|&(port, &channels)| Some(port) != self.main && channels > 0)
+            .map(|(port, &channels)| (port, channels as usize))
+    }
+}
+
+/// A live plugin; lives and dies on its processing thread
+struct ClapInstance {
+    plugin: *const clap_plugin,
+    params_ext: *const clap_plugin_params,
+    state_ext: *const clap_plugin_state,
+    latency_ext: *const clap_plugin_latency,
+    params: Vec<ClapParam>,
+    inputs: AudioPorts,
+    outputs: AudioPorts,
+    active: bool,
+    processing: bool,
+    steady_time: i64,
+    /// Transport at the start of the next block
+    transport: TransportInfo,
+    /// Parameter changes waiting for the next process or flush
+    pending: Vec<clap_event_param_value>,
+    /// Parameter changes the plugin made itself, until the processing
+    /// thread passes them on
+    automation: Vec<ParameterChange>,
+    // Must outlive the plugin, so declared (and dropped) after it
+    host: Box<clap_host>,
+    requests: Box<HostRequests>,
+    library: Rc<ClapLibrary>,
+}
+
+impl ClapInstance {
+    fn create(library: Rc<ClapLibrary>, plugin_id: &str) -> Result<Self> {
+        let requests = Box::new(HostRequests::default());
+        let host = Box::new(clap_host {
+            clap_version: CLAP_VERSION,
+            host_data: &*requests as *const HostRequests as *mut c_void,
+            name: HOST_NAME.as_ptr(),
+            vendor: HOST_VENDOR.as_ptr(),
+            url: HOST_URL.as_ptr(),
+            version: HOST_VERSION.as_ptr(),
+            get_extension: Some(host_get_extension),
+            request_restart: Some(host_request_restart),
+            request_process: Some(host_request_process),
+            request_callback: Some(host_request_callback),
+        });
+
+        let factory = library.factory()?;
+        let create = factory
+            .create_plugin
+            .context("Factory cannot create plugins")?;
+        let c_id = CString::new(plugin_id)?;
+        let plugin = unsafe { create(factory, &*host, c_id.as_ptr()) };
+        if plugin.is_null() {
+            bail!("Factory refused to create {}", plugin_id);
+        }
+
+        let mut instance = Self {
+            plugin,
+            params_ext: std::ptr::null(),
+            state_ext: std::ptr::null(),
+            latency_ext: std::ptr::null(),
+            params: Vec::new(),
+            inputs: AudioPorts::default(),
+            outputs: AudioPorts::default(),
+            active: false,
+            processing: false,
+            steady_time: 0,
+            transport: TransportInfo::default(),
+            pending: Vec::new(),
+            automation: Vec::new(),
+            host,
+            requests,
+            library,
+        };
+
+        let init = instance.plugin().init.context("Plugin has no init")?;
+        if !unsafe { init(plugin) } {
+            bail!("Plugin init failed");
+        }
+
+        instance.params_ext = instance.extension(CLAP_EXT_PARAMS) as *const clap_plugin_params;
+        instance.state_ext = instance.extension(CLAP_EXT_STATE) as *const clap_plugin_state;
+        instance.latency_ext = instance.extension(CLAP_EXT_LATENCY) as *const clap_plugin_latency;
+        instance.params = instance.query_params();
+        instance.inputs = instance.audio_ports(true);
+        instance.outputs = instance.audio_ports(false);
+        Ok(instance)
+    }
+
+    fn plugin(&self) -> &clap_plugin {
+        unsafe { &*self.plugin }
+    }
+
+    fn extension(&self, id: &CStr) -> *const c_void {
+        match self.plugin().get_extension {
+            Some(get) => unsafe { get(self.plugin, id.as_ptr()) },
+            None => std::ptr::null(),
+        }
+    }
+
+    fn query_params(&self) -> Vec<ClapParam> {
+        let Some(params) = (unsafe { self.params_ext.as_ref() }) else {
+            return Vec::new();
+        };
+        let (Some(count), Some(get_info)) = (params.count, params.get_info) else {
+            return Vec::new();
+        };
+
+        (0..unsafe { count(self.plugin) })
+            .filter_map(|index| {
+                let mut info: clap_param_info = unsafe { std::mem::zeroed() };
+                if !unsafe { get_info(self.plugin, index, &mut info) }
+                    || info.flags & CLAP_PARAM_IS_HIDDEN != 0
+                {
+                    return None;
+                }
+                Some(ClapParam {
+                    id: info.id,
+                    cookie: info.cookie,
+                    name: c_array_string(&info.name),
+                    min: info.min_value,
+                    max: info.max_value,
+                    default: info.default_value,
+                })
+            })
+            .collect()
+    }
+
+    /// The input or output ports and which one is main
+    fn audio_ports(&self, is_input: bool) -> AudioPorts {
+        let ports = self.extension(CLAP_EXT_AUDIO_PORTS) as *const clap_plugin_audio_ports;
+        let Some(ports) = (unsafe { ports.as_ref() }) else {
+            // No ports extension: assume a plain stereo effect
+            return AudioPorts {
+                channels: vec![2],
+                main: Some(0),
+            };
+        };
+        let (Some(count), Some(get)) = (ports.count, ports.get) else {
+            return AudioPorts::default();
+        };
+
+        let mut audio_ports = AudioPorts::default();
+        for index in 0..unsafe { count(self.plugin, is_input) } {
+            let mut info: clap_audio_port_info = unsafe { std::mem::zeroed() };
+            let channels = if unsafe { get(self.plugin, index, is_input, &mut info) } {
+                if info.flags & CLAP_AUDIO_PORT_IS_MAIN != 0 && audio_ports.main.is_none() {
+                    audio_ports.main = Some(index as usize);
+                }
+                info.channel_count
+            } else {
+                0
+            };
+            audio_ports.channels.push(channels);
+        }
+        // Without a flagged main port the first one is used
+        if audio_ports.main.is_none() && !audio_ports.channels.is_empty() {
+            audio_ports.main = Some(0);
+        }
+        audio_ports
+    }
+
+    fn channel_layout(&self) -> ChannelLayout {
+        ChannelLayout::new(self.inputs.main_channels(), self.outputs.main_channels())
+            .with_sidechain(self.inputs.sidechain().map_or(0, |(_, channels)| channels))
+    }
+
+    fn activate(&mut self, sample_rate: f64) -> Result<()> {
+        let activate = self.plugin().activate.context("Plugin has no activate")?;
+        if !unsafe { activate(self.plugin, sample_rate, 1, MAX_FRAMES as u32) } {
+            bail!("Plugin refused to activate at {} Hz", sample_rate);
+        }
+        self.active = true;
+        if let Some(start) = self.plugin().start_processing {
+            self.processing = unsafe { start(self.plugin) };
+        }
+        Ok(())
+    }
+
+    fn latency_samples(&self) -> usize {
+        match unsafe { self.latency_ext.as_ref() }.and_then(|l| l.get) {
+            Some(get) if self.active => (unsafe { get(self.plugin) }) as usize,
+            _ => 0,
+        }
+    }
+
+    /// Run planar audio through the plugin: the main channels into the main
+    /// input port, the sidechain into the first other one, silence elsewhere
+    fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+        let layout = self.channel_layout();
+        let frames_total = inputs.first().map_or(0, |input| input.len());
+        let process = match self.plugin().process {
+            Some(process) if self.processing && layout.outputs > 0 => process,
+            _ => return plugin_host::passthrough(layout, inputs, frames_total),
+        };
+        let sidechain_port = self.inputs.sidechain().map(|(port, _)| port);
+
+        let mut output: Vec<Vec<f32>> = (0..layout.outputs)
+            .map(|_| Vec::with_capacity(frames_total))
+            .collect();
+        for start in (0..frames_total).step_by(MAX_FRAMES) {
+            let frames = (frames_total - start).min(MAX_FRAMES);
+            let block = |channel: usize| -> Vec<f32> {
+                inputs
+                    .get(channel)
+                    .and_then(|input| input.get(start..start + frames))
+                    .map_or_else(|| vec![0.0; frames], <[f32]>::to_vec)
+            };
+
+            // Every port needs buffers, whether or not the strip feeds it
+            let mut port_inputs: Vec<Vec<Vec<f32>>> = self
+                .inputs
+                .channels
+                .iter()
+                .enumerate()
+                .map(|(port, &channels)| {
+                    let first = if Some(port) == self.inputs.main {
+                        Some(0)
+                    } else if Some(port) == sidechain_port {
+                        Some(layout.inputs)
+                    } else {
+                        None
+                    };
+                    (0..channels as usize)
+                        .map(|channel| match first {
+                            Some(first) => block(first + channel),
+                            None => vec![0.0; frames],
+                        })
+                        .collect()
+                })
+                .collect();
+            let mut port_outputs: Vec<Vec<Vec<f32>>> = self
+                .outputs
+                .channels
+                .iter()
+                .map(|&channels| (0..channels).map(|_| vec![0.0; frames]).collect())
+                .collect();
+            let mut input_ptrs: Vec<Vec<*mut f32>> = port_inputs
+                .iter_mut()
+                .map(|port| port.iter_mut().map(|b| b.as_mut_ptr()).collect())
+                .collect();
+            let mut output_ptrs: Vec<Vec<*mut f32>> = port_outputs
+                .iter_mut()
+                .map(|port| port.iter_mut().map(|b| b.as_mut_ptr()).collect())
+                .collect();
+            let audio_buffer = |ptrs: &mut Vec<*mut f32>| clap_audio_buffer {
+                data32: ptrs.as_mut_ptr(),
+                data64: std::ptr::null_mut(),
+                channel_count: ptrs.len() as u32,
+                latency: 0,
+                constant_mask: 0,
+            };
+            let input_buffers: Vec<clap_audio_buffer> =
+                input_ptrs.iter_mut().map(audio_buffer).collect();
+            let mut output_buffers: Vec<clap_audio_buffer> =
+                output_ptrs.iter_mut().map(audio_buffer).collect();
+
+            // Parameter changes apply from the first sample of this block
+            let events = std::mem::take(&mut self.pending);
+            let in_events = clap_input_events {
+                ctx: &events as *const Vec<clap_event_param_value> as *mut c_void,
+                size: Some(input_events_size),
+                get: Some(input_events_get),
+            };
+            let mut outgoing: Vec<(clap_id, f64)> = Vec::new();
+            let out_events = clap_output_events {
+                ctx: &mut outgoing as *mut Vec<(clap_id, f64)> as *mut c_void,
+                try_push: Some(output_events_try_push),
+            };
+
+            let transport = transport_event(&self.transport.offset(start));
+            let context = clap_process {
+                steady_time: self.steady_time,
+                frames_count: frames as u32,
+                transport: &transport,
+                audio_inputs: input_buffers.as_ptr(),
+                audio_outputs: output_buffers.as_mut_ptr(),
+                audio_inputs_count: input_buffers.len() as u32,
+                audio_outputs_count: output_buffers.len() as u32,
+                in_events: &in_events,
+                out_events: &out_events,
+            };
+            // Safety: every buffer and list outlives the call
+            let status = unsafe { process(self.plugin, &context) };
+            self.steady_time += frames as i64;
+            self.record_automation(outgoing);
+
+            let main = match self.outputs.main {
+                Some(main) if status != CLAP_PROCESS_ERROR => {
+                    std::mem::take(&mut port_outputs[main])
+                }
+                _ => {
+                    let dry: Vec<&[f32]> = inputs
+                        .iter()
+                        .filter_map(|input| input.get(start..start + frames))
+                        .collect();
+                    plugin_host::passthrough(layout, &dry, frames)
+                }
+            };
+            for (output, block) in output.iter_mut().zip(main) {
+                output.extend(block);
+            }
+        }
+        output
+    }
+
+    fn queue_parameter(&mut self, index: i32, value: f32) {
+        let Some(param) = usize::try_from(index).ok().and_then(|i| self.params.get(i)) else {
+            return;
+        };
+        self.pending.push(clap_event_param_value {
+            header: clap_event_header {
+                size: std::mem::size_of::<clap_event_param_value>() as u32,
+                time: 0,
+                space_id: CLAP_CORE_EVENT_SPACE_ID,
+                type_: CLAP_EVENT_PARAM_VALUE,
+                flags: 0,
+            },
+            param_id: param.id,
+            cookie: param.cookie,
+            note_id: -1,
+            port_index: -1,
+            channel: -1,
+            key: -1,
+            value: param.denormalize(value),
+        });
+    }
+
+    /// Hand queued parameter changes over without processing audio
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let events = std::mem::take(&mut self.pending);
+        let Some(flush) = (unsafe { self.params_ext.as_ref() }).and_then(|p| p.flush) else {
+            return;
+        };
+        let in_events = clap_input_events {
+            ctx: &events as *const Vec<clap_event_param_value> as *mut c_void,
+            size: Some(input_events_size),
+            get: Some(input_events_get),
+        };
+        let mut outgoing: Vec<(clap_id, f64)> = Vec::new();
+        let out_events = clap_output_events {
+            ctx: &mut outgoing as *mut Vec<(clap_id, f64)> as *mut c_void,
+            try_push: Some(output_events_try_push),
+        };
+        unsafe { flush(self.plugin, &in_events, &out_events) };
+        self.record_automation(outgoing);
+    }
+
+    /// Turn output events into normalised changes by parameter index
+    fn record_automation(&mut self, values: Vec<(clap_id, f64)>) {
+        for (id, value) in values {
+            if let Some(index) = self.params.iter().position(|param| param.id == id) {
+                self.automation.push(ParameterChange {
+                    index: index as i32,
+                    value: self.params[index].normalize(value),
+                });
+            }
+        }
+    }
+
+    fn parameter_value(&self, param: &ClapParam) -> f32 {
+        let Some(get_value) = (unsafe { self.params_ext.as_ref() }).and_then(|p| p.get_value)
+        else {
+            return param.normalize(param.default);
+        };
+        let mut value = param.default;
+        unsafe { get_value(self.plugin, param.id, &mut value) };
+        param.normalize(value)
+    }
+
+    fn parameter_values(&self) -> HashMap<i32, f32> {
+        self.params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| (i as i32, self.parameter_value(param)))
+            .collect()
+    }
+
+    fn parameter_info(&self, index: i32) -> Option<ParameterInfo> {
+        let param = self.params.get(usize::try_from(index).ok()?)?;
+        let mut value = param.default;
+        let params_ext = unsafe { self.params_ext.as_ref() }?;
+        if let Some(get_value) = params_ext.get_value {
+            unsafe { get_value(self.plugin, param.id, &mut value) };
+        }
+
+        let mut text = [0 as c_char; 256];
+        let display = match params_ext.value_to_text {
+            Some(to_text)
+                if unsafe {
+                    to_text(
+                        self.plugin,
+                        param.id,
+                        value,
+                        text.as_mut_ptr(),
+                        text.len() as u32,
+                    )
+                } =>
+            {
+                c_array_string(&text)
+            }
+            _ => format!("{:.2}", value),
+        };
+
+        Some(ParameterInfo {
+            index,
+            name: param.name.clone(),
+            // CLAP has no separate unit; the display text carries it
+            label: String::new(),
+            value: param.normalize(value),
+            display,
+        })
+    }
+
+    /// Every parameter's info, generic for any the plugin cannot describe
+    fn parameter_infos(&self) -> Vec<ParameterInfo> {
+        self.params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| {
+                self.parameter_info(i as i32).unwrap_or_else(|| {
+                    plugin_host::generic_parameter(i as i32, param.normalize(param.default))
+                })
+            })
+            .collect()
+    }
+
+    fn save_state(&mut self) -> Result<Vec<u8>> {
+        self.flush();
+        let state = unsafe { self.state_ext.as_ref() }
+            .and_then(|s| s.save)
+            .context("Plugin does not support saving state")?;
+        let mut data = Vec::new();
+        let stream = clap_ostream {
+            ctx: &mut data as *mut Vec<u8> as *mut c_void,
+            write: Some(ostream_write),
+        };
+        if !unsafe { state(self.plugin, &stream) } {
+            bail!("Plugin failed to save its state");
+        }
+        Ok(data)
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        // Queued changes predate the state and would undo part of it
+        self.pending.clear();
+        let load = unsafe { self.state_ext.as_ref() }
+            .and_then(|s| s.load)
+            .context("Plugin does not support loading state")?;
+        let mut cursor = ReadCursor { data, pos: 0 };
+        let stream = clap_istream {
+            ctx: &mut cursor as *mut ReadCursor as *mut c_void,
+            read: Some(istream_read),
+        };
+        if !unsafe { load(self.plugin, &stream) } {
+            bail!("Plugin rejected the saved state");
+        }
+        Ok(())
+    }
+
+    /// Run main-thread callbacks the plugin asked for
+    fn service_requests(&mut self, sample_rate: f64) {
+        use std::sync::atomic::Ordering;
+        if self.requests.callback.swap(false, Ordering::Relaxed)
+            && let Some(on_main_thread) = self.plugin().on_main_thread
+        {
+            unsafe { on_main_thread(self.plugin) };
+        }
+        if self.requests.restart.swap(false, Ordering::Relaxed) {
+            self.deactivate();
+            if let Err(e) = self.activate(sample_rate) {
+                log::error!("CLAP plugin restart failed: {:#}", e);
+            }
+        }
+    }
+
+    fn deactivate(&mut self) {
+        if self.processing
+            && let Some(stop) = self.plugin().stop_processing
+        {
+            unsafe { stop(self.plugin) };
+        }
+        self.processing = false;
+        if self.active
+            && let Some(deactivate) = self.plugin().deactivate
+        {
+            unsafe { deactivate(self.plugin) };
+        }
+        self.active = false;
+    }
+}
+
+impl Drop for ClapInstance {
+    fn drop(&mut self) {
+        self.deactivate();
+        if let Some(destroy) = self.plugin().destroy {
+            unsafe { destroy(self.plugin) };
+        }
+    }
+}
+
+// ===== Processor =====
+
+/// Message types for CLAP processor thread communication
+enum ClapMessage {
+    ProcessAudio {
+        input: Vec<Vec<f32>>,
+        transport: TransportInfo,
+        response: Sender<Vec<Vec<f32>>>,
+    },
+    SetParameter {
+        index: i32,
+        value: f32,
+    },
+    GetParameterInfo {
+        index: i32,
+        response: Sender<Option<ParameterInfo>>,
+    },
+    GetAllParameterInfo {
+        response: Sender<Vec<ParameterInfo>>,
+    },
+    GetAllParameters {
+        response: Sender<HashMap<i32, f32>>,
+    },
+    SaveState {
+        response: Sender<Result<Vec<u8>>>,
+    },
+    LoadState {
+        data: Vec<u8>,
+        response: Sender<Result<HashMap<i32, f32>>>,
+    },
+    /// Re-activate at a new rate; answers with the latency reported after
+    SetSampleRate {
+        sample_rate: f64,
+        response: Sender<Result<usize>>,
+    },
+    Shutdown,
+}
+
+/// What the processing thread reports once the plugin is running
+struct LoadedPlugin {
+    name: String,
+    parameters: HashMap<i32, f32>,
+    latency: usize,
+    layout: ChannelLayout,
+}
+
+/// CLAP plugin in a channel slot, processed on its own thread
+pub struct ClapProcessor {
+    plugin_name: String,
+    plugin_path: PathBuf,
+    plugin_id: Option<String>,
+    enabled: bool,
+    parameters: HashMap<i32, f32>,
+    /// Delay the plugin reports through `clap.latency`, in samples
+    latency: usize,
+    layout: ChannelLayout,
+    /// Keeps the processing thread one buffer behind the callback
+    pipeline: BlockPipeline,
+    /// Answer to the block submitted last callback
+    pending: Option<Receiver<Vec<Vec<f32>>>>,
+    /// Parameter changes the plugin made itself
+    automation: Receiver<ParameterChange>,
+    message_sender: Option<Sender<ClapMessage>>,
+    processing_thread: Option<std::thread::JoinHandle<()>>,
+    sample_rate: f32,
+}
+
+impl ClapProcessor {
+    /// Load a plugin from a bundle; `plugin_id` picks one when the bundle
+    /// holds several, otherwise the first is used
+    pub fn load(plugin_path: &Path, plugin_id: Option<&str>, sample_rate: f32) -> Result<Self> {
+        let (message_sender, message_receiver) = bounded::<ClapMessage>(64);
+        let (loaded_sender, loaded_receiver) = bounded::<Result<LoadedPlugin>>(1);
+        let (automation_sender, automation) = bounded(plugin_host::AUTOMATION_QUEUE);
+
+        let path = plugin_path.to_path_buf();
+        let id = plugin_id.map(str::to_string);
+        let processing_thread = std::thread::Builder::new()
+            .name("clap-processor".to_string())
+            .spawn(move || {
+                Self::processor_thread_main(
+                    path,
+                    id,
+                    sample_rate as f64,
+                    message_receiver,
+                    loaded_sender,
+                    automation_sender,
+                );
+            })?;
+
+        let loaded = loaded_receiver
+            .recv()
+            .context("CLAP processing thread exited while loading")?;
+        let loaded = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                let _ = processing_thread.join();
+                return Err(e.context(format!("Failed to load {}", plugin_path.display())));
+            }
+        };
+
+        Ok(Self {
+            plugin_name: loaded.name,
+            plugin_path: plugin_path.to_path_buf(),
+            plugin_id: plugin_id.map(str::to_string),
+            enabled: true,
+            parameters: loaded.parameters,
+            latency: loaded.latency,
+            layout: loaded.layout,
+            pipeline: BlockPipeline::new(),
+            pending: None,
+            automation,
+            message_sender: Some(message_sender),
+            processing_thread: Some(processing_thread),
+            sample_rate,
+        })
+    }
+
+    fn instantiate(
+        path: &Path,
+        plugin_id: Option<&str>,
+        sample_rate: f64,
+    ) -> Result<(ClapInstance, String)> {
+        let library = Rc::new(ClapLibrary::open(path)?);
+        let descriptors = library.descriptors()?;
+        let descriptor = match plugin_id {
+            Some(id) => descriptors.iter().find(|d| d.id == id),
+            None => descriptors.first(),
+        }
+        .with_context(|| match plugin_id {
+            Some(id) => format!("Bundle has no plugin {}", id),
+            None => "Bundle contains no plugins".to_string(),
+        })?;
+        let name = descriptor.name.clone();
+
+        let mut instance = ClapInstance::create(Rc::clone(&library), &descriptor.id)?;
+        instance.activate(sample_rate)?;
+        Ok((instance, name))
+    }
+
+    /// Main loop for the processor thread
+    fn processor_thread_main(
+        plugin_path: PathBuf,
+        plugin_id: Option<String>,
+        mut sample_rate: f64,
+        message_receiver: Receiver<ClapMessage>,
+        loaded: Sender<Result<LoadedPlugin>>,
+        automation: Sender<ParameterChange>,
+    ) {
+        let mut instance = match Self::instantiate(&plugin_path, plugin_id.as_deref(), sample_rate)
+        {
+            Ok((instance, name)) => {
+                let _ = loaded.send(Ok(LoadedPlugin {
+                    name,
+                    parameters: instance.parameter_values(),
+                    latency: instance.latency_samples(),
+                    layout: instance.channel_layout(),
+                }));
+                instance
+            }
+            Err(e) => {
+                let _ = loaded.send(Err(e));
+                return;
+            }
+        };
+
+        while let Ok(message) = message_receiver.recv() {
+            instance.service_requests(sample_rate);
+            match message {
+                ClapMessage::ProcessAudio {
+                    input,
+                    transport,
+                    response,
+                } => {
+                    instance.transport = transport;
+                    let inputs: Vec<&[f32]> = input.iter().map(Vec::as_slice).collect();
+                    let _ = response.send(instance.process(&inputs));
+                }
+                ClapMessage::SetParameter { index, value } => {
+                    instance.queue_parameter(index, value);
+                }
+                ClapMessage::GetParameterInfo { index, response } => {
+                    instance.flush();
+                    let _ = response.send(instance.parameter_info(index));
+                }
+                ClapMessage::GetAllParameterInfo { response } => {
+                    instance.flush();
+                    let _ = response.send(instance.parameter_infos());
+                }
+                ClapMessage::GetAllParameters { response } => {
+                    instance.flush();
+                    let _ = response.send(instance.parameter_values());
+                }
+                ClapMessage::SaveState { response } => {
+                    let _ = response.send(instance.save_state());
+                }
+                ClapMessage::LoadState { data, response } => {
+                    let result = instance
+                        .load_state(&data)
+                        .map(|()| instance.parameter_values());
+                    let _ = response.send(result);
+                }
+                ClapMessage::SetSampleRate {
+                    sample_rate: rate,
+                    response,
+                } => {
+                    instance.deactivate();
+                    let result = instance.activate(rate);
+                    if result.is_ok() {
+                        sample_rate = rate;
+                    } else if let Err(e) = instance.activate(sample_rate) {
+                        log::error!("CLAP plugin could not be re-activated: {:#}", e);
+                    }
+                    let _ = response.send(result.map(|()| instance.latency_samples()));
+                }
+                ClapMessage::Shutdown => break,
+            }
+            for change in instance.automation.drain(..) {
+                let _ = automation.try_send(change);
+            }
+        }
+    }
+
+    fn request<T>(
+        &self,
+        message: impl FnOnce(Sender<T>) -> ClapMessage,
+        timeout_ms: u64,
+    ) -> Option<T> {
+        let sender = self.message_sender.as_ref()?;
+        let (response_sender, response_receiver) = bounded(1);
+        sender.try_send(message(response_sender)).ok()?;
+        response_receiver
+            .recv_timeout(std::time::Duration::from_millis(timeout_ms))
+            .ok()
+    }
+
+    pub fn get_plugin_path(&self) -> &Path {
+        &self.plugin_path
+    }
+
+    pub fn get_plugin_id(&self) -> Option<&str> {
+        self.plugin_id.as_deref()
+    }
+
+    /// Get all parameter values from the plugin (blocking)
+    pub fn get_all_parameters(&self) -> HashMap<i32, f32> {
+        self.request(|response| ClapMessage::GetAllParameters { response }, 100)
+            .unwrap_or_else(|| self.parameters.clone())
+    }
+
+    /// Sync local parameter cache with the plugin's values
+    pub fn sync_parameters(&mut self) {
+        self.parameters = self.get_all_parameters();
+    }
+
+    pub fn get_sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+}
+
+impl PluginProcessor for ClapProcessor {
+    fn format(&self) -> PluginFormat {
+        PluginFormat::Clap
+    }
+
+    fn channel_layout(&self) -> ChannelLayout {
+        self.layout
+    }
+
+    fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+        let frames = inputs.first().map_or(0, |input| input.len());
+        if !self.enabled || frames == 0 {
+            self.pipeline.reset();
+            self.pending = None;
+            return plugin_host::passthrough(self.layout, inputs, frames);
+        }
+
+        // The thread works one buffer behind; a late block plays dry
+        let pending = self.pending.take();
+        let previous = self
+            .pipeline
+            .wait_time(frames, self.sample_rate)
+            .and_then(|grace| pending?.recv_timeout(grace).ok());
+
+        let mut submitted = false;
+        if let Some(ref sender) = self.message_sender {
+            let (response_sender, response_receiver) = bounded(1);
+            let message = ClapMessage::ProcessAudio {
+                input: inputs.iter().map(|input| input.to_vec()).collect(),
+                transport: transport::current(),
+                response: response_sender,
+            };
+            if sender.try_send(message).is_ok() {
+                self.pending = Some(response_receiver);
+                submitted = true;
+            }
+        }
+
+        self.pipeline
+            .advance(self.layout, inputs, previous, submitted)
+    }
+
+    fn get_plugin_name(&self) -> String {
+        self.plugin_name.clone()
+    }
+
+    fn set_parameter(&mut self, index: i32, value: f32) {
+        let clamped = value.clamp(0.0, 1.0);
+        if !self.parameters.contains_key(&index) {
+            return;
+        }
+        self.parameters.insert(index, clamped);
+        if let Some(ref sender) = self.message_sender {
+            let _ = sender.try_send(ClapMessage::SetParameter {
+                index,
+                value: clamped,
+            });
+        }
+    }
+
+    fn get_parameter(&self, index: i32) -> f32 {
+        self.parameters.get(&index).copied().unwrap_or(0.0)
+    }
+
+    fn get_parameter_info(&self, index: i32) -> Option<ParameterInfo> {
+        self.request(
+            |response| ClapMessage::GetParameterInfo { index, response },
+            100,
+        )
+        .flatten()
+    }
+
+    fn parameter_query(&self) -> ParameterQuery {
+        let sender = self.message_sender.clone();
+        let cached: Vec<f32> = (0..self.get_parameter_count())
+            .map(|index| self.get_parameter(index))
+            .collect();
+        Box::new(move || {
+            let (response_sender, response_receiver) = bounded(1);
+            let message = ClapMessage::GetAllParameterInfo {
+                response: response_sender,
+            };
+            if let Some(sender) = sender
+                && sender.try_send(message).is_ok()
+                && let Ok(parameters) =
+                    response_receiver.recv_timeout(std::time::Duration::from_millis(100))
+            {
+                return parameters;
+            }
+            cached
+                .iter()
+                .enumerate()
+                .map(|(index, &value)| plugin_host::generic_parameter(index as i32, value))
+                .collect()
+        })
+    }
+
+    fn get_parameter_count(&self) -> i32 {
+        self.parameters.len() as i32
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn latency_samples(&self) -> usize {
+        if self.enabled {
+            self.latency + self.pipeline.latency()
+        } else {
+            0
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) -> Result<()> {
+        if sample_rate == self.sample_rate {
+            return Ok(());
+        }
+        self.pipeline.reset();
+        self.pending = None;
+        self.latency = self
+            .request(
+                |response| ClapMessage::SetSampleRate {
+                    sample_rate: sample_rate as f64,
+                    response,
+                },
+                1000,
+            )
+            .context("CLAP processing thread did not respond")??;
+        self.sample_rate = sample_rate;
+        Ok(())
+    }
+
+    fn xruns(&self) -> u64 {
+        self.pipeline.xruns()
+    }
+
+    fn take_automation(&mut self) -> Vec<ParameterChange> {
+        let changes: Vec<ParameterChange> = self.automation.try_iter().collect();
+        for change in &changes {
+            if let Some(value) = self.parameters.get_mut(&change.index) {
+                *value = change.value;
+            }
+        }
+        changes
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>> {
+        self.request(|response| ClapMessage::SaveState { response }, 1000)
+            .context("CLAP processing thread did not respond")?
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        let parameters = self
+            .request(
+                |response| ClapMessage::LoadState {
+                    data: data.to_vec(),
+                    response,
+                },
+                1000,
+            )
+            .context("CLAP processing thread did not respond")??;
+        self.parameters = parameters;
+        Ok(())
+    }
+}
+
+impl Drop for ClapProcessor {
+    fn drop(&mut self) {
+        if let Some(sender) = self.message_sender.take() {
+            let _ = sender.send(ClapMessage::Shutdown);
+        }
+        if let Some(thread) = self.processing_thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// ===== Scanner =====
+
+/// CLAP plugin scanner, filling the same catalogue as the VST2 scanner
+pub struct ClapScanner {
+    plugins: Vec<VstPluginInfo>,
+    scan_paths: Vec<PathBuf>,
+}
+
+impl ClapScanner {
+    pub fn new() -> Self {
+        // CLAP_PATH comes first, as the specification asks
+        let mut scan_paths: Vec<PathBuf> = std::env::var_os("CLAP_PATH")
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or_default();
+        if let Some(home) = dirs::home_dir() {
+            scan_paths.push(home.join(".clap"));
+        }
+        scan_paths.push(PathBuf::from("/usr/lib/clap"));
+        scan_paths.push(PathBuf::from("/usr/local/lib/clap"));
+
+        Self {
+            plugins: Vec::new(),
+            scan_paths,
+        }
+    }
+
+    pub fn add_scan_path(&mut self, path: PathBuf) {
+        self.scan_paths.push(path);
+    }
+
+    /// `.clap` bundles below every scan path
+    pub fn plugin_files(&self) -> Vec<PathBuf> {
+        let mut bundles = Vec::new();
+        for scan_path in &self.scan_paths {
+            collect_bundles(scan_path, &mut bundles);
+        }
+        bundles
+    }
+
+    /// Scan every path; bundles that fail to load are logged and skipped
+    pub fn scan(&mut self) {
+        self.plugins.clear();
+        for bundle in self.plugin_files() {
+            match plugin_bridge::scan_sandboxed(PluginFormat::Clap, &bundle) {
+                Ok(plugins) => self.plugins.extend(plugins),
+                Err(e) => log::warn!("Skipping CLAP bundle {}: {:#}", bundle.display(), e),
+            }
+        }
+    }
+
+    pub fn get_plugins(&self) -> &[VstPluginInfo] {
+        &self.plugins
+    }
+}
+
+impl Default for ClapScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `.clap` files below `dir`, searched recursively
+fn collect_bundles(dir: &Path, bundles: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "clap") && path.is_file() {
+            bundles.push(path);
+        } else if path.is_dir() {
+            collect_bundles(&path, bundles);
+        }
+    }
+}
+
+/// Catalogue entries for every plugin in one bundle
+pub(crate) fn scan_bundle(path: &Path) -> Result<Vec<VstPluginInfo>> {
+    let library = Rc::new(ClapLibrary::open(path)?);
+    let descriptors = library.descriptors()?;
+
+    Ok(descriptors
+        .into_iter()
+        .map(|descriptor| {
+            // Ports and parameters need an instance; it is never activated
+            let (inputs, outputs, parameters) =
+                match ClapInstance::create(Rc::clone(&library), &descriptor.id) {
+                    Ok(instance) => (
+                        instance.inputs.main_channels() as i32,
+                        instance.outputs.main_channels() as i32,
+                        instance.params.len() as i32,
+                    ),
+                    Err(e) => {
+                        log::debug!("Could not instantiate {}: {:#}", descriptor.id, e);
+                        (0, 0, 0)
+                    }
+                };
+            let category = category_from_features(&descriptor.features);
+            VstPluginInfo {
+                path: path.to_path_buf(),
+                name: descriptor.name,
+                vendor: descriptor.vendor,
+                is_synth: category == VstCategory::Synth,
+                category,
+                // CLAP identifies plugins by `plugin_id`; versions are free-form
+                unique_id: 0,
+                version: 0,
+                inputs,
+                outputs,
+                parameters,
+                format: PluginFormat::Clap,
+                plugin_id: Some(descriptor.id),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_from_features() {
+        assert_eq!(
+            category_from_features(&["audio-effect", "compressor", "stereo"]),
+            VstCategory::Effect
+        );
+        assert_eq!(
+            category_from_features(&["audio-effect", "reverb"]),
+            VstCategory::RoomFx
+        );
+        assert_eq!(
+            category_from_features(&["instrument", "synthesizer"]),
+            VstCategory::Synth
+        );
+        assert_eq!(category_from_features::<&str>(&[]), VstCategory::Unknown);
+    }
+
+    #[test]
+    fn test_clap_scanner_paths_and_empty_scan() {
+        let mut scanner = ClapScanner::new();
+        assert!(
+            scanner
+                .scan_paths
+                .iter()
+                .any(|p| p == Path::new("/usr/lib/clap"))
+        );
+        assert!(scanner.scan_paths.iter().any(|p| p.ends_with(".clap")));
+        scanner.scan();
+
+        let mut bundles = Vec::new();
+        collect_bundles(Path::new("/nonexistent/clap"), &mut bundles);
+        assert!(bundles.is_empty());
+    }
+
+    #[test]
+    fn test_load_rejects_non_clap_files() {
+        let missing = ClapProcessor::load(Path::new("/nonexistent/plugin.clap"), None, 48000.0);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_parameter_normalisation() {
+        let param = ClapParam {
+            id: 3,
+            cookie: std::ptr::null_mut(),
+            name: "Threshold".to_string(),
+            min: -60.0,
+            max: 0.0,
+            default: -20.0,
+        };
+        assert!((param.normalize(-30.0) - 0.5).abs() < 1e-6);
+        assert_eq!(param.denormalize(1.5), 0.0);
+        assert_eq!(param.normalize(param.denormalize(0.25)), 0.25);
+    }
+
+    #[test]
+    fn test_event_list_and_streams() {
+        let events = vec![
+            clap_event_param_value {
+                header: clap_event_header {
+                    size: std::mem::size_of::<clap_event_param_value>() as u32,
+                    time: 0,
+                    space_id: CLAP_CORE_EVENT_SPACE_ID,
+                    type_: CLAP_EVENT_PARAM_VALUE,
+                    flags: 0,
+                },
+                param_id: 7,
+                cookie: std::ptr::null_mut(),
+                note_id: -1,
+                port_index: -1,
+                channel: -1,
+                key: -1,
+                value: 0.5,
+            };
+            2
+        ];
+        let list = clap_input_events {
+            ctx: &events as *const Vec<clap_event_param_value> as *mut c_void,
+            size: Some(input_events_size),
+            get: Some(input_events_get),
+        };
+        unsafe {
+            assert_eq!(input_events_size(&list), 2);
+            let header = input_events_get(&list, 1);
+            assert_eq!((*(header as *const clap_event_param_value)).param_id, 7);
+            assert!(input_events_get(&list, 2).is_null());
+        }
+
+        // State written by a plugin reads back in smaller pieces
+        let mut saved = Vec::new();
+        let ostream = clap_ostream {
+            ctx: &mut saved as *mut Vec<u8> as *mut c_void,
+            write: Some(ostream_write),
+        };
+        unsafe {
+            assert_eq!(
+                ostream_write(&ostream, b"hello".as_ptr() as *const c_void, 5),
+                5
+            );
+            ostream_write(&ostream, b" clap".as_ptr() as *const c_void, 5);
+        }
+        assert_eq!(saved, b"hello clap");
+
+        let mut cursor = ReadCursor {
+            data: &saved,
+            pos: 0,
+        };
+        let istream = clap_istream {
+            ctx: &mut cursor as *mut ReadCursor as *mut c_void,
+            read: Some(istream_read),
+        };
+        let mut buffer = [0u8; 8];
+        unsafe {
+            assert_eq!(
+                istream_read(&istream, buffer.as_mut_ptr() as *mut c_void, 8),
+                8
+            );
+            assert_eq!(
+                istream_read(&istream, buffer.as_mut_ptr() as *mut c_void, 8),
+                2
+            );
+            assert_eq!(
+                istream_read(&istream, buffer.as_mut_ptr() as *mut c_void, 8),
+                0
+            );
+        }
+        assert_eq!(&buffer[..2], b"ap");
+    }
+
+    #[test]
+    fn test_output_events_keep_parameter_values() {
+        let mut event = clap_event_param_value {
+            header: clap_event_header {
+                size: std::mem::size_of::<clap_event_param_value>() as u32,
+                time: 0,
+                space_id: CLAP_CORE_EVENT_SPACE_ID,
+                type_: CLAP_EVENT_PARAM_VALUE,
+                flags: 0,
+            },
+            param_id: 3,
+            cookie: std::ptr::null_mut(),
+            note_id: -1,
+            port_index: -1,
+            channel: -1,
+            key: -1,
+            value: -6.0,
+        };
+        let mut outgoing: Vec<(clap_id, f64)> = Vec::new();
+        let list = clap_output_events {
+            ctx: &mut outgoing as *mut Vec<(clap_id, f64)> as *mut c_void,
+            try_push: Some(output_events_try_push),
+        };
+        unsafe {
+            assert!(output_events_try_push(&list, &event.header));
+            // Anything but a parameter value is accepted and ignored
+            event.header.type_ = CLAP_EVENT_PARAM_VALUE + 1;
+            assert!(output_events_try_push(&list, &event.header));
+        }
+        assert_eq!(outgoing, vec![(3, -6.0)]);
+    }
+
+    #[test]
+    fn test_transport_event_in_fixed_point() {
+        let info = TransportInfo {
+            playing: true,
+            sample_position: 24000 * 9,
+            sample_rate: 48000.0,
+            tempo: 120.0,
+            beats_per_bar: 4,
+            beat_unit: 4,
+            musical: None,
+        };
+        let event = transport_event(&info);
+        assert_eq!(event.header.type_, CLAP_EVENT_TRANSPORT);
+        assert_eq!(event.song_pos_beats, 9 * CLAP_BEATTIME_FACTOR);
+        assert_eq!(event.song_pos_seconds, 9 * CLAP_SECTIME_FACTOR / 2);
+        assert_eq!(event.bar_start, 8 * CLAP_BEATTIME_FACTOR);
+        assert_eq!(event.bar_number, 2);
+        assert_ne!(event.flags & CLAP_TRANSPORT_IS_PLAYING, 0);
+
+        // Later sub-blocks see the position move on only while playing
+        let stopped = TransportInfo {
+            playing: false,
+            ..info
+        };
+        assert_eq!(info.offset(64).sample_position, 24000 * 9 + 64);
+        assert_eq!(stopped.offset(64), stopped);
+        assert_eq!(
+            transport_event(&stopped).flags & CLAP_TRANSPORT_IS_PLAYING,
+            0
+        );
+    }
+}
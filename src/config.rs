@@ -2,14 +2,23 @@
 
 #![allow(dead_code)] // Config API for save/load functionality
 
+use crate::advanced_denoising::NoiseProfile;
+use crate::agc::AgcConfig;
+use crate::calibration::LatencyCalibration;
+use crate::onnx_denoiser::OnnxModelConfig;
+use crate::plugin_bridge::SandboxConfig;
+use crate::plugin_host::{PluginScene, SavedPlugin};
+use crate::recorder::RecorderConfig;
+use crate::transport::TransportConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
 const CONFIG_FILE_NAME: &str = "phantomlink_config.json";
 
 /// Professional microphone presets with optimized settings
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 pub enum MicrophonePreset {
     /// Rode PodMic - Dynamic, needs moderate gain, warm sound
     #[default]
@@ -42,6 +51,21 @@ impl MicrophonePreset {
         }
     }
 
+    /// Parse a display name, ignoring case, spaces, dashes and underscores
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalize = |s: &str| {
+            s.chars()
+                .filter(|c| !matches!(c, ' ' | '-' | '_'))
+                .collect::<String>()
+                .to_ascii_lowercase()
+        };
+        let wanted = normalize(name);
+        Self::all()
+            .iter()
+            .copied()
+            .find(|preset| normalize(preset.name()) == wanted)
+    }
+
     /// Get description
     pub fn description(&self) -> &'static str {
         match self {
@@ -188,6 +212,17 @@ impl Default for PipeWireConfig {
     }
 }
 
+impl PipeWireConfig {
+    /// Round trip expected by latency calibration for the configured preset.
+    /// The Custom preset uses `buffer_size` at `sample_rate`.
+    pub fn expected_round_trip_ms(&self) -> f32 {
+        let preset = crate::pipewire::PipeWirePreset::from_name(&self.preset).unwrap_or_default();
+        let custom_buffer =
+            (preset == crate::pipewire::PipeWirePreset::Custom).then_some(self.buffer_size as u32);
+        preset.round_trip_ms(custom_buffer, self.sample_rate)
+    }
+}
+
 fn default_pipewire_preset() -> String {
     "Streaming".to_string()
 }
@@ -234,6 +269,40 @@ pub struct AppConfig {
     /// Echo cancellation enabled
     #[serde(default)]
     pub echo_cancellation: bool,
+    /// Learned room-tone noise profiles, one per microphone preset
+    #[serde(default)]
+    pub noise_profiles: HashMap<MicrophonePreset, NoiseProfile>,
+    /// ONNX model for the advanced denoiser's Deep Learning tier
+    #[serde(default)]
+    pub deep_learning_model: Option<OnnxModelConfig>,
+    /// Multitrack recording settings
+    #[serde(default)]
+    pub recorder: RecorderConfig,
+    /// Loudness AGC per output bus, keyed by bus name
+    #[serde(default)]
+    pub bus_agc: HashMap<String, AgcConfig>,
+    /// Measured hardware round trip, used for latency and marker offsets
+    #[serde(default)]
+    pub latency_calibration: Option<LatencyCalibration>,
+    /// Out-of-process plugin hosting
+    #[serde(default)]
+    pub plugin_sandbox: SandboxConfig,
+    /// Plugin of each channel with its state, restored on startup
+    #[serde(default)]
+    pub channel_plugin_states: Vec<Option<SavedPlugin>>,
+    /// Channel feeding each channel plugin's sidechain inputs
+    #[serde(default)]
+    pub channel_sidechains: Vec<Option<usize>>,
+    /// Starred parameters of each plugin, keyed by its path (and ID within
+    /// a bundle)
+    #[serde(default)]
+    pub plugin_favourites: HashMap<String, Vec<i32>>,
+    /// Recorded channel plugin parameter scenes
+    #[serde(default)]
+    pub plugin_scenes: Vec<PluginScene>,
+    /// Host transport tempo and meter, and whether JACK drives it
+    #[serde(default)]
+    pub transport: TransportConfig,
 }
 
 impl AppConfig {
@@ -328,6 +397,22 @@ impl AppConfig {
     pub fn get_channel_plugin(&self, channel_idx: usize) -> Option<usize> {
         self.channel_plugins.get(channel_idx).and_then(|p| *p)
     }
+
+    pub fn get_noise_profile(&self, preset: MicrophonePreset) -> Option<&NoiseProfile> {
+        self.noise_profiles.get(&preset)
+    }
+
+    /// Store or (with `None`) forget the noise profile for a microphone preset
+    pub fn set_noise_profile(&mut self, preset: MicrophonePreset, profile: Option<NoiseProfile>) {
+        match profile {
+            Some(profile) => {
+                self.noise_profiles.insert(preset, profile);
+            }
+            None => {
+                self.noise_profiles.remove(&preset);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -341,6 +426,19 @@ mod tests {
         assert_eq!(MicrophonePreset::Custom.name(), "Custom");
     }
 
+    #[test]
+    fn test_microphone_preset_from_name() {
+        assert_eq!(
+            MicrophonePreset::from_name("shure-sm7b"),
+            Some(MicrophonePreset::ShureSM7B)
+        );
+        assert_eq!(
+            MicrophonePreset::from_name("Rode PodMic"),
+            Some(MicrophonePreset::RodePodMic)
+        );
+        assert_eq!(MicrophonePreset::from_name("sm58"), None);
+    }
+
     #[test]
     fn test_microphone_preset_gains() {
         assert!(MicrophonePreset::ShureSM7B.recommended_gain_db() > MicrophonePreset::RodePodMic.recommended_gain_db());
@@ -403,6 +501,32 @@ mod tests {
         assert_eq!(config.get_channel_plugin(99), None);
     }
 
+    #[test]
+    fn test_app_config_noise_profiles_round_trip() {
+        let mut config = AppConfig::default();
+        let profile = NoiseProfile {
+            sample_rate: 48000,
+            fft_size: 4,
+            magnitudes: vec![0.1, 0.05, 0.02],
+        };
+        config.set_noise_profile(MicrophonePreset::ShureSM7B, Some(profile.clone()));
+
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: AppConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            restored.get_noise_profile(MicrophonePreset::ShureSM7B),
+            Some(&profile)
+        );
+        assert!(
+            restored
+                .get_noise_profile(MicrophonePreset::RodePodMic)
+                .is_none()
+        );
+
+        config.set_noise_profile(MicrophonePreset::ShureSM7B, None);
+        assert!(config.noise_profiles.is_empty());
+    }
+
     #[test]
     fn test_ghostwave_config_defaults() {
         let config = GhostWaveConfig::default();
@@ -0,0 +1,165 @@
+//! Real-time DSP load measurement.
+//!
+//! Load is the time spent processing a buffer divided by the duration of audio
+//! that buffer holds. At 100% a stage alone uses the whole callback budget and
+//! the device underruns, so these numbers show which denoiser or plugin is
+//! eating the budget rather than guessing from wall-clock milliseconds.
+
+use serde::Serialize;
+use std::time::Duration;
+
+/// Weight of the newest buffer in the smoothed load
+const AVERAGE_WEIGHT: f32 = 0.1;
+
+/// Per-buffer decay of the held peak, roughly a few seconds at typical sizes
+const PEAK_DECAY: f32 = 0.995;
+
+/// Processing stages timed inside a channel strip, in signal order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DspStage {
+    Gain,
+    Denoiser,
+    Vst,
+    Effects,
+    /// Volume, pan and metering
+    Output,
+}
+
+impl DspStage {
+    pub const COUNT: usize = 5;
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gain => "Gain",
+            Self::Denoiser => "Denoiser",
+            Self::Vst => "VST",
+            Self::Effects => "Effects",
+            Self::Output => "Output",
+        }
+    }
+
+    pub fn all() -> &'static [DspStage] {
+        &[
+            Self::Gain,
+            Self::Denoiser,
+            Self::Vst,
+            Self::Effects,
+            Self::Output,
+        ]
+    }
+}
+
+/// Load of one stage, channel or callback
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LoadMeter {
+    /// Smoothed load in percent of the buffer duration
+    pub average_percent: f32,
+    /// Decaying peak load in percent
+    pub peak_percent: f32,
+    /// Load of the most recent buffer in percent
+    pub last_percent: f32,
+    /// Buffers that took longer to process than they last
+    pub overruns: u64,
+}
+
+impl LoadMeter {
+    /// Record `elapsed` processing time for a buffer of `buffer` duration
+    pub fn record(&mut self, elapsed: Duration, buffer: Duration) {
+        if buffer.is_zero() {
+            return;
+        }
+
+        let percent = elapsed.as_secs_f32() / buffer.as_secs_f32() * 100.0;
+        self.last_percent = percent;
+        self.average_percent += (percent - self.average_percent) * AVERAGE_WEIGHT;
+        self.peak_percent = percent.max(self.peak_percent * PEAK_DECAY);
+        if percent > 100.0 {
+            self.overruns += 1;
+        }
+    }
+}
+
+/// Load of a channel strip, in total and per stage
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChannelDspLoad {
+    pub total: LoadMeter,
+    pub stages: [LoadMeter; DspStage::COUNT],
+    /// Plugin blocks that came back too late and were played dry
+    pub plugin_xruns: u64,
+}
+
+impl ChannelDspLoad {
+    pub fn stage(&self, stage: DspStage) -> &LoadMeter {
+        &self.stages[stage as usize]
+    }
+
+    pub fn record_stage(&mut self, stage: DspStage, elapsed: Duration, buffer: Duration) {
+        self.stages[stage as usize].record(elapsed, buffer);
+    }
+
+    /// The stage with the highest smoothed load
+    pub fn heaviest_stage(&self) -> DspStage {
+        DspStage::all()
+            .iter()
+            .copied()
+            .max_by(|a, b| {
+                self.stage(*a)
+                    .average_percent
+                    .total_cmp(&self.stage(*b).average_percent)
+            })
+            .unwrap_or(DspStage::Gain)
+    }
+}
+
+/// Snapshot of the engine's DSP load
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DspLoadReport {
+    /// The whole input callback, all channels plus mixing
+    pub callback: LoadMeter,
+    pub channels: Vec<ChannelDspLoad>,
+}
+
+/// Duration of `frames` samples per channel at `sample_rate`
+pub fn buffer_duration(frames: usize, sample_rate: u32) -> Duration {
+    Duration::from_secs_f64(frames as f64 / sample_rate.max(1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_is_time_over_buffer_duration() {
+        let mut meter = LoadMeter::default();
+        let buffer = buffer_duration(480, 48000); // 10 ms
+        meter.record(Duration::from_millis(5), buffer);
+        assert!((meter.last_percent - 50.0).abs() < 0.01);
+        assert!((meter.peak_percent - 50.0).abs() < 0.01);
+        assert_eq!(meter.overruns, 0);
+
+        meter.record(Duration::from_millis(12), buffer);
+        assert_eq!(meter.overruns, 1);
+    }
+
+    #[test]
+    fn test_average_converges_and_peak_decays() {
+        let mut meter = LoadMeter::default();
+        let buffer = Duration::from_millis(10);
+        meter.record(Duration::from_millis(9), buffer);
+        for _ in 0..200 {
+            meter.record(Duration::from_millis(2), buffer);
+        }
+        assert!((meter.average_percent - 20.0).abs() < 0.5);
+        assert!(meter.peak_percent < 90.0 && meter.peak_percent >= 20.0);
+    }
+
+    #[test]
+    fn test_heaviest_stage() {
+        let mut load = ChannelDspLoad::default();
+        let buffer = Duration::from_millis(10);
+        load.record_stage(DspStage::Gain, Duration::from_micros(10), buffer);
+        load.record_stage(DspStage::Vst, Duration::from_millis(3), buffer);
+        load.record_stage(DspStage::Denoiser, Duration::from_millis(1), buffer);
+        assert_eq!(load.heaviest_stage(), DspStage::Vst);
+    }
+}
@@ -0,0 +1,448 @@
+//! Minimal streaming FLAC encoder.
+//!
+//! Frames use a fixed block size and, per channel, whichever of the constant,
+//! fixed-predictor (orders 0-4, one Rice partition) or verbatim subframes is
+//! smallest. That is far simpler than a full LPC encoder but still roughly
+//! halves the size of speech compared to WAV. STREAMINFO is rewritten with
+//! the final sample count and frame sizes when the writer is finished; the
+//! MD5 signature is left zero, which the format defines as "not computed".
+//!
+//! Why not a crate: the recorder needs to append blocks as they arrive for a
+//! recording of unknown length, from a plain Rust build.
+//! - `claxon`, already used to read FLAC back, only decodes
+//! - libFLAC bindings (`libflac-sys`, `flac-bound`) build or link the C
+//!   library, a dependency the packages do not otherwise have
+//! - `flacenc`, the pure-Rust encoder, encodes a whole `Source` in one call
+//!   and hands back the finished stream, rather than taking blocks as the
+//!   recorder produces them
+//!
+//! The output is checked by decoding it with `claxon` in the tests below.
+
+#![allow(dead_code)] // Complete encoder API
+
+use anyhow::Result;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Samples per channel in every frame but the last
+const BLOCK_SIZE: usize = 4096;
+
+/// STREAMINFO follows the "fLaC" marker and its 4-byte block header
+const STREAMINFO_OFFSET: u64 = 8;
+
+const STREAMINFO_LENGTH: usize = 34;
+
+/// Largest Rice parameter the 4-bit field can hold without escaping
+const MAX_RICE_PARAMETER: u32 = 14;
+
+const MAX_FIXED_ORDER: usize = 4;
+
+/// MSB-first bit packer
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    /// Append the low `bits` bits of `value` (at most 32)
+    fn write(&mut self, value: u64, bits: u32) {
+        if bits == 0 {
+            return;
+        }
+        self.acc = (self.acc << bits) | (value & ((1u64 << bits) - 1));
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.bytes.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1u64 << self.bits) - 1;
+    }
+
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64, bits);
+    }
+
+    /// `zeros` zero bits followed by a one
+    fn write_unary(&mut self, mut zeros: u64) {
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros as u32 + 1);
+    }
+
+    /// FLAC's UTF-8-like variable-length integer, used for frame numbers
+    fn write_utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write(value, 8);
+            return;
+        }
+
+        let significant = 64 - value.leading_zeros();
+        let len: u32 = match significant {
+            0..=11 => 2,
+            12..=16 => 3,
+            17..=21 => 4,
+            22..=26 => 5,
+            27..=31 => 6,
+            _ => 7,
+        };
+        let prefix = (0xFF00u64 >> len) & 0xFF;
+        self.write(prefix | (value >> (6 * (len - 1))), 8);
+        for i in (0..len - 1).rev() {
+            self.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+        }
+    }
+
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// Residual of the fixed polynomial predictor of `order`
+fn fixed_residual(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|i| {
+            let s = |k: usize| samples[i - k];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Best Rice parameter for `residual` and the bits it codes into
+fn rice_cost(residual: &[i64]) -> (u32, u64) {
+    let folded: Vec<u64> = residual.iter().map(|&r| zigzag(r)).collect();
+    (0..=MAX_RICE_PARAMETER)
+        .map(|k| {
+            let bits = folded.iter().map(|&u| (u >> k) + 1 + k as u64).sum::<u64>();
+            (k, bits)
+        })
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap_or((0, 0))
+}
+
+/// Append one channel's subframe for `samples`
+fn write_subframe(writer: &mut BitWriter, samples: &[i64], bits_per_sample: u32) {
+    let bps = bits_per_sample as u64;
+
+    // Digital silence and other constant blocks need a single sample
+    if samples.iter().all(|&s| s == samples[0]) {
+        writer.write(0b0000_0000, 8);
+        writer.write_signed(samples[0], bits_per_sample);
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * bps;
+    let best_fixed = (0..=MAX_FIXED_ORDER.min(samples.len().saturating_sub(1)))
+        .map(|order| {
+            let residual = fixed_residual(samples, order);
+            let (parameter, rice_bits) = rice_cost(&residual);
+            let bits = order as u64 * bps + 10 + rice_bits;
+            (order, residual, parameter, bits)
+        })
+        .min_by_key(|(_, _, _, bits)| *bits);
+
+    match best_fixed {
+        Some((order, residual, parameter, bits)) if bits < verbatim_bits => {
+            // Type 001xxx: fixed predictor of order xxx, no wasted bits
+            writer.write(0b0001_0000 | ((order as u64) << 1), 8);
+            for &warmup in &samples[..order] {
+                writer.write_signed(warmup, bits_per_sample);
+            }
+            // Rice coding with 4-bit parameters, a single partition
+            writer.write(0b00, 2);
+            writer.write(0, 4);
+            writer.write(parameter as u64, 4);
+            for &r in &residual {
+                let folded = zigzag(r);
+                writer.write_unary(folded >> parameter);
+                writer.write(folded, parameter);
+            }
+        }
+        _ => {
+            writer.write(0b0000_0010, 8);
+            for &sample in samples {
+                writer.write_signed(sample, bits_per_sample);
+            }
+        }
+    }
+}
+
+/// Streaming FLAC file writer for interleaved `f32` samples
+pub struct FlacWriter {
+    file: BufWriter<File>,
+    sample_rate: u32,
+    channels: usize,
+    bits_per_sample: u32,
+    /// Interleaved samples waiting for a full block
+    pending: Vec<i64>,
+    frame_number: u64,
+    total_frames: u64,
+    min_frame_bytes: u32,
+    max_frame_bytes: u32,
+}
+
+impl FlacWriter {
+    /// Create `path` for `channels` channels (1-8) of 16- or 24-bit audio
+    pub fn create(
+        path: &Path,
+        sample_rate: u32,
+        channels: u16,
+        bits_per_sample: u16,
+    ) -> Result<Self> {
+        if !(1..=8).contains(&channels) {
+            anyhow::bail!("FLAC supports 1-8 channels, got {}", channels);
+        }
+        if bits_per_sample != 16 && bits_per_sample != 24 {
+            anyhow::bail!(
+                "FLAC output supports 16 or 24 bits, got {}",
+                bits_per_sample
+            );
+        }
+        if sample_rate == 0 || sample_rate > 655_350 {
+            anyhow::bail!("Unsupported FLAC sample rate {}", sample_rate);
+        }
+
+        let file = File::create(path)
+            .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", path.display(), e))?;
+        let mut writer = Self {
+            file: BufWriter::new(file),
+            sample_rate,
+            channels: channels as usize,
+            bits_per_sample: bits_per_sample as u32,
+            pending: Vec::with_capacity(BLOCK_SIZE * channels as usize),
+            frame_number: 0,
+            total_frames: 0,
+            min_frame_bytes: 0,
+            max_frame_bytes: 0,
+        };
+
+        writer.file.write_all(b"fLaC")?;
+        // Last metadata block, type 0 (STREAMINFO)
+        writer
+            .file
+            .write_all(&[0x80, 0, 0, STREAMINFO_LENGTH as u8])?;
+        let streaminfo = writer.streaminfo();
+        writer.file.write_all(&streaminfo)?;
+        Ok(writer)
+    }
+
+    fn streaminfo(&self) -> Vec<u8> {
+        let mut info = BitWriter::default();
+        info.write(BLOCK_SIZE as u64, 16);
+        info.write(BLOCK_SIZE as u64, 16);
+        info.write(self.min_frame_bytes as u64, 24);
+        info.write(self.max_frame_bytes as u64, 24);
+        info.write(self.sample_rate as u64, 20);
+        info.write(self.channels as u64 - 1, 3);
+        info.write(self.bits_per_sample as u64 - 1, 5);
+        info.write(self.total_frames >> 32, 4);
+        info.write(self.total_frames, 32);
+        for _ in 0..4 {
+            info.write(0, 32); // MD5 not computed
+        }
+        info.into_bytes()
+    }
+
+    /// Queue interleaved samples in ±1.0, writing every full block
+    pub fn write_samples(&mut self, samples: &[f32]) -> Result<()> {
+        let max = ((1i64 << (self.bits_per_sample - 1)) - 1) as f32;
+        for &sample in samples {
+            self.pending
+                .push((sample.clamp(-1.0, 1.0) * max).round() as i64);
+            if self.pending.len() == BLOCK_SIZE * self.channels {
+                self.flush_block()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush_block(&mut self) -> Result<()> {
+        let frames = self.pending.len() / self.channels;
+        if frames == 0 {
+            return Ok(());
+        }
+
+        let frame = self.encode_frame(frames);
+        self.file.write_all(&frame)?;
+
+        let size = frame.len() as u32;
+        self.min_frame_bytes = if self.frame_number == 0 {
+            size
+        } else {
+            self.min_frame_bytes.min(size)
+        };
+        self.max_frame_bytes = self.max_frame_bytes.max(size);
+        self.frame_number += 1;
+        self.total_frames += frames as u64;
+        self.pending.clear();
+        Ok(())
+    }
+
+    fn encode_frame(&self, frames: usize) -> Vec<u8> {
+        let mut header = BitWriter::default();
+        header.write(0xFFF8, 16); // Sync code, fixed block size
+        header.write(0b0111, 4); // Block size as 16 bits at the end of the header
+        header.write(0b0000, 4); // Sample rate from STREAMINFO
+        header.write(self.channels as u64 - 1, 4); // Independent channels
+        let size_code = if self.bits_per_sample == 16 {
+            0b100
+        } else {
+            0b110
+        };
+        header.write(size_code, 3);
+        header.write(0, 1);
+        header.write_utf8(self.frame_number);
+        header.write(frames as u64 - 1, 16);
+        let mut bytes = header.into_bytes();
+        bytes.push(crc8(&bytes));
+
+        let mut body = BitWriter::default();
+        for channel in 0..self.channels {
+            let samples: Vec<i64> = self
+                .pending
+                .iter()
+                .skip(channel)
+                .step_by(self.channels)
+                .copied()
+                .collect();
+            write_subframe(&mut body, &samples, self.bits_per_sample);
+        }
+        bytes.extend(body.into_bytes());
+
+        let crc = crc16(&bytes);
+        bytes.extend(crc.to_be_bytes());
+        bytes
+    }
+
+    /// Push encoded blocks to disk; a file cut off after this still decodes,
+    /// with STREAMINFO reporting an unknown length
+    pub fn flush(&mut self) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// Write the final partial block and the completed STREAMINFO
+    pub fn finalize(mut self) -> Result<()> {
+        self.flush_block()?;
+        let streaminfo = self.streaminfo();
+        self.file.seek(SeekFrom::Start(STREAMINFO_OFFSET))?;
+        self.file.write_all(&streaminfo)?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(path: &Path) -> (claxon::metadata::StreamInfo, Vec<i32>) {
+        let mut reader = claxon::FlacReader::open(path).unwrap();
+        let info = reader.streaminfo();
+        let samples = reader.samples().map(|s| s.unwrap()).collect();
+        (info, samples)
+    }
+
+    #[test]
+    fn test_round_trip_through_decoder() {
+        let dir = std::env::temp_dir().join("phantomlink_test_flac");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (channels, bits) in [(1u16, 16u16), (2, 24)] {
+            let path = dir.join(format!("roundtrip_{}ch_{}bit.flac", channels, bits));
+            // Tone, noise and a stretch of silence, not a whole number of blocks
+            let mut seed = 1u32;
+            let frames = BLOCK_SIZE * 2 + 1234;
+            let samples: Vec<f32> = (0..frames * channels as usize)
+                .map(|i| {
+                    seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    let noise = (seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5;
+                    if i / (channels as usize) > BLOCK_SIZE * 2 {
+                        0.0
+                    } else {
+                        0.5 * (i as f32 * 0.01).sin() + 0.01 * noise
+                    }
+                })
+                .collect();
+
+            let mut writer = FlacWriter::create(&path, 44100, channels, bits).unwrap();
+            writer.write_samples(&samples[..1000]).unwrap();
+            writer.write_samples(&samples[1000..]).unwrap();
+            writer.finalize().unwrap();
+
+            let (info, decoded) = decode(&path);
+            assert_eq!(info.sample_rate, 44100);
+            assert_eq!(info.channels, channels as u32);
+            assert_eq!(info.bits_per_sample, bits as u32);
+            assert_eq!(info.samples, Some(frames as u64));
+
+            let max = ((1i64 << (bits - 1)) - 1) as f32;
+            assert_eq!(decoded.len(), samples.len());
+            for (&d, &s) in decoded.iter().zip(&samples) {
+                assert_eq!(d, (s * max).round() as i32);
+            }
+
+            // Prediction should beat storing samples verbatim
+            let size = std::fs::metadata(&path).unwrap().len();
+            assert!(size < (samples.len() * bits as usize / 8) as u64);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rejects_unsupported_formats() {
+        let path = std::env::temp_dir().join("phantomlink_test_flac_invalid.flac");
+        assert!(FlacWriter::create(&path, 48000, 1, 32).is_err());
+        assert!(FlacWriter::create(&path, 48000, 9, 16).is_err());
+    }
+}
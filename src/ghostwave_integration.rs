@@ -687,6 +687,58 @@ impl GhostWaveIntegration {
         self.enabled
     }
 
+    /// Delay added by processing, in samples: output trails input by one
+    /// chunk of the latency mode. Without the processor audio passes through.
+    pub fn latency_samples(&self) -> usize {
+        if !self.enabled || cfg!(not(feature = "ghostwave")) {
+            return 0;
+        }
+        (self.latency_mode.latency_ms() * self.sample_rate / 1000) as usize
+    }
+
+    /// Follow the user-facing settings of another instance.
+    /// Used to keep per-channel processors in step with the shared control instance.
+    pub fn sync_settings_from(&mut self, other: &GhostWaveIntegration) {
+        self.enabled = other.enabled;
+        if self.profile != other.profile
+            && let Err(e) = self.set_profile(other.profile)
+        {
+            log::warn!("Failed to sync GhostWave profile: {}", e);
+        }
+        if self.noise_strength != other.noise_strength
+            && let Err(e) = self.set_noise_strength(other.noise_strength)
+        {
+            log::warn!("Failed to sync GhostWave noise strength: {}", e);
+        }
+        self.latency_mode = other.latency_mode;
+        self.denoise_quality = other.denoise_quality;
+        self.denoiser_backend = other.denoiser_backend;
+        self.echo_cancellation = other.echo_cancellation.clone();
+    }
+
+    /// Replace this instance's metrics with an aggregate of per-channel metrics:
+    /// worst-case latency, summed load and counters
+    pub fn aggregate_metrics<'a>(
+        &mut self,
+        channel_metrics: impl IntoIterator<Item = &'a ProcessingMetrics>,
+    ) {
+        let mut total = ProcessingMetrics::default();
+        let mut any = false;
+        for m in channel_metrics {
+            any = true;
+            total.latency_ms = total.latency_ms.max(m.latency_ms);
+            total.cpu_usage += m.cpu_usage;
+            total.gpu_usage += m.gpu_usage;
+            total.noise_reduction_db = total.noise_reduction_db.max(m.noise_reduction_db);
+            total.voice_activity |= m.voice_activity;
+            total.frames_processed += m.frames_processed;
+            total.xruns += m.xruns;
+        }
+        if any {
+            self.metrics = total;
+        }
+    }
+
     /// Get current status health for UI display
     pub fn get_status_health(&self) -> StatusHealth {
         if !self.enabled {
@@ -1,15 +1,43 @@
 // Additional methods for the enhanced PhantomlinkApp
+use crate::audio::ChannelDenoiser;
 use crate::config::MicrophonePreset;
+use crate::dsp_load::DspStage;
 use crate::ghostwave_integration::PhantomLinkProfile;
 use crate::gui::MainTab;
 use crate::gui::NotificationLevel;
 use crate::gui::theme::{ThemePreset, WavelinkTheme};
 use crate::gui::widgets::{GlowButtonStyle, enhanced_glow_button};
+use crate::onnx_denoiser::TensorLayout;
+use crate::phantomlink;
+use crate::plugin_bridge::{self, BridgeEvent, CrashPolicy};
+use crate::plugin_catalogue::{PluginCatalogue, ScanPath};
+use crate::plugin_host::{ParameterChange, PluginScene, SavedPlugin};
+use crate::recorder::TapPoint;
+use crate::signal_generator::Waveform;
+use crate::transport::{self, BEAT_UNITS, MAX_BEATS_PER_BAR, MAX_TEMPO, MIN_TEMPO};
 use eframe::egui;
 
 // Re-export PipeWirePreset from pipewire module
 pub use crate::pipewire::PipeWirePreset;
 
+/// The microphone preset describes the mic on MIC 1, so its noise profile lives there
+const NOISE_PROFILE_CHANNEL: usize = 0;
+
+/// Mixer strip labels, in engine channel order
+const CHANNEL_LABELS: [&str; 4] = ["MIC 1", "MIC 2", "LINE 1", "LINE 2"];
+
+/// Key plugin favourites are saved under: the file, and the plugin within
+/// a bundle
+fn favourite_key(info: &phantomlink::VstPluginInfo) -> String {
+    match &info.plugin_id {
+        Some(id) => format!("{}#{}", info.path.display(), id),
+        None => info.path.display().to_string(),
+    }
+}
+
+/// Integrated loudness most streaming platforms normalize to
+const STREAMING_TARGET_LUFS: f32 = -14.0;
+
 impl super::PhantomlinkApp {
     pub fn handle_keyboard_shortcuts(&mut self, ctx: &egui::Context) {
         if !self.keyboard_shortcuts_enabled {
@@ -95,6 +123,21 @@ impl super::PhantomlinkApp {
                 );
             }
 
+            // R - Start/Stop recording
+            if i.key_pressed(egui::Key::R) && !i.modifiers.ctrl && !i.modifiers.alt {
+                self.toggle_recording();
+            }
+
+            // K - Drop a marker in the current recording
+            if i.key_pressed(egui::Key::K) && !i.modifiers.ctrl && !i.modifiers.alt {
+                self.add_recording_marker();
+            }
+
+            // B - Save the instant replay buffer
+            if i.key_pressed(egui::Key::B) && !i.modifiers.ctrl && !i.modifiers.alt {
+                self.save_replay();
+            }
+
             // Ctrl+, (Comma) - Open Settings tab
             if i.modifiers.ctrl && i.key_pressed(egui::Key::Comma) {
                 self.active_tab = MainTab::Settings;
@@ -134,6 +177,21 @@ impl super::PhantomlinkApp {
         }
     }
 
+    /// PipeWire settings as chosen in the UI. A custom buffer size is saved
+    /// as the Custom preset so calibration can expect the same round trip.
+    pub fn pipewire_config(&self) -> crate::config::PipeWireConfig {
+        let (preset, buffer_size) = if self.use_custom_buffer {
+            (PipeWirePreset::Custom, self.custom_buffer_size)
+        } else {
+            (self.pipewire_preset, self.pipewire_preset.buffer_size())
+        };
+        crate::config::PipeWireConfig {
+            preset: preset.name().to_string(),
+            buffer_size: buffer_size as usize,
+            ..Default::default()
+        }
+    }
+
     pub fn save_configuration(&mut self) {
         let config = crate::config::AppConfig {
             theme: format!("{:?}", self.theme_preset),
@@ -156,9 +214,27 @@ impl super::PhantomlinkApp {
                 enabled: self.advanced_denoising_enabled,
                 show_metrics: self.show_denoising_metrics,
             },
-            pipewire: crate::config::PipeWireConfig::default(),
+            pipewire: self.pipewire_config(),
             echo_cancellation: self.echo_cancellation_enabled,
             vst_plugin_paths: Vec::new(),
+            noise_profiles: self.noise_profiles.clone(),
+            deep_learning_model: self.deep_learning_model.clone(),
+            recorder: self.recorder_config.clone(),
+            bus_agc: self.bus_agc.clone(),
+            latency_calibration: self.audio_engine.get_latency_calibration(),
+            plugin_sandbox: self.audio_engine.get_plugin_sandbox(),
+            channel_plugin_states: (0..self.channel_strips.len())
+                .map(|i| self.audio_engine.get_saved_plugin(i))
+                .collect(),
+            channel_sidechains: (0..self.channel_strips.len())
+                .map(|i| self.audio_engine.get_channel_sidechain(i))
+                .collect(),
+            plugin_favourites: self.plugin_favourites.clone(),
+            plugin_scenes: self.plugin_scenes.clone(),
+            transport: transport::global()
+                .lock()
+                .map(|t| t.config())
+                .unwrap_or_default(),
         };
 
         match config.save() {
@@ -182,6 +258,9 @@ impl super::PhantomlinkApp {
         let gw_fallback = self.audio_engine.get_ghostwave_fallback_status();
         let rtx_active = self.audio_engine.is_rtx_active();
         let gw_enabled = self.audio_engine.is_ghostwave_enabled();
+        let dsp_load = self.audio_engine.get_dsp_load();
+        let limiter_reduction = self.audio_engine.get_limiter_gain_reduction();
+        let limiter_latency = self.audio_engine.get_limiter_latency_ms();
 
         // Read Scarlett hardware level meters (if available)
         // Channels 0-1 are analog inputs, 2-3 are PCM outputs
@@ -194,13 +273,40 @@ impl super::PhantomlinkApp {
         // Update telemetry for each channel strip
         for (i, strip) in self.channel_strips.iter_mut().enumerate() {
             // Update from GhostWave metrics
+            strip.telemetry.latency_ms = 0.0;
             if let Some(ref metrics) = gw_metrics {
                 strip.telemetry.latency_ms = metrics.latency_ms;
-                strip.telemetry.xruns = metrics.xruns;
                 strip.telemetry.ghostwave_active = gw_enabled;
                 strip.telemetry.rtx_active = rtx_active;
             }
 
+            // Per-channel denoiser latency overrides the GhostWave aggregate
+            let denoiser = self.audio_engine.get_channel_denoiser(i);
+            if let Some(metrics) = self.audio_engine.get_channel_denoising_metrics(i)
+                && denoiser.is_some_and(|d| d != ChannelDenoiser::Off)
+            {
+                strip.telemetry.latency_ms = metrics.latency_ms;
+            }
+
+            // The limiter's lookahead delays the strip on top of the denoiser
+            strip.telemetry.latency_ms += limiter_latency.get(i).copied().unwrap_or(0.0);
+            strip.telemetry.limiter_reduction_db = limiter_reduction.get(i).copied().unwrap_or(0.0);
+
+            // CPU is the strip's measured share of the callback budget
+            strip.telemetry.cpu_percent = dsp_load
+                .channels
+                .get(i)
+                .map_or(0.0, |load| load.total.average_percent);
+            strip.telemetry.ghostwave_active =
+                gw_enabled && denoiser == Some(ChannelDenoiser::GhostWave);
+
+            // Plugin blocks played dry count against the strip too
+            let plugin_xruns = dsp_load.channels.get(i).map_or(0, |load| load.plugin_xruns);
+            strip.telemetry.xruns = gw_metrics
+                .as_ref()
+                .map_or(0, |metrics| metrics.xruns)
+                .saturating_add(u32::try_from(plugin_xruns).unwrap_or(u32::MAX));
+
             // Update GPU fallback info
             if let Some(ref fallback) = gw_fallback
                 && fallback.fallback_active
@@ -231,6 +337,1738 @@ impl super::PhantomlinkApp {
         }
     }
 
+    /// Load the active microphone preset's saved noise profile into MIC 1,
+    /// or clear MIC 1's profile when the preset has none
+    pub fn apply_noise_profile_for_preset(&mut self) {
+        let profile = self.noise_profiles.get(&self.microphone_preset).cloned();
+        if let Err(e) = self
+            .audio_engine
+            .set_channel_noise_profile(NOISE_PROFILE_CHANNEL, profile)
+        {
+            log::warn!("Failed to apply noise profile: {}", e);
+        }
+    }
+
+    /// Store the profile once a capture started from the GUI has finished
+    pub fn poll_noise_learning(&mut self) {
+        if !self.noise_learning
+            || self
+                .audio_engine
+                .get_channel_noise_learning_progress(NOISE_PROFILE_CHANNEL)
+                .is_some()
+        {
+            return;
+        }
+        self.noise_learning = false;
+
+        match self
+            .audio_engine
+            .get_channel_noise_profile(NOISE_PROFILE_CHANNEL)
+        {
+            Some(profile) => {
+                self.noise_profiles.insert(self.microphone_preset, profile);
+                self.add_notification(
+                    format!(
+                        "Noise profile learned for {}",
+                        self.microphone_preset.name()
+                    ),
+                    NotificationLevel::Success,
+                );
+                self.save_configuration();
+            }
+            None => {
+                self.add_notification("Noise learning was interrupted", NotificationLevel::Warning)
+            }
+        }
+    }
+
+    /// Load the plugin a strip selected, or clear it when None is selected
+    pub(crate) fn load_strip_plugin(&mut self, channel: usize) {
+        let selected = self
+            .channel_strips
+            .get(channel)
+            .and_then(|s| s.selected_vst);
+        let info = selected.and_then(|idx| self.vst_plugin_info.get(idx).cloned());
+        if channel == self.preset_channel {
+            self.preset_programs = None;
+            self.plugin_parameters = None;
+        }
+
+        match self
+            .audio_engine
+            .load_channel_plugin(channel, info.as_ref())
+        {
+            Ok(()) => {
+                if let Some(info) = info {
+                    self.add_notification(
+                        format!("Loaded {} ({})", info.name, info.format.name()),
+                        NotificationLevel::Success,
+                    );
+                }
+            }
+            Err(e) => {
+                if let Some(strip) = self.channel_strips.get_mut(channel) {
+                    strip.selected_vst = None;
+                }
+                self.add_notification(
+                    format!("Failed to load plugin: {:#}", e),
+                    NotificationLevel::Error,
+                );
+            }
+        }
+    }
+
+    /// Reload each channel's saved plugin and state, if it is still installed
+    pub(crate) fn restore_channel_plugins(&mut self, saved: &[Option<SavedPlugin>]) {
+        for (channel, saved) in saved.iter().enumerate() {
+            let Some(saved) = saved else { continue };
+            let Some(idx) = self.vst_plugin_info.iter().position(|info| {
+                info.path == saved.info.path && info.plugin_id == saved.info.plugin_id
+            }) else {
+                log::warn!(
+                    "Saved plugin {} is no longer installed at {}",
+                    saved.info.name,
+                    saved.info.path.display()
+                );
+                continue;
+            };
+            match self.audio_engine.restore_channel_plugin(channel, saved) {
+                Ok(()) => {
+                    if let Some(strip) = self.channel_strips.get_mut(channel) {
+                        strip.selected_vst = Some(idx);
+                    }
+                }
+                Err(e) => log::warn!("Failed to restore {}: {:#}", saved.info.name, e),
+            }
+        }
+    }
+
+    /// What the heaviest stage of a strip is running, for the DSP load breakdown
+    fn dsp_stage_detail(&self, channel: usize, stage: DspStage) -> String {
+        match stage {
+            DspStage::Denoiser => self
+                .audio_engine
+                .get_channel_denoiser(channel)
+                .map_or_else(|| stage.name().to_string(), |d| d.name().to_string()),
+            DspStage::Vst => self
+                .channel_strips
+                .get(channel)
+                .and_then(|strip| strip.selected_vst)
+                .and_then(|idx| self.vst_plugin_info.get(idx))
+                .map(|info| info.name.clone())
+                .unwrap_or_else(|| stage.name().to_string()),
+            _ => stage.name().to_string(),
+        }
+    }
+
+    /// EBU R128 readouts per output bus, against the streaming target
+    pub fn draw_loudness_meters(&mut self, ui: &mut egui::Ui) {
+        let buses = self.audio_engine.get_bus_loudness();
+        let format_lufs = |value: Option<f32>| match value {
+            Some(v) => format!("{:.1}", v),
+            None => "-∞".to_string(),
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new("LOUDNESS")
+                    .size(10.0)
+                    .color(self.theme.text_muted),
+            );
+            if ui
+                .small_button("Reset")
+                .on_hover_text("Restart integrated loudness, range and peak")
+                .clicked()
+            {
+                self.audio_engine.reset_loudness();
+            }
+        });
+
+        for bus in &buses {
+            let reading = &bus.reading;
+            // Short-term loudness on a -40..0 LUFS scale, marked at the target
+            let short_term = reading.short_term_lufs.unwrap_or(-70.0);
+            let fill = ((short_term + 40.0) / 40.0).clamp(0.0, 1.0);
+            let color = if short_term > STREAMING_TARGET_LUFS + 2.0 {
+                self.theme.error
+            } else if short_term > STREAMING_TARGET_LUFS - 4.0 {
+                self.theme.success
+            } else {
+                self.theme.text_secondary
+            };
+
+            ui.label(
+                egui::RichText::new(bus.bus.to_uppercase())
+                    .size(10.0)
+                    .strong()
+                    .color(self.theme.accent_secondary),
+            );
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(240.0, 8.0), egui::Sense::hover());
+            let painter = ui.painter();
+            painter.rect_filled(rect, 2.0, self.theme.vu_meter_bg());
+            let mut filled = rect;
+            filled.set_width(rect.width() * fill);
+            painter.rect_filled(filled, 2.0, color);
+            let target_x = rect.left() + rect.width() * (STREAMING_TARGET_LUFS + 40.0) / 40.0;
+            painter.line_segment(
+                [
+                    egui::pos2(target_x, rect.top()),
+                    egui::pos2(target_x, rect.bottom()),
+                ],
+                egui::Stroke::new(1.0, self.theme.text_primary),
+            );
+
+            egui::Grid::new(("loudness_grid", &bus.bus))
+                .num_columns(4)
+                .spacing([10.0, 2.0])
+                .show(ui, |ui| {
+                    for (label, value) in [
+                        ("M", format_lufs(reading.momentary_lufs)),
+                        ("S", format_lufs(reading.short_term_lufs)),
+                    ] {
+                        ui.label(
+                            egui::RichText::new(label)
+                                .size(10.0)
+                                .color(self.theme.text_muted),
+                        );
+                        ui.label(egui::RichText::new(value).size(11.0).monospace());
+                    }
+                    ui.end_row();
+                    ui.label(
+                        egui::RichText::new("I")
+                            .size(10.0)
+                            .color(self.theme.text_muted),
+                    );
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "{} LUFS",
+                            format_lufs(reading.integrated_lufs)
+                        ))
+                        .size(11.0)
+                        .monospace(),
+                    );
+                    ui.label(
+                        egui::RichText::new("LRA")
+                            .size(10.0)
+                            .color(self.theme.text_muted),
+                    );
+                    ui.label(
+                        egui::RichText::new(match reading.loudness_range_lu {
+                            Some(lra) => format!("{:.1} LU", lra),
+                            None => "-".to_string(),
+                        })
+                        .size(11.0)
+                        .monospace(),
+                    );
+                    ui.end_row();
+                    ui.label(
+                        egui::RichText::new("TP")
+                            .size(10.0)
+                            .color(self.theme.text_muted),
+                    );
+                    let true_peak = reading.true_peak_dbtp;
+                    ui.label(
+                        egui::RichText::new(format!("{} dBTP", format_lufs(true_peak)))
+                            .size(11.0)
+                            .monospace()
+                            .color(if true_peak.is_some_and(|tp| tp > -1.0) {
+                                self.theme.error
+                            } else {
+                                self.theme.text_primary
+                            }),
+                    );
+                    ui.end_row();
+                });
+
+            self.draw_bus_agc(ui, &bus.bus);
+        }
+    }
+
+    /// AGC switch, target and live gain for one output bus
+    fn draw_bus_agc(&mut self, ui: &mut egui::Ui, bus: &str) {
+        let status = self
+            .audio_engine
+            .get_agc_status()
+            .into_iter()
+            .find(|s| s.bus == bus);
+        let config = self.bus_agc.entry(bus.to_string()).or_default();
+
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(&mut config.enabled, "AGC")
+                .on_hover_text(
+                    "Slowly steer this bus toward the target loudness while someone talks",
+                )
+                .changed();
+            ui.add_enabled_ui(config.enabled, |ui| {
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut config.target_lufs)
+                            .range(-30.0..=-10.0)
+                            .speed(0.1)
+                            .suffix(" LUFS"),
+                    )
+                    .changed();
+            });
+            if let Some(status) = status.filter(|s| s.enabled) {
+                let text = format!(
+                    "{:+.1} dB{}",
+                    status.gain_db,
+                    if status.voice_active { "" } else { " (hold)" }
+                );
+                ui.label(
+                    egui::RichText::new(text)
+                        .size(10.0)
+                        .color(self.theme.text_secondary),
+                );
+            }
+        });
+
+        if changed && let Err(e) = self.audio_engine.set_bus_agc(bus, config.clone()) {
+            self.add_notification(format!("AGC: {}", e), NotificationLevel::Error);
+        }
+    }
+
+    /// Callback load and per-strip breakdown, naming what eats the budget
+    pub fn draw_dsp_load_section(&self, ui: &mut egui::Ui) {
+        let report = self.audio_engine.get_dsp_load();
+        let load_color = |percent: f32| {
+            if percent < 50.0 {
+                self.theme.success
+            } else if percent < 80.0 {
+                self.theme.warning
+            } else {
+                self.theme.error
+            }
+        };
+
+        ui.label(
+            egui::RichText::new("DSP Load")
+                .size(13.0)
+                .strong()
+                .color(self.theme.accent_secondary),
+        );
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new(format!(
+                    "Callback {:.0}% (peak {:.0}%)",
+                    report.callback.average_percent, report.callback.peak_percent
+                ))
+                .size(12.0)
+                .color(load_color(report.callback.peak_percent)),
+            );
+            if report.callback.overruns > 0 {
+                ui.label(
+                    egui::RichText::new(format!("• {} overruns", report.callback.overruns))
+                        .size(12.0)
+                        .color(self.theme.warning),
+                );
+            }
+        });
+        ui.add_space(4.0);
+
+        egui::Grid::new("dsp_load_breakdown")
+            .num_columns(4)
+            .spacing([16.0, 4.0])
+            .show(ui, |ui| {
+                for (i, load) in report.channels.iter().enumerate() {
+                    let heaviest = load.heaviest_stage();
+                    ui.label(CHANNEL_LABELS.get(i).copied().unwrap_or("CH"));
+                    ui.label(
+                        egui::RichText::new(format!("{:.1}%", load.total.average_percent))
+                            .color(load_color(load.total.average_percent)),
+                    );
+                    if load.stage(heaviest).average_percent > 0.0 {
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "{} {:.1}%",
+                                self.dsp_stage_detail(i, heaviest),
+                                load.stage(heaviest).average_percent
+                            ))
+                            .size(11.0)
+                            .color(self.theme.text_secondary),
+                        );
+                    } else {
+                        ui.label("");
+                    }
+                    if load.plugin_xruns > 0 {
+                        ui.label(
+                            egui::RichText::new(format!("{} plugin xruns", load.plugin_xruns))
+                                .size(11.0)
+                                .color(self.theme.warning),
+                        )
+                        .on_hover_text("Blocks the plugin did not finish in time, played dry");
+                    } else {
+                        ui.label("");
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// Delay of every stage, and the compensation lining channels up
+    pub fn draw_latency_section(&mut self, ui: &mut egui::Ui) {
+        let report = self.audio_engine.get_latency_report();
+        let ms = |samples: usize| samples as f32 * 1000.0 / report.sample_rate.max(1) as f32;
+        let stage = |samples: usize| {
+            if samples == 0 {
+                "-".to_string()
+            } else {
+                format!("{:.1}", ms(samples))
+            }
+        };
+
+        ui.label(
+            egui::RichText::new("Latency")
+                .size(13.0)
+                .strong()
+                .color(self.theme.accent_secondary),
+        );
+        ui.label(
+            egui::RichText::new(format!(
+                "Input to output ~{:.1} ms ({} samples @ {} Hz)",
+                report.total_ms, report.total_samples, report.sample_rate
+            ))
+            .size(12.0)
+            .color(if report.total_ms < 20.0 {
+                self.theme.success
+            } else if report.total_ms < 40.0 {
+                self.theme.warning
+            } else {
+                self.theme.error
+            }),
+        );
+        ui.add_space(4.0);
+
+        egui::Grid::new("latency_breakdown")
+            .num_columns(6)
+            .spacing([16.0, 4.0])
+            .show(ui, |ui| {
+                for heading in ["", "Denoiser", "VST", "Limiter", "Aligned +", "Total ms"] {
+                    ui.label(
+                        egui::RichText::new(heading)
+                            .size(11.0)
+                            .color(self.theme.text_muted),
+                    );
+                }
+                ui.end_row();
+
+                for (i, channel) in report.channels.iter().enumerate() {
+                    ui.label(CHANNEL_LABELS.get(i).copied().unwrap_or("CH"));
+                    ui.label(stage(channel.denoiser));
+                    ui.label(stage(channel.vst));
+                    ui.label(stage(channel.limiter));
+                    ui.label(stage(channel.compensation));
+                    ui.label(format!("{:.1}", ms(channel.total())));
+                    ui.end_row();
+                }
+            });
+
+        for bus in report.buses.iter().filter(|b| b.samples > 0) {
+            ui.label(
+                egui::RichText::new(format!(
+                    "{} AGC limiter +{:.1} ms",
+                    bus.bus,
+                    ms(bus.samples)
+                ))
+                .size(11.0)
+                .color(self.theme.text_secondary),
+            );
+        }
+        let hardware = match report.measured_round_trip {
+            Some(samples) => format!("Measured round trip {:.1} ms", ms(samples)),
+            None => format!(
+                "Device buffers {:.1} ms in + {:.1} ms out",
+                ms(report.input_buffer),
+                ms(report.output_buffer)
+            ),
+        };
+        ui.label(
+            egui::RichText::new(hardware)
+                .size(11.0)
+                .color(self.theme.text_muted),
+        );
+
+        ui.add_space(6.0);
+        self.draw_round_trip_calibration(ui);
+    }
+
+    /// Loopback measurement of the real round trip, compared to the preset
+    fn draw_round_trip_calibration(&mut self, ui: &mut egui::Ui) {
+        if let Some(pending) = &self.round_trip_pending
+            && let Ok(result) = pending.try_recv()
+        {
+            self.round_trip_pending = None;
+            match result {
+                Ok(measurement) => self.round_trip_result = Some(measurement),
+                Err(e) => self.add_notification(
+                    format!("Latency measurement failed: {:#}", e),
+                    NotificationLevel::Error,
+                ),
+            }
+        }
+
+        let expected_ms = self.pipewire_config().expected_round_trip_ms();
+
+        ui.horizontal(|ui| {
+            let measuring = self.round_trip_pending.is_some();
+            let button = ui
+                .add_enabled(
+                    !measuring && !self.audio_started,
+                    egui::Button::new(if measuring {
+                        "Measuring..."
+                    } else {
+                        "Measure round trip"
+                    }),
+                )
+                .on_hover_text(
+                    "Plays a test sequence on the output and listens for it on the input. \
+                     Connect a loopback cable (or hold the mic to the speakers) and stop the \
+                     engine first.",
+                );
+            if button.clicked() {
+                let (sender, receiver) = crossbeam_channel::bounded(1);
+                std::thread::spawn(move || {
+                    let _ = sender.send(crate::calibration::measure_round_trip(
+                        crate::calibration::TestSignal::Mls,
+                        crate::calibration::DEFAULT_LEVEL,
+                        expected_ms,
+                    ));
+                });
+                self.round_trip_pending = Some(receiver);
+            }
+            if measuring {
+                ui.ctx().request_repaint();
+            }
+
+            if self.audio_engine.get_latency_calibration().is_some()
+                && ui.small_button("Clear").clicked()
+            {
+                self.audio_engine.set_latency_calibration(None);
+                self.round_trip_result = None;
+            }
+        });
+
+        if let Some(measurement) = self.round_trip_result.clone() {
+            ui.label(
+                egui::RichText::new(format!(
+                    "Round trip {:.1} ms, {} expects {:.1} ms ({:+.1} ms)",
+                    measurement.round_trip_ms,
+                    self.pipewire_preset.name(),
+                    measurement.expected_ms,
+                    measurement.difference_ms
+                ))
+                .size(11.0)
+                .color(self.theme.text_secondary),
+            );
+            let stored =
+                self.audio_engine.get_latency_calibration() == Some(measurement.calibration());
+            if !stored
+                && ui
+                    .small_button("Use as offset")
+                    .on_hover_text("Report this round trip and move recorder markers back by it")
+                    .clicked()
+            {
+                self.audio_engine
+                    .set_latency_calibration(Some(measurement.calibration()));
+                self.add_notification("Latency offset stored", NotificationLevel::Success);
+            }
+        }
+    }
+
+    /// Test tone that replaces a channel's input, for checking routing and levels
+    pub fn draw_signal_generator_section(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Signal Generator")
+                .size(13.0)
+                .strong()
+                .color(self.theme.accent_secondary),
+        );
+
+        let draft = &mut self.generator_draft;
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("generator_waveform")
+                .selected_text(draft.waveform.name())
+                .show_ui(ui, |ui| {
+                    for waveform in Waveform::all() {
+                        changed |= ui
+                            .selectable_value(&mut draft.waveform, *waveform, waveform.name())
+                            .changed();
+                    }
+                });
+            let tone = matches!(draft.waveform, Waveform::Sine);
+            let adjustable = draft.waveform != Waveform::Reference;
+            ui.add_enabled_ui(tone, |ui| {
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut draft.frequency_hz)
+                            .range(20.0..=20000.0)
+                            .speed(5.0)
+                            .suffix(" Hz"),
+                    )
+                    .changed();
+            });
+            ui.add_enabled_ui(adjustable, |ui| {
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut draft.level_dbfs)
+                            .range(-60.0..=0.0)
+                            .speed(0.5)
+                            .suffix(" dBFS"),
+                    )
+                    .changed();
+            });
+        });
+
+        ui.horizontal(|ui| {
+            for (i, label) in CHANNEL_LABELS.iter().enumerate() {
+                let active = self.audio_engine.get_channel_generator(i).is_some();
+                let mut enabled = active;
+                ui.checkbox(&mut enabled, *label)
+                    .on_hover_text("Replace this channel's input with the test signal");
+                // Running generators follow the settings above
+                if enabled != active || (enabled && changed) {
+                    let config = enabled.then(|| self.generator_draft.clone());
+                    if let Err(e) = self.audio_engine.set_channel_generator(i, config) {
+                        self.add_notification(
+                            format!("Signal generator: {}", e),
+                            NotificationLevel::Error,
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    /// Learn-noise controls and a plot of the captured noise spectrum
+    pub fn draw_noise_profile_section(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Noise Profile")
+                .size(13.0)
+                .strong()
+                .color(self.theme.accent_secondary),
+        );
+        ui.label(
+            egui::RichText::new(format!(
+                "MIC 1 • {} • Advanced denoiser",
+                self.microphone_preset.name()
+            ))
+            .size(11.0)
+            .color(self.theme.text_muted),
+        );
+        ui.add_space(6.0);
+
+        let progress = self
+            .audio_engine
+            .get_channel_noise_learning_progress(NOISE_PROFILE_CHANNEL);
+
+        ui.horizontal(|ui| {
+            if let Some(progress) = progress {
+                ui.add(
+                    egui::ProgressBar::new(progress)
+                        .desired_width(200.0)
+                        .text("Stay quiet… capturing room tone"),
+                );
+                return;
+            }
+
+            ui.add(
+                egui::Slider::new(&mut self.noise_learn_seconds, 1.0..=10.0)
+                    .suffix(" s")
+                    .max_decimals(1),
+            );
+
+            if ui
+                .button("🎙 Learn Noise")
+                .on_hover_text(
+                    "Capture room tone while nobody speaks and freeze it as the noise profile",
+                )
+                .clicked()
+            {
+                match self
+                    .audio_engine
+                    .start_channel_noise_learning(NOISE_PROFILE_CHANNEL, self.noise_learn_seconds)
+                {
+                    Ok(()) => self.noise_learning = true,
+                    Err(e) => self.push_notification(
+                        format!("Cannot learn noise: {}", e),
+                        NotificationLevel::Error,
+                    ),
+                }
+            }
+
+            if self.noise_profiles.contains_key(&self.microphone_preset)
+                && ui.small_button("Clear").clicked()
+            {
+                self.noise_profiles.remove(&self.microphone_preset);
+                self.apply_noise_profile_for_preset();
+                self.save_configuration();
+            }
+        });
+
+        let profile = self.noise_profiles.get(&self.microphone_preset);
+        ui.add_space(6.0);
+        let size = egui::Vec2::new(ui.available_width().min(420.0), 80.0);
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter();
+        painter.rect_filled(rect, egui::Rounding::same(6.0), self.theme.bg_highlight);
+
+        let Some(profile) = profile else {
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "No noise profile captured",
+                egui::FontId::proportional(11.0),
+                self.theme.text_muted,
+            );
+            return;
+        };
+
+        // Plot on a log frequency axis (50 Hz - Nyquist) over a -120..-20 dBFS range
+        const MIN_DB: f32 = -120.0;
+        const MAX_DB: f32 = -20.0;
+        let nyquist = profile.sample_rate as f32 / 2.0;
+        let (log_min, log_max) = (50f32.log10(), nyquist.max(100.0).log10());
+        let points: Vec<egui::Pos2> = (0..=rect.width() as usize)
+            .map(|x| {
+                let t = x as f32 / rect.width();
+                let frequency = 10f32.powf(log_min + t * (log_max - log_min));
+                let db = 20.0 * profile.magnitude_at(frequency).max(1e-9).log10();
+                let level = ((db - MIN_DB) / (MAX_DB - MIN_DB)).clamp(0.0, 1.0);
+                egui::pos2(
+                    rect.left() + x as f32,
+                    rect.bottom() - level * rect.height(),
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(1.5, self.theme.accent_primary),
+        ));
+        painter.text(
+            rect.left_top() + egui::vec2(6.0, 4.0),
+            egui::Align2::LEFT_TOP,
+            format!(
+                "{} bins @ {} Hz",
+                profile.magnitudes.len(),
+                profile.sample_rate
+            ),
+            egui::FontId::proportional(10.0),
+            self.theme.text_muted,
+        );
+    }
+
+    /// Model file and tensor layout for the advanced denoiser's Deep Learning tier
+    pub fn draw_deep_learning_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Deep Learning Denoiser (ONNX):")
+                .size(14.0)
+                .color(self.theme.text_primary),
+        );
+        ui.add_space(6.0);
+
+        if !cfg!(feature = "onnx") {
+            ui.label(
+                egui::RichText::new("Built without the `onnx` feature; models cannot be loaded")
+                    .size(11.0)
+                    .color(self.theme.warning),
+            );
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Model:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.deep_learning_path)
+                    .hint_text("/path/to/denoiser.onnx")
+                    .desired_width(260.0),
+            );
+        });
+
+        let draft = &mut self.deep_learning_draft;
+        ui.horizontal(|ui| {
+            ui.label("Frame:");
+            ui.add(
+                egui::DragValue::new(&mut draft.frame_size)
+                    .range(1..=16384)
+                    .suffix(" samples"),
+            );
+            ui.label("Rate:");
+            egui::ComboBox::from_id_salt("onnx_sample_rate")
+                .selected_text(format!("{} Hz", draft.sample_rate))
+                .show_ui(ui, |ui| {
+                    for rate in [16000, 22050, 24000, 44100, 48000] {
+                        ui.selectable_value(&mut draft.sample_rate, rate, format!("{} Hz", rate));
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            for (label, id, layout) in [
+                ("Input:", "onnx_input_layout", &mut draft.input_layout),
+                ("Output:", "onnx_output_layout", &mut draft.output_layout),
+            ] {
+                ui.label(label);
+                egui::ComboBox::from_id_salt(id)
+                    .selected_text(layout.name())
+                    .show_ui(ui, |ui| {
+                        for option in TensorLayout::all() {
+                            ui.selectable_value(layout, *option, option.name());
+                        }
+                    });
+            }
+        });
+
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            if ui.button("Load Model").clicked() {
+                let mut model = self.deep_learning_draft.clone();
+                model.model_path = self.deep_learning_path.trim().into();
+                match self
+                    .audio_engine
+                    .set_deep_learning_model(Some(model.clone()))
+                {
+                    Ok(()) => {
+                        self.deep_learning_model = Some(model);
+                        self.add_notification("ONNX model loaded", NotificationLevel::Success);
+                    }
+                    Err(e) => self.push_notification(
+                        format!("Failed to load model: {}", e),
+                        NotificationLevel::Error,
+                    ),
+                }
+            }
+
+            if self.deep_learning_model.is_some() && ui.button("Unload").clicked() {
+                let _ = self.audio_engine.set_deep_learning_model(None);
+                self.deep_learning_model = None;
+            }
+        });
+
+        // Channels without the advanced denoiser keep the model for later,
+        // so report the info from whichever channel has it loaded
+        let info = (0..self.channel_strips.len())
+            .find_map(|i| self.audio_engine.get_channel_deep_learning_model_info(i));
+        let status = match (&self.deep_learning_model, info) {
+            (_, Some(info)) => format!(
+                "{} (version {}) • {:.1} MB • {:.0} ms frames • CPU",
+                info.name, info.version, info.size_mb, info.target_latency_ms
+            ),
+            (Some(_), None) => "Loaded; active on channels using the Advanced denoiser".to_string(),
+            (None, None) => "No model loaded".to_string(),
+        };
+        ui.label(
+            egui::RichText::new(status)
+                .size(11.0)
+                .color(self.theme.text_muted),
+        );
+    }
+
+    pub fn toggle_recording(&mut self) {
+        if self.audio_engine.is_recording() {
+            match self.audio_engine.stop_recording() {
+                Ok(summary) => self.add_notification(
+                    format!(
+                        "Saved {} tracks ({:.0} s) to {}",
+                        summary.files.len(),
+                        summary.duration_secs,
+                        summary.directory.display()
+                    ),
+                    if summary.dropped_buffers > 0 {
+                        NotificationLevel::Warning
+                    } else {
+                        NotificationLevel::Success
+                    },
+                ),
+                Err(e) => self.add_notification(
+                    format!("Recording failed: {:#}", e),
+                    NotificationLevel::Error,
+                ),
+            }
+            return;
+        }
+
+        let directory = self.recorder_directory.trim();
+        if !directory.is_empty() {
+            self.recorder_config.directory = std::path::PathBuf::from(directory);
+        }
+        match self.audio_engine.start_recording(&self.recorder_config) {
+            Ok(_) => self.add_notification("Recording started", NotificationLevel::Info),
+            Err(e) => {
+                self.add_notification(format!("Cannot record: {:#}", e), NotificationLevel::Error)
+            }
+        }
+    }
+
+    pub fn add_recording_marker(&mut self) {
+        if !self.audio_engine.is_recording() {
+            return;
+        }
+        match self.audio_engine.add_recording_marker(None) {
+            Ok(()) => self.add_notification("Marker added", NotificationLevel::Info),
+            Err(e) => {
+                self.add_notification(format!("Marker failed: {}", e), NotificationLevel::Error)
+            }
+        }
+    }
+
+    pub fn save_replay(&mut self) {
+        match self.audio_engine.save_replay() {
+            Ok(summary) => self.add_notification(
+                format!("Saved last {:.0} s of audio", summary.duration_secs),
+                NotificationLevel::Success,
+            ),
+            Err(e) => self.add_notification(
+                format!("Replay not saved: {:#}", e),
+                NotificationLevel::Error,
+            ),
+        }
+    }
+
+    /// Surface plugin crashes and restarts from the sandbox
+    pub fn poll_plugin_sandbox(&mut self) {
+        for event in plugin_bridge::take_events() {
+            let level = match event {
+                BridgeEvent::Crashed { .. } => NotificationLevel::Error,
+                BridgeEvent::Bypassed { .. } => NotificationLevel::Warning,
+                BridgeEvent::Restarted { .. } => NotificationLevel::Success,
+            };
+            self.add_notification(event.message(), level);
+        }
+    }
+
+    /// Reflect parameter changes the plugins made themselves in the editor,
+    /// and record them into the scene being recorded
+    pub fn poll_plugin_automation(&mut self) {
+        let mut refresh = Vec::new();
+        for (channel, change) in self.audio_engine.take_plugin_automation() {
+            self.record_scene_change(channel, change);
+            if channel == self.preset_channel
+                && let Some(parameter) = self
+                    .plugin_parameters
+                    .as_mut()
+                    .and_then(|parameters| parameters.iter_mut().find(|p| p.index == change.index))
+            {
+                parameter.value = change.value;
+                if !refresh.contains(&change.index) {
+                    refresh.push(change.index);
+                }
+            }
+        }
+        for index in refresh {
+            self.refresh_parameter_display(index);
+        }
+    }
+
+    fn record_scene_change(&mut self, channel: usize, change: ParameterChange) {
+        let Some(scene) = self
+            .scene_recording
+            .and_then(|i| self.plugin_scenes.get_mut(i))
+        else {
+            return;
+        };
+        if let Some((plugin, _)) = self.audio_engine.get_channel_plugin(channel) {
+            scene.record(channel, &plugin, change);
+        }
+    }
+
+    /// Fetch the plugin's display text for a parameter after it changed
+    fn refresh_parameter_display(&mut self, index: i32) {
+        if let Ok(Some(info)) = self
+            .audio_engine
+            .channel_plugin_parameter(self.preset_channel, index)
+            && let Some(parameter) = self
+                .plugin_parameters
+                .as_mut()
+                .and_then(|parameters| parameters.iter_mut().find(|p| p.index == index))
+        {
+            *parameter = info;
+        }
+    }
+
+    /// Rescan changed plugin files, keeping each strip's selection
+    pub fn rescan_plugins(&mut self) {
+        let selected: Vec<_> = self
+            .channel_strips
+            .iter()
+            .map(|strip| {
+                strip
+                    .selected_vst
+                    .and_then(|idx| self.vst_plugin_info.get(idx))
+                    .map(|info| (info.path.clone(), info.plugin_id.clone()))
+            })
+            .collect();
+
+        self.vst_plugin_info = phantomlink::scan_plugins();
+        for (strip, selected) in self.channel_strips.iter_mut().zip(selected) {
+            strip.selected_vst = selected.and_then(|(path, plugin_id)| {
+                self.vst_plugin_info
+                    .iter()
+                    .position(|info| info.path == path && info.plugin_id == plugin_id)
+            });
+        }
+        self.add_notification(
+            format!("Found {} plugins", self.vst_plugin_info.len()),
+            NotificationLevel::Info,
+        );
+    }
+
+    pub fn draw_plugin_catalogue_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Plugin Catalogue:")
+                .size(14.0)
+                .color(self.theme.text_primary),
+        );
+        ui.add_space(6.0);
+
+        let (scan_paths, blacklist) = match PluginCatalogue::global().lock() {
+            Ok(catalogue) => (catalogue.scan_paths.clone(), catalogue.blacklist().to_vec()),
+            Err(_) => return,
+        };
+        let mut changed = false;
+        let mut removed = None;
+        let mut retried = None;
+
+        for scan_path in &scan_paths {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{}: {}",
+                    scan_path.format.name(),
+                    scan_path.path.display()
+                ));
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(scan_path.clone());
+                }
+            });
+        }
+
+        let mut added = None;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("plugin_scan_path_format")
+                .selected_text(self.plugin_path_format.name())
+                .show_ui(ui, |ui| {
+                    for format in [
+                        phantomlink::PluginFormat::Vst2,
+                        phantomlink::PluginFormat::Clap,
+                        phantomlink::PluginFormat::Lv2,
+                        phantomlink::PluginFormat::Ladspa,
+                    ] {
+                        ui.selectable_value(&mut self.plugin_path_format, format, format.name());
+                    }
+                });
+            ui.add(
+                egui::TextEdit::singleline(&mut self.plugin_path_draft)
+                    .hint_text("Folder to search")
+                    .desired_width(220.0),
+            );
+            if ui.button("Add").clicked() && !self.plugin_path_draft.trim().is_empty() {
+                added = Some(ScanPath {
+                    format: self.plugin_path_format,
+                    path: self.plugin_path_draft.trim().into(),
+                });
+            }
+        });
+
+        if !blacklist.is_empty() {
+            ui.add_space(6.0);
+            ui.label(
+                egui::RichText::new("Blacklisted (crashed while scanning):")
+                    .size(12.0)
+                    .color(self.theme.text_secondary),
+            );
+            for entry in &blacklist {
+                ui.horizontal(|ui| {
+                    ui.label(entry.path.display().to_string())
+                        .on_hover_text(&entry.reason);
+                    if ui.small_button("Retry").clicked() {
+                        retried = Some(entry.path.clone());
+                    }
+                });
+            }
+        }
+
+        if let Ok(mut catalogue) = PluginCatalogue::global().lock() {
+            if let Some(scan_path) = &removed {
+                changed |= catalogue.remove_scan_path(scan_path);
+            }
+            if let Some(scan_path) = added {
+                changed |= catalogue.add_scan_path(scan_path);
+                self.plugin_path_draft.clear();
+            }
+            if let Some(path) = &retried {
+                changed |= catalogue.unblacklist(path);
+            }
+            if changed && let Err(e) = catalogue.save() {
+                log::warn!("Failed to save plugin catalogue: {:#}", e);
+            }
+        }
+
+        ui.add_space(6.0);
+        if ui.button("Rescan Plugins").clicked() || changed {
+            self.rescan_plugins();
+        }
+    }
+
+    /// Sidechain routing, program selection and FXP/FXB import/export for a
+    /// channel's plugin
+    pub fn draw_plugin_preset_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Channel Plugins:")
+                .size(14.0)
+                .color(self.theme.text_primary),
+        );
+        ui.add_space(6.0);
+
+        let channel = self.preset_channel;
+        let Some(((name, _), layout)) = ui
+            .horizontal(|ui| {
+                egui::ComboBox::from_id_salt("plugin_preset_channel")
+                    .selected_text(CHANNEL_LABELS[channel])
+                    .show_ui(ui, |ui| {
+                        for (i, label) in CHANNEL_LABELS.iter().enumerate() {
+                            if ui
+                                .selectable_value(&mut self.preset_channel, i, *label)
+                                .changed()
+                            {
+                                self.preset_programs = None;
+                                self.plugin_parameters = None;
+                            }
+                        }
+                    });
+                let plugin = self.audio_engine.get_channel_plugin(self.preset_channel);
+                let layout = self.audio_engine.channel_plugin_layout(self.preset_channel);
+                match (&plugin, layout) {
+                    (Some((name, format)), Some(layout)) => ui
+                        .label(format!(
+                            "{} ({}, {})",
+                            name,
+                            format.name(),
+                            layout.describe()
+                        ))
+                        .on_hover_text("Input → output channels, plus sidechain inputs"),
+                    _ => ui.label("No plugin loaded"),
+                };
+                plugin.zip(layout)
+            })
+            .inner
+        else {
+            return;
+        };
+
+        let channel = self.preset_channel;
+        if layout.sidechain > 0 {
+            let current = self.audio_engine.get_channel_sidechain(channel);
+            let mut source = current;
+            ui.horizontal(|ui| {
+                ui.label("Sidechain from:");
+                egui::ComboBox::from_id_salt("plugin_sidechain_source")
+                    .selected_text(source.map_or("None", |i| CHANNEL_LABELS[i]))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut source, None, "None");
+                        for (i, label) in CHANNEL_LABELS.iter().enumerate() {
+                            ui.selectable_value(&mut source, Some(i), *label);
+                        }
+                    });
+            });
+            if source != current
+                && let Err(e) = self.audio_engine.set_channel_sidechain(channel, source)
+            {
+                self.add_notification(format!("Sidechain: {:#}", e), NotificationLevel::Error);
+            }
+        }
+
+        let programs = self
+            .preset_programs
+            .get_or_insert_with(|| {
+                self.audio_engine
+                    .channel_plugin_programs(channel)
+                    .unwrap_or_default()
+            })
+            .clone();
+        if !programs.names.is_empty() {
+            let mut current = programs.current;
+            ui.horizontal(|ui| {
+                ui.label("Program:");
+                egui::ComboBox::from_id_salt("plugin_preset_program")
+                    .selected_text(programs.names.get(current).map_or("", |n| n.as_str()))
+                    .show_ui(ui, |ui| {
+                        for (i, program) in programs.names.iter().enumerate() {
+                            ui.selectable_value(&mut current, i, program);
+                        }
+                    });
+            });
+            if current != programs.current {
+                if let Err(e) = self.audio_engine.set_channel_program(channel, current) {
+                    self.add_notification(
+                        format!("Program change failed: {:#}", e),
+                        NotificationLevel::Error,
+                    );
+                }
+                self.preset_programs = None;
+                self.plugin_parameters = None;
+            }
+        }
+
+        let mut import = false;
+        let mut export = None;
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.preset_path_draft)
+                    .hint_text("Preset file (.fxp / .fxb)")
+                    .desired_width(220.0),
+            );
+            import = ui.button("Import").clicked();
+            if ui.button("Export FXP").clicked() {
+                export = Some(false);
+            }
+            if ui.button("Export FXB").clicked() {
+                export = Some(true);
+            }
+        });
+
+        let path = std::path::PathBuf::from(self.preset_path_draft.trim());
+        if import {
+            let result = std::fs::read(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| self.audio_engine.import_channel_preset(channel, &data));
+            match result {
+                Ok(()) => self.add_notification(
+                    format!("Loaded {} into {}", path.display(), name),
+                    NotificationLevel::Success,
+                ),
+                Err(e) => self.add_notification(
+                    format!("Preset import failed: {:#}", e),
+                    NotificationLevel::Error,
+                ),
+            }
+            self.preset_programs = None;
+            self.plugin_parameters = None;
+        }
+        if let Some(bank) = export {
+            let result = self
+                .audio_engine
+                .export_channel_preset(channel, bank)
+                .and_then(|data| std::fs::write(&path, data).map_err(anyhow::Error::from));
+            match result {
+                Ok(()) => self.add_notification(
+                    format!("Saved {}", path.display()),
+                    NotificationLevel::Success,
+                ),
+                Err(e) => self.add_notification(
+                    format!("Preset export failed: {:#}", e),
+                    NotificationLevel::Error,
+                ),
+            }
+        }
+    }
+
+    /// Every parameter of the selected channel's plugin, with search and
+    /// favourites
+    pub fn draw_plugin_parameter_editor(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Plugin Parameters:")
+                .size(14.0)
+                .color(self.theme.text_primary),
+        );
+        ui.add_space(6.0);
+
+        let channel = self.preset_channel;
+        let Some(info) = self.audio_engine.channel_plugin_info(channel) else {
+            ui.label(format!("No plugin on {}", CHANNEL_LABELS[channel]));
+            return;
+        };
+        let parameters = self
+            .plugin_parameters
+            .get_or_insert_with(|| {
+                self.audio_engine
+                    .channel_plugin_parameters(channel)
+                    .unwrap_or_default()
+            })
+            .clone();
+        if parameters.is_empty() {
+            ui.label(format!("{} has no parameters", info.name));
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.parameter_search)
+                    .hint_text("Search parameters")
+                    .desired_width(180.0),
+            );
+            ui.checkbox(&mut self.parameter_favourites_only, "★ only");
+            ui.label(
+                egui::RichText::new(format!("{} parameters", parameters.len()))
+                    .size(11.0)
+                    .color(self.theme.text_secondary),
+            );
+        });
+
+        let key = favourite_key(&info);
+        let favourites = self
+            .plugin_favourites
+            .get(&key)
+            .cloned()
+            .unwrap_or_default();
+        let search = self.parameter_search.trim().to_lowercase();
+        let favourites_only = self.parameter_favourites_only;
+        let mut toggled = None;
+        let mut changed = Vec::new();
+        egui::ScrollArea::vertical()
+            .id_salt("plugin_parameters")
+            .max_height(260.0)
+            .show(ui, |ui| {
+                egui::Grid::new("plugin_parameter_grid")
+                    .num_columns(4)
+                    .spacing([8.0, 4.0])
+                    .show(ui, |ui| {
+                        for parameter in &parameters {
+                            let favourite = favourites.contains(&parameter.index);
+                            if (favourites_only && !favourite)
+                                || !parameter.name.to_lowercase().contains(&search)
+                            {
+                                continue;
+                            }
+                            if ui
+                                .selectable_label(favourite, if favourite { "★" } else { "☆" })
+                                .on_hover_text("Favourite")
+                                .clicked()
+                            {
+                                toggled = Some(parameter.index);
+                            }
+                            ui.label(&parameter.name);
+                            let mut value = parameter.value;
+                            if ui
+                                .add(egui::Slider::new(&mut value, 0.0..=1.0).show_value(false))
+                                .changed()
+                            {
+                                changed.push((parameter.index, value));
+                            }
+                            ui.label(
+                                egui::RichText::new(
+                                    format!("{} {}", parameter.display, parameter.label).trim(),
+                                )
+                                .size(11.0)
+                                .color(self.theme.text_secondary),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some(index) = toggled {
+            let starred = self.plugin_favourites.entry(key.clone()).or_default();
+            match starred.iter().position(|&i| i == index) {
+                Some(pos) => {
+                    starred.remove(pos);
+                }
+                None => {
+                    starred.push(index);
+                    starred.sort_unstable();
+                }
+            }
+            if starred.is_empty() {
+                self.plugin_favourites.remove(&key);
+            }
+        }
+        for (index, value) in changed {
+            if let Err(e) = self
+                .audio_engine
+                .set_channel_plugin_parameter(channel, index, value)
+            {
+                self.add_notification(format!("Parameter: {:#}", e), NotificationLevel::Error);
+                continue;
+            }
+            self.record_scene_change(channel, ParameterChange { index, value });
+            self.refresh_parameter_display(index);
+        }
+    }
+
+    /// Named sets of plugin parameter values: record changes into one,
+    /// recall it later
+    pub fn draw_plugin_scene_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Plugin Scenes:")
+                .size(14.0)
+                .color(self.theme.text_primary),
+        );
+        ui.add_space(6.0);
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.scene_name_draft)
+                    .hint_text("Scene name")
+                    .desired_width(180.0),
+            );
+            let name = self.scene_name_draft.trim();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("New Scene"))
+                .clicked()
+            {
+                self.plugin_scenes.push(PluginScene::new(name));
+                self.scene_name_draft.clear();
+            }
+        });
+        if self.plugin_scenes.is_empty() {
+            ui.label(
+                egui::RichText::new("Record a scene to capture parameter changes as you make them")
+                    .size(11.0)
+                    .color(self.theme.text_secondary),
+            );
+            return;
+        }
+
+        let mut record = None;
+        let mut recall = None;
+        let mut delete = None;
+        for (i, scene) in self.plugin_scenes.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let recording = self.scene_recording == Some(i);
+                ui.label(&scene.name);
+                ui.label(
+                    egui::RichText::new(format!("{} values", scene.parameters.len()))
+                        .size(11.0)
+                        .color(self.theme.text_secondary),
+                );
+                if ui
+                    .selectable_label(recording, if recording { "⏹ Stop" } else { "⏺ Record" })
+                    .on_hover_text("Record parameter changes, from the editor and the plugins")
+                    .clicked()
+                {
+                    record = Some((!recording).then_some(i));
+                }
+                if ui.button("Recall").clicked() {
+                    recall = Some(i);
+                }
+                if ui.button("Delete").clicked() {
+                    delete = Some(i);
+                }
+            });
+        }
+
+        if let Some(recording) = record {
+            self.scene_recording = recording;
+        }
+        if let Some(scene) = recall.and_then(|i| self.plugin_scenes.get(i)) {
+            let applied = self.audio_engine.recall_plugin_scene(scene);
+            let message = format!("Recalled {} ({} values)", scene.name, applied);
+            self.plugin_parameters = None;
+            self.add_notification(message, NotificationLevel::Success);
+        }
+        if let Some(i) = delete {
+            self.plugin_scenes.remove(i);
+            self.scene_recording = match self.scene_recording {
+                Some(r) if r == i => None,
+                Some(r) if r > i => Some(r - 1),
+                other => other,
+            };
+        }
+    }
+
+    /// Tempo, meter and play state given to tempo-synced plugins
+    pub fn draw_transport_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Transport:")
+                .size(14.0)
+                .color(self.theme.text_primary),
+        );
+        ui.add_space(6.0);
+
+        // Edit copies so the audio callback never waits on the GUI
+        let Ok((info, config, following)) = transport::global()
+            .lock()
+            .map(|t| (t.info(), t.config(), t.following_jack()))
+        else {
+            return;
+        };
+        let mut edited = config;
+        let mut play = None;
+        let mut rewind = false;
+
+        ui.horizontal(|ui| {
+            let label = if info.playing { "⏸ Stop" } else { "▶ Play" };
+            if ui.button(label).clicked() {
+                play = Some(!info.playing);
+            }
+            if ui.button("⏮ Rewind").clicked() {
+                rewind = true;
+            }
+            let seconds = info.seconds().max(0.0);
+            ui.label(
+                egui::RichText::new(format!(
+                    "Bar {}  Beat {:.2}  {:02}:{:06.3}",
+                    info.bar() + 1,
+                    info.beat_in_bar() + 1.0,
+                    (seconds / 60.0) as u64,
+                    seconds % 60.0
+                ))
+                .monospace()
+                .color(self.theme.text_primary),
+            );
+        });
+
+        ui.add_enabled_ui(!following, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Tempo:");
+                ui.add(
+                    egui::DragValue::new(&mut edited.tempo)
+                        .range(MIN_TEMPO..=MAX_TEMPO)
+                        .speed(0.5)
+                        .fixed_decimals(1)
+                        .suffix(" BPM"),
+                );
+                ui.label("Meter:");
+                ui.add(
+                    egui::DragValue::new(&mut edited.beats_per_bar).range(1..=MAX_BEATS_PER_BAR),
+                );
+                ui.label("/");
+                egui::ComboBox::from_id_salt("transport_beat_unit")
+                    .selected_text(edited.beat_unit.to_string())
+                    .width(50.0)
+                    .show_ui(ui, |ui| {
+                        for unit in BEAT_UNITS {
+                            ui.selectable_value(&mut edited.beat_unit, unit, unit.to_string());
+                        }
+                    });
+            });
+        });
+
+        let jack_running = self.jack_client.as_ref().is_some_and(|c| c.is_available());
+        ui.checkbox(&mut edited.sync_jack, "Follow JACK transport")
+            .on_hover_text("Take position, tempo and meter from the JACK transport master");
+        let note = if following {
+            "Driven by JACK: Play, Stop and Rewind control the JACK transport"
+        } else if edited.sync_jack && !jack_running {
+            "JACK is not running; using the internal transport"
+        } else {
+            "Plugins see this position, tempo and meter"
+        };
+        ui.label(
+            egui::RichText::new(note)
+                .size(11.0)
+                .color(self.theme.text_secondary),
+        );
+
+        let jack = self.jack_client.as_ref().filter(|_| following);
+        let mut failure = None;
+        if let Some(playing) = play {
+            match jack {
+                Some(jack) => failure = jack.set_transport_rolling(playing).err(),
+                None => {
+                    if let Ok(mut t) = transport::global().lock() {
+                        t.set_playing(playing);
+                    }
+                }
+            }
+        }
+        if rewind {
+            match jack {
+                Some(jack) => failure = jack.locate_transport(0).err(),
+                None => {
+                    if let Ok(mut t) = transport::global().lock() {
+                        t.locate(0);
+                    }
+                }
+            }
+        }
+        if edited != config
+            && let Ok(mut t) = transport::global().lock()
+        {
+            t.configure(&edited);
+        }
+        if let Some(e) = failure {
+            self.add_notification(format!("JACK transport: {}", e), NotificationLevel::Error);
+        }
+    }
+
+    pub fn draw_plugin_sandbox_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Plugin Sandbox:")
+                .size(14.0)
+                .color(self.theme.text_primary),
+        );
+        ui.add_space(6.0);
+
+        let mut sandbox = self.audio_engine.get_plugin_sandbox();
+        ui.checkbox(&mut sandbox.enabled, "Run plugins in a separate process")
+            .on_hover_text(
+                "A crashing plugin cannot take down the mixer; adds a little CPU overhead",
+            );
+        ui.add_enabled_ui(sandbox.enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("On crash:");
+                egui::ComboBox::from_id_salt("plugin_sandbox_crash_policy")
+                    .selected_text(sandbox.on_crash.name())
+                    .show_ui(ui, |ui| {
+                        for policy in CrashPolicy::all() {
+                            ui.selectable_value(&mut sandbox.on_crash, *policy, policy.name());
+                        }
+                    });
+            });
+        });
+        ui.label(
+            egui::RichText::new("Applies to plugins loaded after the change")
+                .size(11.0)
+                .color(self.theme.text_secondary),
+        );
+
+        if sandbox != self.audio_engine.get_plugin_sandbox() {
+            self.audio_engine.set_plugin_sandbox(sandbox);
+        }
+    }
+
+    pub fn draw_recording_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            egui::RichText::new("Multitrack Recording:")
+                .size(14.0)
+                .color(self.theme.text_primary),
+        );
+        ui.add_space(6.0);
+
+        let recording = self.audio_engine.is_recording();
+        ui.add_enabled_ui(!recording, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Folder:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.recorder_directory).desired_width(260.0),
+                );
+            });
+
+            let config = &mut self.recorder_config;
+            ui.horizontal(|ui| {
+                ui.label("Format:");
+                egui::ComboBox::from_id_salt("recording_format")
+                    .selected_text(config.format.to_uppercase())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut config.format, "wav".to_string(), "WAV");
+                        ui.selectable_value(&mut config.format, "flac".to_string(), "FLAC");
+                    });
+                ui.label("Bits:");
+                egui::ComboBox::from_id_salt("recording_bits")
+                    .selected_text(format!("{}", config.bits_per_sample))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut config.bits_per_sample, 16, "16");
+                        ui.selectable_value(&mut config.bits_per_sample, 24, "24");
+                        if config.format == "wav" {
+                            ui.selectable_value(&mut config.bits_per_sample, 32, "32 (float)");
+                        }
+                    });
+                if config.format == "flac" && config.bits_per_sample == 32 {
+                    config.bits_per_sample = 24;
+                }
+            });
+
+            config.channel_taps.resize(CHANNEL_LABELS.len(), None);
+            egui::Grid::new("recording_taps_grid")
+                .num_columns(2)
+                .spacing([12.0, 4.0])
+                .show(ui, |ui| {
+                    for (i, tap) in config.channel_taps.iter_mut().enumerate() {
+                        ui.label(CHANNEL_LABELS[i]);
+                        egui::ComboBox::from_id_salt(("recording_tap", i))
+                            .selected_text(match tap {
+                                None => "Off",
+                                Some(TapPoint::PreFx) => "Pre-FX",
+                                Some(TapPoint::PostFx) => "Post-FX",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(tap, None, "Off");
+                                ui.selectable_value(tap, Some(TapPoint::PreFx), "Pre-FX");
+                                ui.selectable_value(tap, Some(TapPoint::PostFx), "Post-FX");
+                            });
+                        ui.end_row();
+                    }
+                });
+            ui.checkbox(&mut config.record_buses, "Record output buses");
+        });
+
+        ui.add_space(6.0);
+        let config = &mut self.recorder_config;
+        let mut replay_changed = ui
+            .checkbox(&mut config.replay_enabled, "Instant replay buffer")
+            .on_hover_text("Keep recent audio of the master bus in memory (B saves it)")
+            .changed();
+        ui.add_enabled_ui(config.replay_enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Keep:");
+                replay_changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut config.replay_seconds)
+                            .range(10..=600)
+                            .suffix(" s"),
+                    )
+                    .changed();
+                ui.label("Mic:");
+                egui::ComboBox::from_id_salt("replay_channel")
+                    .selected_text(
+                        config
+                            .replay_channel
+                            .and_then(|i| CHANNEL_LABELS.get(i).copied())
+                            .unwrap_or("None"),
+                    )
+                    .show_ui(ui, |ui| {
+                        replay_changed |= ui
+                            .selectable_value(&mut config.replay_channel, None, "None")
+                            .changed();
+                        for (i, label) in CHANNEL_LABELS.iter().enumerate() {
+                            replay_changed |= ui
+                                .selectable_value(&mut config.replay_channel, Some(i), *label)
+                                .changed();
+                        }
+                    });
+            });
+        });
+        if replay_changed && let Err(e) = self.audio_engine.set_replay_buffer(&self.recorder_config)
+        {
+            self.add_notification(
+                format!("Replay buffer failed: {:#}", e),
+                NotificationLevel::Error,
+            );
+        }
+
+        let status = self.audio_engine.get_recorder_status();
+        let (text, color) = if let Some(ref error) = status.error {
+            (error.clone(), self.theme.error)
+        } else if status.recording {
+            (
+                format!(
+                    "● REC {:.0} s, {} tracks, {} markers{}",
+                    status.elapsed_secs,
+                    status.tracks.len(),
+                    status.markers,
+                    status
+                        .free_space_mb
+                        .map(|mb| format!(", {:.1} GB free", mb as f32 / 1024.0))
+                        .unwrap_or_default()
+                ),
+                self.theme.error,
+            )
+        } else if let Some(seconds) = status.replay_buffered_secs {
+            (
+                format!("Replay buffer holds {:.0} s, press B to save it", seconds),
+                self.theme.text_muted,
+            )
+        } else {
+            (
+                "Press R to record, K to add a marker".to_string(),
+                self.theme.text_muted,
+            )
+        };
+        ui.label(egui::RichText::new(text).size(11.0).color(color));
+        if status.dropped_buffers > 0 {
+            ui.label(
+                egui::RichText::new(format!(
+                    "{} buffers dropped: the disk could not keep up",
+                    status.dropped_buffers
+                ))
+                .size(11.0)
+                .color(self.theme.warning),
+            );
+        }
+    }
+
     pub fn add_notification(&mut self, text: impl Into<String>, level: NotificationLevel) {
         use super::NotificationMessage;
         let duration = match level {
@@ -340,6 +2178,9 @@ impl super::PhantomlinkApp {
                     ("M", "Mute/Unmute all channels"),
                     ("G", "Toggle GhostWave AI denoising"),
                     ("I", "Toggle metrics info panel"),
+                    ("R", "Start/Stop recording"),
+                    ("K", "Add a marker to the recording"),
+                    ("B", "Save the instant replay buffer"),
                     ("1-4", "Switch tabs (Mixer/Apps/Advanced/Settings)"),
                     ("Ctrl+,", "Open Settings"),
                     ("Ctrl+S", "Save configuration"),
@@ -504,6 +2345,7 @@ impl super::PhantomlinkApp {
                                     .clicked() && !is_selected
                                 {
                                     self.microphone_preset = *preset;
+                                    self.apply_noise_profile_for_preset();
 
                                     // Show recommended settings
                                     let msg = format!(
@@ -560,6 +2402,54 @@ impl super::PhantomlinkApp {
                     ui.separator();
                     ui.add_space(12.0);
 
+                    self.draw_deep_learning_settings(ui);
+
+                    ui.add_space(16.0);
+                    ui.separator();
+                    ui.add_space(12.0);
+
+                    self.draw_recording_settings(ui);
+
+                    ui.add_space(16.0);
+                    ui.separator();
+                    ui.add_space(12.0);
+
+                    self.draw_plugin_catalogue_settings(ui);
+
+                    ui.add_space(16.0);
+                    ui.separator();
+                    ui.add_space(12.0);
+
+                    self.draw_plugin_preset_settings(ui);
+
+                    ui.add_space(16.0);
+                    ui.separator();
+                    ui.add_space(12.0);
+
+                    self.draw_plugin_parameter_editor(ui);
+
+                    ui.add_space(16.0);
+                    ui.separator();
+                    ui.add_space(12.0);
+
+                    self.draw_plugin_scene_settings(ui);
+
+                    ui.add_space(16.0);
+                    ui.separator();
+                    ui.add_space(12.0);
+
+                    self.draw_transport_settings(ui);
+
+                    ui.add_space(16.0);
+                    ui.separator();
+                    ui.add_space(12.0);
+
+                    self.draw_plugin_sandbox_settings(ui);
+
+                    ui.add_space(16.0);
+                    ui.separator();
+                    ui.add_space(12.0);
+
                     // Interface settings
                     ui.label(egui::RichText::new("Interface Options:")
                         .size(14.0)
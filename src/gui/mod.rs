@@ -6,8 +6,10 @@ pub mod visualizer;
 pub mod waveform;
 pub mod widgets;
 
-use crate::advanced_denoising::{DenoisingMetrics, DenoisingMode};
+use crate::advanced_denoising::{DenoisingMetrics, DenoisingMode, NoiseProfile};
+use crate::agc::AgcConfig;
 use crate::audio::AudioEngine;
+use crate::calibration::RoundTripMeasurement;
 use crate::config::MicrophonePreset;
 use crate::ghostwave_integration::{
     DenoiserBackend, DriverInfo, GhostWaveIntegration, LatencyMode, PhantomLinkProfile,
@@ -21,9 +23,16 @@ use crate::gui::widgets::{
     GlowButtonStyle, ModernChannelStrip, StatusIndicator, enhanced_glow_button,
 };
 use crate::jack_client::JackClient;
+use crate::onnx_denoiser::OnnxModelConfig;
 use crate::phantomlink;
+use crate::plugin_host::{PluginScene, ProgramList};
+use crate::recorder::RecorderConfig;
 use crate::scarlett::{AirMode, CaptureSource, InputLevel, LevelMeters, ScarlettSolo};
+use crate::signal_generator::SignalGeneratorConfig;
+use crate::transport;
+use crate::vst_host::ParameterInfo;
 use eframe::egui;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum MainTab {
@@ -53,7 +62,6 @@ pub enum NotificationLevel {
 }
 
 pub struct PhantomlinkApp {
-    vst_plugins: Vec<std::path::PathBuf>,
     vst_plugin_info: Vec<phantomlink::VstPluginInfo>,
     channel_strips: [ModernChannelStrip; 4],
     scarlett: Option<ScarlettSolo>,
@@ -106,6 +114,41 @@ pub struct PhantomlinkApp {
     use_custom_buffer: bool,
     // Microphone preset
     microphone_preset: MicrophonePreset,
+    // Learned room-tone profiles, saved per microphone preset
+    noise_profiles: HashMap<MicrophonePreset, NoiseProfile>,
+    noise_learn_seconds: f32,
+    noise_learning: bool,
+    // ONNX model for the Deep Learning denoiser tier, and the settings being edited
+    deep_learning_model: Option<OnnxModelConfig>,
+    deep_learning_draft: OnnxModelConfig,
+    deep_learning_path: String,
+    // Multitrack recorder settings, and the folder being edited
+    recorder_config: RecorderConfig,
+    recorder_directory: String,
+    // Loudness AGC settings per output bus
+    bus_agc: HashMap<String, AgcConfig>,
+    // Round-trip measurement running in the background, and the last result
+    round_trip_pending: Option<crossbeam_channel::Receiver<anyhow::Result<RoundTripMeasurement>>>,
+    round_trip_result: Option<RoundTripMeasurement>,
+    // Test signal settings applied to channels switched to the generator
+    generator_draft: SignalGeneratorConfig,
+    // Custom plugin scan path being entered in settings
+    plugin_path_draft: String,
+    plugin_path_format: phantomlink::PluginFormat,
+    // Preset import/export target, and its programs once fetched
+    preset_channel: usize,
+    preset_path_draft: String,
+    preset_programs: Option<ProgramList>,
+    // Parameter editor for the same channel: its parameters once fetched,
+    // the search text, and starred parameters per plugin
+    plugin_parameters: Option<Vec<ParameterInfo>>,
+    parameter_search: String,
+    parameter_favourites_only: bool,
+    plugin_favourites: HashMap<String, Vec<i32>>,
+    // Parameter scenes, the one recording changes, and the name being entered
+    plugin_scenes: Vec<PluginScene>,
+    scene_recording: Option<usize>,
+    scene_name_draft: String,
     // PipeWire virtual device manager
     pipewire_device: Option<crate::pipewire::VirtualDeviceManager>,
     #[allow(dead_code)] // State tracking for PipeWire device status
@@ -120,8 +163,7 @@ pub struct PhantomlinkApp {
 impl Default for PhantomlinkApp {
     fn default() -> Self {
         let scarlett = ScarlettSolo::new().ok();
-        let vst_plugins = phantomlink::find_vst_plugins();
-        let vst_plugin_info = phantomlink::scan_vst_plugins().unwrap_or_default();
+        let vst_plugin_info = phantomlink::scan_plugins();
 
         // Read Scarlett state if available
         let (phantom_power, air_mode, input_level, direct_monitor) = scarlett
@@ -143,6 +185,12 @@ impl Default for PhantomlinkApp {
         // Load saved configuration
         let saved_config = crate::config::AppConfig::load();
 
+        // Restore the PipeWire preset; a saved Custom preset carries the buffer size
+        let saved_preset =
+            crate::pipewire::PipeWirePreset::from_name(&saved_config.pipewire.preset)
+                .unwrap_or_default();
+        let use_custom_buffer = saved_preset == crate::pipewire::PipeWirePreset::Custom;
+
         // Restore theme from config
         let theme_preset = match saved_config.theme.as_str() {
             "TokyoNightStorm" => ThemePreset::TokyoNightStorm,
@@ -151,8 +199,7 @@ impl Default for PhantomlinkApp {
         };
         let theme = WavelinkTheme::with_preset(theme_preset);
 
-        Self {
-            vst_plugins,
+        let mut app = Self {
             vst_plugin_info,
             channel_strips: [
                 ModernChannelStrip::new(),
@@ -200,12 +247,47 @@ impl Default for PhantomlinkApp {
             last_save_time: std::time::Instant::now(),
             master_volume: 0.8,
             mute_all: false,
-            pipewire_preset: enhanced_methods::PipeWirePreset::default(),
+            pipewire_preset: saved_preset,
             // Custom buffer size control
-            custom_buffer_size: 256,
-            use_custom_buffer: false,
+            custom_buffer_size: if use_custom_buffer {
+                saved_config.pipewire.buffer_size as u32
+            } else {
+                256
+            },
+            use_custom_buffer,
             // Microphone preset (default to Rode PodMic)
             microphone_preset: MicrophonePreset::default(),
+            noise_profiles: saved_config.noise_profiles.clone(),
+            noise_learn_seconds: 3.0,
+            noise_learning: false,
+            deep_learning_model: None,
+            deep_learning_draft: saved_config
+                .deep_learning_model
+                .clone()
+                .unwrap_or_else(|| OnnxModelConfig::new("", 480, 48000)),
+            deep_learning_path: saved_config
+                .deep_learning_model
+                .as_ref()
+                .map(|m| m.model_path.display().to_string())
+                .unwrap_or_default(),
+            recorder_directory: saved_config.recorder.directory.display().to_string(),
+            recorder_config: saved_config.recorder.clone(),
+            bus_agc: saved_config.bus_agc.clone(),
+            round_trip_pending: None,
+            round_trip_result: None,
+            generator_draft: SignalGeneratorConfig::default(),
+            plugin_path_draft: String::new(),
+            plugin_path_format: phantomlink::PluginFormat::default(),
+            preset_channel: 0,
+            preset_path_draft: String::new(),
+            preset_programs: None,
+            plugin_parameters: None,
+            parameter_search: String::new(),
+            parameter_favourites_only: false,
+            plugin_favourites: saved_config.plugin_favourites.clone(),
+            plugin_scenes: saved_config.plugin_scenes.clone(),
+            scene_recording: None,
+            scene_name_draft: String::new(),
             // Initialize PipeWire virtual device manager
             pipewire_device: if crate::pipewire::is_pipewire_running() {
                 let mut mgr = crate::pipewire::VirtualDeviceManager::default();
@@ -225,7 +307,45 @@ impl Default for PhantomlinkApp {
             // JACK client (gracefully returns None if JACK server not running)
             jack_client: JackClient::new().ok(),
             jack_processing_enabled: true,
+        };
+
+        // Restore the saved room-tone profile for the active microphone
+        app.apply_noise_profile_for_preset();
+
+        // Reload the saved ONNX model into the Deep Learning tier
+        if let Some(model) = saved_config.deep_learning_model {
+            match app
+                .audio_engine
+                .set_deep_learning_model(Some(model.clone()))
+            {
+                Ok(()) => app.deep_learning_model = Some(model),
+                Err(e) => log::warn!("Failed to load saved ONNX model: {}", e),
+            }
+        }
+
+        // The replay buffer starts along with the engine
+        if let Err(e) = app.audio_engine.set_replay_buffer(&app.recorder_config) {
+            log::warn!("Failed to configure replay buffer: {}", e);
         }
+        for (bus, agc) in &app.bus_agc {
+            if let Err(e) = app.audio_engine.set_bus_agc(bus, agc.clone()) {
+                log::warn!("Failed to configure AGC: {}", e);
+            }
+        }
+        app.audio_engine
+            .set_latency_calibration(saved_config.latency_calibration);
+        app.audio_engine
+            .set_plugin_sandbox(saved_config.plugin_sandbox);
+        if let Ok(mut transport) = transport::global().lock() {
+            transport.configure(&saved_config.transport);
+        }
+        app.restore_channel_plugins(&saved_config.channel_plugin_states);
+        for (channel, &source) in saved_config.channel_sidechains.iter().enumerate() {
+            if let Err(e) = app.audio_engine.set_channel_sidechain(channel, source) {
+                log::warn!("Failed to restore sidechain: {}", e);
+            }
+        }
+        app
     }
 }
 
@@ -246,6 +366,15 @@ impl eframe::App for PhantomlinkApp {
         // Update channel telemetry from GhostWave and audio engine
         self.update_channel_telemetry();
 
+        // Pick up a finished noise-profile capture
+        self.poll_noise_learning();
+
+        // Report sandboxed plugin crashes
+        self.poll_plugin_sandbox();
+
+        // Show and record parameter changes made by the plugins themselves
+        self.poll_plugin_automation();
+
         // Show help overlay if enabled
         if self.show_help_overlay {
             self.draw_help_overlay(ctx);
@@ -358,6 +487,54 @@ impl PhantomlinkApp {
                                 }
                             }
 
+                            ui.add_space(8.0);
+
+                            // Recording transport, with a marker button while rolling
+                            let recording = self.audio_engine.is_recording();
+                            let record_style = if recording {
+                                GlowButtonStyle::Danger
+                            } else {
+                                GlowButtonStyle::Secondary
+                            };
+                            if ui
+                                .add_enabled(
+                                    self.audio_started,
+                                    enhanced_glow_button(
+                                        if recording { "⏹ REC" } else { "⏺ REC" },
+                                        &self.theme,
+                                        record_style,
+                                    ),
+                                )
+                                .on_hover_text("Record channels and buses (R)")
+                                .clicked()
+                            {
+                                self.toggle_recording();
+                            }
+                            if recording
+                                && ui
+                                    .add(enhanced_glow_button(
+                                        "🔖",
+                                        &self.theme,
+                                        GlowButtonStyle::Secondary,
+                                    ))
+                                    .on_hover_text("Add marker (K)")
+                                    .clicked()
+                            {
+                                self.add_recording_marker();
+                            }
+                            if self.audio_engine.is_replay_buffer_enabled()
+                                && ui
+                                    .add(enhanced_glow_button(
+                                        "⟲ REPLAY",
+                                        &self.theme,
+                                        GlowButtonStyle::Secondary,
+                                    ))
+                                    .on_hover_text("Save the last seconds of audio (B)")
+                                    .clicked()
+                            {
+                                self.save_replay();
+                            }
+
                             ui.add_space(16.0);
 
                             // Engine status
@@ -692,6 +869,26 @@ impl PhantomlinkApp {
                         }
                     }
 
+                    ui.add_space(12.0);
+                    ui.separator();
+                    ui.add_space(8.0);
+                    self.draw_dsp_load_section(ui);
+
+                    ui.add_space(12.0);
+                    ui.separator();
+                    ui.add_space(8.0);
+                    self.draw_latency_section(ui);
+
+                    ui.add_space(12.0);
+                    ui.separator();
+                    ui.add_space(8.0);
+                    self.draw_signal_generator_section(ui);
+
+                    ui.add_space(12.0);
+                    ui.separator();
+                    ui.add_space(8.0);
+                    self.draw_noise_profile_section(ui);
+
                     ui.add_space(8.0);
                     ui.label(
                         egui::RichText::new(
@@ -1173,7 +1370,6 @@ impl PhantomlinkApp {
                             ui,
                             &self.theme,
                             name,
-                            &self.vst_plugins,
                             &self.vst_plugin_info,
                         );
 
@@ -1188,6 +1384,10 @@ impl PhantomlinkApp {
                                 .rect_filled(badge_rect, egui::Rounding::same(2.0), badge_color);
                         }
 
+                        if response.vst_changed {
+                            self.load_strip_plugin(i);
+                        }
+
                         if response.volume_changed
                             || response.gain_changed
                             || response.pan_changed
@@ -1260,6 +1460,9 @@ impl PhantomlinkApp {
                                 strip.muted = self.mute_all;
                             }
                         }
+
+                        ui.add_space(12.0);
+                        self.draw_loudness_meters(ui);
                     });
                 });
 
@@ -5,6 +5,7 @@
 #![allow(dead_code)] // Widget library - components used as needed in various panels
 
 use crate::gui::theme::WavelinkTheme;
+use crate::phantomlink::PluginFormat;
 use eframe::egui;
 
 /// Hardware-style rotary knob for gain/pan controls
@@ -296,6 +297,8 @@ pub struct ChannelTelemetry {
     pub rtx_active: bool,
     /// VST processing active
     pub vst_active: bool,
+    /// Limiter gain reduction in dB
+    pub limiter_reduction_db: f32,
 }
 
 pub struct ModernChannelStrip {
@@ -334,7 +337,6 @@ impl ModernChannelStrip {
         ui: &mut egui::Ui,
         theme: &WavelinkTheme,
         channel_name: &str,
-        vst_plugins: &[std::path::PathBuf],
         vst_plugin_info: &[crate::phantomlink::VstPluginInfo],
     ) -> ChannelStripResponse {
         let mut response = ChannelStripResponse::default();
@@ -430,9 +432,9 @@ impl ModernChannelStrip {
 
                 ui.add_space(8.0);
 
-                // VST Plugin selection with modern dropdown
+                // Plugin selection (VST2 and CLAP) with modern dropdown
                 ui.label(
-                    egui::RichText::new("VST")
+                    egui::RichText::new("PLUGIN")
                         .size(11.0)
                         .strong()
                         .color(theme.text_secondary),
@@ -441,14 +443,7 @@ impl ModernChannelStrip {
                 let selected_text = if let Some(plugin_idx) = self.selected_vst {
                     vst_plugin_info
                         .get(plugin_idx)
-                        .map(|info| info.name.as_str())
-                        .or_else(|| {
-                            vst_plugins
-                                .get(plugin_idx)
-                                .and_then(|p| p.file_name())
-                                .and_then(|n| n.to_str())
-                        })
-                        .unwrap_or("Unknown")
+                        .map_or("Unknown", |info| info.name.as_str())
                 } else {
                     "None"
                 };
@@ -464,37 +459,21 @@ impl ModernChannelStrip {
                             response.vst_changed = true;
                         }
 
-                        if !vst_plugin_info.is_empty() {
-                            for (idx, plugin_info) in vst_plugin_info.iter().enumerate() {
-                                let display_name = if plugin_info.vendor.is_empty() {
-                                    plugin_info.name.clone()
-                                } else {
-                                    format!("{}\n{}", plugin_info.name, plugin_info.vendor)
-                                };
-
-                                if ui
-                                    .selectable_value(
-                                        &mut self.selected_vst,
-                                        Some(idx),
-                                        display_name,
-                                    )
-                                    .clicked()
-                                {
-                                    response.vst_changed = true;
-                                }
+                        for (idx, plugin_info) in vst_plugin_info.iter().enumerate() {
+                            let mut display_name = if plugin_info.vendor.is_empty() {
+                                plugin_info.name.clone()
+                            } else {
+                                format!("{}\n{}", plugin_info.name, plugin_info.vendor)
+                            };
+                            if plugin_info.format != PluginFormat::Vst2 {
+                                display_name.push_str(&format!(" ({})", plugin_info.format.name()));
                             }
-                        } else {
-                            for (idx, plugin) in vst_plugins.iter().enumerate() {
-                                let name = plugin
-                                    .file_name()
-                                    .and_then(|n| n.to_str())
-                                    .unwrap_or("Unknown");
-                                if ui
-                                    .selectable_value(&mut self.selected_vst, Some(idx), name)
-                                    .clicked()
-                                {
-                                    response.vst_changed = true;
-                                }
+
+                            if ui
+                                .selectable_value(&mut self.selected_vst, Some(idx), display_name)
+                                .clicked()
+                            {
+                                response.vst_changed = true;
                             }
                         }
                     });
@@ -568,6 +547,31 @@ impl ModernChannelStrip {
                             .color(lat_color),
                     );
 
+                    // Denoiser CPU load
+                    if tel.cpu_percent > 0.0 {
+                        let cpu_color = if tel.cpu_percent < 25.0 {
+                            theme.text_secondary
+                        } else if tel.cpu_percent < 60.0 {
+                            theme.warning
+                        } else {
+                            theme.error
+                        };
+                        ui.label(
+                            egui::RichText::new(format!("{:.0}%", tel.cpu_percent))
+                                .size(8.0)
+                                .color(cpu_color),
+                        );
+                    }
+
+                    // Limiter gain reduction
+                    if tel.limiter_reduction_db > 0.1 {
+                        ui.label(
+                            egui::RichText::new(format!("GR {:.1}", tel.limiter_reduction_db))
+                                .size(8.0)
+                                .color(theme.warning),
+                        );
+                    }
+
                     // XRun indicator
                     if tel.xruns > 0 {
                         ui.label(
@@ -1,10 +1,14 @@
 //! JSON-RPC IPC Server for PhantomLink external control.
 //!
 //! Provides remote control interface via Unix domain sockets for:
-//! - Mixer channel control (volume, mute, gain, pan)
+//! - Mixer channel control (volume, mute, gain, pan, denoiser)
+//! - Test signal generator as a channel source
 //! - GhostWave AI denoising configuration
-//! - VST plugin management
-//! - System status queries
+//! - Multitrack recording (start, stop, markers) and instant replay
+//! - EBU R128 loudness of the output buses and their loudness AGC
+//! - VST plugin management and the plugin catalogue (scan paths, blacklist)
+//! - The host transport plugins sync to (tempo, meter, play/stop, position)
+//! - System status, latency queries and round-trip latency calibration
 //!
 //! ## Usage
 //! Connect via Unix socket at `/run/user/<uid>/phantomlink.sock` or
@@ -20,8 +24,16 @@ use std::thread::{self, JoinHandle};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::audio::AudioEngine;
+use crate::advanced_denoising::DenoisingMode;
+use crate::audio::{AudioEngine, ChannelDenoiser};
+use crate::calibration::{self, TestSignal};
+use crate::config::AppConfig;
 use crate::ghostwave_integration::{GhostWaveIntegration, LatencyMode, PhantomLinkProfile};
+use crate::plugin_catalogue::{PluginCatalogue, ScanPath};
+use crate::plugin_host::PluginFormat;
+use crate::recorder::TapPoint;
+use crate::signal_generator::{SignalGeneratorConfig, Waveform};
+use crate::transport::{self, BEAT_UNITS, Transport};
 
 /// JSON-RPC request structure
 #[derive(Debug, Deserialize)]
@@ -93,6 +105,21 @@ pub struct ChannelState {
     pub pan: f32,
     pub peak_level: f32,
     pub rms_level: f32,
+    pub denoiser: String,
+    pub denoise_latency_ms: f32,
+    pub denoise_cpu_percent: f32,
+    /// Smoothed share of the buffer duration spent in this strip
+    pub dsp_load_percent: f32,
+    /// Stage with the highest load (Gain, Denoiser, VST, Effects, Output)
+    pub heaviest_stage: String,
+    /// Deepest limiter gain reduction during the last buffer, in dB
+    pub limiter_reduction_db: f32,
+    /// Delay of the whole strip, in samples
+    pub latency_samples: usize,
+    /// "Input", or the waveform of the generator feeding the strip
+    pub source: String,
+    /// Loaded effect plugin as "Name (FORMAT)", if any
+    pub plugin: Option<String>,
 }
 
 /// GhostWave state for IPC queries
@@ -305,6 +332,14 @@ impl IpcServer {
             // System methods
             "system.status" => Self::handle_system_status(request.id, audio_engine, ghostwave),
             "system.version" => Self::handle_system_version(request.id),
+            "system.dsp_load" => Self::handle_system_dsp_load(request.id, audio_engine),
+            "system.latency" => Self::handle_system_latency(request.id, audio_engine),
+            "system.measure_latency" => {
+                Self::handle_system_measure_latency(request.id, request.params, audio_engine)
+            }
+            "system.clear_latency_calibration" => {
+                Self::handle_system_clear_latency_calibration(request.id, audio_engine)
+            }
 
             // Mixer methods
             "mixer.get_channels" => Self::handle_get_channels(request.id, audio_engine),
@@ -312,6 +347,16 @@ impl IpcServer {
             "mixer.set_mute" => Self::handle_set_mute(request.id, request.params, audio_engine),
             "mixer.set_gain" => Self::handle_set_gain(request.id, request.params, audio_engine),
             "mixer.set_pan" => Self::handle_set_pan(request.id, request.params, audio_engine),
+            "mixer.set_denoiser" => {
+                Self::handle_set_denoiser(request.id, request.params, audio_engine)
+            }
+
+            // Signal generator methods
+            "generator.set" => Self::handle_generator_set(request.id, request.params, audio_engine),
+            "generator.stop" => {
+                Self::handle_generator_stop(request.id, request.params, audio_engine)
+            }
+            "generator.status" => Self::handle_generator_status(request.id, audio_engine),
 
             // GhostWave methods
             "ghostwave.status" => Self::handle_ghostwave_status(request.id, ghostwave),
@@ -329,6 +374,38 @@ impl IpcServer {
             }
             "ghostwave.restart_gpu" => Self::handle_ghostwave_restart_gpu(request.id, ghostwave),
 
+            // Loudness methods
+            "loudness.status" => Self::handle_loudness_status(request.id, audio_engine),
+            "loudness.reset" => Self::handle_loudness_reset(request.id, audio_engine),
+            "agc.status" => Self::handle_agc_status(request.id, audio_engine),
+            "agc.set" => Self::handle_agc_set(request.id, request.params, audio_engine),
+
+            // Recorder methods
+            "recorder.start" => {
+                Self::handle_recorder_start(request.id, request.params, audio_engine)
+            }
+            "recorder.stop" => Self::handle_recorder_stop(request.id, audio_engine),
+            "recorder.marker" => {
+                Self::handle_recorder_marker(request.id, request.params, audio_engine)
+            }
+            "recorder.status" => Self::handle_recorder_status(request.id, audio_engine),
+            "recorder.save_replay" => Self::handle_recorder_save_replay(request.id, audio_engine),
+
+            // Plugin catalogue methods
+            "plugins.list" => Self::handle_plugins_list(request.id),
+            "plugins.rescan" => Self::handle_plugins_rescan(request.id),
+            "plugins.add_scan_path" => {
+                Self::handle_plugins_scan_path(request.id, request.params, true)
+            }
+            "plugins.remove_scan_path" => {
+                Self::handle_plugins_scan_path(request.id, request.params, false)
+            }
+            "plugins.unblacklist" => Self::handle_plugins_unblacklist(request.id, request.params),
+
+            // Transport methods
+            "transport.get" => Self::handle_transport_get(request.id),
+            "transport.set" => Self::handle_transport_set(request.id, request.params),
+
             // Unknown method
             _ => JsonRpcResponse::error(
                 request.id,
@@ -391,24 +468,7 @@ impl IpcServer {
             .unwrap_or(false);
 
         // Get channel states
-        let channels: Vec<ChannelState> = (0..4)
-            .filter_map(|i| {
-                audio_engine.lock().ok().map(|e| {
-                    let levels = e.get_channel_levels(i).unwrap_or([0.0, 0.0]);
-                    let (volume, muted, gain, pan) =
-                        e.get_channel_state(i).unwrap_or((0.8, false, 0.0, 0.0));
-                    ChannelState {
-                        index: i,
-                        volume,
-                        muted,
-                        gain,
-                        pan,
-                        peak_level: levels[0],
-                        rms_level: levels[1],
-                    }
-                })
-            })
-            .collect();
+        let channels = Self::collect_channel_states(audio_engine);
 
         let state = SystemState {
             version: env!("CARGO_PKG_VERSION").to_string(),
@@ -422,6 +482,51 @@ impl IpcServer {
         JsonRpcResponse::success(id, serde_json::to_value(state).unwrap())
     }
 
+    fn collect_channel_states(audio_engine: &Arc<Mutex<AudioEngine>>) -> Vec<ChannelState> {
+        let Ok(e) = audio_engine.lock() else {
+            return Vec::new();
+        };
+        // The all-channel getters are read once, then indexed per channel
+        let limiter_reduction = e.get_limiter_gain_reduction();
+        let latency = e.get_channel_latency_samples();
+        (0..4)
+            .map(|i| {
+                let levels = e.get_channel_levels(i).unwrap_or([0.0, 0.0]);
+                let (volume, muted, gain, pan) =
+                    e.get_channel_state(i).unwrap_or((0.8, false, 0.0, 0.0));
+                let denoise = e.get_channel_denoising_metrics(i).unwrap_or_default();
+                let dsp_load = e.get_channel_dsp_load(i).unwrap_or_default();
+                ChannelState {
+                    index: i,
+                    volume,
+                    muted,
+                    gain,
+                    pan,
+                    peak_level: levels[0],
+                    rms_level: levels[1],
+                    denoiser: e
+                        .get_channel_denoiser(i)
+                        .unwrap_or_default()
+                        .name()
+                        .to_string(),
+                    denoise_latency_ms: denoise.latency_ms,
+                    denoise_cpu_percent: denoise.cpu_usage_percent,
+                    dsp_load_percent: dsp_load.total.average_percent,
+                    heaviest_stage: dsp_load.heaviest_stage().name().to_string(),
+                    limiter_reduction_db: limiter_reduction.get(i).copied().unwrap_or(0.0),
+                    latency_samples: latency.get(i).copied().unwrap_or(0),
+                    source: e
+                        .get_channel_generator(i)
+                        .map_or("Input", |g| g.waveform.name())
+                        .to_string(),
+                    plugin: e
+                        .get_channel_plugin(i)
+                        .map(|(name, format)| format!("{} ({})", name, format.name())),
+                }
+            })
+            .collect()
+    }
+
     fn handle_system_version(id: Option<serde_json::Value>) -> JsonRpcResponse {
         let version = serde_json::json!({
             "name": "PhantomLink",
@@ -431,30 +536,689 @@ impl IpcServer {
         JsonRpcResponse::success(id, version)
     }
 
+    fn handle_system_dsp_load(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(engine) => {
+                JsonRpcResponse::success(id, serde_json::to_value(engine.get_dsp_load()).unwrap())
+            }
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    fn handle_system_latency(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(engine) => JsonRpcResponse::success(
+                id,
+                serde_json::to_value(engine.get_latency_report()).unwrap(),
+            ),
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    /// Measure the round trip through a loopback; optional params choose the
+    /// signal ("mls"/"impulse"), its level, the expected round trip in ms
+    /// (default: the configured PipeWire preset) and whether to store the
+    /// result (default: no)
+    fn handle_system_measure_latency(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        let params = params.unwrap_or_default();
+        let signal = match params.get("signal").and_then(|v| v.as_str()) {
+            Some(name) => match TestSignal::from_name(name) {
+                Some(signal) => signal,
+                None => {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        format!("Unknown test signal: {}", name),
+                    );
+                }
+            },
+            None => TestSignal::default(),
+        };
+        let level = params
+            .get("level")
+            .and_then(|v| v.as_f64())
+            .map_or(calibration::DEFAULT_LEVEL, |v| v as f32);
+        let store = params
+            .get("store")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        // The engine is only checked here; the measurement takes a couple of
+        // seconds and must not hold its lock that long
+        let expected_ms = {
+            let Ok(engine) = audio_engine.lock() else {
+                return JsonRpcResponse::error(
+                    id,
+                    INTERNAL_ERROR,
+                    "Failed to lock audio engine".to_string(),
+                );
+            };
+            if engine.is_running() {
+                return JsonRpcResponse::error(
+                    id,
+                    INVALID_PARAMS,
+                    "Stop the audio engine before measuring latency".to_string(),
+                );
+            }
+            // Same expectation the GUI uses for the configured preset
+            params
+                .get("expected_ms")
+                .and_then(|v| v.as_f64())
+                .map_or_else(
+                    || AppConfig::load().pipewire.expected_round_trip_ms(),
+                    |v| v as f32,
+                )
+        };
+
+        let measurement = match calibration::measure_round_trip(signal, level, expected_ms) {
+            Ok(measurement) => measurement,
+            Err(e) => return JsonRpcResponse::error(id, INTERNAL_ERROR, format!("{:#}", e)),
+        };
+
+        if store {
+            if let Ok(mut engine) = audio_engine.lock() {
+                engine.set_latency_calibration(Some(measurement.calibration()));
+            }
+            let mut config = AppConfig::load();
+            config.latency_calibration = Some(measurement.calibration());
+            if let Err(e) = config.save() {
+                log::warn!("Failed to save latency calibration: {}", e);
+            }
+        }
+
+        JsonRpcResponse::success(id, serde_json::to_value(measurement).unwrap())
+    }
+
+    fn handle_system_clear_latency_calibration(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(mut engine) => {
+                engine.set_latency_calibration(None);
+                let mut config = AppConfig::load();
+                config.latency_calibration = None;
+                if let Err(e) = config.save() {
+                    log::warn!("Failed to save latency calibration: {}", e);
+                }
+                JsonRpcResponse::success(id, serde_json::json!({"success": true}))
+            }
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    // ===== Loudness Methods =====
+
+    fn handle_loudness_status(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(engine) => JsonRpcResponse::success(
+                id,
+                serde_json::to_value(engine.get_bus_loudness()).unwrap(),
+            ),
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    fn handle_loudness_reset(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(engine) => {
+                engine.reset_loudness();
+                JsonRpcResponse::success(id, serde_json::json!({"success": true}))
+            }
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    fn handle_agc_status(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(engine) => {
+                JsonRpcResponse::success(id, serde_json::to_value(engine.get_agc_status()).unwrap())
+            }
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    fn handle_agc_set(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        let params = params.unwrap_or_default();
+        let bus = params
+            .get("bus")
+            .and_then(|v| v.as_str())
+            .unwrap_or("master");
+
+        let Ok(engine) = audio_engine.lock() else {
+            return JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            );
+        };
+        let Some(mut config) = engine.get_bus_agc(bus) else {
+            return JsonRpcResponse::error(
+                id,
+                INVALID_PARAMS,
+                format!("Unknown output bus: {}", bus),
+            );
+        };
+
+        if let Some(enabled) = params.get("enabled").and_then(|v| v.as_bool()) {
+            config.enabled = enabled;
+        }
+        for (key, value) in [
+            ("target_lufs", &mut config.target_lufs),
+            ("max_boost_db", &mut config.max_boost_db),
+            ("max_cut_db", &mut config.max_cut_db),
+            ("response_secs", &mut config.response_secs),
+            ("ceiling_dbtp", &mut config.ceiling_dbtp),
+        ] {
+            if let Some(v) = params.get(key).and_then(|v| v.as_f64()) {
+                *value = v as f32;
+            }
+        }
+
+        match engine.set_bus_agc(bus, config.clone()) {
+            Ok(()) => JsonRpcResponse::success(id, serde_json::to_value(config).unwrap()),
+            Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, format!("{:#}", e)),
+        }
+    }
+
+    // ===== Signal Generator Methods =====
+
+    /// Feed a channel from the generator; `waveform` is Sine, White, Pink,
+    /// Sweep or Reference, other fields keep their defaults when omitted
+    fn handle_generator_set(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        let params = match params {
+            Some(p) => p,
+            None => {
+                return JsonRpcResponse::error(id, INVALID_PARAMS, "Missing params".to_string());
+            }
+        };
+
+        let channel: usize = match params.get("channel").and_then(|v| v.as_u64()) {
+            Some(c) => c as usize,
+            None => {
+                return JsonRpcResponse::error(
+                    id,
+                    INVALID_PARAMS,
+                    "Missing channel parameter".to_string(),
+                );
+            }
+        };
+
+        let mut config = SignalGeneratorConfig::default();
+        if let Some(name) = params.get("waveform").and_then(|v| v.as_str()) {
+            match Waveform::from_name(name) {
+                Some(waveform) => config.waveform = waveform,
+                None => {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        format!("Unknown waveform: {}", name),
+                    );
+                }
+            }
+        }
+        for (key, value) in [
+            ("frequency_hz", &mut config.frequency_hz),
+            ("level_dbfs", &mut config.level_dbfs),
+            ("sweep_start_hz", &mut config.sweep_start_hz),
+            ("sweep_end_hz", &mut config.sweep_end_hz),
+            ("sweep_secs", &mut config.sweep_secs),
+        ] {
+            if let Some(v) = params.get(key).and_then(|v| v.as_f64()) {
+                *value = v as f32;
+            }
+        }
+
+        let Ok(engine) = audio_engine.lock() else {
+            return JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            );
+        };
+        match engine.set_channel_generator(channel, Some(config.clone())) {
+            Ok(()) => JsonRpcResponse::success(id, serde_json::to_value(config).unwrap()),
+            Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, format!("{:#}", e)),
+        }
+    }
+
+    /// Return a channel to its input
+    fn handle_generator_stop(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        let Some(channel) = params
+            .as_ref()
+            .and_then(|p| p.get("channel"))
+            .and_then(|v| v.as_u64())
+        else {
+            return JsonRpcResponse::error(
+                id,
+                INVALID_PARAMS,
+                "Missing channel parameter".to_string(),
+            );
+        };
+
+        let Ok(engine) = audio_engine.lock() else {
+            return JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            );
+        };
+        match engine.set_channel_generator(channel as usize, None) {
+            Ok(()) => JsonRpcResponse::success(id, serde_json::json!({"success": true})),
+            Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, format!("{:#}", e)),
+        }
+    }
+
+    fn handle_generator_status(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(engine) => {
+                let generators: Vec<_> = (0..4)
+                    .map(|i| {
+                        serde_json::json!({
+                            "channel": i,
+                            "generator": engine.get_channel_generator(i),
+                        })
+                    })
+                    .collect();
+                JsonRpcResponse::success(id, serde_json::Value::Array(generators))
+            }
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    // ===== Plugin Catalogue Methods =====
+
+    fn catalogue_json(catalogue: &PluginCatalogue) -> serde_json::Value {
+        serde_json::json!({
+            "plugins": catalogue.plugins(),
+            "scan_paths": catalogue.scan_paths,
+            "blacklist": catalogue.blacklist(),
+        })
+    }
+
+    /// Catalogued plugins, custom scan paths and blacklist, without scanning
+    fn handle_plugins_list(id: Option<serde_json::Value>) -> JsonRpcResponse {
+        match PluginCatalogue::global().lock() {
+            Ok(catalogue) => JsonRpcResponse::success(id, Self::catalogue_json(&catalogue)),
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock plugin catalogue".to_string(),
+            ),
+        }
+    }
+
+    /// Scan new and changed plugin files; returns the counts and the catalogue
+    fn handle_plugins_rescan(id: Option<serde_json::Value>) -> JsonRpcResponse {
+        let Ok(mut catalogue) = PluginCatalogue::global().lock() else {
+            return JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock plugin catalogue".to_string(),
+            );
+        };
+        let summary = catalogue.refresh();
+        if let Err(e) = catalogue.save() {
+            log::warn!("Failed to save plugin catalogue: {:#}", e);
+        }
+        let mut result = Self::catalogue_json(&catalogue);
+        result["summary"] = serde_json::to_value(summary).unwrap();
+        JsonRpcResponse::success(id, result)
+    }
+
+    /// Add or remove a custom scan path: params `format` ("VST2", "CLAP",
+    /// "LV2", "LADSPA") and `path`. Takes effect on the next rescan.
+    fn handle_plugins_scan_path(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+        add: bool,
+    ) -> JsonRpcResponse {
+        let params = params.unwrap_or_default();
+        let Some(path) = params.get("path").and_then(|v| v.as_str()) else {
+            return JsonRpcResponse::error(
+                id,
+                INVALID_PARAMS,
+                "Missing path parameter".to_string(),
+            );
+        };
+        let format_name = params
+            .get("format")
+            .and_then(|v| v.as_str())
+            .unwrap_or("VST2");
+        let Some(format) = PluginFormat::from_name(format_name) else {
+            return JsonRpcResponse::error(
+                id,
+                INVALID_PARAMS,
+                format!("Unknown plugin format: {}", format_name),
+            );
+        };
+
+        let Ok(mut catalogue) = PluginCatalogue::global().lock() else {
+            return JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock plugin catalogue".to_string(),
+            );
+        };
+        let scan_path = ScanPath {
+            format,
+            path: PathBuf::from(path),
+        };
+        let changed = if add {
+            catalogue.add_scan_path(scan_path)
+        } else {
+            catalogue.remove_scan_path(&scan_path)
+        };
+        if changed && let Err(e) = catalogue.save() {
+            return JsonRpcResponse::error(id, INTERNAL_ERROR, format!("{:#}", e));
+        }
+        JsonRpcResponse::success(
+            id,
+            serde_json::json!({"success": true, "changed": changed, "scan_paths": catalogue.scan_paths}),
+        )
+    }
+
+    /// Let a blacklisted file be scanned again on the next rescan
+    fn handle_plugins_unblacklist(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+    ) -> JsonRpcResponse {
+        let Some(path) = params
+            .as_ref()
+            .and_then(|p| p.get("path"))
+            .and_then(|v| v.as_str())
+        else {
+            return JsonRpcResponse::error(
+                id,
+                INVALID_PARAMS,
+                "Missing path parameter".to_string(),
+            );
+        };
+        let Ok(mut catalogue) = PluginCatalogue::global().lock() else {
+            return JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock plugin catalogue".to_string(),
+            );
+        };
+        if !catalogue.unblacklist(std::path::Path::new(path)) {
+            return JsonRpcResponse::error(
+                id,
+                INVALID_PARAMS,
+                format!("Not blacklisted: {}", path),
+            );
+        }
+        if let Err(e) = catalogue.save() {
+            return JsonRpcResponse::error(id, INTERNAL_ERROR, format!("{:#}", e));
+        }
+        JsonRpcResponse::success(id, serde_json::json!({"success": true}))
+    }
+
+    // ===== Transport Methods =====
+
+    fn transport_json(transport: &Transport) -> serde_json::Value {
+        let info = transport.info();
+        let mut result = serde_json::to_value(info).unwrap();
+        result["bar"] = serde_json::json!(info.bar() + 1);
+        result["beat"] = serde_json::json!(info.beat_in_bar() + 1.0);
+        result["sync_jack"] = serde_json::json!(transport.config().sync_jack);
+        result["following_jack"] = serde_json::json!(transport.following_jack());
+        result
+    }
+
+    /// Position, tempo, meter and play state; `bar` and `beat` count from 1
+    fn handle_transport_get(id: Option<serde_json::Value>) -> JsonRpcResponse {
+        match transport::global().lock() {
+            Ok(transport) => JsonRpcResponse::success(id, Self::transport_json(&transport)),
+            Err(_) => {
+                JsonRpcResponse::error(id, INTERNAL_ERROR, "Failed to lock transport".to_string())
+            }
+        }
+    }
+
+    /// Optional params `tempo`, `beats_per_bar`, `beat_unit`, `playing`,
+    /// `position` (samples) and `sync_jack`. While the JACK transport is
+    /// followed it overrides position, play state, tempo and meter.
+    fn handle_transport_set(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+    ) -> JsonRpcResponse {
+        let params = params.unwrap_or_default();
+        let Ok(mut transport) = transport::global().lock() else {
+            return JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock transport".to_string(),
+            );
+        };
+        let info = transport.info();
+        let beats_per_bar = params.get("beats_per_bar").and_then(|v| v.as_u64());
+        let beat_unit = params.get("beat_unit").and_then(|v| v.as_u64());
+        if let Some(unit) = beat_unit
+            && !BEAT_UNITS.contains(&(unit as u32))
+        {
+            return JsonRpcResponse::error(
+                id,
+                INVALID_PARAMS,
+                format!("Invalid beat_unit: {} (1, 2, 4, 8, 16 or 32)", unit),
+            );
+        }
+
+        if let Some(tempo) = params.get("tempo").and_then(|v| v.as_f64()) {
+            transport.set_tempo(tempo);
+        }
+        if beats_per_bar.is_some() || beat_unit.is_some() {
+            transport.set_time_signature(
+                beats_per_bar.map_or(info.beats_per_bar, |v| v as u32),
+                beat_unit.map_or(info.beat_unit, |v| v as u32),
+            );
+        }
+        if let Some(playing) = params.get("playing").and_then(|v| v.as_bool()) {
+            transport.set_playing(playing);
+        }
+        if let Some(position) = params.get("position").and_then(|v| v.as_i64()) {
+            transport.locate(position);
+        }
+        if let Some(sync) = params.get("sync_jack").and_then(|v| v.as_bool()) {
+            transport.set_sync_jack(sync);
+        }
+        JsonRpcResponse::success(id, Self::transport_json(&transport))
+    }
+
+    // ===== Recorder Methods =====
+
+    /// Start recording with the saved settings; optional params override the
+    /// folder, format ("wav"/"flac"), bit depth and the tap ("pre"/"post")
+    /// used for every armed channel
+    fn handle_recorder_start(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        let mut config = AppConfig::load().recorder;
+        if let Some(params) = params {
+            if let Some(directory) = params.get("directory").and_then(|v| v.as_str()) {
+                config.directory = PathBuf::from(directory);
+            }
+            if let Some(format) = params.get("format").and_then(|v| v.as_str()) {
+                config.format = format.to_ascii_lowercase();
+            }
+            if let Some(bits) = params.get("bits").and_then(|v| v.as_u64()) {
+                config.bits_per_sample = bits as u16;
+            }
+            if let Some(name) = params.get("tap").and_then(|v| v.as_str()) {
+                let Some(tap) = TapPoint::from_name(name) else {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        format!("Unknown tap point: {} (expected pre or post)", name),
+                    );
+                };
+                for armed in config.channel_taps.iter_mut().flatten() {
+                    *armed = tap;
+                }
+            }
+        }
+
+        match audio_engine.lock() {
+            Ok(mut engine) => match engine.start_recording(&config) {
+                Ok(directory) => {
+                    JsonRpcResponse::success(id, serde_json::json!({"directory": directory}))
+                }
+                Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, format!("{:#}", e)),
+            },
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    fn handle_recorder_stop(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(mut engine) => match engine.stop_recording() {
+                Ok(summary) => JsonRpcResponse::success(id, serde_json::to_value(summary).unwrap()),
+                Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, format!("{:#}", e)),
+            },
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    fn handle_recorder_marker(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        let label = params
+            .as_ref()
+            .and_then(|p| p.get("label"))
+            .and_then(|v| v.as_str())
+            .map(String::from);
+
+        match audio_engine.lock() {
+            Ok(engine) => match engine.add_recording_marker(label) {
+                Ok(()) => JsonRpcResponse::success(id, serde_json::json!({"success": true})),
+                Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, e.to_string()),
+            },
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    fn handle_recorder_save_replay(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(engine) => match engine.save_replay() {
+                Ok(summary) => JsonRpcResponse::success(id, serde_json::to_value(summary).unwrap()),
+                Err(e) => JsonRpcResponse::error(id, INTERNAL_ERROR, format!("{:#}", e)),
+            },
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
+    fn handle_recorder_status(
+        id: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        match audio_engine.lock() {
+            Ok(engine) => JsonRpcResponse::success(
+                id,
+                serde_json::to_value(engine.get_recorder_status()).unwrap(),
+            ),
+            Err(_) => JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                "Failed to lock audio engine".to_string(),
+            ),
+        }
+    }
+
     // ===== Mixer Methods =====
 
     fn handle_get_channels(
         id: Option<serde_json::Value>,
         audio_engine: &Arc<Mutex<AudioEngine>>,
     ) -> JsonRpcResponse {
-        let channels: Vec<ChannelState> = (0..4)
-            .filter_map(|i| {
-                audio_engine.lock().ok().map(|e| {
-                    let levels = e.get_channel_levels(i).unwrap_or([0.0, 0.0]);
-                    let (volume, muted, gain, pan) =
-                        e.get_channel_state(i).unwrap_or((0.8, false, 0.0, 0.0));
-                    ChannelState {
-                        index: i,
-                        volume,
-                        muted,
-                        gain,
-                        pan,
-                        peak_level: levels[0],
-                        rms_level: levels[1],
-                    }
-                })
-            })
-            .collect();
+        let channels = Self::collect_channel_states(audio_engine);
 
         JsonRpcResponse::success(id, serde_json::to_value(channels).unwrap())
     }
@@ -655,6 +1419,97 @@ impl IpcServer {
         }
     }
 
+    fn handle_set_denoiser(
+        id: Option<serde_json::Value>,
+        params: Option<serde_json::Value>,
+        audio_engine: &Arc<Mutex<AudioEngine>>,
+    ) -> JsonRpcResponse {
+        let params = match params {
+            Some(p) => p,
+            None => {
+                return JsonRpcResponse::error(id, INVALID_PARAMS, "Missing params".to_string());
+            }
+        };
+
+        let channel: usize = match params.get("channel").and_then(|v| v.as_u64()) {
+            Some(c) => c as usize,
+            None => {
+                return JsonRpcResponse::error(
+                    id,
+                    INVALID_PARAMS,
+                    "Missing channel parameter".to_string(),
+                );
+            }
+        };
+
+        let denoiser_str: &str = match params.get("denoiser").and_then(|v| v.as_str()) {
+            Some(d) => d,
+            None => {
+                return JsonRpcResponse::error(
+                    id,
+                    INVALID_PARAMS,
+                    "Missing denoiser parameter".to_string(),
+                );
+            }
+        };
+
+        let denoiser = match ChannelDenoiser::from_name(denoiser_str) {
+            Some(d) => d,
+            None => {
+                return JsonRpcResponse::error(
+                    id,
+                    INVALID_PARAMS,
+                    format!("Unknown denoiser: {}", denoiser_str),
+                );
+            }
+        };
+
+        // Optional advanced denoising mode
+        let mode = match params.get("mode").and_then(|v| v.as_str()) {
+            None => None,
+            Some(m) => match m.to_lowercase().as_str() {
+                "basic" => Some(DenoisingMode::Basic),
+                "enhanced" => Some(DenoisingMode::Enhanced),
+                "maximum" => Some(DenoisingMode::Maximum),
+                _ => {
+                    return JsonRpcResponse::error(
+                        id,
+                        INVALID_PARAMS,
+                        format!("Unknown denoising mode: {}", m),
+                    );
+                }
+            },
+        };
+
+        let engine = match audio_engine.lock() {
+            Ok(e) => e,
+            Err(_) => {
+                return JsonRpcResponse::error(
+                    id,
+                    INTERNAL_ERROR,
+                    "Failed to lock audio engine".to_string(),
+                );
+            }
+        };
+
+        if let Some(mode) = mode
+            && let Err(e) = engine.set_channel_denoising_mode(channel, mode)
+        {
+            return JsonRpcResponse::error(
+                id,
+                INTERNAL_ERROR,
+                format!("Failed to set denoising mode: {}", e),
+            );
+        }
+
+        match engine.set_channel_denoiser(channel, denoiser) {
+            Ok(()) => JsonRpcResponse::success(id, serde_json::json!({"success": true})),
+            Err(e) => {
+                JsonRpcResponse::error(id, INVALID_PARAMS, format!("Failed to set denoiser: {}", e))
+            }
+        }
+    }
+
     // ===== GhostWave Methods =====
 
     fn handle_ghostwave_status(
@@ -2,9 +2,15 @@
 //!
 //! Provides low-latency audio I/O through JACK when available,
 //! with automatic fallback to ALSA/PipeWire when JACK is not running.
+//! While active, the JACK transport drives the host transport plugins see
+//! unless the user turns "Follow JACK transport" off.
 
+use crate::transport::{self, MusicalPosition};
 use crossbeam_channel::{Receiver, Sender, bounded};
-use jack::{AudioIn, AudioOut, Client, ClientOptions, Control, Port, ProcessHandler, ProcessScope};
+use jack::{
+    AudioIn, AudioOut, Client, ClientOptions, Control, Port, ProcessHandler, ProcessScope,
+    TransportState,
+};
 use std::sync::{
     Arc,
     atomic::{AtomicBool, AtomicU32, Ordering},
@@ -40,8 +46,43 @@ struct JackHandler {
     silence: Vec<f32>,
 }
 
+/// Pass this cycle's JACK transport on to the host transport. Tempo and
+/// meter are kept when no JACK client publishes bar/beat/tick; when one does,
+/// its bar and beat are passed on as well so plugins stay on the master's grid
+/// after tempo changes.
+fn follow_transport(client: &Client) {
+    let Ok(query) = client.transport().query() else {
+        return;
+    };
+    // Never wait on the GUI from the process thread; a missed cycle is caught up next time
+    let Ok(mut host) = transport::global().try_lock() else {
+        return;
+    };
+    let mut info = host.info();
+    info.playing = query.state == TransportState::Rolling;
+    info.sample_position = i64::from(query.pos.frame());
+    if let Some(rate) = query.pos.frame_rate() {
+        info.sample_rate = f64::from(rate);
+    }
+    if let Some(bbt) = query.pos.bbt() {
+        info.tempo = bbt.bpm;
+        info.beats_per_bar = bbt.sig_num.round().max(1.0) as u32;
+        info.beat_unit = bbt.sig_denom.round().max(1.0) as u32;
+        // JACK counts bars and beats from one
+        info.musical = Some(MusicalPosition {
+            bar: bbt.bar as i64 - 1,
+            beat: (bbt.beat as f64 - 1.0) + bbt.tick as f64 / bbt.ticks_per_beat,
+        });
+    } else {
+        info.musical = None;
+    }
+    host.follow_jack(info);
+}
+
 impl ProcessHandler for JackHandler {
-    fn process(&mut self, _client: &Client, scope: &ProcessScope) -> Control {
+    fn process(&mut self, client: &Client, scope: &ProcessScope) -> Control {
+        follow_transport(client);
+
         let in_left = self.input_left.as_slice(scope);
         let in_right = self.input_right.as_slice(scope);
         let out_left = self.output_left.as_mut_slice(scope);
@@ -260,6 +301,27 @@ impl JackClient {
         }
     }
 
+    /// Start or stop the JACK transport, for every client following it
+    pub fn set_transport_rolling(&self, rolling: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(ref client) = self.client {
+            let transport = client.as_client().transport();
+            if rolling {
+                transport.start()?;
+            } else {
+                transport.stop()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the JACK transport to `frame`
+    pub fn locate_transport(&self, frame: u32) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(ref client) = self.client {
+            client.as_client().transport().locate(frame)?;
+        }
+        Ok(())
+    }
+
     /// Connect PhantomLink input ports to system capture ports
     pub fn connect_inputs_to_system(&self) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(ref client) = self.client {
@@ -379,6 +441,9 @@ impl Drop for JackClient {
     fn drop(&mut self) {
         if self.available {
             log::info!("JACK client shutting down");
+            if let Ok(mut host) = transport::global().lock() {
+                host.set_jack_active(false);
+            }
         }
     }
 }
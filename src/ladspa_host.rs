@@ -0,0 +1,738 @@
+//! LADSPA plugin hosting.
+//!
+//! The classic Linux effect format: a shared library exports
+//! `ladspa_descriptor`, and every port is a plain `f32` buffer. There is no
+//! metadata beyond the descriptor, so scanning only opens each library.
+//!
+//! - Libraries are found through `LADSPA_PATH`, or the usual system paths
+//! - Mono and stereo plugins go in channel slots; others are not listed
+//! - Control inputs are parameters, with ranges and defaults from the hints
+//! - Port values are saved and restored by port name
+//!
+//! Like LV2, `run()` is called inline from the audio callback.
+
+#![allow(dead_code)] // Complete LADSPA hosting API for plugin management
+
+use crate::plugin_bridge;
+use crate::plugin_host::{self, ChannelLayout, PluginFormat, PluginProcessor};
+use crate::vst_host::{ParameterInfo, VstCategory, VstPluginInfo};
+use anyhow::{Context, Result, bail};
+use libloading::Library;
+use std::collections::BTreeMap;
+use std::ffi::{CStr, c_char, c_int, c_ulong, c_void};
+use std::path::{Path, PathBuf};
+
+/// Largest block handed to `run()`; longer buffers are split
+const MAX_FRAMES: usize = 4096;
+
+const PORT_INPUT: c_int = 0x1;
+const PORT_OUTPUT: c_int = 0x2;
+const PORT_CONTROL: c_int = 0x4;
+const PORT_AUDIO: c_int = 0x8;
+
+const HINT_BOUNDED_BELOW: c_int = 0x1;
+const HINT_BOUNDED_ABOVE: c_int = 0x2;
+const HINT_TOGGLED: c_int = 0x4;
+const HINT_SAMPLE_RATE: c_int = 0x8;
+const HINT_LOGARITHMIC: c_int = 0x10;
+const HINT_INTEGER: c_int = 0x20;
+const HINT_DEFAULT_MASK: c_int = 0x3C0;
+const HINT_DEFAULT_MINIMUM: c_int = 0x40;
+const HINT_DEFAULT_LOW: c_int = 0x80;
+const HINT_DEFAULT_MIDDLE: c_int = 0xC0;
+const HINT_DEFAULT_HIGH: c_int = 0x100;
+const HINT_DEFAULT_MAXIMUM: c_int = 0x140;
+const HINT_DEFAULT_0: c_int = 0x200;
+const HINT_DEFAULT_1: c_int = 0x240;
+const HINT_DEFAULT_100: c_int = 0x280;
+const HINT_DEFAULT_440: c_int = 0x2C0;
+
+#[repr(C)]
+struct LadspaRangeHint {
+    hint: c_int,
+    lower: f32,
+    upper: f32,
+}
+
+#[repr(C)]
+struct LadspaDescriptor {
+    unique_id: c_ulong,
+    label: *const c_char,
+    properties: c_int,
+    name: *const c_char,
+    maker: *const c_char,
+    copyright: *const c_char,
+    port_count: c_ulong,
+    port_descriptors: *const c_int,
+    port_names: *const *const c_char,
+    port_range_hints: *const LadspaRangeHint,
+    implementation_data: *mut c_void,
+    instantiate: Option<unsafe extern "C" fn(*const LadspaDescriptor, c_ulong) -> *mut c_void>,
+    connect_port: Option<unsafe extern "C" fn(*mut c_void, c_ulong, *mut f32)>,
+    activate: Option<unsafe extern "C" fn(*mut c_void)>,
+    run: Option<unsafe extern "C" fn(*mut c_void, c_ulong)>,
+    run_adding: Option<unsafe extern "C" fn(*mut c_void, c_ulong)>,
+    set_run_adding_gain: Option<unsafe extern "C" fn(*mut c_void, f32)>,
+    deactivate: Option<unsafe extern "C" fn(*mut c_void)>,
+    cleanup: Option<unsafe extern "C" fn(*mut c_void)>,
+}
+
+type DescriptorFn = unsafe extern "C" fn(c_ulong) -> *const LadspaDescriptor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PortKind {
+    AudioIn,
+    AudioOut,
+    ControlIn,
+    ControlOut,
+}
+
+/// A port with its range resolved for the current sample rate
+#[derive(Debug, Clone)]
+struct LadspaPort {
+    index: usize,
+    name: String,
+    kind: PortKind,
+    min: f32,
+    max: f32,
+    default: f32,
+    toggled: bool,
+    integer: bool,
+    logarithmic: bool,
+}
+
+impl LadspaPort {
+    fn from_hint(
+        index: usize,
+        name: String,
+        kind: PortKind,
+        hint: &LadspaRangeHint,
+        rate: f32,
+    ) -> Self {
+        let flags = hint.hint;
+        let scale = if flags & HINT_SAMPLE_RATE != 0 {
+            rate
+        } else {
+            1.0
+        };
+        let toggled = flags & HINT_TOGGLED != 0;
+        let (min, max) = if toggled {
+            (0.0, 1.0)
+        } else {
+            (
+                if flags & HINT_BOUNDED_BELOW != 0 {
+                    hint.lower * scale
+                } else {
+                    0.0
+                },
+                if flags & HINT_BOUNDED_ABOVE != 0 {
+                    hint.upper * scale
+                } else {
+                    1.0
+                },
+            )
+        };
+        let logarithmic = flags & HINT_LOGARITHMIC != 0 && min > 0.0 && max > min;
+
+        // Low/middle/high are a quarter, half and three quarters of the way,
+        // in the log domain for logarithmic ports
+        let between = |amount: f32| {
+            if logarithmic {
+                (min.ln() * (1.0 - amount) + max.ln() * amount).exp()
+            } else {
+                min * (1.0 - amount) + max * amount
+            }
+        };
+        let default = match flags & HINT_DEFAULT_MASK {
+            HINT_DEFAULT_MINIMUM => min,
+            HINT_DEFAULT_LOW => between(0.25),
+            HINT_DEFAULT_MIDDLE => between(0.5),
+            HINT_DEFAULT_HIGH => between(0.75),
+            HINT_DEFAULT_MAXIMUM => max,
+            HINT_DEFAULT_0 => 0.0,
+            HINT_DEFAULT_1 => 1.0,
+            HINT_DEFAULT_100 => 100.0,
+            HINT_DEFAULT_440 => 440.0,
+            _ => min.max(0.0).min(max),
+        };
+
+        Self {
+            index,
+            name,
+            kind,
+            min,
+            max,
+            default,
+            toggled,
+            integer: flags & HINT_INTEGER != 0,
+            logarithmic,
+        }
+    }
+
+    fn normalize(&self, value: f32) -> f32 {
+        if self.max <= self.min {
+            return 0.0;
+        }
+        let value = value.clamp(self.min, self.max);
+        if self.logarithmic {
+            (value / self.min).ln() / (self.max / self.min).ln()
+        } else {
+            (value - self.min) / (self.max - self.min)
+        }
+    }
+
+    fn denormalize(&self, value: f32) -> f32 {
+        let value = value.clamp(0.0, 1.0);
+        if self.toggled {
+            return if value >= 0.5 { 1.0 } else { 0.0 };
+        }
+        let plain = if self.logarithmic {
+            self.min * (self.max / self.min).powf(value)
+        } else {
+            self.min + value * (self.max - self.min)
+        };
+        if self.integer { plain.round() } else { plain }
+    }
+
+    fn display(&self, value: f32) -> String {
+        if self.toggled {
+            if value > 0.0 { "On" } else { "Off" }.to_string()
+        } else if self.integer {
+            format!("{:.0}", value)
+        } else {
+            format!("{:.2}", value)
+        }
+    }
+}
+
+/// One plugin of a library, as its descriptor describes it
+#[derive(Debug, Clone)]
+struct LadspaPluginDesc {
+    library: PathBuf,
+    label: String,
+    name: String,
+    maker: String,
+    unique_id: u64,
+    ports: Vec<LadspaPort>,
+}
+
+impl LadspaPluginDesc {
+    /// Read a descriptor the library owns
+    ///
+    /// # Safety
+    /// `descriptor` must point to a valid LADSPA descriptor.
+    unsafe fn read(library: &Path, descriptor: &LadspaDescriptor, rate: f32) -> Self {
+        let mut ports = Vec::new();
+        for index in 0..descriptor.port_count as usize {
+            let (flags, name, hint) = unsafe {
+                (
+                    *descriptor.port_descriptors.add(index),
+                    c_string(*descriptor.port_names.add(index)),
+                    &*descriptor.port_range_hints.add(index),
+                )
+            };
+            let kind = match (flags & PORT_INPUT != 0, flags & PORT_AUDIO != 0) {
+                (true, true) => PortKind::AudioIn,
+                (false, true) => PortKind::AudioOut,
+                (true, false) if flags & PORT_CONTROL != 0 => PortKind::ControlIn,
+                // Anything else still needs memory to write to
+                _ => PortKind::ControlOut,
+            };
+            ports.push(LadspaPort::from_hint(index, name, kind, hint, rate));
+        }
+
+        Self {
+            library: library.to_path_buf(),
+            label: unsafe { c_string(descriptor.label) },
+            name: unsafe { c_string(descriptor.name) },
+            maker: unsafe { c_string(descriptor.maker) },
+            unique_id: descriptor.unique_id as u64,
+            ports,
+        }
+    }
+
+    fn count(&self, kind: PortKind) -> usize {
+        self.ports.iter().filter(|p| p.kind == kind).count()
+    }
+
+    /// Only mono and stereo effects fit a channel slot
+    fn is_usable(&self) -> bool {
+        let (inputs, outputs) = (
+            self.count(PortKind::AudioIn),
+            self.count(PortKind::AudioOut),
+        );
+        inputs <= 2 && (1..=2).contains(&outputs)
+    }
+
+    fn to_info(&self) -> VstPluginInfo {
+        VstPluginInfo {
+            path: self.library.clone(),
+            name: self.name.clone(),
+            vendor: self.maker.clone(),
+            // LADSPA has no categories, and every plugin is an effect
+            category: VstCategory::Effect,
+            unique_id: self.unique_id as i32,
+            version: 0,
+            inputs: self.count(PortKind::AudioIn) as i32,
+            outputs: self.count(PortKind::AudioOut) as i32,
+            parameters: self.count(PortKind::ControlIn) as i32,
+            is_synth: false,
+            format: PluginFormat::Ladspa,
+            plugin_id: Some(self.label.clone()),
+        }
+    }
+}
+
+/// Copy a C string the plugin owns; null becomes empty
+unsafe fn c_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Catalogue entries for the usable plugins in one library
+pub(crate) fn scan_library(path: &Path) -> Result<Vec<VstPluginInfo>> {
+    let (_library, plugins) = read_library(path, 48000.0)?;
+    Ok(plugins
+        .iter()
+        .filter(|p| p.is_usable())
+        .map(LadspaPluginDesc::to_info)
+        .collect())
+}
+
+/// Every plugin in a library; the descriptors' pointers die with `library`
+fn read_library(path: &Path, rate: f32) -> Result<(Library, Vec<LadspaPluginDesc>)> {
+    // Safety: loading a plugin runs its initialisers; that is the point
+    let library = unsafe { Library::new(path) }
+        .with_context(|| format!("Failed to load {}", path.display()))?;
+    let descriptor_fn = *unsafe { library.get::<DescriptorFn>(b"ladspa_descriptor\0") }
+        .context("Not a LADSPA library: no ladspa_descriptor symbol")?;
+
+    let mut plugins = Vec::new();
+    for index in 0.. {
+        let descriptor = unsafe { descriptor_fn(index) };
+        if descriptor.is_null() {
+            break;
+        }
+        plugins.push(unsafe { LadspaPluginDesc::read(path, &*descriptor, rate) });
+    }
+    Ok((library, plugins))
+}
+
+// ===== Processor =====
+
+/// LADSPA plugin in a channel slot
+pub struct LadspaProcessor {
+    desc: LadspaPluginDesc,
+    handle: *mut c_void,
+    descriptor: *const LadspaDescriptor,
+    /// Control values, indexed by port index
+    controls: Box<[f32]>,
+    audio_inputs: Vec<Box<[f32]>>,
+    audio_outputs: Vec<Box<[f32]>>,
+    /// Control inputs, in the order the slot API indexes them
+    parameters: Vec<LadspaPort>,
+    enabled: bool,
+    sample_rate: f32,
+    // Must outlive the instance, so declared (and dropped) after it
+    _library: Library,
+}
+
+// Safety: the instance is only called through `&mut self`, so no two plugin
+// functions run at once; LADSPA instances carry no thread affinity.
+unsafe impl Send for LadspaProcessor {}
+
+impl LadspaProcessor {
+    /// Load a plugin from a library; `label` picks one when the library holds
+    /// several, otherwise the first usable one is taken
+    pub fn load(library_path: &Path, label: Option<&str>, sample_rate: f32) -> Result<Self> {
+        let (library, plugins) = read_library(library_path, sample_rate)?;
+        let index = match label {
+            Some(label) => plugins.iter().position(|p| p.label == label),
+            None => plugins.iter().position(LadspaPluginDesc::is_usable),
+        }
+        .with_context(|| match label {
+            Some(label) => format!("Library has no plugin {}", label),
+            None => "Library contains no mono or stereo plugins".to_string(),
+        })?;
+        let desc = plugins[index].clone();
+        if !desc.is_usable() {
+            bail!("{} is not a mono or stereo effect", desc.name);
+        }
+
+        let descriptor_fn = *unsafe { library.get::<DescriptorFn>(b"ladspa_descriptor\0") }?;
+        let descriptor = unsafe { descriptor_fn(index as c_ulong) };
+        let instantiate = unsafe { (*descriptor).instantiate }.context("No instantiate")?;
+        let connect = unsafe { (*descriptor).connect_port }.context("No connect_port")?;
+        if unsafe { (*descriptor).run }.is_none() {
+            bail!("{} has no run function", desc.name);
+        }
+
+        let handle = unsafe { instantiate(descriptor, sample_rate as c_ulong) };
+        if handle.is_null() {
+            bail!("{} failed to instantiate", desc.name);
+        }
+
+        let port_count = unsafe { (*descriptor).port_count } as usize;
+        let mut processor = Self {
+            parameters: desc
+                .ports
+                .iter()
+                .filter(|p| p.kind == PortKind::ControlIn)
+                .cloned()
+                .collect(),
+            desc,
+            handle,
+            descriptor,
+            controls: vec![0.0; port_count].into_boxed_slice(),
+            audio_inputs: Vec::new(),
+            audio_outputs: Vec::new(),
+            enabled: true,
+            sample_rate,
+            _library: library,
+        };
+
+        // Separate input and output buffers, so in-place-broken plugins work
+        for port in &processor.desc.ports {
+            let data = match port.kind {
+                PortKind::ControlIn | PortKind::ControlOut => {
+                    processor.controls[port.index] = port.default;
+                    &mut processor.controls[port.index] as *mut f32
+                }
+                PortKind::AudioIn => {
+                    processor
+                        .audio_inputs
+                        .push(vec![0.0; MAX_FRAMES].into_boxed_slice());
+                    processor.audio_inputs.last_mut().unwrap().as_mut_ptr()
+                }
+                PortKind::AudioOut => {
+                    processor
+                        .audio_outputs
+                        .push(vec![0.0; MAX_FRAMES].into_boxed_slice());
+                    processor.audio_outputs.last_mut().unwrap().as_mut_ptr()
+                }
+            };
+            unsafe { connect(handle, port.index as c_ulong, data) };
+        }
+
+        if let Some(activate) = unsafe { (*descriptor).activate } {
+            unsafe { activate(handle) };
+        }
+        Ok(processor)
+    }
+
+    pub fn get_label(&self) -> &str {
+        &self.desc.label
+    }
+
+    pub fn get_library_path(&self) -> &Path {
+        &self.desc.library
+    }
+
+    /// Control input values by port name
+    pub fn port_values(&self) -> BTreeMap<String, f32> {
+        self.parameters
+            .iter()
+            .map(|p| (p.name.clone(), self.controls[p.index]))
+            .collect()
+    }
+
+    /// Set a control input by name, in the port's own range
+    pub fn set_port_value(&mut self, name: &str, value: f32) -> bool {
+        let Some(port) = self.parameters.iter().find(|p| p.name == name) else {
+            return false;
+        };
+        self.controls[port.index] = value.clamp(port.min, port.max);
+        true
+    }
+
+    pub fn get_sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+}
+
+impl PluginProcessor for LadspaProcessor {
+    fn format(&self) -> PluginFormat {
+        PluginFormat::Ladspa
+    }
+
+    /// LADSPA has no sidechain ports
+    fn channel_layout(&self) -> ChannelLayout {
+        ChannelLayout::new(self.audio_inputs.len(), self.audio_outputs.len())
+    }
+
+    fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+        let frames = inputs.first().map_or(0, |input| input.len());
+        let run = match unsafe { (*self.descriptor).run } {
+            Some(run) if self.enabled && frames > 0 => run,
+            _ => return plugin_host::passthrough(self.channel_layout(), inputs, frames),
+        };
+
+        let mut output: Vec<Vec<f32>> = self
+            .audio_outputs
+            .iter()
+            .map(|_| Vec::with_capacity(frames))
+            .collect();
+        for start in (0..frames).step_by(MAX_FRAMES) {
+            let end = (start + MAX_FRAMES).min(frames);
+            let block = end - start;
+            for (channel, buffer) in self.audio_inputs.iter_mut().enumerate() {
+                match inputs.get(channel).and_then(|input| input.get(start..end)) {
+                    Some(input) => buffer[..block].copy_from_slice(input),
+                    None => buffer[..block].fill(0.0),
+                }
+            }
+
+            unsafe { run(self.handle, block as c_ulong) };
+
+            for (output, buffer) in output.iter_mut().zip(&self.audio_outputs) {
+                output.extend_from_slice(&buffer[..block]);
+            }
+        }
+        output
+    }
+
+    fn get_plugin_name(&self) -> String {
+        self.desc.name.clone()
+    }
+
+    fn set_parameter(&mut self, index: i32, value: f32) {
+        if let Some(port) = usize::try_from(index)
+            .ok()
+            .and_then(|i| self.parameters.get(i))
+        {
+            self.controls[port.index] = port.denormalize(value);
+        }
+    }
+
+    fn get_parameter(&self, index: i32) -> f32 {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.parameters.get(i))
+            .map_or(0.0, |port| port.normalize(self.controls[port.index]))
+    }
+
+    fn get_parameter_info(&self, index: i32) -> Option<ParameterInfo> {
+        let port = self.parameters.get(usize::try_from(index).ok()?)?;
+        let value = self.controls[port.index];
+        Some(ParameterInfo {
+            index,
+            name: port.name.clone(),
+            // Units, when given, are part of LADSPA port names
+            label: String::new(),
+            value: port.normalize(value),
+            display: port.display(value),
+        })
+    }
+
+    fn get_parameter_count(&self) -> i32 {
+        self.parameters.len() as i32
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn latency_samples(&self) -> usize {
+        // By convention a control output named "latency" reports the delay
+        self.desc
+            .ports
+            .iter()
+            .find(|p| p.kind == PortKind::ControlOut && p.name.eq_ignore_ascii_case("latency"))
+            .filter(|_| self.enabled)
+            .map_or(0, |p| self.controls[p.index].max(0.0) as usize)
+    }
+
+    /// LADSPA fixes the rate at instantiation, and scales rate-relative port
+    /// ranges by it, so the plugin is loaded again and takes over the values
+    fn set_sample_rate(&mut self, sample_rate: f32) -> Result<()> {
+        if sample_rate == self.sample_rate {
+            return Ok(());
+        }
+        let mut reloaded = Self::load(&self.desc.library, Some(&self.desc.label), sample_rate)?;
+        for (name, value) in self.port_values() {
+            reloaded.set_port_value(&name, value);
+        }
+        reloaded.enabled = self.enabled;
+        *self = reloaded;
+        Ok(())
+    }
+
+    /// Port values as JSON, keyed by port name
+    fn save_state(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&self.port_values())?)
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        let values: BTreeMap<String, f32> =
+            serde_json::from_slice(data).context("Invalid LADSPA port values")?;
+        for (name, value) in values {
+            if !self.set_port_value(&name, value) {
+                log::warn!("{} has no control port {}", self.desc.name, name);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LadspaProcessor {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(deactivate) = (*self.descriptor).deactivate {
+                deactivate(self.handle);
+            }
+            if let Some(cleanup) = (*self.descriptor).cleanup {
+                cleanup(self.handle);
+            }
+        }
+    }
+}
+
+// ===== Scanner =====
+
+/// LADSPA plugin scanner, filling the same catalogue as the VST2 scanner
+pub struct LadspaScanner {
+    plugins: Vec<VstPluginInfo>,
+    scan_paths: Vec<PathBuf>,
+}
+
+impl LadspaScanner {
+    pub fn new() -> Self {
+        // LADSPA_PATH replaces the defaults rather than adding to them
+        let scan_paths = match std::env::var_os("LADSPA_PATH") {
+            Some(paths) => std::env::split_paths(&paths).collect(),
+            None => {
+                let mut paths: Vec<PathBuf> = dirs::home_dir()
+                    .map(|home| home.join(".ladspa"))
+                    .into_iter()
+                    .collect();
+                paths.push(PathBuf::from("/usr/local/lib/ladspa"));
+                paths.push(PathBuf::from("/usr/lib/ladspa"));
+                paths.push(PathBuf::from("/usr/lib/x86_64-linux-gnu/ladspa"));
+                paths
+            }
+        };
+
+        Self {
+            plugins: Vec::new(),
+            scan_paths,
+        }
+    }
+
+    pub fn add_scan_path(&mut self, path: PathBuf) {
+        self.scan_paths.push(path);
+    }
+
+    /// `.so` libraries directly inside the scan paths
+    pub fn plugin_files(&self) -> Vec<PathBuf> {
+        self.scan_paths
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flat_map(|entries| entries.flatten().map(|e| e.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "so"))
+            .collect()
+    }
+
+    /// Scan every library, each in a child process; only plugins a channel
+    /// slot can run are listed
+    pub fn scan(&mut self) {
+        self.plugins.clear();
+        for path in self.plugin_files() {
+            match plugin_bridge::scan_sandboxed(PluginFormat::Ladspa, &path) {
+                Ok(plugins) => self.plugins.extend(plugins),
+                Err(e) => log::warn!("Skipping LADSPA library {}: {:#}", path.display(), e),
+            }
+        }
+    }
+
+    pub fn get_plugins(&self) -> &[VstPluginInfo] {
+        &self.plugins
+    }
+}
+
+impl Default for LadspaScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(hint: c_int, lower: f32, upper: f32) -> LadspaPort {
+        let hint = LadspaRangeHint { hint, lower, upper };
+        LadspaPort::from_hint(0, "Port".to_string(), PortKind::ControlIn, &hint, 48000.0)
+    }
+
+    #[test]
+    fn test_range_hint_defaults() {
+        let bounded = HINT_BOUNDED_BELOW | HINT_BOUNDED_ABOVE;
+        assert_eq!(port(bounded | HINT_DEFAULT_MIDDLE, 0.0, 10.0).default, 5.0);
+        assert_eq!(port(bounded | HINT_DEFAULT_LOW, 0.0, 8.0).default, 2.0);
+        assert_eq!(
+            port(bounded | HINT_DEFAULT_440, 20.0, 20000.0).default,
+            440.0
+        );
+
+        let log = port(
+            bounded | HINT_LOGARITHMIC | HINT_DEFAULT_MIDDLE,
+            10.0,
+            1000.0,
+        );
+        assert!((log.default - 100.0).abs() < 1e-3);
+        assert!((log.normalize(100.0) - 0.5).abs() < 1e-5);
+
+        // Fractions of the sample rate, e.g. a filter cutoff
+        let cutoff = port(bounded | HINT_SAMPLE_RATE | HINT_DEFAULT_MAXIMUM, 0.0, 0.5);
+        assert_eq!((cutoff.max, cutoff.default), (24000.0, 24000.0));
+
+        let toggle = port(HINT_TOGGLED | HINT_DEFAULT_1, 0.0, 0.0);
+        assert_eq!((toggle.min, toggle.max, toggle.default), (0.0, 1.0, 1.0));
+        assert_eq!(toggle.denormalize(0.3), 0.0);
+        assert_eq!(toggle.display(1.0), "On");
+    }
+
+    #[test]
+    fn test_integer_ports_round() {
+        let steps = port(
+            HINT_BOUNDED_BELOW | HINT_BOUNDED_ABOVE | HINT_INTEGER,
+            0.0,
+            4.0,
+        );
+        assert_eq!(steps.denormalize(0.4), 2.0);
+        assert_eq!(steps.display(2.0), "2");
+        assert_eq!(steps.default, 0.0);
+    }
+
+    #[test]
+    fn test_load_rejects_missing_library() {
+        let result = LadspaProcessor::load(Path::new("/nonexistent/plugin.so"), None, 48000.0);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_ladspa_scanner_paths() {
+        let mut scanner = LadspaScanner::new();
+        if std::env::var_os("LADSPA_PATH").is_none() {
+            assert!(
+                scanner
+                    .scan_paths
+                    .iter()
+                    .any(|p| p == Path::new("/usr/lib/ladspa"))
+            );
+        }
+        // Missing directories are skipped
+        scanner.scan_paths = vec![PathBuf::from("/nonexistent/ladspa")];
+        scanner.scan();
+        assert!(scanner.get_plugins().is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+//! Latency reporting and delay compensation.
+//!
+//! Every stage of a channel strip reports the delay it adds: the denoiser's
+//! frame buffering, a plugin's initial delay and the limiter's lookahead.
+//! Channels mixed onto the same bus are lined up by delaying each one to
+//! match the slowest, the same plugin delay compensation a DAW does, so a
+//! denoised mic and an unprocessed line input stay in phase.
+
+use serde::Serialize;
+
+/// Longest delay a [`DelayLine`] holds by default: one second of
+/// interleaved stereo at 96 kHz
+pub const MAX_DELAY_SAMPLES: usize = 2 * 96000;
+
+/// Delay for a signal, adjustable while running. The buffer is allocated
+/// once; changing the delay only moves the read position, so the audio
+/// already held keeps playing instead of being dropped.
+#[derive(Debug, Clone)]
+pub struct DelayLine {
+    /// Every sample written, the last `MAX + 1` kept
+    buffer: Vec<f32>,
+    write: usize,
+    delay: usize,
+}
+
+impl Default for DelayLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DelayLine {
+    pub fn new() -> Self {
+        Self::with_max_delay(MAX_DELAY_SAMPLES)
+    }
+
+    pub fn with_max_delay(max_delay: usize) -> Self {
+        Self {
+            buffer: vec![0.0; max_delay + 1],
+            write: 0,
+            delay: 0,
+        }
+    }
+
+    pub fn delay(&self) -> usize {
+        self.delay
+    }
+
+    pub fn max_delay(&self) -> usize {
+        self.buffer.len() - 1
+    }
+
+    /// Change the delay, clamped to the maximum. Safe to call every block.
+    pub fn set_delay(&mut self, samples: usize) {
+        self.delay = samples.min(self.max_delay());
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        let len = self.buffer.len();
+        for sample in samples.iter_mut() {
+            // History is written even without a delay so a later one has audio to play
+            self.buffer[self.write] = *sample;
+            *sample = self.buffer[(self.write + len - self.delay) % len];
+            self.write = (self.write + 1) % len;
+        }
+    }
+}
+
+/// Delay of a channel strip by stage, in samples
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ChannelLatency {
+    /// RNNoise frames, the Wiener filter's FFT or GhostWave's chunks
+    pub denoiser: usize,
+    /// The plugin's reported initial delay
+    pub vst: usize,
+    /// Limiter lookahead
+    pub limiter: usize,
+    /// Added to line this channel up with the slowest one on the bus
+    pub compensation: usize,
+}
+
+impl ChannelLatency {
+    /// Delay from the processing itself, before compensation
+    pub fn processing(&self) -> usize {
+        self.denoiser + self.vst + self.limiter
+    }
+
+    pub fn total(&self) -> usize {
+        self.processing() + self.compensation
+    }
+}
+
+/// Delay added on an output bus, in samples
+#[derive(Debug, Clone, Serialize)]
+pub struct BusLatency {
+    pub bus: String,
+    /// Lookahead of the bus AGC's limiter
+    pub samples: usize,
+}
+
+/// Input-to-output latency of the engine, stage by stage
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencyReport {
+    pub sample_rate: u32,
+    /// Device buffer on the capture side
+    pub input_buffer: usize,
+    pub channels: Vec<ChannelLatency>,
+    pub buses: Vec<BusLatency>,
+    /// Device buffer on the playback side
+    pub output_buffer: usize,
+    /// Round trip measured by calibration; replaces the two device buffers
+    pub measured_round_trip: Option<usize>,
+    pub total_samples: usize,
+    pub total_ms: f32,
+}
+
+impl LatencyReport {
+    pub fn new(
+        sample_rate: u32,
+        buffer_size: usize,
+        channels: Vec<ChannelLatency>,
+        buses: Vec<BusLatency>,
+        measured_round_trip: Option<usize>,
+    ) -> Self {
+        // Compensated channels all share the slowest channel's delay
+        let channel_samples = channels.iter().map(|c| c.total()).max().unwrap_or(0);
+        let bus_samples = buses.iter().map(|b| b.samples).max().unwrap_or(0);
+        // The measurement also covers driver and converter delay the buffer
+        // sizes know nothing about
+        let hardware = measured_round_trip.unwrap_or(buffer_size * 2);
+        let total_samples = hardware + channel_samples + bus_samples;
+        Self {
+            sample_rate,
+            input_buffer: buffer_size,
+            channels,
+            buses,
+            output_buffer: buffer_size,
+            measured_round_trip,
+            total_samples,
+            total_ms: total_samples as f32 * 1000.0 / sample_rate.max(1) as f32,
+        }
+    }
+}
+
+/// Compensation for each channel so all of them match the slowest
+pub fn compensation_for(processing: &[usize]) -> Vec<usize> {
+    let slowest = processing.iter().copied().max().unwrap_or(0);
+    processing.iter().map(|&p| slowest - p).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_line_delays_by_exact_samples() {
+        let mut line = DelayLine::new();
+        line.set_delay(3);
+        let mut samples = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        line.process(&mut samples);
+        assert_eq!(samples, vec![0.0, 0.0, 0.0, 1.0, 2.0]);
+
+        let mut more = vec![6.0, 7.0];
+        line.process(&mut more);
+        assert_eq!(more, vec![3.0, 4.0]);
+
+        line.set_delay(0);
+        let mut passthrough = vec![8.0];
+        line.process(&mut passthrough);
+        assert_eq!(passthrough, vec![8.0]);
+    }
+
+    #[test]
+    fn test_delay_change_keeps_held_audio() {
+        let mut line = DelayLine::with_max_delay(8);
+        line.set_delay(2);
+        let mut samples = vec![1.0, 2.0, 3.0, 4.0];
+        line.process(&mut samples);
+        assert_eq!(samples, vec![0.0, 0.0, 1.0, 2.0]);
+
+        // A longer delay replays audio already heard instead of silence
+        line.set_delay(4);
+        let mut samples = vec![5.0, 6.0];
+        line.process(&mut samples);
+        assert_eq!(samples, vec![1.0, 2.0]);
+
+        // A shorter one skips ahead without losing the stream
+        line.set_delay(1);
+        let mut samples = vec![7.0, 8.0];
+        line.process(&mut samples);
+        assert_eq!(samples, vec![6.0, 7.0]);
+
+        line.set_delay(100);
+        assert_eq!(line.delay(), 8);
+    }
+
+    #[test]
+    fn test_compensation_lines_up_channels() {
+        let processing = [960, 0, 96, 480];
+        let compensation = compensation_for(&processing);
+        assert_eq!(compensation, vec![0, 960, 864, 480]);
+        assert!(
+            processing
+                .iter()
+                .zip(&compensation)
+                .all(|(p, c)| p + c == 960)
+        );
+
+        let channels: Vec<ChannelLatency> = processing
+            .iter()
+            .zip(&compensation)
+            .map(|(&denoiser, &compensation)| ChannelLatency {
+                denoiser,
+                compensation,
+                ..Default::default()
+            })
+            .collect();
+        let report = LatencyReport::new(48000, 256, channels.clone(), Vec::new(), None);
+        assert_eq!(report.total_samples, 256 + 960 + 256);
+        assert!((report.total_ms - 30.666).abs() < 0.01);
+
+        let measured = LatencyReport::new(48000, 256, channels, Vec::new(), Some(1200));
+        assert_eq!(measured.total_samples, 1200 + 960);
+    }
+}
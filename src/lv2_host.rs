@@ -0,0 +1,2279 @@
+//! LV2 plugin hosting.
+//!
+//! Provides LV2 effect hosting alongside the VST2 and CLAP hosts with:
+//! - Bundle discovery through `LV2_PATH`, or `~/.lv2` and the system paths
+//! - A small Turtle reader for `manifest.ttl` and the files it points to
+//! - Mono and stereo audio plugins, control ports mapped to [`ParameterInfo`]
+//! - URID map/unmap, the feature nearly every modern plugin requires
+//! - Port values saved and restored by symbol
+//! - `time:Position` events for atom inputs that support them
+//!
+//! `run()` is realtime-safe by the LV2 specification, so unlike the other
+//! hosts the plugin is processed inline rather than on a worker thread.
+
+#![allow(dead_code)] // Complete LV2 hosting API for plugin management
+
+use crate::plugin_host::{self, ChannelLayout, PluginFormat, PluginProcessor};
+use crate::transport::{self, TransportInfo};
+use crate::vst_host::{ParameterInfo, VstCategory, VstPluginInfo};
+use anyhow::{Context, Result, bail};
+use libloading::Library;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Largest block handed to `run()`; longer buffers are split
+const MAX_FRAMES: usize = 4096;
+
+/// Capacity of each atom port buffer, in bytes
+const ATOM_CAPACITY: usize = 8192;
+
+const LV2: &str = "http://lv2plug.in/ns/lv2core#";
+const ATOM: &str = "http://lv2plug.in/ns/ext/atom#";
+const URID: &str = "http://lv2plug.in/ns/ext/urid#";
+const UNITS: &str = "http://lv2plug.in/ns/extensions/units#";
+const PPROPS: &str = "http://lv2plug.in/ns/ext/port-props#";
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+const DOAP: &str = "http://usefulinc.com/ns/doap#";
+const FOAF: &str = "http://xmlns.com/foaf/0.1/";
+const TIME: &str = "http://lv2plug.in/ns/ext/time#";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+// ===== Turtle =====
+//
+// Enough of Turtle for plugin descriptions: prefixes, @base and relative
+// IRIs, blank nodes, lists, and literals with their datatype or language tag.
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Literal {
+    value: String,
+    /// Full IRI; numbers and booleans written bare get their XSD type
+    datatype: Option<String>,
+    language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+    Iri(String),
+    Blank(usize),
+    Literal(Literal),
+}
+
+impl Node {
+    fn iri(&self) -> Option<&str> {
+        match self {
+            Node::Iri(iri) => Some(iri),
+            _ => None,
+        }
+    }
+
+    fn literal(&self) -> Option<&str> {
+        match self {
+            Node::Literal(literal) => Some(&literal.value),
+            _ => None,
+        }
+    }
+
+    fn number(&self) -> Option<f32> {
+        let Node::Literal(literal) = self else {
+            return None;
+        };
+        let value = literal.value.trim();
+        if literal.datatype.as_deref() == Some(&format!("{}boolean", XSD)) {
+            return match value {
+                "true" | "1" => Some(1.0),
+                "false" | "0" => Some(0.0),
+                _ => None,
+            };
+        }
+        value.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Iri(String),
+    PrefixedName(String, String),
+    /// The datatype is an `Iri` or `PrefixedName` token, expanded by the parser
+    Literal {
+        value: String,
+        datatype: Option<Box<Token>>,
+        language: Option<String>,
+    },
+    BlankLabel(String),
+    A,
+    PrefixDirective,
+    BaseDirective,
+    Punct(char),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '%');
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '<' {
+            let end = chars[i..]
+                .iter()
+                .position(|&c| c == '>')
+                .context("Unterminated IRI")?;
+            tokens.push(Token::Iri(chars[i + 1..i + end].iter().collect()));
+            i += end + 1;
+        } else if c == '"' || c == '\'' {
+            let long = chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c);
+            i += if long { 3 } else { 1 };
+            let closes = |at: usize| {
+                chars.get(at) == Some(&c)
+                    && (!long || (chars.get(at + 1) == Some(&c) && chars.get(at + 2) == Some(&c)))
+            };
+            let mut value = String::new();
+            loop {
+                let Some(&ch) = chars.get(i) else {
+                    bail!("Unterminated string literal");
+                };
+                // A long string may end with quotes of its own, as in """say "hi""""
+                if closes(i) && !(long && chars.get(i + 3) == Some(&c)) {
+                    i += if long { 3 } else { 1 };
+                    break;
+                }
+                if ch == '\\' {
+                    let escaped = chars.get(i + 1).copied().unwrap_or('\\');
+                    i += 2;
+                    value.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' | 'U' => {
+                            let digits = if escaped == 'u' { 4 } else { 8 };
+                            let hex: String = chars
+                                .get(i..i + digits)
+                                .unwrap_or_default()
+                                .iter()
+                                .collect();
+                            i += digits;
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .with_context(|| format!("Invalid escape \\{}{}", escaped, hex))?
+                        }
+                        other => other,
+                    });
+                } else {
+                    value.push(ch);
+                    i += 1;
+                }
+            }
+            let mut language = None;
+            let mut datatype = None;
+            if chars.get(i) == Some(&'@') {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '-') {
+                    i += 1;
+                }
+                language = Some(chars[start..i].iter().collect());
+            } else if chars.get(i) == Some(&'^') && chars.get(i + 1) == Some(&'^') {
+                i += 2;
+                datatype = Some(Box::new(if chars.get(i) == Some(&'<') {
+                    let end = chars[i..]
+                        .iter()
+                        .position(|&c| c == '>')
+                        .context("Unterminated datatype IRI")?;
+                    let iri = chars[i + 1..i + end].iter().collect();
+                    i += end + 1;
+                    Token::Iri(iri)
+                } else {
+                    let start = i;
+                    while i < chars.len() && is_name_char(chars[i]) {
+                        i += 1;
+                    }
+                    let name: String = chars[start..i].iter().collect();
+                    let (prefix, local) = name
+                        .split_once(':')
+                        .with_context(|| format!("Malformed datatype {:?}", name))?;
+                    Token::PrefixedName(prefix.to_string(), local.to_string())
+                }));
+            }
+            tokens.push(Token::Literal {
+                value,
+                datatype,
+                language,
+            });
+        } else if c == '@' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i].is_alphabetic() {
+                i += 1;
+            }
+            match chars[start..i].iter().collect::<String>().as_str() {
+                "prefix" => tokens.push(Token::PrefixDirective),
+                "base" => tokens.push(Token::BaseDirective),
+                other => bail!("Unknown directive @{}", other),
+            }
+        } else if c.is_ascii_digit()
+            || ((c == '-' || c == '+' || c == '.')
+                && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()))
+        {
+            let start = i;
+            i += 1;
+            while i < chars.len() {
+                let ch = chars[i];
+                let exponent_sign = (ch == '-' || ch == '+') && matches!(chars[i - 1], 'e' | 'E');
+                let decimal_point =
+                    ch == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit());
+                if ch.is_ascii_digit() || matches!(ch, 'e' | 'E') || exponent_sign || decimal_point
+                {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            let value: String = chars[start..i].iter().collect();
+            let kind = if value.contains(['e', 'E']) {
+                "double"
+            } else if value.contains('.') {
+                "decimal"
+            } else {
+                "integer"
+            };
+            tokens.push(Token::Literal {
+                value,
+                datatype: Some(Box::new(Token::Iri(format!("{}{}", XSD, kind)))),
+                language: None,
+            });
+        } else if matches!(c, '.' | ';' | ',' | '[' | ']' | '(' | ')') {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        } else {
+            let start = i;
+            while i < chars.len()
+                && (is_name_char(chars[i])
+                    || (chars[i] == '.' && chars.get(i + 1).is_some_and(|&n| is_name_char(n))))
+            {
+                i += 1;
+            }
+            if i == start {
+                bail!("Unexpected character {:?}", c);
+            }
+            let word: String = chars[start..i].iter().collect();
+            tokens.push(match word.as_str() {
+                "a" => Token::A,
+                "true" | "false" => Token::Literal {
+                    value: word,
+                    datatype: Some(Box::new(Token::Iri(format!("{}boolean", XSD)))),
+                    language: None,
+                },
+                "PREFIX" => Token::PrefixDirective,
+                "BASE" => Token::BaseDirective,
+                _ => match word.split_once(':') {
+                    Some(("_", label)) => Token::BlankLabel(label.to_string()),
+                    Some((prefix, local)) => {
+                        Token::PrefixedName(prefix.to_string(), local.to_string())
+                    }
+                    None => bail!("Unexpected word {:?}", word),
+                },
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+/// Triples from every file read so far
+#[derive(Debug, Default)]
+struct Graph {
+    triples: Vec<(Node, String, Node)>,
+    next_blank: usize,
+}
+
+impl Graph {
+    fn parse_file(&mut self, path: &Path) -> Result<()> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        // Relative IRIs resolve against the file itself, as lilv does
+        let base = path_to_iri(&std::path::absolute(path)?);
+        self.parse(&text, &base)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn parse(&mut self, text: &str, base: &str) -> Result<()> {
+        let tokens = tokenize(text)?;
+        let mut parser = TurtleParser {
+            tokens: &tokens,
+            pos: 0,
+            base: base.to_string(),
+            prefixes: HashMap::new(),
+            labels: HashMap::new(),
+            graph: self,
+        };
+        parser.document()
+    }
+
+    fn objects<'a>(
+        &'a self,
+        subject: &Node,
+        predicate: &str,
+    ) -> impl Iterator<Item = &'a Node> + use<'a> {
+        self.triples
+            .iter()
+            .filter(|(s, p, _)| s == subject && p == predicate)
+            .map(|(_, _, o)| o)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn object(&self, subject: &Node, predicate: &str) -> Option<&Node> {
+        self.objects(subject, predicate).next()
+    }
+
+    /// A literal in the host's language: untagged or English first, else any
+    fn text(&self, subject: &Node, predicate: &str) -> Option<&str> {
+        let literals: Vec<&Literal> = self
+            .objects(subject, predicate)
+            .filter_map(|o| match o {
+                Node::Literal(literal) => Some(literal),
+                _ => None,
+            })
+            .collect();
+        let english = |literal: &&&Literal| {
+            literal.language.as_deref().is_some_and(|language| {
+                let language = language.to_ascii_lowercase();
+                language == "en" || language.starts_with("en-")
+            })
+        };
+        literals
+            .iter()
+            .find(|literal| literal.language.is_none())
+            .or_else(|| literals.iter().find(english))
+            .or(literals.first())
+            .map(|literal| literal.value.as_str())
+    }
+
+    fn has(&self, subject: &Node, predicate: &str, object: &str) -> bool {
+        self.objects(subject, predicate)
+            .any(|o| o.iri() == Some(object))
+    }
+
+    fn subjects_of_type(&self, class: &str) -> Vec<Node> {
+        let rdf_type = format!("{}type", RDF);
+        let mut subjects: Vec<Node> = Vec::new();
+        for (s, p, o) in &self.triples {
+            if *p == rdf_type && o.iri() == Some(class) && !subjects.contains(s) {
+                subjects.push(s.clone());
+            }
+        }
+        subjects
+    }
+}
+
+struct TurtleParser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    base: String,
+    prefixes: HashMap<String, String>,
+    labels: HashMap<String, usize>,
+    graph: &'a mut Graph,
+}
+
+impl TurtleParser<'_> {
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.next() {
+            Some(Token::Punct(p)) if *p == c => Ok(()),
+            other => bail!("Expected '{}', found {:?}", c, other),
+        }
+    }
+
+    fn new_blank(&mut self) -> Node {
+        self.graph.next_blank += 1;
+        Node::Blank(self.graph.next_blank)
+    }
+
+    fn resolve(&self, iri: &str) -> String {
+        resolve_iri(&self.base, iri)
+    }
+
+    fn expand(&self, prefix: &str, local: &str) -> Result<String> {
+        let namespace = self
+            .prefixes
+            .get(prefix)
+            .with_context(|| format!("Undeclared prefix {}:", prefix))?;
+        Ok(format!("{}{}", namespace, local))
+    }
+
+    fn document(&mut self) -> Result<()> {
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::PrefixDirective => {
+                    self.pos += 1;
+                    let Some(Token::PrefixedName(prefix, _)) = self.next().cloned() else {
+                        bail!("Malformed prefix declaration");
+                    };
+                    let Some(Token::Iri(iri)) = self.next().cloned() else {
+                        bail!("Malformed prefix declaration");
+                    };
+                    let iri = self.resolve(&iri);
+                    self.prefixes.insert(prefix, iri);
+                    if self.peek() == Some(&Token::Punct('.')) {
+                        self.pos += 1;
+                    }
+                }
+                Token::BaseDirective => {
+                    self.pos += 1;
+                    let Some(Token::Iri(iri)) = self.next().cloned() else {
+                        bail!("Malformed base declaration");
+                    };
+                    self.base = self.resolve(&iri);
+                    if self.peek() == Some(&Token::Punct('.')) {
+                        self.pos += 1;
+                    }
+                }
+                _ => {
+                    let subject = self.subject()?;
+                    if self.peek() != Some(&Token::Punct('.')) {
+                        self.predicate_objects(&subject)?;
+                    }
+                    self.expect('.')?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn subject(&mut self) -> Result<Node> {
+        match self.peek() {
+            Some(Token::Punct('[')) => self.blank_node(),
+            Some(Token::Punct('(')) => self.collection(),
+            _ => self.iri_or_label(),
+        }
+    }
+
+    fn iri_or_label(&mut self) -> Result<Node> {
+        match self.next().cloned() {
+            Some(Token::Iri(iri)) => Ok(Node::Iri(self.resolve(&iri))),
+            Some(Token::PrefixedName(prefix, local)) => {
+                Ok(Node::Iri(self.expand(&prefix, &local)?))
+            }
+            Some(Token::BlankLabel(label)) => {
+                let id = match self.labels.get(&label) {
+                    Some(&id) => id,
+                    None => {
+                        let Node::Blank(id) = self.new_blank() else {
+                            unreachable!()
+                        };
+                        self.labels.insert(label, id);
+                        id
+                    }
+                };
+                Ok(Node::Blank(id))
+            }
+            other => bail!("Expected a resource, found {:?}", other),
+        }
+    }
+
+    fn blank_node(&mut self) -> Result<Node> {
+        self.expect('[')?;
+        let node = self.new_blank();
+        if self.peek() != Some(&Token::Punct(']')) {
+            self.predicate_objects(&node)?;
+        }
+        self.expect(']')?;
+        Ok(node)
+    }
+
+    /// Lists are parsed but not linked up; no host query needs them
+    fn collection(&mut self) -> Result<Node> {
+        self.expect('(')?;
+        while self.peek() != Some(&Token::Punct(')')) {
+            if self.peek().is_none() {
+                bail!("Unterminated collection");
+            }
+            self.object()?;
+        }
+        self.expect(')')?;
+        Ok(self.new_blank())
+    }
+
+    fn object(&mut self) -> Result<Node> {
+        match self.peek() {
+            Some(Token::Literal { .. }) => {
+                let Some(Token::Literal {
+                    value,
+                    datatype,
+                    language,
+                }) = self.next().cloned()
+                else {
+                    unreachable!()
+                };
+                let datatype = match datatype.map(|token| *token) {
+                    Some(Token::Iri(iri)) => Some(self.resolve(&iri)),
+                    Some(Token::PrefixedName(prefix, local)) => Some(self.expand(&prefix, &local)?),
+                    _ => None,
+                };
+                Ok(Node::Literal(Literal {
+                    value,
+                    datatype,
+                    language,
+                }))
+            }
+            _ => self.subject(),
+        }
+    }
+
+    fn predicate_objects(&mut self, subject: &Node) -> Result<()> {
+        loop {
+            let predicate = match self.peek() {
+                Some(Token::A) => {
+                    self.pos += 1;
+                    format!("{}type", RDF)
+                }
+                _ => match self.iri_or_label()? {
+                    Node::Iri(iri) => iri,
+                    _ => bail!("Blank node used as a predicate"),
+                },
+            };
+            loop {
+                let object = self.object()?;
+                self.graph
+                    .triples
+                    .push((subject.clone(), predicate.clone(), object));
+                if self.peek() == Some(&Token::Punct(',')) {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+            // Trailing and repeated semicolons are allowed
+            let mut more = false;
+            while self.peek() == Some(&Token::Punct(';')) {
+                self.pos += 1;
+                more = true;
+            }
+            if !more || matches!(self.peek(), Some(Token::Punct('.' | ']')) | None) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Resolve an IRI reference against a base IRI (RFC 3986 section 5.2)
+fn resolve_iri(base: &str, reference: &str) -> String {
+    let has_scheme = reference.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    if has_scheme {
+        return reference.to_string();
+    }
+    let base = base.split('#').next().unwrap_or(base);
+    if reference.is_empty() || reference.starts_with('#') {
+        return format!("{}{}", base, reference);
+    }
+    let scheme = base.split_once(':').map_or("", |(scheme, _)| scheme);
+    if reference.starts_with("//") {
+        return format!("{}:{}", scheme, reference);
+    }
+
+    // Split "scheme://authority" from the path
+    let path_start = match base.find("://") {
+        Some(at) => base[at + 3..].find('/').map_or(base.len(), |p| at + 3 + p),
+        None => scheme.len() + 1,
+    };
+    let (origin, path) = base.split_at(path_start.min(base.len()));
+    let merged = if reference.starts_with('/') {
+        reference.to_string()
+    } else {
+        let directory = path.rfind('/').map_or("", |slash| &path[..=slash]);
+        format!("{}{}", directory, reference)
+    };
+
+    // Drop "." and ".." segments
+    let segments: Vec<&str> = merged.split('/').collect();
+    let mut output: Vec<&str> = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        match *segment {
+            "." => {}
+            ".." => {
+                if output.len() > 1 {
+                    output.pop();
+                }
+            }
+            segment => {
+                output.push(segment);
+                continue;
+            }
+        }
+        if last {
+            output.push("");
+        }
+    }
+    format!("{}{}", origin, output.join("/"))
+}
+
+fn path_to_iri(path: &Path) -> String {
+    let mut iri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~!$&'()*+,;=:@".contains(&byte) {
+            iri.push(byte as char);
+        } else {
+            iri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    iri
+}
+
+fn iri_to_path(iri: &str) -> Option<PathBuf> {
+    let path = iri.strip_prefix("file://")?;
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    let path = path.split(['#', '?']).next().unwrap_or(path);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(
+        String::from_utf8_lossy(&decoded).into_owned(),
+    ))
+}
+
+// ===== Plugin descriptions =====
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PortKind {
+    AudioIn,
+    AudioOut,
+    ControlIn,
+    ControlOut,
+    AtomIn,
+    AtomOut,
+    CvIn,
+    CvOut,
+    Unsupported,
+}
+
+/// One port as the plugin's Turtle describes it
+#[derive(Debug, Clone)]
+struct Lv2Port {
+    index: u32,
+    symbol: String,
+    name: String,
+    kind: PortKind,
+    default: f32,
+    min: f32,
+    max: f32,
+    toggled: bool,
+    integer: bool,
+    logarithmic: bool,
+    optional: bool,
+    /// Audio input for a key signal rather than the main signal
+    sidechain: bool,
+    reports_latency: bool,
+    /// Atom input that wants `time:Position` events
+    time_position: bool,
+    unit: String,
+    scale_points: Vec<(f32, String)>,
+}
+
+impl Lv2Port {
+    fn normalize(&self, value: f32) -> f32 {
+        if self.max <= self.min {
+            return 0.0;
+        }
+        let value = value.clamp(self.min, self.max);
+        if self.logarithmic && self.min > 0.0 {
+            (value / self.min).ln() / (self.max / self.min).ln()
+        } else {
+            (value - self.min) / (self.max - self.min)
+        }
+    }
+
+    fn denormalize(&self, value: f32) -> f32 {
+        let value = value.clamp(0.0, 1.0);
+        let plain = if self.logarithmic && self.min > 0.0 && self.max > self.min {
+            self.min * (self.max / self.min).powf(value)
+        } else {
+            self.min + value * (self.max - self.min)
+        };
+        if self.toggled {
+            if value >= 0.5 { self.max.max(1.0) } else { 0.0 }
+        } else if self.integer {
+            plain.round()
+        } else {
+            plain
+        }
+    }
+
+    fn display(&self, value: f32) -> String {
+        if let Some((_, label)) = self
+            .scale_points
+            .iter()
+            .find(|(point, _)| (point - value).abs() < 1e-4)
+        {
+            label.clone()
+        } else if self.toggled {
+            if value > 0.0 { "On" } else { "Off" }.to_string()
+        } else if self.integer {
+            format!("{:.0}", value)
+        } else {
+            format!("{:.2}", value)
+        }
+    }
+}
+
+/// Everything needed to list and instantiate one plugin
+#[derive(Debug, Clone)]
+struct Lv2PluginDesc {
+    uri: String,
+    name: String,
+    vendor: String,
+    bundle: PathBuf,
+    binary: PathBuf,
+    ports: Vec<Lv2Port>,
+    classes: Vec<String>,
+    /// Required features the host does not provide
+    missing_features: Vec<String>,
+}
+
+impl Lv2PluginDesc {
+    fn count(&self, kind: PortKind) -> usize {
+        self.ports.iter().filter(|p| p.kind == kind).count()
+    }
+
+    fn channel_layout(&self) -> ChannelLayout {
+        let sidechain = self
+            .ports
+            .iter()
+            .filter(|p| p.kind == PortKind::AudioIn && p.sidechain)
+            .count();
+        ChannelLayout::new(
+            self.count(PortKind::AudioIn) - sidechain,
+            self.count(PortKind::AudioOut),
+        )
+        .with_sidechain(sidechain)
+    }
+
+    /// Why this plugin cannot go in a channel slot, if it cannot
+    fn unsupported_reason(&self) -> Option<String> {
+        if let Some(feature) = self.missing_features.first() {
+            return Some(format!("requires unsupported feature {}", feature));
+        }
+        if let Some(port) = self
+            .ports
+            .iter()
+            .find(|p| p.kind == PortKind::Unsupported && !p.optional)
+        {
+            return Some(format!("port {} has an unsupported type", port.symbol));
+        }
+        let ChannelLayout {
+            inputs, outputs, ..
+        } = self.channel_layout();
+        if inputs > 2 || outputs == 0 || outputs > 2 {
+            return Some(format!(
+                "{} in / {} out is not mono or stereo",
+                inputs, outputs
+            ));
+        }
+        None
+    }
+
+    fn category(&self) -> VstCategory {
+        let is = |class: &str| {
+            self.classes
+                .iter()
+                .any(|c| *c == format!("{}{}", LV2, class))
+        };
+        if is("InstrumentPlugin") {
+            VstCategory::Synth
+        } else if is("AnalyserPlugin") {
+            VstCategory::Analysis
+        } else if is("ReverbPlugin") {
+            VstCategory::RoomFx
+        } else if is("SpatialPlugin") {
+            VstCategory::SpaciaIizer
+        } else if is("GeneratorPlugin") || is("OscillatorPlugin") {
+            VstCategory::Generator
+        } else if self.classes.iter().any(|c| *c != format!("{}Plugin", LV2)) {
+            VstCategory::Effect
+        } else {
+            VstCategory::Unknown
+        }
+    }
+
+    fn to_info(&self) -> VstPluginInfo {
+        let category = self.category();
+        VstPluginInfo {
+            path: self.bundle.clone(),
+            name: self.name.clone(),
+            vendor: self.vendor.clone(),
+            is_synth: category == VstCategory::Synth,
+            category,
+            unique_id: 0,
+            version: 0,
+            inputs: self.count(PortKind::AudioIn) as i32,
+            outputs: self.count(PortKind::AudioOut) as i32,
+            parameters: self.count(PortKind::ControlIn) as i32,
+            format: PluginFormat::Lv2,
+            plugin_id: Some(self.uri.clone()),
+        }
+    }
+}
+
+/// Features the host passes to `instantiate()`, or that need no support
+fn feature_supported(uri: &str) -> bool {
+    [
+        format!("{}map", URID),
+        format!("{}unmap", URID),
+        format!("{}isLive", LV2),
+        format!("{}hardRTCapable", LV2),
+        format!("{}inPlaceBroken", LV2),
+    ]
+    .iter()
+    .any(|f| f == uri)
+}
+
+/// Catalogue entries for the plugins in a bundle a channel slot can run.
+/// Only Turtle is read; the plugin binary is not loaded.
+pub(crate) fn scan_bundle(bundle: &Path) -> Result<Vec<VstPluginInfo>> {
+    let mut plugins = Vec::new();
+    for desc in describe_bundle(bundle)? {
+        match desc.unsupported_reason() {
+            None => plugins.push(desc.to_info()),
+            Some(reason) => log::debug!("Skipping {}: {}", desc.uri, reason),
+        }
+    }
+    Ok(plugins)
+}
+
+/// Read a bundle's manifest and the files it refers to
+fn describe_bundle(bundle: &Path) -> Result<Vec<Lv2PluginDesc>> {
+    let mut graph = Graph::default();
+    graph.parse_file(&bundle.join("manifest.ttl"))?;
+
+    let plugins = graph.subjects_of_type(&format!("{}Plugin", LV2));
+    let mut read = HashSet::new();
+    for plugin in &plugins {
+        let see_also: Vec<PathBuf> = graph
+            .objects(plugin, &format!("{}seeAlso", RDFS))
+            .filter_map(|o| o.iri().and_then(iri_to_path))
+            .collect();
+        for file in see_also {
+            if read.insert(file.clone()) {
+                graph.parse_file(&file)?;
+            }
+        }
+    }
+
+    let mut descriptions = Vec::new();
+    for plugin in plugins {
+        let Node::Iri(uri) = &plugin else { continue };
+        let Some(binary) = graph
+            .object(&plugin, &format!("{}binary", LV2))
+            .and_then(|o| o.iri().and_then(iri_to_path))
+        else {
+            log::debug!("LV2 plugin {} has no binary", uri);
+            continue;
+        };
+        descriptions.push(describe_plugin(&graph, &plugin, uri, bundle, binary));
+    }
+    Ok(descriptions)
+}
+
+fn describe_plugin(
+    graph: &Graph,
+    plugin: &Node,
+    uri: &str,
+    bundle: &Path,
+    binary: PathBuf,
+) -> Lv2PluginDesc {
+    let name = graph
+        .text(plugin, &format!("{}name", DOAP))
+        .unwrap_or(uri)
+        .to_string();
+
+    // The maintainer sits on the plugin or on its project
+    let maintainer_name = |subject: &Node| {
+        graph
+            .object(subject, &format!("{}maintainer", DOAP))
+            .and_then(|m| graph.text(m, &format!("{}name", FOAF)))
+            .map(str::to_string)
+    };
+    let vendor = maintainer_name(plugin)
+        .or_else(|| {
+            graph
+                .object(plugin, &format!("{}project", LV2))
+                .and_then(maintainer_name)
+        })
+        .unwrap_or_default();
+
+    let classes = graph
+        .objects(plugin, &format!("{}type", RDF))
+        .filter_map(|o| o.iri().map(str::to_string))
+        .collect();
+    let missing_features = graph
+        .objects(plugin, &format!("{}requiredFeature", LV2))
+        .filter_map(Node::iri)
+        .filter(|f| !feature_supported(f))
+        .map(str::to_string)
+        .collect();
+
+    let mut ports: Vec<Lv2Port> = graph
+        .objects(plugin, &format!("{}port", LV2))
+        .filter_map(|port| describe_port(graph, port))
+        .collect();
+    ports.sort_by_key(|p| p.index);
+
+    Lv2PluginDesc {
+        uri: uri.to_string(),
+        name,
+        vendor,
+        bundle: bundle.to_path_buf(),
+        binary,
+        ports,
+        classes,
+        missing_features,
+    }
+}
+
+fn describe_port(graph: &Graph, port: &Node) -> Option<Lv2Port> {
+    let lv2 = |term: &str| format!("{}{}", LV2, term);
+    let rdf_type = format!("{}type", RDF);
+    let is = |class: String| graph.has(port, &rdf_type, &class);
+    let has_property = |property: String| graph.has(port, &lv2("portProperty"), &property);
+    let number = |term: &str| graph.object(port, &lv2(term)).and_then(Node::number);
+
+    let input = is(lv2("InputPort"));
+    let kind = if is(lv2("AudioPort")) {
+        if input {
+            PortKind::AudioIn
+        } else {
+            PortKind::AudioOut
+        }
+    } else if is(lv2("ControlPort")) {
+        if input {
+            PortKind::ControlIn
+        } else {
+            PortKind::ControlOut
+        }
+    } else if is(format!("{}AtomPort", ATOM)) {
+        if input {
+            PortKind::AtomIn
+        } else {
+            PortKind::AtomOut
+        }
+    } else if is(lv2("CVPort")) {
+        if input {
+            PortKind::CvIn
+        } else {
+            PortKind::CvOut
+        }
+    } else {
+        PortKind::Unsupported
+    };
+
+    let min = number("minimum").unwrap_or(0.0);
+    let max = number("maximum").unwrap_or(1.0);
+    let unit = graph
+        .object(port, &format!("{}unit", UNITS))
+        .and_then(|unit| match unit {
+            Node::Iri(iri) => iri.strip_prefix(UNITS).map(unit_label),
+            node => graph
+                .text(node, &format!("{}symbol", UNITS))
+                .map(str::to_string),
+        })
+        .unwrap_or_default();
+    let scale_points = graph
+        .objects(port, &lv2("scalePoint"))
+        .filter_map(|point| {
+            let value = graph.object(point, &format!("{}value", RDF))?.number()?;
+            let label = graph.text(point, &format!("{}label", RDFS))?;
+            Some((value, label.to_string()))
+        })
+        .collect();
+
+    Some(Lv2Port {
+        index: number("index")? as u32,
+        symbol: graph.object(port, &lv2("symbol"))?.literal()?.to_string(),
+        name: graph
+            .text(port, &lv2("name"))
+            .unwrap_or_default()
+            .to_string(),
+        kind,
+        default: number("default")
+            .unwrap_or(min)
+            .clamp(min.min(max), max.max(min)),
+        min,
+        max,
+        toggled: has_property(lv2("toggled")),
+        integer: has_property(lv2("integer")) || has_property(lv2("enumeration")),
+        logarithmic: has_property(format!("{}logarithmic", PPROPS)),
+        optional: has_property(lv2("connectionOptional")),
+        sidechain: has_property(lv2("isSideChain")),
+        reports_latency: has_property(lv2("reportsLatency"))
+            || graph.has(port, &lv2("designation"), &lv2("latency")),
+        time_position: kind == PortKind::AtomIn
+            && graph.has(
+                port,
+                &format!("{}supports", ATOM),
+                &format!("{}Position", TIME),
+            ),
+        unit,
+        scale_points,
+    })
+}
+
+fn unit_label(unit: &str) -> String {
+    match unit {
+        "db" => "dB",
+        "hz" => "Hz",
+        "khz" => "kHz",
+        "ms" => "ms",
+        "s" => "s",
+        "pc" => "%",
+        "bpm" => "BPM",
+        "cent" => "ct",
+        "semitone12TET" => "st",
+        "coef" => "",
+        other => other,
+    }
+    .to_string()
+}
+
+// ===== C API =====
+
+#[repr(C)]
+struct Lv2Descriptor {
+    uri: *const c_char,
+    instantiate: Option<
+        unsafe extern "C" fn(
+            *const Lv2Descriptor,
+            f64,
+            *const c_char,
+            *const *const Lv2Feature,
+        ) -> *mut c_void,
+    >,
+    connect_port: Option<unsafe extern "C" fn(*mut c_void, u32, *mut c_void)>,
+    activate: Option<unsafe extern "C" fn(*mut c_void)>,
+    run: Option<unsafe extern "C" fn(*mut c_void, u32)>,
+    deactivate: Option<unsafe extern "C" fn(*mut c_void)>,
+    cleanup: Option<unsafe extern "C" fn(*mut c_void)>,
+    extension_data: Option<unsafe extern "C" fn(*const c_char) -> *const c_void>,
+}
+
+#[repr(C)]
+struct Lv2Feature {
+    uri: *const c_char,
+    data: *mut c_void,
+}
+
+#[repr(C)]
+struct Lv2UridMap {
+    handle: *mut c_void,
+    map: unsafe extern "C" fn(*mut c_void, *const c_char) -> u32,
+}
+
+#[repr(C)]
+struct Lv2UridUnmap {
+    handle: *mut c_void,
+    unmap: unsafe extern "C" fn(*mut c_void, u32) -> *const c_char,
+}
+
+type DescriptorFn = unsafe extern "C" fn(u32) -> *const Lv2Descriptor;
+
+/// URIs mapped to integers for one instance; URID 0 is reserved
+#[derive(Debug, Default)]
+struct UridTable {
+    uris: Mutex<Vec<CString>>,
+}
+
+impl UridTable {
+    fn map(&self, uri: &CStr) -> u32 {
+        let Ok(mut uris) = self.uris.lock() else {
+            return 0;
+        };
+        if let Some(index) = uris.iter().position(|u| u.as_c_str() == uri) {
+            return index as u32 + 1;
+        }
+        uris.push(uri.to_owned());
+        uris.len() as u32
+    }
+
+    fn unmap(&self, urid: u32) -> *const c_char {
+        let Ok(uris) = self.uris.lock() else {
+            return std::ptr::null();
+        };
+        // CStrings never move once stored, so the pointer outlives the lock
+        uris.get((urid as usize).wrapping_sub(1))
+            .map_or(std::ptr::null(), |u| u.as_ptr())
+    }
+}
+
+/// URIDs used to describe the transport to a plugin
+#[derive(Debug, Clone, Copy)]
+struct TimeUrids {
+    object: u32,
+    long: u32,
+    float: u32,
+    int: u32,
+    position: u32,
+    frame: u32,
+    speed: u32,
+    bar: u32,
+    bar_beat: u32,
+    beat_unit: u32,
+    beats_per_bar: u32,
+    beats_per_minute: u32,
+}
+
+impl TimeUrids {
+    fn map(urids: &UridTable) -> Self {
+        Self {
+            object: urids.map(c"http://lv2plug.in/ns/ext/atom#Object"),
+            long: urids.map(c"http://lv2plug.in/ns/ext/atom#Long"),
+            float: urids.map(c"http://lv2plug.in/ns/ext/atom#Float"),
+            int: urids.map(c"http://lv2plug.in/ns/ext/atom#Int"),
+            position: urids.map(c"http://lv2plug.in/ns/ext/time#Position"),
+            frame: urids.map(c"http://lv2plug.in/ns/ext/time#frame"),
+            speed: urids.map(c"http://lv2plug.in/ns/ext/time#speed"),
+            bar: urids.map(c"http://lv2plug.in/ns/ext/time#bar"),
+            bar_beat: urids.map(c"http://lv2plug.in/ns/ext/time#barBeat"),
+            beat_unit: urids.map(c"http://lv2plug.in/ns/ext/time#beatUnit"),
+            beats_per_bar: urids.map(c"http://lv2plug.in/ns/ext/time#beatsPerBar"),
+            beats_per_minute: urids.map(c"http://lv2plug.in/ns/ext/time#beatsPerMinute"),
+        }
+    }
+}
+
+/// Fill an atom input with a sequence holding one `time:Position` object
+/// at frame 0. Every property is 24 bytes: key and context, the value's
+/// atom header, then the value padded to 8 bytes.
+fn write_time_position(
+    buffer: &mut [u64],
+    sequence_urid: u32,
+    urids: &TimeUrids,
+    info: &TransportInfo,
+) {
+    let word = |low: u32, high: u32| u64::from(low) | (u64::from(high) << 32);
+    let properties = [
+        (urids.frame, urids.long, info.sample_position as u64),
+        (
+            urids.speed,
+            urids.float,
+            u64::from(if info.playing { 1.0f32 } else { 0.0 }.to_bits()),
+        ),
+        (urids.bar, urids.long, info.bar() as u64),
+        (
+            urids.bar_beat,
+            urids.float,
+            u64::from((info.beat_in_bar() as f32).to_bits()),
+        ),
+        (urids.beat_unit, urids.int, u64::from(info.beat_unit)),
+        (
+            urids.beats_per_bar,
+            urids.float,
+            u64::from((info.beats_per_bar as f32).to_bits()),
+        ),
+        (
+            urids.beats_per_minute,
+            urids.float,
+            u64::from((info.tempo as f32).to_bits()),
+        ),
+    ];
+    let object_size = 8 + 24 * properties.len() as u32;
+    // Sequence body: unit and pad, the event's frame time, the object atom
+    let sequence_size = 8 + 8 + 8 + object_size;
+    let mut words = vec![
+        word(sequence_size, sequence_urid),
+        0,
+        0,
+        word(object_size, urids.object),
+        word(0, urids.position),
+    ];
+    for (key, value_type, value) in properties {
+        let size = if value_type == urids.long { 8 } else { 4 };
+        words.extend([word(key, 0), word(size, value_type), value]);
+    }
+    buffer[..words.len()].copy_from_slice(&words);
+}
+
+unsafe extern "C" fn urid_map(handle: *mut c_void, uri: *const c_char) -> u32 {
+    if uri.is_null() {
+        return 0;
+    }
+    let table = unsafe { &*(handle as *const UridTable) };
+    table.map(unsafe { CStr::from_ptr(uri) })
+}
+
+unsafe extern "C" fn urid_unmap(handle: *mut c_void, urid: u32) -> *const c_char {
+    let table = unsafe { &*(handle as *const UridTable) };
+    table.unmap(urid)
+}
+
+// ===== Instance =====
+
+/// A running plugin with every port connected to host-owned memory
+struct Lv2Instance {
+    handle: *mut c_void,
+    descriptor: *const Lv2Descriptor,
+    /// Values of control ports, indexed by port index
+    controls: Box<[f32]>,
+    audio_inputs: Vec<Box<[f32]>>,
+    sidechain_inputs: Vec<Box<[f32]>>,
+    audio_outputs: Vec<Box<[f32]>>,
+    /// Port-sized scratch for CV ports, which are left silent
+    cv_buffers: Vec<Box<[f32]>>,
+    atom_inputs: Vec<Box<[u64]>>,
+    atom_outputs: Vec<Box<[u64]>>,
+    /// Indices into `atom_inputs` of the ports that take `time:Position`
+    time_inputs: Vec<usize>,
+    /// Transport the plugin will extrapolate to for the next block; a
+    /// position is only sent when the real one differs
+    expected_transport: Option<TransportInfo>,
+    sequence_urid: u32,
+    chunk_urid: u32,
+    time_urids: TimeUrids,
+    // Must outlive the plugin, so declared (and dropped) after it
+    _features: Vec<Lv2Feature>,
+    _map: Box<Lv2UridMap>,
+    _unmap: Box<Lv2UridUnmap>,
+    _urids: Box<UridTable>,
+    _library: Library,
+}
+
+impl Lv2Instance {
+    fn new(desc: &Lv2PluginDesc, sample_rate: f64) -> Result<Self> {
+        if let Some(reason) = desc.unsupported_reason() {
+            bail!("{} {}", desc.name, reason);
+        }
+
+        // Safety: loading a plugin runs its initialisers; that is the point
+        let library = unsafe { Library::new(&desc.binary) }
+            .with_context(|| format!("Failed to load {}", desc.binary.display()))?;
+        let descriptor_fn = *unsafe { library.get::<DescriptorFn>(b"lv2_descriptor\0") }
+            .context("Not an LV2 binary: no lv2_descriptor symbol")?;
+
+        let mut descriptor = std::ptr::null();
+        for index in 0.. {
+            let candidate = unsafe { descriptor_fn(index) };
+            if candidate.is_null() {
+                break;
+            }
+            if unsafe { c_string((*candidate).uri) } == desc.uri {
+                descriptor = candidate;
+                break;
+            }
+        }
+        if descriptor.is_null() {
+            bail!("{} does not contain {}", desc.binary.display(), desc.uri);
+        }
+
+        let urids = Box::new(UridTable::default());
+        let handle = &*urids as *const UridTable as *mut c_void;
+        let map = Box::new(Lv2UridMap {
+            handle,
+            map: urid_map,
+        });
+        let unmap = Box::new(Lv2UridUnmap {
+            handle,
+            unmap: urid_unmap,
+        });
+        let map_uri = c"http://lv2plug.in/ns/ext/urid#map";
+        let unmap_uri = c"http://lv2plug.in/ns/ext/urid#unmap";
+        let features = vec![
+            Lv2Feature {
+                uri: map_uri.as_ptr(),
+                data: &*map as *const Lv2UridMap as *mut c_void,
+            },
+            Lv2Feature {
+                uri: unmap_uri.as_ptr(),
+                data: &*unmap as *const Lv2UridUnmap as *mut c_void,
+            },
+        ];
+        let mut feature_list: Vec<*const Lv2Feature> =
+            features.iter().map(|f| f as *const Lv2Feature).collect();
+        feature_list.push(std::ptr::null());
+
+        let bundle_path = CString::new(format!("{}/", desc.bundle.display()))?;
+        let instantiate = unsafe { (*descriptor).instantiate }.context("No instantiate")?;
+        let plugin_handle = unsafe {
+            instantiate(
+                descriptor,
+                sample_rate,
+                bundle_path.as_ptr(),
+                feature_list.as_ptr(),
+            )
+        };
+        if plugin_handle.is_null() {
+            bail!("{} failed to instantiate", desc.name);
+        }
+
+        let sequence_urid = urids.map(c"http://lv2plug.in/ns/ext/atom#Sequence");
+        let chunk_urid = urids.map(c"http://lv2plug.in/ns/ext/atom#Chunk");
+        let time_urids = TimeUrids::map(&urids);
+        let port_count = desc
+            .ports
+            .iter()
+            .map(|p| p.index as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let mut instance = Self {
+            handle: plugin_handle,
+            descriptor,
+            controls: vec![0.0; port_count].into_boxed_slice(),
+            audio_inputs: Vec::new(),
+            sidechain_inputs: Vec::new(),
+            audio_outputs: Vec::new(),
+            cv_buffers: Vec::new(),
+            atom_inputs: Vec::new(),
+            atom_outputs: Vec::new(),
+            time_inputs: Vec::new(),
+            expected_transport: None,
+            sequence_urid,
+            chunk_urid,
+            time_urids,
+            _features: features,
+            _map: map,
+            _unmap: unmap,
+            _urids: urids,
+            _library: library,
+        };
+        instance.connect_ports(&desc.ports);
+
+        if let Some(activate) = unsafe { (*descriptor).activate } {
+            unsafe { activate(plugin_handle) };
+        }
+        Ok(instance)
+    }
+
+    fn connect_ports(&mut self, ports: &[Lv2Port]) {
+        let Some(connect) = (unsafe { (*self.descriptor).connect_port }) else {
+            return;
+        };
+        let atom_words = ATOM_CAPACITY / 8;
+        for port in ports {
+            let data: *mut c_void = match port.kind {
+                PortKind::ControlIn | PortKind::ControlOut => {
+                    self.controls[port.index as usize] = port.default;
+                    &mut self.controls[port.index as usize] as *mut f32 as *mut c_void
+                }
+                PortKind::AudioIn => {
+                    let inputs = if port.sidechain {
+                        &mut self.sidechain_inputs
+                    } else {
+                        &mut self.audio_inputs
+                    };
+                    inputs.push(vec![0.0; MAX_FRAMES].into_boxed_slice());
+                    inputs.last_mut().unwrap().as_mut_ptr() as *mut c_void
+                }
+                PortKind::AudioOut => {
+                    self.audio_outputs
+                        .push(vec![0.0; MAX_FRAMES].into_boxed_slice());
+                    self.audio_outputs.last_mut().unwrap().as_mut_ptr() as *mut c_void
+                }
+                PortKind::CvIn | PortKind::CvOut => {
+                    self.cv_buffers
+                        .push(vec![0.0; MAX_FRAMES].into_boxed_slice());
+                    self.cv_buffers.last_mut().unwrap().as_mut_ptr() as *mut c_void
+                }
+                PortKind::AtomIn => {
+                    let mut buffer = vec![0u64; atom_words].into_boxed_slice();
+                    // An empty sequence: atom header, then the body's unit and pad
+                    buffer[0] = 8 | (u64::from(self.sequence_urid) << 32);
+                    if port.time_position {
+                        self.time_inputs.push(self.atom_inputs.len());
+                    }
+                    self.atom_inputs.push(buffer);
+                    self.atom_inputs.last_mut().unwrap().as_mut_ptr() as *mut c_void
+                }
+                PortKind::AtomOut => {
+                    self.atom_outputs
+                        .push(vec![0u64; atom_words].into_boxed_slice());
+                    self.atom_outputs.last_mut().unwrap().as_mut_ptr() as *mut c_void
+                }
+                PortKind::Unsupported => std::ptr::null_mut(),
+            };
+            unsafe { connect(self.handle, port.index, data) };
+        }
+    }
+
+    /// Run `frames` of planar audio through the plugin: main inputs first,
+    /// then sidechain inputs; missing channels are silent
+    fn process(
+        &mut self,
+        inputs: &[&[f32]],
+        frames: usize,
+        transport: &TransportInfo,
+    ) -> Vec<Vec<f32>> {
+        let Some(run) = (unsafe { (*self.descriptor).run }) else {
+            return inputs.iter().map(|input| input.to_vec()).collect();
+        };
+        let send_position = self.expected_transport != Some(*transport);
+        self.expected_transport = Some(transport.offset(frames));
+
+        let mut output: Vec<Vec<f32>> = self
+            .audio_outputs
+            .iter()
+            .map(|_| Vec::with_capacity(frames))
+            .collect();
+        for start in (0..frames).step_by(MAX_FRAMES) {
+            let end = (start + MAX_FRAMES).min(frames);
+            let block = end - start;
+            for (channel, buffer) in self
+                .audio_inputs
+                .iter_mut()
+                .chain(self.sidechain_inputs.iter_mut())
+                .enumerate()
+            {
+                match inputs.get(channel).and_then(|input| input.get(start..end)) {
+                    Some(input) => buffer[..block].copy_from_slice(input),
+                    None => buffer[..block].fill(0.0),
+                }
+            }
+            // Output sequences announce their capacity before each run
+            for buffer in &mut self.atom_outputs {
+                let capacity = (ATOM_CAPACITY - 8) as u64;
+                buffer[0] = capacity | (u64::from(self.chunk_urid) << 32);
+            }
+            // Plugins carry the position on themselves between changes
+            for &index in &self.time_inputs {
+                let buffer = &mut self.atom_inputs[index];
+                if send_position && start == 0 {
+                    write_time_position(buffer, self.sequence_urid, &self.time_urids, transport);
+                } else {
+                    buffer[0] = 8 | (u64::from(self.sequence_urid) << 32);
+                }
+            }
+
+            unsafe { run(self.handle, block as u32) };
+
+            for (output, buffer) in output.iter_mut().zip(&self.audio_outputs) {
+                output.extend_from_slice(&buffer[..block]);
+            }
+        }
+        output
+    }
+}
+
+impl Drop for Lv2Instance {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(deactivate) = (*self.descriptor).deactivate {
+                deactivate(self.handle);
+            }
+            if let Some(cleanup) = (*self.descriptor).cleanup {
+                cleanup(self.handle);
+            }
+        }
+    }
+}
+
+/// Copy a C string the plugin owns; null becomes empty
+unsafe fn c_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+// ===== Processor =====
+
+/// LV2 plugin in a channel slot
+pub struct Lv2Processor {
+    desc: Lv2PluginDesc,
+    instance: Lv2Instance,
+    /// Control input ports, in the order the slot API indexes them
+    parameters: Vec<Lv2Port>,
+    latency_port: Option<u32>,
+    enabled: bool,
+    sample_rate: f32,
+}
+
+// Safety: the instance is only reached through `&mut self` or `&self` on
+// plain port memory, so no two plugin functions ever run at once. LV2 lets
+// instances move between threads under that rule.
+unsafe impl Send for Lv2Processor {}
+
+impl Lv2Processor {
+    /// Load a plugin from a bundle directory; `plugin_uri` picks one when the
+    /// bundle holds several, otherwise the first usable one is taken
+    pub fn load(bundle: &Path, plugin_uri: Option<&str>, sample_rate: f32) -> Result<Self> {
+        let descriptions = describe_bundle(bundle)?;
+        let desc = match plugin_uri {
+            Some(uri) => descriptions.into_iter().find(|d| d.uri == uri),
+            None => descriptions
+                .into_iter()
+                .find(|d| d.unsupported_reason().is_none()),
+        }
+        .with_context(|| match plugin_uri {
+            Some(uri) => format!("Bundle has no plugin {}", uri),
+            None => "Bundle contains no usable plugins".to_string(),
+        })?;
+
+        let instance = Lv2Instance::new(&desc, sample_rate as f64)?;
+        let parameters = desc
+            .ports
+            .iter()
+            .filter(|p| p.kind == PortKind::ControlIn)
+            .cloned()
+            .collect();
+        let latency_port = desc
+            .ports
+            .iter()
+            .find(|p| p.kind == PortKind::ControlOut && p.reports_latency)
+            .map(|p| p.index);
+
+        let mut processor = Self {
+            desc,
+            instance,
+            parameters,
+            latency_port,
+            enabled: true,
+            sample_rate,
+        };
+        // Output controls, latency included, are only valid after a run
+        processor
+            .instance
+            .process(&[], 64, &TransportInfo::default());
+        Ok(processor)
+    }
+
+    pub fn get_plugin_uri(&self) -> &str {
+        &self.desc.uri
+    }
+
+    pub fn get_bundle_path(&self) -> &Path {
+        &self.desc.bundle
+    }
+
+    /// Plain value of a control port by symbol
+    pub fn get_port_value(&self, symbol: &str) -> Option<f32> {
+        let port = self.desc.ports.iter().find(|p| p.symbol == symbol)?;
+        matches!(port.kind, PortKind::ControlIn | PortKind::ControlOut)
+            .then(|| self.instance.controls[port.index as usize])
+    }
+
+    /// Set a control input by symbol, in the port's own range
+    pub fn set_port_value(&mut self, symbol: &str, value: f32) -> bool {
+        let Some(port) = self.parameters.iter().find(|p| p.symbol == symbol) else {
+            return false;
+        };
+        self.instance.controls[port.index as usize] = value.clamp(port.min, port.max);
+        true
+    }
+
+    /// Control input values by port symbol
+    pub fn port_values(&self) -> BTreeMap<String, f32> {
+        self.parameters
+            .iter()
+            .map(|p| (p.symbol.clone(), self.instance.controls[p.index as usize]))
+            .collect()
+    }
+
+    pub fn get_sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+}
+
+impl PluginProcessor for Lv2Processor {
+    fn format(&self) -> PluginFormat {
+        PluginFormat::Lv2
+    }
+
+    fn channel_layout(&self) -> ChannelLayout {
+        self.desc.channel_layout()
+    }
+
+    fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+        let frames = inputs.first().map_or(0, |input| input.len());
+        if !self.enabled || frames == 0 {
+            return plugin_host::passthrough(self.channel_layout(), inputs, frames);
+        }
+        self.instance.process(inputs, frames, &transport::current())
+    }
+
+    fn get_plugin_name(&self) -> String {
+        self.desc.name.clone()
+    }
+
+    fn set_parameter(&mut self, index: i32, value: f32) {
+        if let Some(port) = usize::try_from(index)
+            .ok()
+            .and_then(|i| self.parameters.get(i))
+        {
+            self.instance.controls[port.index as usize] = port.denormalize(value);
+        }
+    }
+
+    fn get_parameter(&self, index: i32) -> f32 {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.parameters.get(i))
+            .map_or(0.0, |port| {
+                port.normalize(self.instance.controls[port.index as usize])
+            })
+    }
+
+    fn get_parameter_info(&self, index: i32) -> Option<ParameterInfo> {
+        let port = self.parameters.get(usize::try_from(index).ok()?)?;
+        let value = self.instance.controls[port.index as usize];
+        Some(ParameterInfo {
+            index,
+            name: port.name.clone(),
+            label: port.unit.clone(),
+            value: port.normalize(value),
+            display: port.display(value),
+        })
+    }
+
+    fn get_parameter_count(&self) -> i32 {
+        self.parameters.len() as i32
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn latency_samples(&self) -> usize {
+        match self.latency_port {
+            Some(port) if self.enabled => self.instance.controls[port as usize].max(0.0) as usize,
+            _ => 0,
+        }
+    }
+
+    /// LV2 fixes the rate at instantiation, so a new instance takes over
+    /// the control values
+    fn set_sample_rate(&mut self, sample_rate: f32) -> Result<()> {
+        if sample_rate == self.sample_rate {
+            return Ok(());
+        }
+        let values = self.port_values();
+        self.instance = Lv2Instance::new(&self.desc, sample_rate as f64)?;
+        self.sample_rate = sample_rate;
+        for (symbol, value) in values {
+            self.set_port_value(&symbol, value);
+        }
+        self.instance.process(&[], 64, &TransportInfo::default());
+        Ok(())
+    }
+
+    /// Port values as JSON, keyed by symbol so they survive plugin updates
+    fn save_state(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&self.port_values())?)
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        let values: BTreeMap<String, f32> =
+            serde_json::from_slice(data).context("Invalid LV2 port values")?;
+        for (symbol, value) in values {
+            if !self.set_port_value(&symbol, value) {
+                log::warn!("{} has no control port {}", self.desc.name, symbol);
+            }
+        }
+        Ok(())
+    }
+}
+
+// ===== Scanner =====
+
+/// LV2 plugin scanner, filling the same catalogue as the VST2 scanner
+pub struct Lv2Scanner {
+    plugins: Vec<VstPluginInfo>,
+    scan_paths: Vec<PathBuf>,
+}
+
+impl Lv2Scanner {
+    pub fn new() -> Self {
+        // LV2_PATH replaces the defaults rather than adding to them
+        let scan_paths = match std::env::var_os("LV2_PATH") {
+            Some(paths) => std::env::split_paths(&paths).collect(),
+            None => {
+                let mut paths: Vec<PathBuf> = dirs::home_dir()
+                    .map(|home| home.join(".lv2"))
+                    .into_iter()
+                    .collect();
+                paths.push(PathBuf::from("/usr/local/lib/lv2"));
+                paths.push(PathBuf::from("/usr/lib/lv2"));
+                paths.push(PathBuf::from("/usr/lib/x86_64-linux-gnu/lv2"));
+                paths
+            }
+        };
+
+        Self {
+            plugins: Vec::new(),
+            scan_paths,
+        }
+    }
+
+    pub fn add_scan_path(&mut self, path: PathBuf) {
+        self.scan_paths.push(path);
+    }
+
+    /// Bundle directories (those with a manifest) inside the scan paths
+    pub fn plugin_files(&self) -> Vec<PathBuf> {
+        self.scan_paths
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flat_map(|entries| entries.flatten().map(|e| e.path()))
+            .filter(|bundle| bundle.join("manifest.ttl").is_file())
+            .collect()
+    }
+
+    /// Scan every bundle; only plugins a channel slot can run are listed
+    pub fn scan(&mut self) {
+        self.plugins.clear();
+        for bundle in self.plugin_files() {
+            match scan_bundle(&bundle) {
+                Ok(plugins) => self.plugins.extend(plugins),
+                Err(e) => log::warn!("Skipping LV2 bundle {}: {:#}", bundle.display(), e),
+            }
+        }
+    }
+
+    pub fn get_plugins(&self) -> &[VstPluginInfo] {
+        &self.plugins
+    }
+}
+
+impl Default for Lv2Scanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+@prefix lv2:  <http://lv2plug.in/ns/lv2core#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+<urn:test:gain>
+    a lv2:Plugin ;
+    lv2:binary <gain.so> ;
+    rdfs:seeAlso <gain.ttl> .
+"#;
+
+    const PLUGIN: &str = r#"
+@prefix doap:  <http://usefulinc.com/ns/doap#> .
+@prefix foaf:  <http://xmlns.com/foaf/0.1/> .
+@prefix lv2:   <http://lv2plug.in/ns/lv2core#> .
+@prefix pprop: <http://lv2plug.in/ns/ext/port-props#> .
+@prefix rdf:   <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+@prefix rdfs:  <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix units: <http://lv2plug.in/ns/extensions/units#> .
+@prefix urid:  <http://lv2plug.in/ns/ext/urid#> .
+
+# A stereo gain with a mode switch
+<urn:test:gain>
+    a lv2:Plugin, lv2:AmplifierPlugin ;
+    doap:name "Test Gain"@en ;
+    doap:maintainer [ foaf:name "Test Vendor" ] ;
+    lv2:requiredFeature urid:map ;
+    lv2:optionalFeature lv2:hardRTCapable ;
+    lv2:port [
+        a lv2:InputPort, lv2:ControlPort ;
+        lv2:index 0 ;
+        lv2:symbol "gain" ;
+        lv2:name "Gain" ;
+        lv2:default 0.0 ;
+        lv2:minimum -90.0 ;
+        lv2:maximum 24.0 ;
+        units:unit units:db ;
+    ] , [
+        a lv2:InputPort, lv2:ControlPort ;
+        lv2:index 1 ;
+        lv2:symbol "mode" ;
+        lv2:name """Mode""" ;
+        lv2:default 1 ;
+        lv2:minimum 0 ;
+        lv2:maximum 2 ;
+        lv2:portProperty lv2:integer, lv2:enumeration ;
+        lv2:scalePoint [ rdfs:label "Soft" ; rdf:value 1 ] ;
+    ] , [
+        a lv2:InputPort, lv2:AudioPort ; lv2:index 2 ; lv2:symbol "in_l" ; lv2:name "In L"
+    ] , [
+        a lv2:InputPort, lv2:AudioPort ; lv2:index 3 ; lv2:symbol "in_r" ; lv2:name "In R"
+    ] , [
+        a lv2:OutputPort, lv2:AudioPort ; lv2:index 4 ; lv2:symbol "out_l" ; lv2:name "Out L"
+    ] , [
+        a lv2:OutputPort, lv2:AudioPort ; lv2:index 5 ; lv2:symbol "out_r" ; lv2:name "Out R"
+    ] , [
+        a lv2:OutputPort, lv2:ControlPort ;
+        lv2:index 6 ;
+        lv2:symbol "latency" ;
+        lv2:name "Latency" ;
+        lv2:portProperty lv2:reportsLatency, pprop:notOnGUI ;
+    ] .
+"#;
+
+    /// A two-plugin bundle laid out the way distributed ones are: plugins,
+    /// a UI and a preset in the manifest, descriptions split over files that
+    /// set their own `@base`
+    const FIXTURE_MANIFEST: &str = r#"
+@prefix lv2:  <http://lv2plug.in/ns/lv2core#> .
+@prefix pset: <http://lv2plug.in/ns/ext/presets#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix ui:   <http://lv2plug.in/ns/extensions/ui#> .
+
+<http://example.org/plugins/comp#mono>
+    a lv2:Plugin ;
+    lv2:binary <lib/comp%20dsp.so> ;
+    rdfs:seeAlso <comp.ttl> , <common.ttl> .
+
+<http://example.org/plugins/comp#stereo>
+    a lv2:Plugin ;
+    lv2:binary <./lib/../lib/comp%20dsp.so> ;
+    rdfs:seeAlso <comp.ttl> , <common.ttl> .
+
+<http://example.org/plugins/comp#ui>
+    a ui:X11UI ;
+    ui:binary <lib/comp_ui.so> ;
+    rdfs:seeAlso <comp_ui.ttl> .
+
+<http://example.org/plugins/comp#gentle>
+    a pset:Preset ;
+    lv2:appliesTo <http://example.org/plugins/comp#mono> ;
+    rdfs:seeAlso <presets/gentle.ttl> .
+"#;
+
+    const FIXTURE_COMMON: &str = r#"
+@base <http://example.org/plugins/comp> .
+@prefix doap: <http://usefulinc.com/ns/doap#> .
+@prefix foaf: <http://xmlns.com/foaf/0.1/> .
+@prefix lv2:  <http://lv2plug.in/ns/lv2core#> .
+
+<#project>
+    a doap:Project ;
+    doap:maintainer [
+        foaf:name "Beispiel Audio"@de , "Example Audio"@en-GB ;
+    ] .
+"#;
+
+    const FIXTURE_PLUGINS: &str = r#"
+@base <http://example.org/plugins/comp> .
+@prefix doap:  <http://usefulinc.com/ns/doap#> .
+@prefix lv2:   <http://lv2plug.in/ns/lv2core#> .
+@prefix opts:  <http://lv2plug.in/ns/ext/options#> .
+@prefix rdfs:  <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix state: <http://lv2plug.in/ns/ext/state#> .
+@prefix xsd:   <http://www.w3.org/2001/XMLSchema#> .
+
+<#mono>
+    a lv2:Plugin , lv2:CompressorPlugin ;
+    lv2:project <#project> ;
+    doap:name "Kompressor"@de , "Compressor"@en ;
+    rdfs:comment """A feed-forward compressor.
+Its "knee" is soft; the manual says "use it""""@en ;
+    lv2:extensionData state:interface , opts:interface ;
+    lv2:port [
+        a lv2:InputPort , lv2:ControlPort ;
+        lv2:index 0 ;
+        lv2:symbol "ratio" ;
+        lv2:name "Verhältnis"@de , "Ratio"@en ;
+        lv2:default "4.0"^^xsd:float ;
+        lv2:minimum "1"^^<http://www.w3.org/2001/XMLSchema#float> ;
+        lv2:maximum 20 ;
+    ] , [
+        a lv2:InputPort , lv2:ControlPort ;
+        lv2:index 1 ;
+        lv2:symbol "enable" ;
+        lv2:name 'Enable' ;
+        lv2:default true ;
+        lv2:portProperty lv2:toggled ;
+    ] , [
+        a lv2:InputPort , lv2:AudioPort ; lv2:index 2 ; lv2:symbol "in" ; lv2:name "In"
+    ] , [
+        a lv2:OutputPort , lv2:AudioPort ; lv2:index 3 ; lv2:symbol "out" ; lv2:name "Out"
+    ] .
+
+<#stereo>
+    a lv2:Plugin , lv2:CompressorPlugin ;
+    lv2:project <#project> ;
+    doap:name "Compressor (Stereo)" ;
+    lv2:extensionData <http://lv2plug.in/ns/ext/state#interface> ;
+    lv2:port [
+        a lv2:InputPort , lv2:AudioPort ; lv2:index 0 ; lv2:symbol "in_l" ; lv2:name "In L"
+    ] , [
+        a lv2:InputPort , lv2:AudioPort ; lv2:index 1 ; lv2:symbol "in_r" ; lv2:name "In R"
+    ] , [
+        a lv2:OutputPort , lv2:AudioPort ; lv2:index 2 ; lv2:symbol "out_l" ; lv2:name "Out L"
+    ] , [
+        a lv2:OutputPort , lv2:AudioPort ; lv2:index 3 ; lv2:symbol "out_r" ; lv2:name "Out R"
+    ] .
+"#;
+
+    fn write_bundle(name: &str, plugin_ttl: &str) -> PathBuf {
+        let bundle = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&bundle);
+        std::fs::create_dir_all(&bundle).unwrap();
+        std::fs::write(bundle.join("manifest.ttl"), MANIFEST).unwrap();
+        std::fs::write(bundle.join("gain.ttl"), plugin_ttl).unwrap();
+        bundle
+    }
+
+    #[test]
+    fn test_turtle_parsing() {
+        let mut graph = Graph::default();
+        graph
+            .parse(
+                r#"@prefix ex: <http://example.org/> .
+                PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+                <#me> a ex:Person ; ex:age 42 ; ex:height 1.8e0 ;
+                    ex:tags ( "a" "b" ) ; rdfs:label "Me"^^ex:text , 'Moi'@fr ;;
+                    ex:knows _:friend .
+                _:friend ex:name "Say \"hi\"" . # trailing comment
+                "#,
+                "file:///bundle/",
+            )
+            .unwrap();
+
+        let me = Node::Iri("file:///bundle/#me".to_string());
+        assert!(graph.has(&me, &format!("{}type", RDF), "http://example.org/Person"));
+        assert_eq!(
+            graph
+                .object(&me, "http://example.org/age")
+                .and_then(Node::number),
+            Some(42.0)
+        );
+        assert_eq!(
+            graph
+                .object(&me, "http://example.org/height")
+                .and_then(Node::number),
+            Some(1.8)
+        );
+        let labels: Vec<_> = graph
+            .objects(&me, &format!("{}label", RDFS))
+            .filter_map(Node::literal)
+            .collect();
+        assert_eq!(labels, ["Me", "Moi"]);
+        let friend = graph.object(&me, "http://example.org/knows").unwrap();
+        assert_eq!(
+            graph
+                .object(friend, "http://example.org/name")
+                .and_then(Node::literal),
+            Some("Say \"hi\"")
+        );
+
+        assert!(Graph::default().parse("<a> <b> .", "file:///").is_err());
+    }
+
+    #[test]
+    fn test_iri_resolution() {
+        let base = "file:///usr/lib/lv2/comp.lv2/manifest.ttl";
+        assert_eq!(
+            resolve_iri(base, "comp.ttl"),
+            "file:///usr/lib/lv2/comp.lv2/comp.ttl"
+        );
+        assert_eq!(
+            resolve_iri(base, "../eq.lv2/eq.ttl"),
+            "file:///usr/lib/lv2/eq.lv2/eq.ttl"
+        );
+        assert_eq!(
+            resolve_iri(base, "./lib/x.so"),
+            "file:///usr/lib/lv2/comp.lv2/lib/x.so"
+        );
+        assert_eq!(resolve_iri(base, "/opt/x.so"), "file:///opt/x.so");
+        assert_eq!(resolve_iri(base, ""), base);
+        assert_eq!(
+            resolve_iri("http://example.org/comp#x", "#y"),
+            "http://example.org/comp#y"
+        );
+        assert_eq!(
+            resolve_iri("http://example.org/a/b", "c"),
+            "http://example.org/a/c"
+        );
+        assert_eq!(resolve_iri(base, "urn:test:gain"), "urn:test:gain");
+
+        let path = Path::new("/tmp/my plugins/100%.lv2/manifest.ttl");
+        assert_eq!(iri_to_path(&path_to_iri(path)).as_deref(), Some(path));
+    }
+
+    #[test]
+    fn test_multi_file_bundle_fixture() {
+        let bundle = std::env::temp_dir().join("phantomlink test lv2 fixture.lv2");
+        let _ = std::fs::remove_dir_all(&bundle);
+        std::fs::create_dir_all(&bundle).unwrap();
+        std::fs::write(bundle.join("manifest.ttl"), FIXTURE_MANIFEST).unwrap();
+        std::fs::write(bundle.join("common.ttl"), FIXTURE_COMMON).unwrap();
+        std::fs::write(bundle.join("comp.ttl"), FIXTURE_PLUGINS).unwrap();
+
+        // The UI and preset files are never read, only the plugins' seeAlso
+        let descriptions = describe_bundle(&bundle).unwrap();
+        assert_eq!(descriptions.len(), 2);
+        let (mono, stereo) = (&descriptions[0], &descriptions[1]);
+        assert_eq!(mono.uri, "http://example.org/plugins/comp#mono");
+        assert_eq!(mono.binary, bundle.join("lib/comp dsp.so"));
+        assert_eq!(stereo.binary, mono.binary);
+
+        // English wins over other languages; the maintainer comes from the
+        // project described in another file
+        assert_eq!(mono.name, "Compressor");
+        assert_eq!(mono.vendor, "Example Audio");
+        assert_eq!(stereo.name, "Compressor (Stereo)");
+        assert_eq!(stereo.vendor, "Example Audio");
+
+        let ratio = &mono.ports[0];
+        assert_eq!(ratio.name, "Ratio");
+        assert_eq!((ratio.min, ratio.max, ratio.default), (1.0, 20.0, 4.0));
+        let enable = &mono.ports[1];
+        assert!(enable.toggled);
+        assert_eq!(enable.default, 1.0);
+        assert!(mono.unsupported_reason().is_none());
+        assert_eq!(stereo.channel_layout(), ChannelLayout::new(2, 2));
+
+        // Datatypes, language tags and long strings survive parsing
+        let mut graph = Graph::default();
+        graph.parse_file(&bundle.join("comp.ttl")).unwrap();
+        let plugin = Node::Iri(mono.uri.clone());
+        let Some(Node::Literal(comment)) = graph.object(&plugin, &format!("{}comment", RDFS))
+        else {
+            panic!("comment missing");
+        };
+        assert_eq!(
+            comment.value,
+            "A feed-forward compressor.\nIts \"knee\" is soft; the manual says \"use it\""
+        );
+        assert_eq!(comment.language.as_deref(), Some("en"));
+        let port = graph.object(&plugin, &format!("{}port", LV2)).unwrap();
+        let Some(Node::Literal(default)) = graph.object(port, &format!("{}default", LV2)) else {
+            panic!("default missing");
+        };
+        assert_eq!(default.datatype, Some(format!("{}float", XSD)));
+        let names: Vec<_> = graph
+            .objects(port, &format!("{}name", LV2))
+            .filter_map(Node::literal)
+            .collect();
+        assert_eq!(names, ["Verhältnis", "Ratio"]);
+        let extensions: Vec<_> = graph
+            .objects(&plugin, &format!("{}extensionData", LV2))
+            .filter_map(Node::iri)
+            .collect();
+        assert_eq!(
+            extensions,
+            [
+                "http://lv2plug.in/ns/ext/state#interface",
+                "http://lv2plug.in/ns/ext/options#interface"
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&bundle);
+    }
+
+    #[test]
+    fn test_describe_bundle() {
+        let bundle = write_bundle("phantomlink_test_lv2_bundle.lv2", PLUGIN);
+        let descriptions = describe_bundle(&bundle).unwrap();
+        assert_eq!(descriptions.len(), 1);
+        let desc = &descriptions[0];
+
+        assert_eq!(desc.name, "Test Gain");
+        assert_eq!(desc.vendor, "Test Vendor");
+        assert_eq!(desc.binary, bundle.join("gain.so"));
+        assert_eq!(desc.category(), VstCategory::Effect);
+        assert!(desc.unsupported_reason().is_none());
+
+        let info = desc.to_info();
+        assert_eq!((info.inputs, info.outputs, info.parameters), (2, 2, 2));
+        assert_eq!(info.format, PluginFormat::Lv2);
+        assert_eq!(info.plugin_id.as_deref(), Some("urn:test:gain"));
+
+        let gain = &desc.ports[0];
+        assert_eq!((gain.symbol.as_str(), gain.unit.as_str()), ("gain", "dB"));
+        assert_eq!((gain.min, gain.max, gain.default), (-90.0, 24.0, 0.0));
+        let mode = &desc.ports[1];
+        assert!(mode.integer);
+        assert_eq!(mode.display(1.0), "Soft");
+        assert_eq!(mode.display(2.0), "2");
+        assert!(desc.ports[6].reports_latency);
+
+        // No binary to load; the error names it
+        let err = Lv2Processor::load(&bundle, None, 48000.0).err().unwrap();
+        assert!(format!("{:#}", err).contains("gain.so"));
+        let _ = std::fs::remove_dir_all(&bundle);
+    }
+
+    #[test]
+    fn test_unsupported_plugins_are_flagged() {
+        let needs_worker = PLUGIN.replace(
+            "lv2:requiredFeature urid:map ;",
+            "lv2:requiredFeature <http://lv2plug.in/ns/ext/worker#schedule> ;",
+        );
+        let bundle = write_bundle("phantomlink_test_lv2_worker.lv2", &needs_worker);
+        let desc = &describe_bundle(&bundle).unwrap()[0];
+        assert!(
+            desc.unsupported_reason()
+                .unwrap()
+                .contains("worker#schedule")
+        );
+        let _ = std::fs::remove_dir_all(&bundle);
+
+        let surround = PLUGIN.replace(
+            "a lv2:OutputPort, lv2:AudioPort ; lv2:index 5",
+            "a lv2:InputPort, lv2:AudioPort ; lv2:index 5",
+        );
+        let bundle = write_bundle("phantomlink_test_lv2_surround.lv2", &surround);
+        let desc = &describe_bundle(&bundle).unwrap()[0];
+        assert!(desc.unsupported_reason().unwrap().contains("3 in / 1 out"));
+        let _ = std::fs::remove_dir_all(&bundle);
+
+        // The same port as a key input leaves a usable stereo effect
+        let keyed = PLUGIN.replace(
+            "a lv2:OutputPort, lv2:AudioPort ; lv2:index 5",
+            "a lv2:InputPort, lv2:AudioPort ; lv2:portProperty lv2:isSideChain ; lv2:index 5",
+        );
+        let bundle = write_bundle("phantomlink_test_lv2_keyed.lv2", &keyed);
+        let desc = &describe_bundle(&bundle).unwrap()[0];
+        assert!(desc.unsupported_reason().is_none());
+        assert_eq!(
+            desc.channel_layout(),
+            ChannelLayout::new(2, 1).with_sidechain(1)
+        );
+        let _ = std::fs::remove_dir_all(&bundle);
+    }
+
+    #[test]
+    fn test_port_normalisation() {
+        let mut port = Lv2Port {
+            index: 0,
+            symbol: "freq".to_string(),
+            name: "Frequency".to_string(),
+            kind: PortKind::ControlIn,
+            default: 1000.0,
+            min: 20.0,
+            max: 20000.0,
+            toggled: false,
+            integer: false,
+            logarithmic: true,
+            optional: false,
+            sidechain: false,
+            reports_latency: false,
+            time_position: false,
+            unit: "Hz".to_string(),
+            scale_points: Vec::new(),
+        };
+        // 632 Hz is the geometric middle of 20 Hz - 20 kHz
+        assert!((port.normalize(632.46) - 0.5).abs() < 1e-3);
+        assert!((port.denormalize(0.5) - 632.46).abs() < 0.1);
+
+        port.logarithmic = false;
+        port.toggled = true;
+        port.min = 0.0;
+        port.max = 1.0;
+        assert_eq!(port.denormalize(0.7), 1.0);
+        assert_eq!(port.display(0.0), "Off");
+    }
+
+    #[test]
+    fn test_time_position_atom_layout() {
+        let table = UridTable::default();
+        let sequence = table.map(c"http://lv2plug.in/ns/ext/atom#Sequence");
+        let urids = TimeUrids::map(&table);
+        let info = TransportInfo {
+            playing: true,
+            sample_position: 24000 * 9,
+            sample_rate: 48000.0,
+            tempo: 120.0,
+            beats_per_bar: 4,
+            beat_unit: 4,
+            musical: None,
+        };
+        let mut buffer = vec![0u64; ATOM_CAPACITY / 8];
+        write_time_position(&mut buffer, sequence, &urids, &info);
+
+        let low = |word: u64| word as u32;
+        let high = |word: u64| (word >> 32) as u32;
+        // Sequence of 200 bytes holding one object event at frame 0
+        assert_eq!((low(buffer[0]), high(buffer[0])), (200, sequence));
+        assert_eq!((buffer[1], buffer[2]), (0, 0));
+        assert_eq!((low(buffer[3]), high(buffer[3])), (176, urids.object));
+        assert_eq!(high(buffer[4]), urids.position);
+
+        let property = |key: u32| {
+            let at = (5..26).step_by(3).find(|&i| low(buffer[i]) == key).unwrap();
+            (high(buffer[at + 1]), buffer[at + 2])
+        };
+        assert_eq!(property(urids.frame), (urids.long, 216000));
+        assert_eq!(property(urids.bar), (urids.long, 2));
+        let (float, speed) = property(urids.speed);
+        assert_eq!(float, urids.float);
+        assert_eq!(f32::from_bits(speed as u32), 1.0);
+        assert_eq!(f32::from_bits(property(urids.bar_beat).1 as u32), 1.0);
+        assert_eq!(property(urids.beat_unit), (urids.int, 4));
+        assert_eq!(
+            f32::from_bits(property(urids.beats_per_minute).1 as u32),
+            120.0
+        );
+    }
+
+    #[test]
+    fn test_urid_table_round_trip() {
+        let table = UridTable::default();
+        let handle = &table as *const UridTable as *mut c_void;
+        unsafe {
+            let a = urid_map(handle, c"urn:a".as_ptr());
+            let b = urid_map(handle, c"urn:b".as_ptr());
+            assert_eq!((a, b), (1, 2));
+            assert_eq!(urid_map(handle, c"urn:a".as_ptr()), a);
+            assert_eq!(CStr::from_ptr(urid_unmap(handle, b)), c"urn:b");
+            assert!(urid_unmap(handle, 0).is_null());
+        }
+    }
+
+    #[test]
+    fn test_lv2_scanner_respects_lv2_path() {
+        let scanner = Lv2Scanner::new();
+        match std::env::var_os("LV2_PATH") {
+            Some(_) => assert!(!scanner.scan_paths.is_empty()),
+            None => assert!(
+                scanner
+                    .scan_paths
+                    .iter()
+                    .any(|p| p == Path::new("/usr/lib/lv2"))
+            ),
+        }
+    }
+}
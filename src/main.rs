@@ -1,19 +1,38 @@
 mod advanced_denoising;
+mod agc;
 mod audio;
 #[allow(dead_code)] // Public effects API, wired incrementally from GUI
 mod audio_effects;
+mod audio_file;
+mod calibration;
+mod clap_host;
 mod config;
+mod dsp_load;
+mod flac_encoder;
 mod ghostwave_integration;
 mod gpu;
 mod gui;
 mod ipc;
 #[allow(dead_code)] // Kept for future JACK pro audio support
 mod jack_client;
+mod ladspa_host;
+mod latency;
+mod loudness;
+mod lv2_host;
+mod offline;
+mod onnx_denoiser;
 mod phantomlink;
 mod pipewire;
+mod plugin_bridge;
+mod plugin_catalogue;
+mod plugin_host;
+mod recorder;
 mod rnnoise;
 mod scarlett;
+mod signal_generator;
+mod transport;
 mod vst_host;
+mod vst_preset;
 
 use eframe::egui;
 use std::sync::{Arc, Mutex};
@@ -24,6 +43,25 @@ fn main() {
         .format_timestamp_millis()
         .init();
 
+    // `phantomlink process ...` runs a file through the mixer chain and exits
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).is_some_and(|command| command == "process") {
+        if let Err(e) = offline::run_cli(&args[2..]) {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Sandboxed plugins run in child copies of this executable
+    if let Some(command) = args.get(1).filter(|c| plugin_bridge::is_child_command(c)) {
+        if let Err(e) = plugin_bridge::run_child(command, &args[2..]) {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     log::info!("PhantomLink v{} starting...", env!("CARGO_PKG_VERSION"));
 
     // Initialize GPU manager
@@ -51,6 +89,19 @@ fn main() {
 
     // Initialize audio engine
     let audio_engine = Arc::new(Mutex::new(audio::AudioEngine::new()));
+    if let Ok(mut engine) = audio_engine.lock() {
+        let saved = config::AppConfig::load();
+        if let Err(e) = engine.set_replay_buffer(&saved.recorder) {
+            log::warn!("Failed to configure replay buffer: {}", e);
+        }
+        for (bus, agc) in saved.bus_agc {
+            if let Err(e) = engine.set_bus_agc(&bus, agc) {
+                log::warn!("Failed to configure AGC: {}", e);
+            }
+        }
+        engine.set_latency_calibration(saved.latency_calibration);
+        engine.set_plugin_sandbox(saved.plugin_sandbox);
+    }
 
     // Get GhostWave reference for IPC
     let ghostwave = audio_engine
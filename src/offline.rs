@@ -0,0 +1,670 @@
+//! Offline file processing.
+//!
+//! Runs a recorded WAV or FLAC file through the same `ChannelProcessor` chain
+//! the live mixer uses (gain, denoiser, VST, dynamics) as fast as the CPU
+//! allows, with no audio device involved. Each file channel gets its own
+//! strip, denoiser delay is trimmed so the output lines up with the input,
+//! and adaptive denoising is off so the result does not depend on machine
+//! speed. This is the `phantomlink process` command.
+
+use crate::advanced_denoising::{DenoisingMode, NoiseProfile};
+use crate::audio::{ChannelDenoiser, ChannelProcessor};
+use crate::audio_effects::{ChannelEffects, ChannelEffectsConfig};
+use crate::audio_file::{AudioData, AudioFileWriter, AudioFormat, AudioSpec, read_audio_file};
+use crate::config::{AppConfig, MicrophonePreset};
+use crate::loudness::LoudnessMeter;
+use crate::onnx_denoiser::OnnxModelConfig;
+use crate::plugin_host::{self, PluginFormat};
+use anyhow::{Context, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Block size of the live engine, used unless another is requested
+const DEFAULT_BLOCK_SIZE: usize = 1024;
+
+/// Window for the noise-floor estimate
+const NOISE_WINDOW_SECONDS: f32 = 0.05;
+
+/// The noise floor is the level this share of windows stays below
+const NOISE_FLOOR_PERCENTILE: f32 = 0.1;
+
+/// Level reported for digital silence
+const SILENCE_DBFS: f32 = -120.0;
+
+const USAGE: &str = "\
+Usage: phantomlink process <input> <output> [options]
+
+Runs a WAV or FLAC file through a mixer channel strip faster than realtime.
+
+Options:
+  --scene <name>       podcast, stream, music or raw (default: podcast)
+  --denoiser <name>    off, rnnoise, advanced or ghostwave (overrides the scene)
+  --mode <name>        basic, enhanced or maximum advanced denoising
+  --mic <preset>       microphone preset for the gate and compressor; its
+                       saved noise profile is loaded too (e.g. shure-sm7b)
+  --vst <path>         plugin to run after the denoiser: VST2, CLAP (.clap)
+                       or LV2 (.lv2 bundle)
+  --gain <dB>          input gain (default: 0)
+  --bits <n>           output bit depth: 16, 24 or 32 (WAV float only)
+  --buffer <samples>   processing block size (default: 1024)";
+
+/// Built-in processing chains for common material
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProcessingScene {
+    /// Every denoising tier, gate, gentle compression and a limiter
+    #[default]
+    Podcast,
+    /// RNNoise with broadcast-style compression, as for live streaming
+    Stream,
+    /// No denoising, limiter only, to keep dynamics intact
+    Music,
+    /// No processing at all beyond gain
+    Raw,
+}
+
+impl ProcessingScene {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Podcast => "podcast",
+            Self::Stream => "stream",
+            Self::Music => "music",
+            Self::Raw => "raw",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|scene| scene.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn all() -> &'static [ProcessingScene] {
+        &[Self::Podcast, Self::Stream, Self::Music, Self::Raw]
+    }
+
+    pub fn denoiser(&self) -> ChannelDenoiser {
+        match self {
+            Self::Podcast => ChannelDenoiser::Advanced,
+            Self::Stream => ChannelDenoiser::Rnnoise,
+            Self::Music | Self::Raw => ChannelDenoiser::Off,
+        }
+    }
+
+    pub fn denoising_mode(&self) -> DenoisingMode {
+        match self {
+            Self::Podcast => DenoisingMode::Maximum,
+            _ => DenoisingMode::Basic,
+        }
+    }
+
+    pub fn effects(&self) -> ChannelEffectsConfig {
+        match self {
+            Self::Podcast => ChannelEffectsConfig {
+                gate_enabled: true,
+                gate_threshold_db: -45.0,
+                compressor_enabled: true,
+                compressor_threshold_db: -20.0,
+                compressor_ratio: 3.0,
+                limiter_enabled: true,
+                limiter_ceiling_db: -1.0,
+                limiter_lookahead_ms: 5.0,
+            },
+            Self::Stream => ChannelEffectsConfig {
+                gate_enabled: true,
+                gate_threshold_db: -40.0,
+                compressor_enabled: true,
+                compressor_threshold_db: -18.0,
+                compressor_ratio: 4.0,
+                limiter_enabled: true,
+                limiter_ceiling_db: -0.3,
+                limiter_lookahead_ms: 2.0,
+            },
+            Self::Music => ChannelEffectsConfig::default(),
+            Self::Raw => ChannelEffectsConfig {
+                limiter_enabled: false,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// How a file is processed
+#[derive(Debug, Clone)]
+pub struct OfflineOptions {
+    pub scene: ProcessingScene,
+    /// Overrides the scene's denoiser
+    pub denoiser: Option<ChannelDenoiser>,
+    /// Overrides the scene's advanced denoising mode
+    pub mode: Option<DenoisingMode>,
+    /// Takes gate and compressor settings from a microphone preset
+    pub microphone: Option<MicrophonePreset>,
+    pub noise_profile: Option<NoiseProfile>,
+    pub deep_learning_model: Option<OnnxModelConfig>,
+    pub vst_plugin: Option<PathBuf>,
+    pub gain_db: f32,
+    pub block_size: usize,
+    /// Output bit depth; defaults to the input's where the format allows
+    pub bits_per_sample: Option<u16>,
+}
+
+impl Default for OfflineOptions {
+    fn default() -> Self {
+        Self {
+            scene: ProcessingScene::default(),
+            denoiser: None,
+            mode: None,
+            microphone: None,
+            noise_profile: None,
+            deep_learning_model: None,
+            vst_plugin: None,
+            gain_db: 0.0,
+            block_size: DEFAULT_BLOCK_SIZE,
+            bits_per_sample: None,
+        }
+    }
+}
+
+impl OfflineOptions {
+    fn effects(&self) -> ChannelEffectsConfig {
+        let mut effects = self.scene.effects();
+        if let Some(preset) = self.microphone {
+            effects.gate_threshold_db = preset.gate_threshold_db();
+            effects.compressor_threshold_db = preset.compressor_threshold_db();
+            effects.compressor_ratio = preset.compressor_ratio();
+        }
+        effects
+    }
+
+    /// A channel strip set up for unity volume, centre pan and this chain
+    fn build_processor(&self, sample_rate: u32) -> Result<ChannelProcessor> {
+        let mut channel = ChannelProcessor::new();
+        channel.volume = 1.0;
+        channel.pan = 0.0;
+        channel.gain = self.gain_db;
+        channel.set_sample_rate(sample_rate);
+        channel.set_adaptive_denoising(false);
+        channel.effects = ChannelEffects::new(sample_rate as f32);
+        channel.configure_effects(&self.effects());
+
+        channel.set_deep_learning_model(self.deep_learning_model.clone())?;
+        if let Some(ref profile) = self.noise_profile {
+            channel.set_noise_profile(profile.clone())?;
+        }
+        channel.set_denoising_mode(self.mode.clone().unwrap_or(self.scene.denoising_mode()))?;
+        channel.set_denoiser(self.denoiser.unwrap_or(self.scene.denoiser()))?;
+
+        if let Some(ref path) = self.vst_plugin {
+            let format = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("clap") => PluginFormat::Clap,
+                Some("lv2") => PluginFormat::Lv2,
+                _ => PluginFormat::Vst2,
+            };
+            let plugin = plugin_host::load_plugin_path(format, path, None, sample_rate as f32)
+                .with_context(|| format!("Failed to load plugin {}", path.display()))?;
+            channel.plugin = Some(plugin);
+        }
+        Ok(channel)
+    }
+}
+
+/// Levels of one side of the processing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelSummary {
+    pub peak_dbfs: f32,
+    /// Integrated loudness; None for silence
+    pub integrated_lufs: Option<f32>,
+    /// Level of the quiet parts between speech
+    pub noise_floor_dbfs: f32,
+}
+
+impl LevelSummary {
+    pub fn measure(audio: &AudioData) -> Self {
+        let channels = audio.spec.channels.max(1) as usize;
+        let peak = audio.samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+
+        let mut loudness = LoudnessMeter::new(audio.spec.sample_rate, channels);
+        loudness.process_interleaved(&audio.samples);
+
+        let window =
+            ((audio.spec.sample_rate as f32 * NOISE_WINDOW_SECONDS) as usize).max(1) * channels;
+        let mut window_levels: Vec<f32> = audio
+            .samples
+            .chunks(window)
+            .map(|chunk| {
+                let mean_square = chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32;
+                to_dbfs(mean_square.sqrt())
+            })
+            .collect();
+        window_levels.sort_by(f32::total_cmp);
+        let noise_floor_dbfs = window_levels
+            .get((window_levels.len() as f32 * NOISE_FLOOR_PERCENTILE) as usize)
+            .copied()
+            .unwrap_or(SILENCE_DBFS);
+
+        Self {
+            peak_dbfs: to_dbfs(peak),
+            integrated_lufs: loudness.integrated_lufs(),
+            noise_floor_dbfs,
+        }
+    }
+}
+
+fn to_dbfs(level: f32) -> f32 {
+    if level > 0.0 {
+        (20.0 * level.log10()).max(SILENCE_DBFS)
+    } else {
+        SILENCE_DBFS
+    }
+}
+
+/// What processing a file did
+#[derive(Debug, Clone)]
+pub struct ProcessSummary {
+    pub spec: AudioSpec,
+    pub duration_secs: f32,
+    pub processing_secs: f32,
+    /// Denoiser and limiter delay trimmed from the output, in samples
+    pub latency_samples: usize,
+    pub input: LevelSummary,
+    pub output: LevelSummary,
+}
+
+impl ProcessSummary {
+    /// How much the noise floor dropped
+    pub fn noise_reduction_db(&self) -> f32 {
+        self.input.noise_floor_dbfs - self.output.noise_floor_dbfs
+    }
+
+    /// Seconds of audio processed per second of wall-clock time
+    pub fn realtime_factor(&self) -> f32 {
+        self.duration_secs / self.processing_secs.max(1e-6)
+    }
+}
+
+impl fmt::Display for ProcessSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lufs = |value: Option<f32>| match value {
+            Some(lufs) => format!("{:.1} LUFS", lufs),
+            None => "silent".to_string(),
+        };
+
+        writeln!(
+            f,
+            "  {:.1} s @ {} Hz, {} ch in {:.2} s ({:.0}x realtime)",
+            self.duration_secs,
+            self.spec.sample_rate,
+            self.spec.channels,
+            self.processing_secs,
+            self.realtime_factor()
+        )?;
+        if self.latency_samples > 0 {
+            writeln!(
+                f,
+                "  Processing delay of {} samples trimmed",
+                self.latency_samples
+            )?;
+        }
+        writeln!(
+            f,
+            "  Peak:        {:.1} dBFS -> {:.1} dBFS",
+            self.input.peak_dbfs, self.output.peak_dbfs
+        )?;
+        writeln!(
+            f,
+            "  Loudness:    {} -> {}",
+            lufs(self.input.integrated_lufs),
+            lufs(self.output.integrated_lufs)
+        )?;
+        write!(
+            f,
+            "  Noise floor: {:.1} dBFS -> {:.1} dBFS ({:.1} dB reduction)",
+            self.input.noise_floor_dbfs,
+            self.output.noise_floor_dbfs,
+            self.noise_reduction_db()
+        )
+    }
+}
+
+/// Run decoded audio through one channel strip per file channel.
+/// The output has the input's length with the strip's delay removed.
+pub fn process_audio(audio: &AudioData, options: &OfflineOptions) -> Result<(AudioData, usize)> {
+    let channels = audio.spec.channels.max(1) as usize;
+    let frames = audio.frames();
+    let block_size = options.block_size.max(1);
+    let dt = block_size as f32 / audio.spec.sample_rate.max(1) as f32;
+
+    let mut latency = 0;
+    let mut planar_output = Vec::with_capacity(channels);
+    for channel_idx in 0..channels {
+        let mut channel = options.build_processor(audio.spec.sample_rate)?;
+        latency = channel.latency_samples();
+
+        let input: Vec<f32> = audio
+            .samples
+            .iter()
+            .skip(channel_idx)
+            .step_by(channels)
+            .copied()
+            .chain(std::iter::repeat_n(0.0, latency))
+            .collect();
+
+        let mut output = Vec::with_capacity(input.len());
+        for block in input.chunks(block_size) {
+            let (stereo, _) = channel.process(block, dt);
+            // Centre pan at unity volume: both sides carry the mono result
+            output.extend(stereo.iter().step_by(2));
+        }
+        output.drain(..latency.min(output.len()));
+        output.truncate(frames);
+        planar_output.push(output);
+    }
+
+    let mut samples = Vec::with_capacity(frames * channels);
+    for frame in 0..frames {
+        for channel in &planar_output {
+            samples.push(channel[frame]);
+        }
+    }
+
+    Ok((
+        AudioData {
+            spec: audio.spec,
+            samples,
+        },
+        latency,
+    ))
+}
+
+/// Process `input` into `output`, choosing formats from the file extensions
+pub fn process_file(
+    input: &Path,
+    output: &Path,
+    options: &OfflineOptions,
+) -> Result<ProcessSummary> {
+    let audio = read_audio_file(input)?;
+    let format = AudioFormat::from_path(output)?;
+
+    let bits_per_sample = match options.bits_per_sample {
+        Some(bits) => bits,
+        None if format.supports_bits(audio.spec.bits_per_sample) => audio.spec.bits_per_sample,
+        None => 24,
+    };
+    let out_spec = AudioSpec {
+        bits_per_sample,
+        ..audio.spec
+    };
+
+    let start = std::time::Instant::now();
+    let (processed, latency_samples) = process_audio(&audio, options)?;
+    let processing_secs = start.elapsed().as_secs_f32();
+
+    let mut writer = AudioFileWriter::create(output, out_spec)?;
+    writer.write(&processed.samples)?;
+    writer.finalize()?;
+
+    Ok(ProcessSummary {
+        spec: out_spec,
+        duration_secs: audio.duration_secs(),
+        processing_secs,
+        latency_samples,
+        input: LevelSummary::measure(&audio),
+        output: LevelSummary::measure(&processed),
+    })
+}
+
+fn parse_mode(name: &str) -> Option<DenoisingMode> {
+    match name.to_ascii_lowercase().as_str() {
+        "basic" => Some(DenoisingMode::Basic),
+        "enhanced" => Some(DenoisingMode::Enhanced),
+        "maximum" => Some(DenoisingMode::Maximum),
+        _ => None,
+    }
+}
+
+/// Parse `process` arguments into input, output and options
+fn parse_args(args: &[String]) -> Result<(PathBuf, PathBuf, OfflineOptions)> {
+    let mut options = OfflineOptions::default();
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            paths.push(PathBuf::from(arg));
+            continue;
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--scene" => {
+                options.scene = ProcessingScene::from_name(value)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown scene: {}", value))?;
+            }
+            "--denoiser" => {
+                options.denoiser = Some(
+                    ChannelDenoiser::from_name(value)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown denoiser: {}", value))?,
+                );
+            }
+            "--mode" => {
+                options.mode = Some(
+                    parse_mode(value)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown denoising mode: {}", value))?,
+                );
+            }
+            "--mic" => {
+                options.microphone = Some(
+                    MicrophonePreset::from_name(value)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown microphone preset: {}", value))?,
+                );
+            }
+            "--vst" => options.vst_plugin = Some(PathBuf::from(value)),
+            "--gain" => {
+                options.gain_db = value
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid gain: {}", value))?;
+            }
+            "--bits" => {
+                options.bits_per_sample = Some(
+                    value
+                        .parse()
+                        .map_err(|_| anyhow::anyhow!("Invalid bit depth: {}", value))?,
+                );
+            }
+            "--buffer" => {
+                options.block_size = value
+                    .parse()
+                    .ok()
+                    .filter(|&size| size > 0)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid buffer size: {}", value))?;
+            }
+            _ => anyhow::bail!("Unknown option: {}", arg),
+        }
+    }
+
+    match <[PathBuf; 2]>::try_from(paths) {
+        Ok([input, output]) => Ok((input, output, options)),
+        Err(_) => anyhow::bail!("Expected an input and an output file"),
+    }
+}
+
+/// Entry point for `phantomlink process ...`; `args` follow the subcommand
+pub fn run_cli(args: &[String]) -> Result<()> {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let (input, output, mut options) =
+        parse_args(args).map_err(|e| anyhow::anyhow!("{}\n\n{}", e, USAGE))?;
+
+    // Use the same noise profile and model as the live mixer
+    let config = AppConfig::load();
+    if let Some(preset) = options.microphone {
+        options.noise_profile = config.get_noise_profile(preset).cloned();
+    }
+    options.deep_learning_model = config.deep_learning_model;
+
+    let summary = process_file(&input, &output, &options)?;
+    println!(
+        "Processed {} -> {} ({} scene)",
+        input.display(),
+        output.display(),
+        options.scene.name()
+    );
+    println!("{}", summary);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mono(samples: Vec<f32>) -> AudioData {
+        AudioData {
+            spec: AudioSpec {
+                sample_rate: 48000,
+                channels: 1,
+                bits_per_sample: 24,
+            },
+            samples,
+        }
+    }
+
+    #[test]
+    fn test_raw_scene_is_transparent() {
+        let samples: Vec<f32> = (0..10_000).map(|i| 0.3 * (i as f32 * 0.02).sin()).collect();
+        let audio = AudioData {
+            spec: AudioSpec {
+                sample_rate: 44100,
+                channels: 2,
+                bits_per_sample: 16,
+            },
+            samples: samples.clone(),
+        };
+        let options = OfflineOptions {
+            scene: ProcessingScene::Raw,
+            block_size: 333,
+            ..Default::default()
+        };
+
+        let (processed, latency) = process_audio(&audio, &options).unwrap();
+        assert_eq!(latency, 0);
+        assert_eq!(processed.samples, samples);
+    }
+
+    #[test]
+    fn test_denoiser_delay_is_trimmed() {
+        // An impulse must come out where it went in, whatever the denoiser delay
+        let mut samples = vec![0.0; 48000];
+        samples[24000] = 0.5;
+        let options = OfflineOptions {
+            scene: ProcessingScene::Raw,
+            denoiser: Some(ChannelDenoiser::Advanced),
+            mode: Some(DenoisingMode::Custom {
+                use_rnnoise: false,
+                use_deep_learning: false,
+                use_spectral: true,
+            }),
+            ..Default::default()
+        };
+
+        let (processed, _) = process_audio(&mono(samples.clone()), &options).unwrap();
+        assert_eq!(processed.samples.len(), samples.len());
+        let peak = processed
+            .samples
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .unwrap()
+            .0;
+        assert_eq!(peak, 24000);
+    }
+
+    #[test]
+    fn test_podcast_scene_lowers_noise_floor() {
+        // Speech-like bursts over a steady hiss
+        let mut seed = 3u32;
+        let samples: Vec<f32> = (0..48000 * 4)
+            .map(|i| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let hiss = ((seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5) * 0.02;
+                let talking = (i / 24000) % 2 == 1;
+                let voice = if talking {
+                    0.3 * (i as f32 * 2.0 * std::f32::consts::PI * 220.0 / 48000.0).sin()
+                } else {
+                    0.0
+                };
+                voice + hiss
+            })
+            .collect();
+        let audio = mono(samples);
+
+        let (processed, _) = process_audio(&audio, &OfflineOptions::default()).unwrap();
+        let before = LevelSummary::measure(&audio);
+        let after = LevelSummary::measure(&processed);
+        assert!(
+            before.noise_floor_dbfs - after.noise_floor_dbfs > 6.0,
+            "noise floor {:.1} -> {:.1} dBFS",
+            before.noise_floor_dbfs,
+            after.noise_floor_dbfs
+        );
+        assert!(after.peak_dbfs <= -0.9);
+    }
+
+    #[test]
+    fn test_process_file_writes_output_and_summary() {
+        let dir = std::env::temp_dir().join("phantomlink_test_offline");
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("in.wav");
+        let output = dir.join("out.flac");
+
+        let samples: Vec<f32> = (0..48000).map(|i| 0.1 * (i as f32 * 0.13).sin()).collect();
+        let audio = mono(samples);
+        let mut writer = AudioFileWriter::create(&input, audio.spec).unwrap();
+        writer.write(&audio.samples).unwrap();
+        writer.finalize().unwrap();
+
+        let options = OfflineOptions {
+            scene: ProcessingScene::Raw,
+            ..Default::default()
+        };
+        let summary = process_file(&input, &output, &options).unwrap();
+        assert_eq!(summary.spec.bits_per_sample, 24);
+        assert!((summary.input.peak_dbfs - summary.output.peak_dbfs).abs() < 0.01);
+        assert!(summary.noise_reduction_db().abs() < 0.01);
+        assert!(summary.to_string().contains("LUFS"));
+
+        let written = read_audio_file(&output).unwrap();
+        assert_eq!(written.frames(), 48000);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        // "sm7b" is ambiguous without the brand
+        let args: Vec<String> = ["in.wav", "--scene", "stream", "out.flac", "--mic", "sm7b"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(parse_args(&args).is_err());
+
+        let args: Vec<String> = [
+            "in.wav", "--scene", "stream", "out.flac", "--mode", "maximum", "--gain", "-3",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let (input, output, options) = parse_args(&args).unwrap();
+        assert_eq!(input, PathBuf::from("in.wav"));
+        assert_eq!(output, PathBuf::from("out.flac"));
+        assert_eq!(options.scene, ProcessingScene::Stream);
+        assert_eq!(options.mode, Some(DenoisingMode::Maximum));
+        assert_eq!(options.gain_db, -3.0);
+
+        assert!(parse_args(&["in.wav".to_string()]).is_err());
+    }
+}
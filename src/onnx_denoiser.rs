@@ -0,0 +1,317 @@
+//! ONNX deep-learning denoiser backend.
+//!
+//! Runs a user-supplied time-domain denoising model on the CPU through tract,
+//! filling the Deep Learning tier of the advanced denoising system on machines
+//! without an NVIDIA GPU. The model receives fixed-size frames of mono audio
+//! and must return a frame of the same length; the tensor shape around the
+//! frame is configurable so common export layouts work without re-exporting.
+//!
+//! Inference is only compiled in with the `onnx` feature. Without it, loading
+//! a model fails with an explanatory error and the tier stays empty.
+
+#![allow(dead_code)] // Complete ONNX backend API
+
+use crate::advanced_denoising::{DeepLearningDenoiser, ModelInfo};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+#[cfg(feature = "onnx")]
+use anyhow::Context;
+#[cfg(feature = "onnx")]
+use std::sync::Arc;
+#[cfg(feature = "onnx")]
+use tract_onnx::prelude::*;
+
+/// How a frame of samples is shaped into a model tensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TensorLayout {
+    /// `[time]`
+    Flat,
+    /// `[batch, time]`
+    #[default]
+    BatchTime,
+    /// `[batch, channel, time]`
+    BatchChannelTime,
+    /// `[batch, time, channel]`
+    BatchTimeChannel,
+}
+
+impl TensorLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Flat => "[T]",
+            Self::BatchTime => "[1, T]",
+            Self::BatchChannelTime => "[1, 1, T]",
+            Self::BatchTimeChannel => "[1, T, 1]",
+        }
+    }
+
+    /// Tensor shape holding one frame of `frame_size` samples
+    pub fn shape(&self, frame_size: usize) -> Vec<usize> {
+        match self {
+            Self::Flat => vec![frame_size],
+            Self::BatchTime => vec![1, frame_size],
+            Self::BatchChannelTime => vec![1, 1, frame_size],
+            Self::BatchTimeChannel => vec![1, frame_size, 1],
+        }
+    }
+
+    pub fn all() -> &'static [TensorLayout] {
+        &[
+            Self::Flat,
+            Self::BatchTime,
+            Self::BatchChannelTime,
+            Self::BatchTimeChannel,
+        ]
+    }
+}
+
+/// A user-supplied ONNX model and the shape of its audio input and output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OnnxModelConfig {
+    pub model_path: PathBuf,
+    /// Samples per inference
+    pub frame_size: usize,
+    /// Rate the model was trained at; the stream must run at the same rate
+    pub sample_rate: u32,
+    #[serde(default)]
+    pub input_layout: TensorLayout,
+    #[serde(default)]
+    pub output_layout: TensorLayout,
+}
+
+impl OnnxModelConfig {
+    pub fn new(model_path: impl Into<PathBuf>, frame_size: usize, sample_rate: u32) -> Self {
+        Self {
+            model_path: model_path.into(),
+            frame_size,
+            sample_rate,
+            input_layout: TensorLayout::default(),
+            output_layout: TensorLayout::default(),
+        }
+    }
+
+    /// Check the settings and the model file, returning the file size in bytes
+    fn validate(&self) -> Result<u64> {
+        if self.frame_size == 0 {
+            anyhow::bail!("Model frame size must be at least one sample");
+        }
+        if self.sample_rate == 0 {
+            anyhow::bail!("Model sample rate must be non-zero");
+        }
+        let metadata = std::fs::metadata(&self.model_path).map_err(|e| {
+            anyhow::anyhow!("Cannot read model {}: {}", self.model_path.display(), e)
+        })?;
+        Ok(metadata.len())
+    }
+}
+
+/// CPU ONNX denoiser that streams arbitrary buffer sizes through fixed frames
+pub struct OnnxDenoiser {
+    config: OnnxModelConfig,
+    info: ModelInfo,
+    /// Stateless once optimized, so instances of the same model share it
+    #[cfg(feature = "onnx")]
+    model: Arc<TypedRunnableModel<TypedModel>>,
+    /// Samples waiting for a full frame
+    input_fifo: VecDeque<f32>,
+    /// Denoised samples waiting to be handed back
+    output_fifo: VecDeque<f32>,
+}
+
+impl OnnxDenoiser {
+    /// Load and optimize the model described by `config`
+    #[cfg(feature = "onnx")]
+    pub fn load(config: OnnxModelConfig) -> Result<Self> {
+        let file_size = config.validate()?;
+
+        let onnx = tract_onnx::onnx();
+        let proto = onnx
+            .proto_model_for_path(&config.model_path)
+            .with_context(|| format!("Failed to read {}", config.model_path.display()))?;
+        let version = if proto.model_version > 0 {
+            proto.model_version.to_string()
+        } else {
+            "unknown".to_string()
+        };
+
+        let input_shape = config.input_layout.shape(config.frame_size);
+        let model = onnx
+            .model_for_proto_model(&proto)
+            .context("Failed to parse ONNX model")?
+            .with_input_fact(0, f32::fact(input_shape.clone()).into())
+            .with_context(|| format!("Model does not accept an f32 {:?} input", input_shape))?
+            .into_optimized()
+            .context("Failed to optimize ONNX model")?
+            .into_runnable()
+            .context("Failed to prepare ONNX model")?;
+
+        let info = Self::model_info(&config, version, file_size);
+        log::info!(
+            "Loaded ONNX denoiser {} ({} samples @ {} Hz)",
+            info.name,
+            config.frame_size,
+            config.sample_rate
+        );
+
+        Ok(Self::with_parts(config, info, Arc::new(model)))
+    }
+
+    /// Without the `onnx` feature there is no inference engine to load into
+    #[cfg(not(feature = "onnx"))]
+    pub fn load(config: OnnxModelConfig) -> Result<Self> {
+        config.validate()?;
+        anyhow::bail!(
+            "Cannot load {}: PhantomLink was built without the `onnx` feature",
+            config.model_path.display()
+        )
+    }
+
+    /// Another instance of the loaded model with its own, empty buffers, so
+    /// every channel can run it without reading the file again
+    pub fn instance(&self) -> Self {
+        Self::with_parts(
+            self.config.clone(),
+            self.info.clone(),
+            #[cfg(feature = "onnx")]
+            Arc::clone(&self.model),
+        )
+    }
+
+    fn with_parts(
+        config: OnnxModelConfig,
+        info: ModelInfo,
+        #[cfg(feature = "onnx")] model: Arc<TypedRunnableModel<TypedModel>>,
+    ) -> Self {
+        let mut output_fifo = VecDeque::with_capacity(config.frame_size * 3);
+        // Prime the output so a full frame is ready before the first one is run
+        output_fifo.extend(std::iter::repeat_n(0.0, config.frame_size));
+
+        Self {
+            input_fifo: VecDeque::with_capacity(config.frame_size * 2),
+            output_fifo,
+            config,
+            info,
+            #[cfg(feature = "onnx")]
+            model,
+        }
+    }
+
+    fn model_info(config: &OnnxModelConfig, version: String, file_size: u64) -> ModelInfo {
+        let name = config
+            .model_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "onnx".to_string());
+
+        ModelInfo {
+            name,
+            version,
+            size_mb: file_size as f32 / (1024.0 * 1024.0),
+            target_latency_ms: config.frame_size as f32 * 1000.0 / config.sample_rate as f32,
+            supported_sample_rates: vec![config.sample_rate],
+        }
+    }
+
+    pub fn config(&self) -> &OnnxModelConfig {
+        &self.config
+    }
+
+    /// Delay added by frame buffering, in samples
+    pub fn latency_samples(&self) -> usize {
+        self.config.frame_size
+    }
+
+    /// Run the model on exactly one frame
+    #[cfg(feature = "onnx")]
+    fn infer(&self, frame: &[f32]) -> Result<Vec<f32>> {
+        let input_shape = self.config.input_layout.shape(frame.len());
+        let input = Tensor::from_shape(&input_shape, frame)?;
+        let outputs = self.model.run(tvec!(input.into()))?;
+        let output = outputs
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("ONNX model produced no outputs"))?;
+
+        let expected = self.config.output_layout.shape(frame.len());
+        if output.shape() != expected.as_slice() {
+            anyhow::bail!(
+                "ONNX model output shape {:?} does not match the configured {:?}",
+                output.shape(),
+                expected
+            );
+        }
+        Ok(output.as_slice::<f32>()?.to_vec())
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    fn infer(&self, _frame: &[f32]) -> Result<Vec<f32>> {
+        anyhow::bail!("PhantomLink was built without the `onnx` feature")
+    }
+}
+
+impl DeepLearningDenoiser for OnnxDenoiser {
+    fn process(&mut self, input: &[f32]) -> Result<Vec<f32>> {
+        let frame_size = self.config.frame_size;
+        self.input_fifo.extend(input.iter().copied());
+
+        while self.input_fifo.len() >= frame_size {
+            let frame: Vec<f32> = self.input_fifo.drain(..frame_size).collect();
+            let denoised = self.infer(&frame)?;
+            self.output_fifo
+                .extend(denoised.iter().map(|s| s.clamp(-1.0, 1.0)));
+        }
+
+        let available = self.output_fifo.len().min(input.len());
+        let mut output: Vec<f32> = self.output_fifo.drain(..available).collect();
+        output.resize(input.len(), 0.0);
+        Ok(output)
+    }
+
+    fn get_latency(&self) -> f32 {
+        self.info.target_latency_ms
+    }
+
+    fn is_gpu_accelerated(&self) -> bool {
+        false
+    }
+
+    fn get_model_info(&self) -> ModelInfo {
+        self.info.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tensor_layout_shapes() {
+        assert_eq!(TensorLayout::Flat.shape(480), vec![480]);
+        assert_eq!(TensorLayout::BatchTime.shape(480), vec![1, 480]);
+        assert_eq!(TensorLayout::BatchChannelTime.shape(480), vec![1, 1, 480]);
+        assert_eq!(TensorLayout::BatchTimeChannel.shape(480), vec![1, 480, 1]);
+    }
+
+    #[test]
+    fn test_load_rejects_missing_model() {
+        let config = OnnxModelConfig::new("/nonexistent/denoiser.onnx", 480, 48000);
+        let err = OnnxDenoiser::load(config).err().unwrap();
+        assert!(err.to_string().contains("Cannot read model"));
+    }
+
+    #[test]
+    fn test_load_rejects_empty_frame() {
+        let config = OnnxModelConfig::new("/nonexistent/denoiser.onnx", 0, 48000);
+        assert!(OnnxDenoiser::load(config).is_err());
+    }
+
+    #[test]
+    fn test_model_config_layout_defaults() {
+        let json = r#"{"model_path":"dtln.onnx","frame_size":512,"sample_rate":16000}"#;
+        let config: OnnxModelConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.input_layout, TensorLayout::BatchTime);
+        assert_eq!(config.output_layout, TensorLayout::BatchTime);
+    }
+}
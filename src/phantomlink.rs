@@ -1,36 +1,6 @@
-use std::fs;
-use std::path::PathBuf;
-
-// Re-export the VST types from vst_host module
-pub use crate::vst_host::{VstPluginInfo, VstScanner};
-
-pub fn find_vst_plugins() -> Vec<PathBuf> {
-    let dirs = vec![
-        dirs::home_dir().map(|h| h.join(".vst")),
-        Some(PathBuf::from("/usr/lib/vst")),
-        Some(PathBuf::from("/usr/local/lib/vst")),
-        Some(PathBuf::from("/usr/lib/lxvst")),
-        Some(PathBuf::from("/usr/local/lib/lxvst")),
-    ];
-    let mut plugins = Vec::new();
-    for dir in dirs.into_iter().flatten() {
-        if let Ok(entries) = fs::read_dir(&dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() && path.extension().is_some_and(|ext| ext == "so") {
-                    plugins.push(path);
-                }
-            }
-        }
-    }
-    plugins
-}
-
-pub fn scan_vst_plugins() -> Result<Vec<VstPluginInfo>, Box<dyn std::error::Error>> {
-    let mut scanner = VstScanner::new();
-    scanner.scan()?;
-    Ok(scanner.get_plugins().to_vec())
-}
+// Re-export the plugin types from the host modules
+pub use crate::plugin_host::{PluginFormat, scan_plugins};
+pub use crate::vst_host::VstPluginInfo;
 
 // Audio routing configuration
 #[derive(Debug, Clone)]
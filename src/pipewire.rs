@@ -70,6 +70,24 @@ impl PipeWirePreset {
         }
     }
 
+    /// Look a preset up by its display name, as stored in the config
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|preset| preset.name() == name)
+    }
+
+    /// Round trip a loopback measurement should see: one quantum each way
+    /// through PipeWire. A custom buffer size replaces the preset's quantum.
+    pub fn round_trip_ms(&self, custom_buffer: Option<u32>, sample_rate: u32) -> f32 {
+        let one_way = match custom_buffer {
+            Some(buffer) => buffer as f32 * 1000.0 / sample_rate.max(1) as f32,
+            None => self.latency_ms(),
+        };
+        2.0 * one_way
+    }
+
     pub fn all() -> &'static [PipeWirePreset] {
         &[
             Self::Gaming,
@@ -533,6 +551,23 @@ mod tests {
         assert_eq!(PipeWirePreset::Recording.buffer_size(), 1024);
     }
 
+    #[test]
+    fn test_round_trip_matches_saved_config() {
+        for preset in PipeWirePreset::all() {
+            assert_eq!(PipeWirePreset::from_name(preset.name()), Some(*preset));
+        }
+        assert_eq!(PipeWirePreset::Streaming.round_trip_ms(None, 48000), 20.0);
+
+        let config = crate::config::PipeWireConfig {
+            preset: "Custom".to_string(),
+            buffer_size: 256,
+            ..Default::default()
+        };
+        let expected = PipeWirePreset::Custom.round_trip_ms(Some(256), 48000);
+        assert_eq!(config.expected_round_trip_ms(), expected);
+        assert!((expected - 10.667).abs() < 0.01);
+    }
+
     #[test]
     fn test_virtual_device_manager() {
         let manager = VirtualDeviceManager::default();
@@ -0,0 +1,1278 @@
+//! Out-of-process plugin sandbox.
+//!
+//! In bridge mode each plugin runs in a child copy of PhantomLink, so a
+//! plugin that segfaults takes down only its own process:
+//! - Audio moves through a shared-memory buffer, one block per request,
+//!   with the child working one buffer behind the mixer
+//! - Requests and replies are JSON lines over the child's stdin/stdout
+//! - A crash or hang is reported as a [`BridgeEvent`]; the plugin is
+//!   restarted with its parameters restored, or bypassed, per [`CrashPolicy`]
+//!
+//! Scanning uses the same children: each library is opened in a throwaway
+//! process, so a broken `.so` only costs that one entry.
+
+#![allow(dead_code)] // Complete sandbox API, used by the engine, scanners and GUI
+
+use crate::clap_host;
+use crate::ladspa_host;
+use crate::lv2_host;
+use crate::plugin_host::{
+    self, BlockPipeline, ChannelLayout, ParameterChange, ParameterQuery, PluginFormat,
+    PluginProcessor, ProgramList,
+};
+use crate::transport::{self, TransportInfo};
+use crate::vst_host::{ParameterInfo, VstPluginInfo, VstScanner};
+use anyhow::{Context, Result, anyhow, bail};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, bounded};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, ExitStatus, Stdio};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+/// Subcommand that hosts one plugin for the mixer
+pub const BRIDGE_COMMAND: &str = "plugin-bridge";
+
+/// Subcommand that scans one plugin file and prints its catalogue entries
+pub const SCAN_COMMAND: &str = "plugin-scan";
+
+/// Overrides the executable used for children, e.g. when installed under
+/// another name
+const EXECUTABLE_ENV: &str = "PHANTOMLINK_BRIDGE";
+
+/// Largest block per request; a longer callback buffer plays dry
+const MAX_FRAMES: usize = 8192;
+
+/// Channels the shared memory holds each way; ports beyond are not connected
+const MAX_CHANNELS: usize = 8;
+
+/// Crashes tolerated before a plugin is bypassed for good
+const MAX_RESTARTS: u32 = 3;
+
+/// How long past its own duration a block may stay unanswered before the
+/// child counts as hung
+const PROCESS_TIMEOUT: Duration = Duration::from_millis(50);
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(500);
+const START_TIMEOUT: Duration = Duration::from_secs(10);
+const SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What to do when a sandboxed plugin dies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CrashPolicy {
+    /// Start a fresh process with the same parameters
+    #[default]
+    Restart,
+    /// Pass audio through untouched until the plugin is reloaded
+    Bypass,
+}
+
+impl CrashPolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrashPolicy::Restart => "Restart",
+            CrashPolicy::Bypass => "Bypass",
+        }
+    }
+
+    pub fn all() -> &'static [CrashPolicy] {
+        &[CrashPolicy::Restart, CrashPolicy::Bypass]
+    }
+}
+
+/// Sandbox settings, saved with the app config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// Load channel plugins in child processes
+    pub enabled: bool,
+    pub on_crash: CrashPolicy,
+}
+
+/// Something the user should hear about
+#[derive(Debug, Clone, PartialEq)]
+pub enum BridgeEvent {
+    Crashed { plugin: String, reason: String },
+    Restarted { plugin: String },
+    Bypassed { plugin: String },
+}
+
+impl BridgeEvent {
+    pub fn message(&self) -> String {
+        match self {
+            BridgeEvent::Crashed { plugin, reason } => format!("{} {}", plugin, reason),
+            BridgeEvent::Restarted { plugin } => format!("{} was restarted", plugin),
+            BridgeEvent::Bypassed { plugin } => format!("{} is bypassed after crashing", plugin),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        !matches!(self, BridgeEvent::Restarted { .. })
+    }
+}
+
+fn events() -> &'static (Sender<BridgeEvent>, Receiver<BridgeEvent>) {
+    static EVENTS: OnceLock<(Sender<BridgeEvent>, Receiver<BridgeEvent>)> = OnceLock::new();
+    EVENTS.get_or_init(|| bounded(64))
+}
+
+fn report(event: BridgeEvent) {
+    log::warn!("Plugin sandbox: {}", event.message());
+    // Nobody draining the queue must not block the audio thread
+    let _ = events().0.try_send(event);
+}
+
+/// Sandbox events since the last call, oldest first
+pub fn take_events() -> Vec<BridgeEvent> {
+    events().1.try_iter().collect()
+}
+
+/// The executable serving the child commands, None when this process
+/// cannot (test binaries, embedders); plugins then load in-process
+fn bridge_executable() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(EXECUTABLE_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    let exe = std::env::current_exe().ok()?;
+    (exe.file_stem()? == env!("CARGO_PKG_NAME")).then_some(exe)
+}
+
+/// A child process died or hung, as opposed to reporting an error. Scans
+/// that fail this way are worth blacklisting.
+#[derive(Debug)]
+pub struct ChildCrashed(pub String);
+
+impl std::fmt::Display for ChildCrashed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ChildCrashed {}
+
+fn describe_exit(status: ExitStatus) -> String {
+    match (status.signal(), status.code()) {
+        (Some(signal), _) => format!("crashed (signal {})", signal),
+        (None, Some(code)) => format!("exited with status {}", code),
+        _ => "exited".to_string(),
+    }
+}
+
+// ===== Shared memory =====
+
+/// Input and output blocks mapped into both processes
+struct SharedAudio {
+    ptr: *mut f32,
+    len: usize,
+}
+
+// Safety: the mapping is owned by this value and only reached through it
+unsafe impl Send for SharedAudio {}
+
+impl SharedAudio {
+    const LEN: usize = 2 * MAX_CHANNELS * MAX_FRAMES;
+    const BYTES: usize = Self::LEN * std::mem::size_of::<f32>();
+
+    fn create(path: &Path) -> Result<Self> {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        file.set_len(Self::BYTES as u64)?;
+        Self::map(&file)
+    }
+
+    fn open(path: &Path) -> Result<Self> {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        Self::map(&file)
+    }
+
+    fn map(file: &File) -> Result<Self> {
+        // Safety: a fresh shared mapping of a file of exactly BYTES
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                Self::BYTES,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            bail!("mmap failed: {}", std::io::Error::last_os_error());
+        }
+        Ok(Self {
+            ptr: ptr as *mut f32,
+            len: Self::LEN,
+        })
+    }
+
+    /// (input, output) blocks, each planar with channel `n` at
+    /// `n * MAX_FRAMES`
+    fn blocks(&mut self) -> (&mut [f32], &mut [f32]) {
+        let all = unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) };
+        all.split_at_mut(MAX_CHANNELS * MAX_FRAMES)
+    }
+
+    /// Copy of the first `channels` output channels
+    fn read_outputs(&self, channels: usize, frames: usize) -> Vec<Vec<f32>> {
+        let all = unsafe { std::slice::from_raw_parts(self.ptr, self.len) };
+        all[MAX_CHANNELS * MAX_FRAMES..]
+            .chunks(MAX_FRAMES)
+            .take(channels)
+            .map(|block| block[..frames].to_vec())
+            .collect()
+    }
+}
+
+impl Drop for SharedAudio {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr as *mut libc::c_void, Self::BYTES) };
+    }
+}
+
+/// A name for a new shared buffer, in tmpfs when there is one
+fn shared_memory_path() -> PathBuf {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    let dir = Path::new("/dev/shm");
+    let dir = if dir.is_dir() {
+        dir.to_path_buf()
+    } else {
+        std::env::temp_dir()
+    };
+    dir.join(format!(
+        "phantomlink-bridge-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+// ===== Protocol =====
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Request {
+    /// Process `frames` samples from the input block into the output block;
+    /// the plugin sees `transport` as the host transport
+    Process {
+        frames: usize,
+        #[serde(default)]
+        transport: Option<TransportInfo>,
+    },
+    /// Not answered
+    SetParameter {
+        index: i32,
+        value: f32,
+    },
+    GetParameterInfo {
+        index: i32,
+    },
+    GetAllParameterInfo,
+    SaveState,
+    LoadState {
+        data: Vec<u8>,
+    },
+    GetPrograms,
+    SetProgram {
+        index: usize,
+    },
+    ExportPreset {
+        bank: bool,
+    },
+    ImportPreset {
+        data: Vec<u8>,
+    },
+    SetSampleRate {
+        sample_rate: f32,
+    },
+    Shutdown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Reply {
+    Ready {
+        name: String,
+        parameters: Vec<f32>,
+        latency: usize,
+        layout: ChannelLayout,
+    },
+    Processed {
+        latency: usize,
+        /// Parameter changes the plugin made itself
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        automation: Vec<ParameterChange>,
+    },
+    ParameterInfo(Option<ParameterInfo>),
+    AllParameterInfo(Vec<ParameterInfo>),
+    State(Vec<u8>),
+    /// Parameter values after a state, program or preset change
+    Parameters(Vec<f32>),
+    Programs(ProgramList),
+    Failed(String),
+}
+
+/// The part of a plugin's ports the shared memory can carry
+fn fit_layout(layout: ChannelLayout) -> ChannelLayout {
+    let inputs = layout.inputs.min(MAX_CHANNELS);
+    ChannelLayout::new(inputs, layout.outputs.min(MAX_CHANNELS))
+        .with_sidechain(layout.sidechain.min(MAX_CHANNELS - inputs))
+}
+
+/// Whether `command` is one of the sandbox's child subcommands
+pub fn is_child_command(command: &str) -> bool {
+    command == BRIDGE_COMMAND || command == SCAN_COMMAND
+}
+
+/// Entry point of a child process
+pub fn run_child(command: &str, args: &[String]) -> Result<()> {
+    match command {
+        BRIDGE_COMMAND => run_bridge(args),
+        SCAN_COMMAND => run_scan(args),
+        other => bail!("Unknown sandbox command {}", other),
+    }
+}
+
+/// The real stdout, kept for replies; fd 1 then points at stderr, because
+/// plugins print whatever they like
+fn protocol_output() -> Result<File> {
+    let fd = unsafe { libc::dup(1) };
+    if fd < 0 || unsafe { libc::dup2(2, 1) } < 0 {
+        bail!(
+            "Failed to redirect stdout: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+fn write_line<T: Serialize>(out: &mut impl Write, message: &T) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    out.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+fn parameter_values(plugin: &dyn PluginProcessor) -> Vec<f32> {
+    (0..plugin.get_parameter_count())
+        .map(|i| plugin.get_parameter(i))
+        .collect()
+}
+
+fn run_bridge(args: &[String]) -> Result<()> {
+    let [format, path, plugin_id, sample_rate, shared] = args else {
+        bail!(
+            "usage: {} <format> <path> <plugin id or -> <sample rate> <shared memory>",
+            BRIDGE_COMMAND
+        );
+    };
+    let format = PluginFormat::from_name(format).context("Unknown plugin format")?;
+    let plugin_id = (plugin_id != "-").then_some(plugin_id.as_str());
+    let sample_rate: f32 = sample_rate.parse().context("Invalid sample rate")?;
+
+    let mut out = protocol_output()?;
+    let mut shared = SharedAudio::open(Path::new(shared))?;
+    let mut plugin =
+        match plugin_host::load_plugin_path(format, Path::new(path), plugin_id, sample_rate) {
+            Ok(plugin) => plugin,
+            Err(e) => {
+                write_line(&mut out, &Reply::Failed(format!("{:#}", e)))?;
+                return Err(e);
+            }
+        };
+    let layout = fit_layout(plugin.channel_layout());
+    write_line(
+        &mut out,
+        &Reply::Ready {
+            name: plugin.get_plugin_name(),
+            parameters: parameter_values(plugin.as_ref()),
+            latency: plugin.latency_samples(),
+            layout,
+        },
+    )?;
+
+    for line in std::io::stdin().lock().lines() {
+        let reply = match serde_json::from_str(&line?)? {
+            Request::Process { frames, transport } => {
+                if let Some(info) = transport
+                    && let Ok(mut global) = transport::global().lock()
+                {
+                    global.publish(info);
+                }
+                let (input, output) = shared.blocks();
+                let frames = frames.min(MAX_FRAMES);
+                let inputs: Vec<&[f32]> = input
+                    .chunks(MAX_FRAMES)
+                    .take(layout.inputs + layout.sidechain)
+                    .map(|channel| &channel[..frames])
+                    .collect();
+                let processed = plugin.process(&inputs);
+                for (block, channel) in output.chunks_mut(MAX_FRAMES).zip(&processed) {
+                    let count = channel.len().min(frames);
+                    block[..count].copy_from_slice(&channel[..count]);
+                }
+                Reply::Processed {
+                    latency: plugin.latency_samples(),
+                    automation: plugin.take_automation(),
+                }
+            }
+            Request::SetParameter { index, value } => {
+                plugin.set_parameter(index, value);
+                continue;
+            }
+            Request::GetParameterInfo { index } => {
+                Reply::ParameterInfo(plugin.get_parameter_info(index))
+            }
+            Request::GetAllParameterInfo => Reply::AllParameterInfo(plugin.parameter_query()()),
+            Request::SaveState => match plugin.save_state() {
+                Ok(data) => Reply::State(data),
+                Err(e) => Reply::Failed(format!("{:#}", e)),
+            },
+            Request::LoadState { data } => match plugin.load_state(&data) {
+                Ok(()) => Reply::Parameters(parameter_values(plugin.as_ref())),
+                Err(e) => Reply::Failed(format!("{:#}", e)),
+            },
+            Request::GetPrograms => Reply::Programs(plugin.programs()),
+            Request::SetProgram { index } => match plugin.set_program(index) {
+                Ok(()) => Reply::Parameters(parameter_values(plugin.as_ref())),
+                Err(e) => Reply::Failed(format!("{:#}", e)),
+            },
+            Request::ExportPreset { bank } => match plugin.export_preset(bank) {
+                Ok(data) => Reply::State(data),
+                Err(e) => Reply::Failed(format!("{:#}", e)),
+            },
+            Request::ImportPreset { data } => match plugin.import_preset(&data) {
+                Ok(()) => Reply::Parameters(parameter_values(plugin.as_ref())),
+                Err(e) => Reply::Failed(format!("{:#}", e)),
+            },
+            Request::SetSampleRate { sample_rate } => match plugin.set_sample_rate(sample_rate) {
+                Ok(()) => Reply::Parameters(parameter_values(plugin.as_ref())),
+                Err(e) => Reply::Failed(format!("{:#}", e)),
+            },
+            Request::Shutdown => break,
+        };
+        write_line(&mut out, &reply)?;
+    }
+    Ok(())
+}
+
+fn run_scan(args: &[String]) -> Result<()> {
+    let [format, path] = args else {
+        bail!("usage: {} <format> <path>", SCAN_COMMAND);
+    };
+    let format = PluginFormat::from_name(format).context("Unknown plugin format")?;
+    let mut out = protocol_output()?;
+    // Errors are reported, so only a crash or hang leaves no answer
+    let scanned = scan_in_process(format, Path::new(path)).map_err(|e| format!("{:#}", e));
+    write_line(&mut out, &scanned)
+}
+
+fn scan_in_process(format: PluginFormat, path: &Path) -> Result<Vec<VstPluginInfo>> {
+    match format {
+        PluginFormat::Vst2 => VstScanner::scan_plugin(path)
+            .map(|info| vec![info])
+            .map_err(|e| anyhow!("{}", e)),
+        PluginFormat::Clap => clap_host::scan_bundle(path),
+        PluginFormat::Ladspa => ladspa_host::scan_library(path),
+        PluginFormat::Lv2 => lv2_host::scan_bundle(path),
+    }
+}
+
+/// Catalogue entries for one plugin file, read in a child process so a
+/// crashing or hanging library cannot take the scanner with it; that case
+/// fails with [`ChildCrashed`]. LV2 bundles are only Turtle and are read here.
+pub fn scan_sandboxed(format: PluginFormat, path: &Path) -> Result<Vec<VstPluginInfo>> {
+    let executable = match bridge_executable() {
+        Some(executable) if format != PluginFormat::Lv2 => executable,
+        _ => return scan_in_process(format, path),
+    };
+    let mut child = Command::new(executable)
+        .arg(SCAN_COMMAND)
+        .arg(format.name())
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to start scanner process")?;
+
+    // Read on a thread so a hung plugin can be timed out
+    let mut stdout = child.stdout.take().context("No scanner output")?;
+    let (sender, receiver) = bounded(1);
+    std::thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stdout.read_to_string(&mut output);
+        let _ = sender.send(output);
+    });
+
+    match receiver.recv_timeout(SCAN_TIMEOUT) {
+        Ok(output) => {
+            let status = child.wait()?;
+            match serde_json::from_str::<std::result::Result<_, String>>(output.trim()) {
+                Ok(scanned) => scanned.map_err(|e| anyhow!(e)),
+                Err(_) if !status.success() => {
+                    Err(ChildCrashed(format!("Scanner {}", describe_exit(status))).into())
+                }
+                Err(e) => Err(e).context("Malformed scanner output"),
+            }
+        }
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(ChildCrashed(format!(
+                "Scanner timed out after {} s",
+                SCAN_TIMEOUT.as_secs()
+            ))
+            .into())
+        }
+    }
+}
+
+// ===== Parent side =====
+
+/// A running child and its end of the pipes
+struct BridgeChild {
+    process: Child,
+    requests: ChildStdin,
+    replies: Receiver<Reply>,
+    shared: SharedAudio,
+    /// The block the child is processing, if any
+    in_flight: Cell<Option<InFlight>>,
+    /// A finished block whose reply arrived while waiting for another
+    /// request's answer, as (outputs, latency)
+    processed: RefCell<Option<(Vec<Vec<f32>>, usize)>>,
+    /// Parameter changes reported with processed blocks, not yet taken
+    automation: RefCell<Vec<ParameterChange>>,
+}
+
+/// A block sent to the child and not yet answered
+#[derive(Debug, Clone, Copy)]
+struct InFlight {
+    frames: usize,
+    outputs: usize,
+    /// When the child counts as hung
+    deadline: Instant,
+}
+
+/// What a child reports once its plugin is loaded
+struct Started {
+    child: BridgeChild,
+    name: String,
+    parameters: Vec<f32>,
+    latency: usize,
+    layout: ChannelLayout,
+}
+
+impl BridgeChild {
+    fn spawn(
+        format: PluginFormat,
+        path: &Path,
+        plugin_id: Option<&str>,
+        sample_rate: f32,
+    ) -> Result<Started> {
+        let executable =
+            bridge_executable().context("The plugin sandbox needs the phantomlink executable")?;
+        let shared_path = shared_memory_path();
+        let shared = SharedAudio::create(&shared_path)?;
+        let spawned = Command::new(executable)
+            .arg(BRIDGE_COMMAND)
+            .arg(format.name())
+            .arg(path)
+            .arg(plugin_id.unwrap_or("-"))
+            .arg(sample_rate.to_string())
+            .arg(&shared_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn();
+        let mut process = match spawned {
+            Ok(process) => process,
+            Err(e) => {
+                let _ = std::fs::remove_file(&shared_path);
+                return Err(e).context("Failed to start plugin process");
+            }
+        };
+
+        let requests = process.stdin.take().context("No plugin process input")?;
+        let stdout = process.stdout.take().context("No plugin process output")?;
+        let (sender, replies) = bounded(16);
+        std::thread::Builder::new()
+            .name("plugin-bridge".to_string())
+            .spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    let Ok(line) = line else { break };
+                    match serde_json::from_str::<Reply>(&line) {
+                        Ok(reply) => {
+                            if sender.send(reply).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => log::warn!("Malformed reply from plugin process: {}", e),
+                    }
+                }
+                // Dropping the sender tells the mixer the child is gone
+            })?;
+
+        let mut child = Self {
+            process,
+            requests,
+            replies,
+            shared,
+            in_flight: Cell::new(None),
+            processed: RefCell::new(None),
+            automation: RefCell::new(Vec::new()),
+        };
+        let ready = child.replies.recv_timeout(START_TIMEOUT);
+        // Both processes have mapped it, or never will
+        let _ = std::fs::remove_file(&shared_path);
+        match ready {
+            Ok(Reply::Ready {
+                name,
+                parameters,
+                latency,
+                layout,
+            }) => Ok(Started {
+                child,
+                name,
+                parameters,
+                latency,
+                layout,
+            }),
+            Ok(Reply::Failed(e)) => Err(anyhow!(e)),
+            Ok(other) => bail!("Unexpected reply from plugin process: {:?}", other),
+            Err(e) => bail!("Plugin process {}", child.failure(e)),
+        }
+    }
+
+    fn send(&self, request: &Request) -> Result<()> {
+        write_line(&mut &self.requests, request)
+    }
+
+    /// The next reply that is not for an audio block. A block's reply
+    /// arriving first is set aside for [`BridgeChild::collect`].
+    fn next_reply(&self, timeout: Duration) -> Result<Reply, RecvTimeoutError> {
+        loop {
+            match self.replies.recv_timeout(timeout)? {
+                Reply::Processed {
+                    latency,
+                    automation,
+                } => {
+                    self.automation.borrow_mut().extend(automation);
+                    if let Some(block) = self.in_flight.take() {
+                        let outputs = self.shared.read_outputs(block.outputs, block.frames);
+                        *self.processed.borrow_mut() = Some((outputs, latency));
+                    }
+                }
+                reply => return Ok(reply),
+            }
+        }
+    }
+
+    fn call(&mut self, request: &Request, timeout: Duration) -> Result<Reply> {
+        self.send(request)
+            .map_err(|_| anyhow!(self.failure(RecvTimeoutError::Disconnected)))?;
+        self.next_reply(timeout)
+            .map_err(|e| anyhow!(self.failure(e)))
+    }
+
+    /// A request from a `&self` method: a failure is only reported, and the
+    /// next process call deals with the child
+    fn ask(&self, request: &Request, timeout: Duration) -> Result<Reply> {
+        self.send(request)?;
+        match self.next_reply(timeout) {
+            Ok(Reply::Failed(e)) => Err(anyhow!(e)),
+            Ok(reply) => Ok(reply),
+            Err(_) => bail!("Plugin process did not answer"),
+        }
+    }
+
+    /// Why the child did not answer; a hung child is killed
+    fn failure(&mut self, error: RecvTimeoutError) -> String {
+        if error == RecvTimeoutError::Timeout {
+            let _ = self.process.kill();
+        }
+        match self.process.wait() {
+            Ok(status) if error == RecvTimeoutError::Disconnected => describe_exit(status),
+            _ => "stopped responding".to_string(),
+        }
+    }
+
+    /// Hand a block to the child through the shared inputs. Returns false,
+    /// leaving the block unprocessed, if the child is still busy with the
+    /// last one or the block does not fit.
+    fn submit(
+        &mut self,
+        inputs: &[&[f32]],
+        layout: ChannelLayout,
+        sample_rate: f32,
+    ) -> Result<bool> {
+        let frames = inputs.first().map_or(0, |input| input.len());
+        if self.in_flight.get().is_some() || frames > MAX_FRAMES {
+            return Ok(false);
+        }
+        let (shared_inputs, _) = self.shared.blocks();
+        for (channel, block) in shared_inputs
+            .chunks_mut(MAX_FRAMES)
+            .take(layout.inputs + layout.sidechain)
+            .enumerate()
+        {
+            match inputs.get(channel) {
+                Some(input) => block[..frames].copy_from_slice(input),
+                None => block[..frames].fill(0.0),
+            }
+        }
+        self.send(&Request::Process {
+            frames,
+            transport: Some(transport::current()),
+        })
+        .map_err(|_| anyhow!(self.failure(RecvTimeoutError::Disconnected)))?;
+        let duration = Duration::from_secs_f32(frames as f32 / sample_rate);
+        self.in_flight.set(Some(InFlight {
+            frames,
+            outputs: layout.outputs,
+            deadline: Instant::now() + duration + PROCESS_TIMEOUT,
+        }));
+        Ok(true)
+    }
+
+    /// The submitted block, as (outputs, latency), if it is done within
+    /// `grace`. A block still unanswered [`PROCESS_TIMEOUT`] after it
+    /// should have finished means the child has hung.
+    fn collect(&mut self, grace: Duration) -> Result<Option<(Vec<Vec<f32>>, usize)>> {
+        if let Some(done) = self.processed.get_mut().take() {
+            return Ok(Some(done));
+        }
+        let Some(block) = self.in_flight.get() else {
+            return Ok(None);
+        };
+        match self.replies.recv_timeout(grace) {
+            Ok(Reply::Processed {
+                latency,
+                automation,
+            }) => {
+                self.automation.get_mut().extend(automation);
+                self.in_flight.set(None);
+                let outputs = self.shared.read_outputs(block.outputs, block.frames);
+                Ok(Some((outputs, latency)))
+            }
+            Ok(other) => bail!("Unexpected reply from plugin process: {:?}", other),
+            Err(RecvTimeoutError::Timeout) if Instant::now() < block.deadline => Ok(None),
+            Err(e) => bail!(self.failure(e)),
+        }
+    }
+}
+
+impl Drop for BridgeChild {
+    fn drop(&mut self) {
+        // Give the plugin a moment to clean up, then make sure it is gone
+        if self.send(&Request::Shutdown).is_ok() {
+            for _ in 0..20 {
+                if matches!(self.process.try_wait(), Ok(Some(_))) {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// A plugin in a channel slot, hosted by a child process
+pub struct BridgedProcessor {
+    format: PluginFormat,
+    path: PathBuf,
+    plugin_id: Option<String>,
+    name: String,
+    /// Normalised values, restored into a restarted plugin
+    parameters: Vec<f32>,
+    /// Last state loaded by the user, restored before the parameters
+    state: Option<Vec<u8>>,
+    latency: usize,
+    layout: ChannelLayout,
+    /// Keeps the child one buffer behind the callback
+    pipeline: BlockPipeline,
+    /// Rate the child's plugin runs at, also setting how long blocks may take
+    sample_rate: f32,
+    enabled: bool,
+    policy: CrashPolicy,
+    restarts: u32,
+    child: Option<BridgeChild>,
+    restarting: Option<Receiver<Result<Started>>>,
+}
+
+impl BridgedProcessor {
+    pub fn load(info: &VstPluginInfo, policy: CrashPolicy, sample_rate: f32) -> Result<Self> {
+        Self::load_path(
+            info.format,
+            &info.path,
+            info.plugin_id.as_deref(),
+            policy,
+            sample_rate,
+        )
+    }
+
+    pub fn load_path(
+        format: PluginFormat,
+        path: &Path,
+        plugin_id: Option<&str>,
+        policy: CrashPolicy,
+        sample_rate: f32,
+    ) -> Result<Self> {
+        let started = BridgeChild::spawn(format, path, plugin_id, sample_rate)
+            .with_context(|| format!("Failed to load {}", path.display()))?;
+        Ok(Self {
+            format,
+            path: path.to_path_buf(),
+            plugin_id: plugin_id.map(str::to_string),
+            name: started.name,
+            parameters: started.parameters,
+            state: None,
+            latency: started.latency,
+            layout: started.layout,
+            pipeline: BlockPipeline::new(),
+            sample_rate,
+            enabled: true,
+            policy,
+            restarts: 0,
+            child: Some(started.child),
+            restarting: None,
+        })
+    }
+
+    /// Whether a child is running; false while restarting or bypassed
+    pub fn is_running(&self) -> bool {
+        self.child.is_some()
+    }
+
+    fn on_crash(&mut self, reason: String) {
+        report(BridgeEvent::Crashed {
+            plugin: self.name.clone(),
+            reason,
+        });
+        // Reaping can block briefly; keep it off the audio thread
+        if let Some(dead) = self.child.take() {
+            std::thread::spawn(move || drop(dead));
+        }
+
+        if self.policy == CrashPolicy::Bypass || self.restarts >= MAX_RESTARTS {
+            report(BridgeEvent::Bypassed {
+                plugin: self.name.clone(),
+            });
+            return;
+        }
+        self.restarts += 1;
+        self.restart();
+    }
+
+    /// Start a new child in the background with the last known state; the
+    /// audio callback picks it up once it is ready
+    fn restart(&mut self) {
+        let (sender, receiver) = bounded(1);
+        let (format, path, plugin_id) = (self.format, self.path.clone(), self.plugin_id.clone());
+        let sample_rate = self.sample_rate;
+        let (parameters, state) = (self.parameters.clone(), self.state.clone());
+        std::thread::spawn(move || {
+            let started = BridgeChild::spawn(format, &path, plugin_id.as_deref(), sample_rate)
+                .and_then(|mut started| {
+                    if let Some(data) = state {
+                        started
+                            .child
+                            .call(&Request::LoadState { data }, START_TIMEOUT)?;
+                    }
+                    for (index, &value) in parameters.iter().enumerate() {
+                        started.child.send(&Request::SetParameter {
+                            index: index as i32,
+                            value,
+                        })?;
+                    }
+                    started.parameters = parameters;
+                    Ok(started)
+                });
+            let _ = sender.send(started);
+        });
+        self.restarting = Some(receiver);
+    }
+
+    /// Switch program, import a preset or change rate, and remember the
+    /// resulting state for a restart
+    fn change_state(&mut self, request: &Request) -> Result<()> {
+        let child = self
+            .child
+            .as_mut()
+            .context("Plugin process is not running")?;
+        match child.call(request, START_TIMEOUT)? {
+            Reply::Parameters(parameters) => self.parameters = parameters,
+            Reply::Failed(e) => bail!(e),
+            other => bail!("Unexpected reply from plugin process: {:?}", other),
+        }
+        self.state = self.save_state().ok();
+        Ok(())
+    }
+
+    /// Collect the last block and submit this one, as (previous result,
+    /// whether this block was submitted)
+    fn exchange(
+        &mut self,
+        inputs: &[&[f32]],
+        frames: usize,
+    ) -> Result<(Option<Vec<Vec<f32>>>, bool)> {
+        let child = self
+            .child
+            .as_mut()
+            .context("Plugin process is not running")?;
+        // A block that already missed its turn is still collected, and
+        // dropped, so the child is free for this one
+        let grace = self.pipeline.wait_time(frames, self.sample_rate);
+        let mut previous = None;
+        if let Some((outputs, latency)) = child.collect(grace.unwrap_or_default())? {
+            self.latency = latency;
+            previous = grace.map(|_| outputs);
+        }
+        let submitted = child.submit(inputs, self.layout, self.sample_rate)?;
+        Ok((previous, submitted))
+    }
+
+    fn poll_restart(&mut self) {
+        let Some(result) = self.restarting.as_ref().and_then(|r| r.try_recv().ok()) else {
+            return;
+        };
+        self.restarting = None;
+        match result {
+            Ok(started) => {
+                self.latency = started.latency;
+                self.child = Some(started.child);
+                report(BridgeEvent::Restarted {
+                    plugin: self.name.clone(),
+                });
+            }
+            Err(e) => self.on_crash(format!("failed to restart: {:#}", e)),
+        }
+    }
+}
+
+impl PluginProcessor for BridgedProcessor {
+    fn format(&self) -> PluginFormat {
+        self.format
+    }
+
+    fn channel_layout(&self) -> ChannelLayout {
+        self.layout
+    }
+
+    fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+        self.poll_restart();
+        let frames = inputs.first().map_or(0, |input| input.len());
+        if !self.enabled || frames == 0 || self.child.is_none() {
+            self.pipeline.reset();
+            return plugin_host::passthrough(self.layout, inputs, frames);
+        }
+
+        match self.exchange(inputs, frames) {
+            Ok((previous, submitted)) => {
+                self.pipeline
+                    .advance(self.layout, inputs, previous, submitted)
+            }
+            Err(e) => {
+                self.on_crash(e.to_string());
+                self.pipeline.reset();
+                plugin_host::passthrough(self.layout, inputs, frames)
+            }
+        }
+    }
+
+    fn get_plugin_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn set_parameter(&mut self, index: i32, value: f32) {
+        let Some(slot) = usize::try_from(index)
+            .ok()
+            .and_then(|i| self.parameters.get_mut(i))
+        else {
+            return;
+        };
+        *slot = value.clamp(0.0, 1.0);
+        if let Some(child) = &self.child {
+            // A dead child is noticed by the next process call
+            let _ = child.send(&Request::SetParameter {
+                index,
+                value: *slot,
+            });
+        }
+    }
+
+    fn get_parameter(&self, index: i32) -> f32 {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.parameters.get(i))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    fn get_parameter_info(&self, index: i32) -> Option<ParameterInfo> {
+        let child = self.child.as_ref()?;
+        match child
+            .ask(&Request::GetParameterInfo { index }, REQUEST_TIMEOUT)
+            .ok()?
+        {
+            Reply::ParameterInfo(info) => info,
+            _ => None,
+        }
+    }
+
+    /// One round trip for all of them. The child's replies are read on the
+    /// audio thread's pipe, so unlike a worker-thread format the request
+    /// cannot be made once the mixer lock is released.
+    fn parameter_query(&self) -> ParameterQuery {
+        let parameters = match self
+            .child
+            .as_ref()
+            .map(|child| child.ask(&Request::GetAllParameterInfo, REQUEST_TIMEOUT))
+        {
+            Some(Ok(Reply::AllParameterInfo(parameters))) => parameters,
+            _ => self
+                .parameters
+                .iter()
+                .enumerate()
+                .map(|(index, &value)| plugin_host::generic_parameter(index as i32, value))
+                .collect(),
+        };
+        Box::new(move || parameters)
+    }
+
+    fn get_parameter_count(&self) -> i32 {
+        self.parameters.len() as i32
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn latency_samples(&self) -> usize {
+        if self.enabled && self.child.is_some() {
+            self.latency + self.pipeline.latency()
+        } else {
+            0
+        }
+    }
+
+    /// A child being restarted was started at the old rate, so it is
+    /// replaced by one started at the new rate
+    fn set_sample_rate(&mut self, sample_rate: f32) -> Result<()> {
+        if sample_rate == self.sample_rate {
+            return Ok(());
+        }
+        self.sample_rate = sample_rate;
+        self.pipeline.reset();
+        if self.child.is_some() {
+            self.change_state(&Request::SetSampleRate { sample_rate })
+        } else {
+            if self.restarting.take().is_some() {
+                self.restart();
+            }
+            Ok(())
+        }
+    }
+
+    fn xruns(&self) -> u64 {
+        self.pipeline.xruns()
+    }
+
+    fn take_automation(&mut self) -> Vec<ParameterChange> {
+        let Some(child) = self.child.as_mut() else {
+            return Vec::new();
+        };
+        let changes = std::mem::take(child.automation.get_mut());
+        for change in &changes {
+            if let Some(slot) = usize::try_from(change.index)
+                .ok()
+                .and_then(|i| self.parameters.get_mut(i))
+            {
+                *slot = change.value;
+            }
+        }
+        changes
+    }
+
+    fn save_state(&self) -> Result<Vec<u8>> {
+        let child = self
+            .child
+            .as_ref()
+            .context("Plugin process is not running")?;
+        match child.ask(&Request::SaveState, START_TIMEOUT)? {
+            Reply::State(data) => Ok(data),
+            other => bail!("Unexpected reply from plugin process: {:?}", other),
+        }
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        let child = self
+            .child
+            .as_mut()
+            .context("Plugin process is not running")?;
+        let request = Request::LoadState {
+            data: data.to_vec(),
+        };
+        match child.call(&request, START_TIMEOUT)? {
+            Reply::Parameters(parameters) => {
+                self.parameters = parameters;
+                self.state = Some(data.to_vec());
+                Ok(())
+            }
+            Reply::Failed(e) => Err(anyhow!(e)),
+            other => bail!("Unexpected reply from plugin process: {:?}", other),
+        }
+    }
+
+    fn programs(&self) -> ProgramList {
+        match self
+            .child
+            .as_ref()
+            .map(|child| child.ask(&Request::GetPrograms, REQUEST_TIMEOUT))
+        {
+            Some(Ok(Reply::Programs(programs))) => programs,
+            _ => ProgramList::default(),
+        }
+    }
+
+    fn set_program(&mut self, index: usize) -> Result<()> {
+        self.change_state(&Request::SetProgram { index })
+    }
+
+    fn export_preset(&self, bank: bool) -> Result<Vec<u8>> {
+        let child = self
+            .child
+            .as_ref()
+            .context("Plugin process is not running")?;
+        match child.ask(&Request::ExportPreset { bank }, START_TIMEOUT)? {
+            Reply::State(data) => Ok(data),
+            other => bail!("Unexpected reply from plugin process: {:?}", other),
+        }
+    }
+
+    fn import_preset(&mut self, data: &[u8]) -> Result<()> {
+        self.change_state(&Request::ImportPreset {
+            data: data.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_audio_is_shared() {
+        let path = shared_memory_path();
+        let mut parent = SharedAudio::create(&path).unwrap();
+        let mut child = SharedAudio::open(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        parent.blocks().0[..3].copy_from_slice(&[0.1, 0.2, 0.3]);
+        assert_eq!(&child.blocks().0[..3], &[0.1, 0.2, 0.3]);
+        child.blocks().1[MAX_FRAMES - 1] = -1.0;
+        assert_eq!(parent.blocks().1[MAX_FRAMES - 1], -1.0);
+        assert!(SharedAudio::create(&path).is_ok());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_protocol_round_trip() {
+        let requests = [
+            Request::Process {
+                frames: 512,
+                transport: None,
+            },
+            Request::Process {
+                frames: 64,
+                transport: Some(TransportInfo {
+                    playing: true,
+                    sample_position: 96000,
+                    ..TransportInfo::default()
+                }),
+            },
+            Request::SetParameter {
+                index: 2,
+                value: 0.75,
+            },
+            Request::LoadState {
+                data: vec![0, 255, 7],
+            },
+        ];
+        for request in requests {
+            let line = serde_json::to_string(&request).unwrap();
+            assert!(!line.contains('\n'));
+            assert_eq!(serde_json::from_str::<Request>(&line).unwrap(), request);
+        }
+
+        let reply = Reply::Ready {
+            name: "Gate".to_string(),
+            parameters: vec![0.5, 1.0],
+            latency: 64,
+            layout: ChannelLayout::new(2, 2).with_sidechain(2),
+        };
+        let line = serde_json::to_string(&reply).unwrap();
+        assert_eq!(serde_json::from_str::<Reply>(&line).unwrap(), reply);
+
+        let reply = Reply::Processed {
+            latency: 0,
+            automation: vec![ParameterChange {
+                index: 1,
+                value: 0.25,
+            }],
+        };
+        let line = serde_json::to_string(&reply).unwrap();
+        assert_eq!(serde_json::from_str::<Reply>(&line).unwrap(), reply);
+    }
+
+    #[test]
+    fn test_exit_descriptions() {
+        // Raw wait statuses: a SIGSEGV death, then exit code 3
+        assert_eq!(
+            describe_exit(ExitStatus::from_raw(11)),
+            "crashed (signal 11)"
+        );
+        assert_eq!(
+            describe_exit(ExitStatus::from_raw(3 << 8)),
+            "exited with status 3"
+        );
+    }
+
+    #[test]
+    fn test_scan_falls_back_in_process_in_tests() {
+        // The test binary cannot serve child commands
+        if std::env::var_os(EXECUTABLE_ENV).is_none() {
+            assert!(bridge_executable().is_none());
+        }
+        let missing = Path::new("/nonexistent/plugin.so");
+        let error = scan_sandboxed(PluginFormat::Ladspa, missing).unwrap_err();
+        assert!(!error.is::<ChildCrashed>());
+        assert!(scan_sandboxed(PluginFormat::Lv2, missing).is_err());
+    }
+
+    #[test]
+    fn test_sandbox_config_defaults() {
+        let config: SandboxConfig = serde_json::from_str("{}").unwrap();
+        assert!(!config.enabled);
+        assert_eq!(config.on_crash, CrashPolicy::Restart);
+        assert!(
+            BridgeEvent::Bypassed {
+                plugin: "EQ".to_string()
+            }
+            .is_error()
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! Persistent plugin catalogue.
+//!
+//! Scanning opens every plugin library, which is slow and occasionally
+//! fatal, so the results are kept on disk between runs:
+//! - Entries are keyed by path and reused while the file's size and
+//!   modification time are unchanged
+//! - Libraries whose scan crashes or hangs are blacklisted until they change
+//!   or the user retries them
+//! - Custom scan paths are searched alongside each format's standard ones
+
+#![allow(dead_code)] // Complete catalogue API, used by the GUI and IPC
+
+use crate::plugin_bridge::{self, ChildCrashed};
+use crate::plugin_host::{self, PluginFormat};
+use crate::vst_host::VstPluginInfo;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::UNIX_EPOCH;
+
+const CATALOGUE_FILE_NAME: &str = "plugins.json";
+
+/// Formats in the order their plugins are listed
+const FORMATS: [PluginFormat; 4] = [
+    PluginFormat::Vst2,
+    PluginFormat::Clap,
+    PluginFormat::Lv2,
+    PluginFormat::Ladspa,
+];
+
+static CATALOGUE: OnceLock<Mutex<PluginCatalogue>> = OnceLock::new();
+
+/// Size and modification time of a plugin file. For an LV2 bundle
+/// directory: the total size and newest time of the files inside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub size: u64,
+    /// Nanoseconds since the Unix epoch
+    pub modified: u64,
+}
+
+impl Fingerprint {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        if !metadata.is_dir() {
+            return Some(Self::from_metadata(&metadata));
+        }
+        let mut fingerprint = Self::default();
+        for entry in fs::read_dir(path).ok()?.flatten() {
+            if let Ok(metadata) = entry.metadata()
+                && metadata.is_file()
+            {
+                let file = Self::from_metadata(&metadata);
+                fingerprint.size += file.size;
+                fingerprint.modified = fingerprint.modified.max(file.modified);
+            }
+        }
+        Some(fingerprint)
+    }
+
+    fn from_metadata(metadata: &fs::Metadata) -> Self {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        Self {
+            size: metadata.len(),
+            modified,
+        }
+    }
+}
+
+/// What a scan of one file found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogueEntry {
+    pub format: PluginFormat,
+    pub fingerprint: Fingerprint,
+    pub plugins: Vec<VstPluginInfo>,
+    /// Why the file held no usable plugin, if the scan failed
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A file skipped by scans because scanning it crashed or hung
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlacklistEntry {
+    pub path: PathBuf,
+    pub format: PluginFormat,
+    pub reason: String,
+    /// The file is retried once this no longer matches
+    pub fingerprint: Fingerprint,
+}
+
+/// A user-added directory to search for one format
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanPath {
+    pub format: PluginFormat,
+    pub path: PathBuf,
+}
+
+/// Counts from one refresh
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ScanSummary {
+    /// Files scanned because they were new or changed
+    pub scanned: usize,
+    /// Files whose cached entry was reused
+    pub cached: usize,
+    /// Files skipped because they are blacklisted
+    pub blacklisted: usize,
+    /// Files that failed to scan, newly blacklisted ones included
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginCatalogue {
+    /// Searched in addition to the standard locations
+    pub scan_paths: Vec<ScanPath>,
+    entries: BTreeMap<PathBuf, CatalogueEntry>,
+    blacklist: Vec<BlacklistEntry>,
+    /// Where the catalogue is saved
+    #[serde(skip)]
+    file: PathBuf,
+}
+
+impl PluginCatalogue {
+    /// The application's catalogue, loaded from disk on first use
+    pub fn global() -> &'static Mutex<PluginCatalogue> {
+        CATALOGUE.get_or_init(|| Mutex::new(Self::load_from(&Self::default_path())))
+    }
+
+    /// Load a saved catalogue; a missing or unreadable file gives an empty one
+    pub fn load_from(path: &Path) -> Self {
+        let mut catalogue = fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<Self>(&text).ok())
+            .unwrap_or_default();
+        catalogue.file = path.to_path_buf();
+        catalogue
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.file, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", self.file.display()))
+    }
+
+    fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .map(|dir| dir.join("phantomlink"))
+            .or_else(|| dirs::home_dir().map(|home| home.join(".phantomlink")))
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(CATALOGUE_FILE_NAME)
+    }
+
+    /// Bring the catalogue up to date with the standard and custom locations
+    pub fn refresh(&mut self) -> ScanSummary {
+        let mut files = Vec::new();
+        for format in FORMATS {
+            let extra: Vec<PathBuf> = self
+                .scan_paths
+                .iter()
+                .filter(|scan_path| scan_path.format == format)
+                .map(|scan_path| scan_path.path.clone())
+                .collect();
+            files.extend(
+                plugin_host::plugin_files(format, &extra)
+                    .into_iter()
+                    .map(|path| (format, path)),
+            );
+        }
+        self.refresh_files(files)
+    }
+
+    /// Rescan whichever of `files` are new or changed, and forget entries
+    /// for files no longer present
+    fn refresh_files(&mut self, files: Vec<(PluginFormat, PathBuf)>) -> ScanSummary {
+        let mut summary = ScanSummary::default();
+        let mut previous = std::mem::take(&mut self.entries);
+        self.blacklist.retain(|entry| entry.path.exists());
+
+        for (format, path) in files {
+            if self.entries.contains_key(&path) {
+                continue;
+            }
+            let Some(fingerprint) = Fingerprint::of(&path) else {
+                continue;
+            };
+
+            if let Some(index) = self.blacklist.iter().position(|entry| entry.path == path) {
+                if self.blacklist[index].fingerprint == fingerprint {
+                    summary.blacklisted += 1;
+                    continue;
+                }
+                // Updated since it crashed; give it another chance
+                self.blacklist.remove(index);
+            }
+
+            if let Some(entry) = previous.remove(&path)
+                && entry.format == format
+                && entry.fingerprint == fingerprint
+            {
+                summary.cached += 1;
+                self.entries.insert(path, entry);
+                continue;
+            }
+
+            summary.scanned += 1;
+            let (plugins, error) = match plugin_bridge::scan_sandboxed(format, &path) {
+                Ok(plugins) => (plugins, None),
+                Err(e) if e.is::<ChildCrashed>() => {
+                    log::warn!("Blacklisting {}: {:#}", path.display(), e);
+                    summary.failed += 1;
+                    self.blacklist.push(BlacklistEntry {
+                        path,
+                        format,
+                        reason: e.to_string(),
+                        fingerprint,
+                    });
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Skipping {} plugin {}: {:#}",
+                        format.name(),
+                        path.display(),
+                        e
+                    );
+                    summary.failed += 1;
+                    (Vec::new(), Some(format!("{:#}", e)))
+                }
+            };
+            self.entries.insert(
+                path,
+                CatalogueEntry {
+                    format,
+                    fingerprint,
+                    plugins,
+                    error,
+                },
+            );
+        }
+        summary
+    }
+
+    /// Every catalogued plugin, grouped by format
+    pub fn plugins(&self) -> Vec<VstPluginInfo> {
+        FORMATS
+            .iter()
+            .flat_map(|&format| {
+                self.entries
+                    .values()
+                    .filter(move |entry| entry.format == format)
+                    .flat_map(|entry| entry.plugins.iter().cloned())
+            })
+            .collect()
+    }
+
+    pub fn blacklist(&self) -> &[BlacklistEntry] {
+        &self.blacklist
+    }
+
+    /// Retry a blacklisted file on the next refresh; false if it was not listed
+    pub fn unblacklist(&mut self, path: &Path) -> bool {
+        let before = self.blacklist.len();
+        self.blacklist.retain(|entry| entry.path != path);
+        before != self.blacklist.len()
+    }
+
+    /// Add a custom location; false if it is already searched
+    pub fn add_scan_path(&mut self, scan_path: ScanPath) -> bool {
+        if self.scan_paths.contains(&scan_path) {
+            return false;
+        }
+        self.scan_paths.push(scan_path);
+        true
+    }
+
+    /// Remove a custom location; false if it was not added
+    pub fn remove_scan_path(&mut self, scan_path: &ScanPath) -> bool {
+        let before = self.scan_paths.len();
+        self.scan_paths.retain(|existing| existing != scan_path);
+        before != self.scan_paths.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("phantomlink_catalogue_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_unchanged_files_are_not_rescanned() {
+        let dir = temp_dir("cache");
+        let bundle = dir.join("empty.lv2");
+        fs::create_dir_all(&bundle).unwrap();
+        fs::write(bundle.join("manifest.ttl"), "").unwrap();
+        let files = vec![(PluginFormat::Lv2, bundle.clone())];
+
+        let mut catalogue = PluginCatalogue::load_from(&dir.join(CATALOGUE_FILE_NAME));
+        assert_eq!(catalogue.refresh_files(files.clone()).scanned, 1);
+        catalogue.save().unwrap();
+
+        let mut reloaded = PluginCatalogue::load_from(&dir.join(CATALOGUE_FILE_NAME));
+        let summary = reloaded.refresh_files(files.clone());
+        assert_eq!((summary.scanned, summary.cached), (0, 1));
+
+        // A changed bundle is scanned again, a vanished one forgotten
+        fs::write(bundle.join("manifest.ttl"), "# edited\n").unwrap();
+        assert_eq!(reloaded.refresh_files(files).scanned, 1);
+        reloaded.refresh_files(Vec::new());
+        assert!(reloaded.entries.is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_failed_scans_are_recorded() {
+        let dir = temp_dir("failed");
+        let library = dir.join("broken.so");
+        fs::write(&library, b"not a library").unwrap();
+
+        let mut catalogue = PluginCatalogue::load_from(&dir.join(CATALOGUE_FILE_NAME));
+        let summary = catalogue.refresh_files(vec![(PluginFormat::Ladspa, library.clone())]);
+        assert_eq!(summary.failed, 1);
+        assert!(catalogue.plugins().is_empty());
+        assert!(catalogue.entries[&library].error.is_some());
+        // Reporting an error is not crashing
+        assert!(catalogue.blacklist().is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_blacklist_until_changed() {
+        let dir = temp_dir("blacklist");
+        let library = dir.join("crashy.so");
+        fs::write(&library, b"crash").unwrap();
+
+        let mut catalogue = PluginCatalogue::load_from(&dir.join(CATALOGUE_FILE_NAME));
+        catalogue.blacklist.push(BlacklistEntry {
+            path: library.clone(),
+            format: PluginFormat::Vst2,
+            reason: "Scanner crashed (signal 11)".to_string(),
+            fingerprint: Fingerprint::of(&library).unwrap(),
+        });
+        let files = vec![(PluginFormat::Vst2, library.clone())];
+        assert_eq!(catalogue.refresh_files(files.clone()).blacklisted, 1);
+
+        assert!(catalogue.unblacklist(&library));
+        assert!(!catalogue.unblacklist(&library));
+        let summary = catalogue.refresh_files(files);
+        assert_eq!((summary.blacklisted, summary.scanned), (0, 1));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_custom_scan_paths() {
+        let mut catalogue = PluginCatalogue::default();
+        let scan_path = ScanPath {
+            format: PluginFormat::Clap,
+            path: PathBuf::from("/opt/plugins/clap"),
+        };
+        assert!(catalogue.add_scan_path(scan_path.clone()));
+        assert!(!catalogue.add_scan_path(scan_path.clone()));
+
+        let json = serde_json::to_string(&catalogue).unwrap();
+        let loaded: PluginCatalogue = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.scan_paths, vec![scan_path.clone()]);
+
+        assert!(catalogue.remove_scan_path(&scan_path));
+        assert!(catalogue.scan_paths.is_empty());
+    }
+}
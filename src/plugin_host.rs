@@ -0,0 +1,744 @@
+//! Format-independent plugin slots.
+//!
+//! A channel strip holds one effect plugin, whatever format it was built
+//! for. Each host module (VST2, CLAP, LV2, LADSPA) implements [`PluginProcessor`] for its
+//! processor, and the scanners all fill the same [`VstPluginInfo`]
+//! catalogue, tagged with the format, so the two can be mixed freely.
+
+#![allow(dead_code)] // Complete plugin slot API, used incrementally by the GUI and IPC
+
+use crate::clap_host::{ClapProcessor, ClapScanner};
+use crate::ladspa_host::{LadspaProcessor, LadspaScanner};
+use crate::lv2_host::{Lv2Processor, Lv2Scanner};
+use crate::plugin_catalogue::PluginCatalogue;
+use crate::vst_host::{ParameterInfo, VstPluginInfo, VstProcessor, VstScanner};
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Plugin standard a catalogue entry or processor belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PluginFormat {
+    #[default]
+    Vst2,
+    Clap,
+    Lv2,
+    Ladspa,
+}
+
+impl PluginFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PluginFormat::Vst2 => "VST2",
+            PluginFormat::Clap => "CLAP",
+            PluginFormat::Lv2 => "LV2",
+            PluginFormat::Ladspa => "LADSPA",
+        }
+    }
+
+    /// Inverse of [`PluginFormat::name`], ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            PluginFormat::Vst2,
+            PluginFormat::Clap,
+            PluginFormat::Lv2,
+            PluginFormat::Ladspa,
+        ]
+        .into_iter()
+        .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Audio channels a plugin's ports take and produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ChannelLayout {
+    /// Main input channels
+    pub inputs: usize,
+    pub outputs: usize,
+    /// Key-signal inputs, passed after the main ones
+    pub sidechain: usize,
+}
+
+impl ChannelLayout {
+    pub fn new(inputs: usize, outputs: usize) -> Self {
+        Self {
+            inputs,
+            outputs,
+            sidechain: 0,
+        }
+    }
+
+    pub fn with_sidechain(mut self, sidechain: usize) -> Self {
+        self.sidechain = sidechain;
+        self
+    }
+
+    /// Channels the strip carries after this plugin: stereo if the plugin
+    /// produces it, otherwise mono
+    pub fn strip_channels(&self) -> usize {
+        if self.outputs >= 2 { 2 } else { 1 }
+    }
+
+    /// Short description such as "1→2" or "2→2 +2 sc"
+    pub fn describe(&self) -> String {
+        let mut text = format!("{}→{}", self.inputs, self.outputs);
+        if self.sidechain > 0 {
+            text.push_str(&format!(" +{} sc", self.sidechain));
+        }
+        text
+    }
+}
+
+/// Parameter changes a threaded plugin holds until they are taken; more
+/// are dropped
+pub const AUTOMATION_QUEUE: usize = 1024;
+
+/// A parameter value the plugin set itself, from its own editor or
+/// internal modulation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ParameterChange {
+    pub index: i32,
+    pub value: f32,
+}
+
+/// One channel plugin parameter value kept in a [`PluginScene`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneParameter {
+    pub channel: usize,
+    /// Name of the plugin the value was recorded from; recall skips the
+    /// value if the channel holds another plugin by then
+    pub plugin: String,
+    pub index: i32,
+    pub value: f32,
+}
+
+/// A named set of channel plugin parameter values, recorded as they change
+/// and recalled in one go
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PluginScene {
+    pub name: String,
+    pub parameters: Vec<SceneParameter>,
+}
+
+impl PluginScene {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Keep a parameter value of a channel's plugin, replacing any earlier
+    /// one. Values from a plugin the channel held before are dropped.
+    pub fn record(&mut self, channel: usize, plugin: &str, change: ParameterChange) {
+        self.parameters
+            .retain(|p| p.channel != channel || p.plugin == plugin);
+        let value = change.value.clamp(0.0, 1.0);
+        match self
+            .parameters
+            .iter_mut()
+            .find(|p| p.channel == channel && p.index == change.index)
+        {
+            Some(parameter) => parameter.value = value,
+            None => self.parameters.push(SceneParameter {
+                channel,
+                plugin: plugin.to_string(),
+                index: change.index,
+                value,
+            }),
+        }
+    }
+}
+
+/// A plugin's built-in programs (factory presets)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProgramList {
+    pub names: Vec<String>,
+    /// Index of the selected program
+    pub current: usize,
+}
+
+/// Describes every parameter of a plugin when called; see
+/// [`PluginProcessor::parameter_query`]
+pub type ParameterQuery = Box<dyn FnOnce() -> Vec<ParameterInfo> + Send>;
+
+/// Description for a parameter the plugin cannot describe itself
+pub fn generic_parameter(index: i32, value: f32) -> ParameterInfo {
+    ParameterInfo {
+        index,
+        name: format!("Parameter {}", index + 1),
+        label: String::new(),
+        value,
+        display: format!("{:.2}", value),
+    }
+}
+
+/// A channel's plugin and its state, as saved with the channel config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPlugin {
+    pub info: VstPluginInfo,
+    /// From [`PluginProcessor::save_state`]; empty if the plugin had none
+    #[serde(default, with = "hex_bytes")]
+    pub state: Vec<u8>,
+}
+
+/// Binary state as a hex string, far smaller in JSON than a number array
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        if hex.len() % 2 != 0 {
+            return Err(D::Error::custom("odd number of hex digits"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| D::Error::custom("invalid hex digit"))
+            })
+            .collect()
+    }
+}
+
+/// An effect plugin loaded into a channel strip.
+///
+/// Audio is planar, one buffer per port channel as given by
+/// [`PluginProcessor::channel_layout`]; [`process_strip`] maps the strip's
+/// signal onto it. Parameters are addressed by index with values normalised
+/// to 0.0 - 1.0 whatever the format's native range.
+pub trait PluginProcessor: Send {
+    fn format(&self) -> PluginFormat;
+
+    fn channel_layout(&self) -> ChannelLayout;
+
+    /// Process one block. `inputs` holds the main then the sidechain
+    /// channels, all the same length; the result has one buffer per output
+    /// channel, of that length too.
+    fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>>;
+
+    fn get_plugin_name(&self) -> String;
+
+    fn set_parameter(&mut self, index: i32, value: f32);
+
+    /// Cached parameter value
+    fn get_parameter(&self, index: i32) -> f32;
+
+    /// Name, unit and display text from the plugin itself (blocking)
+    fn get_parameter_info(&self, index: i32) -> Option<ParameterInfo>;
+
+    /// Every parameter's description. Formats processed on a worker thread
+    /// return a query that asks it for all of them in one request, so the
+    /// caller can let go of the mixer lock before waiting; the rest are
+    /// described straight away.
+    fn parameter_query(&self) -> ParameterQuery {
+        let parameters: Vec<ParameterInfo> = (0..self.get_parameter_count())
+            .map(|index| {
+                self.get_parameter_info(index)
+                    .unwrap_or_else(|| generic_parameter(index, self.get_parameter(index)))
+            })
+            .collect();
+        Box::new(move || parameters)
+    }
+
+    fn get_parameter_count(&self) -> i32;
+
+    fn set_enabled(&mut self, enabled: bool);
+
+    fn is_enabled(&self) -> bool;
+
+    /// Delay the plugin adds, or 0 while bypassed
+    fn latency_samples(&self) -> usize;
+
+    /// Run at a new stream rate. Formats that fix the rate when a plugin is
+    /// created re-create it, keeping its parameter values.
+    fn set_sample_rate(&mut self, _sample_rate: f32) -> Result<()> {
+        Ok(())
+    }
+
+    /// Blocks that came back too late and were played dry instead
+    fn xruns(&self) -> u64 {
+        0
+    }
+
+    /// Parameter changes the plugin made itself since the last call, oldest
+    /// first; the cached values already include them
+    fn take_automation(&mut self) -> Vec<ParameterChange> {
+        Vec::new()
+    }
+
+    /// The plugin's opaque state, for restoring it later
+    fn save_state(&self) -> Result<Vec<u8>> {
+        bail!(
+            "{} plugins do not support saving state",
+            self.format().name()
+        )
+    }
+
+    fn load_state(&mut self, _data: &[u8]) -> Result<()> {
+        bail!(
+            "{} plugins do not support loading state",
+            self.format().name()
+        )
+    }
+
+    /// Built-in programs; empty for formats without them
+    fn programs(&self) -> ProgramList {
+        ProgramList::default()
+    }
+
+    fn set_program(&mut self, _index: usize) -> Result<()> {
+        bail!("{} plugins have no programs", self.format().name())
+    }
+
+    /// The current program, or with `bank` every program, as an .fxp/.fxb file
+    fn export_preset(&self, _bank: bool) -> Result<Vec<u8>> {
+        bail!("FXP/FXB presets are only for VST2 plugins")
+    }
+
+    /// Apply an .fxp or .fxb file
+    fn import_preset(&mut self, _data: &[u8]) -> Result<()> {
+        bail!("FXP/FXB presets are only for VST2 plugins")
+    }
+}
+
+/// Output for a bypassed or failed block: the main inputs carried over to
+/// the outputs, the last one repeated if there are fewer, silence if none
+pub fn passthrough(layout: ChannelLayout, inputs: &[&[f32]], frames: usize) -> Vec<Vec<f32>> {
+    let main = &inputs[..layout.inputs.min(inputs.len())];
+    (0..layout.outputs)
+        .map(|channel| match main.get(channel).or(main.last()) {
+            Some(input) => input.to_vec(),
+            None => vec![0.0; frames],
+        })
+        .collect()
+}
+
+/// Run a strip's planar channels through a plugin. A mono strip feeds every
+/// main input (mono to stereo), a stereo one feeds its left and right to the
+/// first two, the sidechain inputs get `sidechain` or silence, and the first
+/// two outputs come back as the strip's new channels. A plugin without
+/// outputs passes the strip on unchanged.
+pub fn process_strip(
+    plugin: &mut dyn PluginProcessor,
+    inputs: &[&[f32]],
+    sidechain: Option<&[f32]>,
+) -> Vec<Vec<f32>> {
+    let layout = plugin.channel_layout();
+    let frames = inputs.first().map_or(0, |input| input.len());
+    let silence = vec![0.0; frames];
+    let key = match sidechain {
+        Some(key) if key.len() == frames => key,
+        _ => &silence,
+    };
+    let mut ports: Vec<&[f32]> = (0..layout.inputs)
+        .map(|port| {
+            inputs
+                .get(port)
+                .or(inputs.last())
+                .copied()
+                .unwrap_or(&silence)
+        })
+        .collect();
+    ports.extend(std::iter::repeat_n(key, layout.sidechain));
+
+    let mut outputs = plugin.process(&ports);
+    outputs.truncate(2);
+    if outputs.is_empty() || outputs.iter().any(|output| output.len() != frames) {
+        return inputs.iter().map(|input| input.to_vec()).collect();
+    }
+    outputs
+}
+
+/// Keeps a plugin that runs on another thread (or process) exactly one
+/// buffer behind the audio callback. Each callback collects the block
+/// submitted last time and submits the current one, so the plugin gets a
+/// whole buffer period to work in and the strip always hears it with the
+/// same delay. A block that is not back in time is played dry in its place
+/// and counted as an xrun, rather than the effect dropping in and out.
+#[derive(Debug, Default)]
+pub struct BlockPipeline {
+    /// Dry copy of the block the plugin is working on, and whether it
+    /// actually reached the plugin
+    in_flight: Option<(Vec<Vec<f32>>, bool)>,
+    /// Processed audio waiting to be played, per output channel
+    queue: Vec<VecDeque<f32>>,
+    latency: usize,
+    xruns: u64,
+}
+
+impl BlockPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long to wait for the block in flight: half of this callback's
+    /// buffer, or nothing if there is no block to wait for
+    pub fn wait_time(&self, frames: usize, sample_rate: f32) -> Option<Duration> {
+        match self.in_flight {
+            Some((_, true)) if sample_rate > 0.0 => {
+                Some(Duration::from_secs_f32(frames as f32 / sample_rate / 2.0))
+            }
+            _ => None,
+        }
+    }
+
+    /// Queue the result of the previous block (`None` if it missed its
+    /// deadline), remember the current one, and return this callback's
+    /// output. `submitted` says whether the current block reached the plugin.
+    pub fn advance(
+        &mut self,
+        layout: ChannelLayout,
+        inputs: &[&[f32]],
+        previous: Option<Vec<Vec<f32>>>,
+        submitted: bool,
+    ) -> Vec<Vec<f32>> {
+        let frames = inputs.first().map_or(0, |input| input.len());
+        if frames == 0 {
+            return vec![Vec::new(); layout.outputs];
+        }
+        self.queue.resize_with(layout.outputs, VecDeque::new);
+
+        if let Some((dry, _)) = self.in_flight.take() {
+            let expected = dry.first().map_or(0, Vec::len);
+            let late = previous.as_ref().is_none_or(|outputs| {
+                outputs.len() != layout.outputs
+                    || outputs.iter().any(|output| output.len() != expected)
+            });
+            let block = if late {
+                self.xruns += 1;
+                dry
+            } else {
+                previous.unwrap_or_default()
+            };
+            for (queue, channel) in self.queue.iter_mut().zip(block) {
+                queue.extend(channel);
+            }
+        }
+        self.in_flight = Some((passthrough(layout, inputs, frames), submitted));
+        self.latency = frames;
+
+        self.queue
+            .iter_mut()
+            .map(|queue| {
+                let missing = frames.saturating_sub(queue.len());
+                let mut output = vec![0.0; missing];
+                output.extend(queue.drain(..frames - missing));
+                output
+            })
+            .collect()
+    }
+
+    /// Delay the pipeline adds: one buffer, once audio has gone through it
+    pub fn latency(&self) -> usize {
+        self.latency
+    }
+
+    pub fn xruns(&self) -> u64 {
+        self.xruns
+    }
+
+    /// Drop the queued audio, e.g. on bypass, so it is not played later.
+    /// The xrun count is kept.
+    pub fn reset(&mut self) {
+        self.in_flight = None;
+        self.queue.clear();
+        self.latency = 0;
+    }
+}
+
+/// Load the plugin a catalogue entry describes, set up for `sample_rate`
+pub fn load_plugin(info: &VstPluginInfo, sample_rate: f32) -> Result<Box<dyn PluginProcessor>> {
+    load_plugin_path(
+        info.format,
+        &info.path,
+        info.plugin_id.as_deref(),
+        sample_rate,
+    )
+}
+
+/// Load a plugin by file (LV2: bundle directory); `plugin_id` picks one
+/// from a multi-plugin bundle
+pub fn load_plugin_path(
+    format: PluginFormat,
+    path: &Path,
+    plugin_id: Option<&str>,
+    sample_rate: f32,
+) -> Result<Box<dyn PluginProcessor>> {
+    Ok(match format {
+        PluginFormat::Vst2 => Box::new(
+            VstProcessor::load(path, sample_rate)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?,
+        ),
+        PluginFormat::Clap => Box::new(ClapProcessor::load(path, plugin_id, sample_rate)?),
+        PluginFormat::Lv2 => Box::new(Lv2Processor::load(path, plugin_id, sample_rate)?),
+        PluginFormat::Ladspa => Box::new(LadspaProcessor::load(path, plugin_id, sample_rate)?),
+    })
+}
+
+/// Plugin files of one format (LV2: bundle directories) in its standard
+/// locations and `extra_paths`
+pub fn plugin_files(format: PluginFormat, extra_paths: &[PathBuf]) -> Vec<PathBuf> {
+    let extra = extra_paths.iter().cloned();
+    match format {
+        PluginFormat::Vst2 => {
+            let mut scanner = VstScanner::new();
+            extra.for_each(|path| scanner.add_scan_path(path));
+            scanner.plugin_files()
+        }
+        PluginFormat::Clap => {
+            let mut scanner = ClapScanner::new();
+            extra.for_each(|path| scanner.add_scan_path(path));
+            scanner.plugin_files()
+        }
+        PluginFormat::Lv2 => {
+            let mut scanner = Lv2Scanner::new();
+            extra.for_each(|path| scanner.add_scan_path(path));
+            scanner.plugin_files()
+        }
+        PluginFormat::Ladspa => {
+            let mut scanner = LadspaScanner::new();
+            extra.for_each(|path| scanner.add_scan_path(path));
+            scanner.plugin_files()
+        }
+    }
+}
+
+/// Every VST2, CLAP, LV2 and LADSPA plugin in the standard and custom
+/// locations. Only files changed since the last scan are opened.
+pub fn scan_plugins() -> Vec<VstPluginInfo> {
+    let Ok(mut catalogue) = PluginCatalogue::global().lock() else {
+        return Vec::new();
+    };
+    let summary = catalogue.refresh();
+    log::info!(
+        "Plugin scan: {} scanned, {} cached, {} blacklisted, {} failed",
+        summary.scanned,
+        summary.cached,
+        summary.blacklisted,
+        summary.failed
+    );
+    if let Err(e) = catalogue.save() {
+        log::warn!("Failed to save plugin catalogue: {:#}", e);
+    }
+    catalogue.plugins()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loading_missing_plugin_fails() {
+        let path = Path::new("/nonexistent/phantomlink-test.clap");
+        assert!(load_plugin_path(PluginFormat::Clap, path, None, 48000.0).is_err());
+        assert!(load_plugin_path(PluginFormat::Vst2, path, None, 48000.0).is_err());
+        assert!(load_plugin_path(PluginFormat::Lv2, path, None, 48000.0).is_err());
+        assert!(load_plugin_path(PluginFormat::Ladspa, path, None, 48000.0).is_err());
+    }
+
+    /// Copies its inputs to its outputs in turn, to show what reached which port
+    struct Probe(ChannelLayout);
+
+    impl PluginProcessor for Probe {
+        fn format(&self) -> PluginFormat {
+            PluginFormat::Clap
+        }
+        fn channel_layout(&self) -> ChannelLayout {
+            self.0
+        }
+        fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+            (0..self.0.outputs)
+                .map(|output| inputs[output % inputs.len()].to_vec())
+                .collect()
+        }
+        fn get_plugin_name(&self) -> String {
+            "Probe".to_string()
+        }
+        fn set_parameter(&mut self, _index: i32, _value: f32) {}
+        fn get_parameter(&self, _index: i32) -> f32 {
+            0.0
+        }
+        fn get_parameter_info(&self, _index: i32) -> Option<ParameterInfo> {
+            None
+        }
+        fn get_parameter_count(&self) -> i32 {
+            0
+        }
+        fn set_enabled(&mut self, _enabled: bool) {}
+        fn is_enabled(&self) -> bool {
+            true
+        }
+        fn latency_samples(&self) -> usize {
+            0
+        }
+    }
+
+    #[test]
+    fn test_strip_channel_negotiation() {
+        let input = [0.5, -0.5];
+        let key = [0.1, 0.2];
+
+        // Mono into both inputs of a stereo effect
+        let mut stereo = Probe(ChannelLayout::new(2, 2));
+        assert_eq!(
+            process_strip(&mut stereo, &[&input], Some(&key)),
+            vec![input.to_vec(), input.to_vec()]
+        );
+
+        // Stereo keeps its sides apart
+        let right = [0.25, 0.75];
+        assert_eq!(
+            process_strip(&mut stereo, &[&input, &right], None),
+            vec![input.to_vec(), right.to_vec()]
+        );
+
+        // The sidechain port gets the key, or silence without one
+        let mut keyed = Probe(ChannelLayout::new(1, 2).with_sidechain(1));
+        assert_eq!(
+            process_strip(&mut keyed, &[&input], Some(&key)),
+            vec![input.to_vec(), key.to_vec()]
+        );
+        assert_eq!(
+            process_strip(&mut keyed, &[&input], None),
+            vec![input.to_vec(), vec![0.0; 2]]
+        );
+
+        // Extra outputs are dropped; no outputs passes the strip through
+        let mut surround = Probe(ChannelLayout::new(1, 6));
+        assert_eq!(process_strip(&mut surround, &[&input], None).len(), 2);
+        let mut analyser = Probe(ChannelLayout::new(1, 0));
+        assert_eq!(
+            process_strip(&mut analyser, &[&input], None),
+            vec![input.to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_passthrough_maps_inputs_to_outputs() {
+        let left = [1.0, 2.0];
+        let right = [3.0, 4.0];
+        let layout = ChannelLayout::new(2, 1).with_sidechain(1);
+        assert_eq!(
+            passthrough(layout, &[&left, &right, &[9.0, 9.0]], 2),
+            vec![left.to_vec()]
+        );
+        let layout = ChannelLayout::new(1, 2);
+        assert_eq!(
+            passthrough(layout, &[&left], 2),
+            vec![left.to_vec(), left.to_vec()]
+        );
+        let layout = ChannelLayout::new(0, 1);
+        assert_eq!(passthrough(layout, &[], 2), vec![vec![0.0; 2]]);
+    }
+
+    #[test]
+    fn test_scene_keeps_last_value_per_parameter() {
+        let change = |index, value| ParameterChange { index, value };
+        let mut scene = PluginScene::new("Intro");
+        scene.record(0, "Gate", change(2, 0.25));
+        scene.record(1, "Gate", change(2, 0.5));
+        scene.record(0, "Gate", change(2, 1.5));
+        let values: Vec<_> = scene
+            .parameters
+            .iter()
+            .map(|p| (p.channel, p.index, p.value))
+            .collect();
+        assert_eq!(values, vec![(0, 2, 1.0), (1, 2, 0.5)]);
+
+        // A new plugin on channel 1 replaces what the old one recorded
+        scene.record(1, "Comp", change(0, 0.75));
+        let values: Vec<_> = scene
+            .parameters
+            .iter()
+            .map(|p| (p.channel, p.plugin.as_str(), p.index))
+            .collect();
+        assert_eq!(values, vec![(0, "Gate", 2), (1, "Comp", 0)]);
+        let json = serde_json::to_string(&scene).unwrap();
+        assert_eq!(serde_json::from_str::<PluginScene>(&json).unwrap(), scene);
+    }
+
+    #[test]
+    fn test_pipeline_runs_one_buffer_behind() {
+        let layout = ChannelLayout::new(1, 1);
+        let mut pipeline = BlockPipeline::new();
+        assert_eq!(pipeline.wait_time(4, 48000.0), None);
+
+        // The first buffer is silence while the plugin works on it
+        let out = pipeline.advance(layout, &[&[1.0; 4]], None, true);
+        assert_eq!(out, vec![vec![0.0; 4]]);
+        assert_eq!(pipeline.latency(), 4);
+        assert!(pipeline.wait_time(4, 48000.0).is_some());
+
+        // Then each buffer plays the block processed the callback before
+        let out = pipeline.advance(layout, &[&[2.0; 4]], Some(vec![vec![0.5; 4]]), true);
+        assert_eq!(out, vec![vec![0.5; 4]]);
+        assert_eq!(pipeline.xruns(), 0);
+    }
+
+    #[test]
+    fn test_pipeline_plays_late_blocks_dry() {
+        let layout = ChannelLayout::new(1, 2);
+        let mut pipeline = BlockPipeline::new();
+        pipeline.advance(layout, &[&[1.0; 4]], None, true);
+
+        // Missing, or the wrong shape: the dry block stands in
+        let out = pipeline.advance(layout, &[&[2.0; 4]], None, true);
+        assert_eq!(out, vec![vec![1.0; 4]; 2]);
+        let out = pipeline.advance(layout, &[&[3.0; 4]], Some(vec![vec![0.0; 3]; 2]), false);
+        assert_eq!(out, vec![vec![2.0; 4]; 2]);
+        assert_eq!(pipeline.xruns(), 2);
+
+        // Nothing to wait for after a block that was never submitted
+        assert_eq!(pipeline.wait_time(4, 48000.0), None);
+        pipeline.reset();
+        assert_eq!(pipeline.latency(), 0);
+        assert_eq!(pipeline.xruns(), 2);
+    }
+
+    #[test]
+    fn test_saved_plugin_state_is_hex() {
+        let mut saved: SavedPlugin = serde_json::from_value(serde_json::json!({
+            "info": {
+                "path": "/usr/lib/vst/gate.so", "name": "Gate", "vendor": "",
+                "category": "Effect", "unique_id": 1, "version": 1, "inputs": 2,
+                "outputs": 2, "parameters": 3, "is_synth": false,
+                "format": "Vst2", "plugin_id": null
+            }
+        }))
+        .unwrap();
+        assert!(saved.state.is_empty());
+
+        saved.state = vec![0x00, 0x7f, 0xff];
+        let json = serde_json::to_value(&saved).unwrap();
+        assert_eq!(json["state"], "007fff");
+        let loaded: SavedPlugin = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.state, saved.state);
+
+        let mut bad = serde_json::to_value(&saved).unwrap();
+        bad["state"] = "0g".into();
+        assert!(serde_json::from_value::<SavedPlugin>(bad).is_err());
+    }
+
+    #[test]
+    fn test_format_names_round_trip() {
+        for format in [
+            PluginFormat::Vst2,
+            PluginFormat::Clap,
+            PluginFormat::Lv2,
+            PluginFormat::Ladspa,
+        ] {
+            assert_eq!(PluginFormat::from_name(format.name()), Some(format));
+        }
+        assert_eq!(PluginFormat::from_name("clap"), Some(PluginFormat::Clap));
+        assert_eq!(PluginFormat::from_name("AU"), None);
+    }
+}
@@ -0,0 +1,1260 @@
+//! Multitrack recording of channels and output buses.
+//!
+//! The audio callback never touches the disk. While a recording runs it copies
+//! each buffer into a bounded lock-free queue and moves on, dropping the
+//! buffer (and counting it) if the queue is full. A disk-writer thread drains
+//! the queue into one WAV or FLAC file per track.
+//!
+//! Recordings survive crashes: WAV headers are rewritten every couple of
+//! seconds, FLAC frames are self-contained, and a session directory carries a
+//! `.recording` lock file until it is closed cleanly. Starting the next
+//! recording repairs the headers of any session still holding that lock.
+//!
+//! The same queue feeds an optional instant-replay buffer: the writer keeps
+//! the last few minutes of the master bus and one microphone channel in
+//! memory, and saves them to disk on request even when nothing was recording.
+
+#![allow(dead_code)] // Complete recorder API
+
+use crate::audio::BUS_CHANNELS;
+use crate::audio_file::{AudioFileWriter, AudioFormat, AudioSpec};
+use anyhow::Result;
+use crossbeam_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Audio buffers that may wait for the disk; about 20 s at 1024-frame buffers
+const QUEUE_CAPACITY: usize = 1024;
+
+/// How often WAV headers are brought up to date while recording
+const HEADER_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often free disk space is checked while recording
+const SPACE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Present in a session directory until the recording is closed cleanly
+const LOCK_FILE_NAME: &str = ".recording";
+
+const MARKERS_FILE_NAME: &str = "markers.csv";
+
+/// Where a channel is tapped for recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TapPoint {
+    /// The signal entering the channel strip
+    PreFx,
+    /// The channel's stereo output after denoising, plugins and effects
+    PostFx,
+}
+
+impl TapPoint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TapPoint::PreFx => "pre",
+            TapPoint::PostFx => "post",
+        }
+    }
+
+    /// Parse a name as used by IPC ("pre"/"post", case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "pre" | "prefx" | "pre-fx" => Some(TapPoint::PreFx),
+            "post" | "postfx" | "post-fx" => Some(TapPoint::PostFx),
+            _ => None,
+        }
+    }
+}
+
+/// What to record and where
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecorderConfig {
+    /// Each recording gets a timestamped directory below this one
+    #[serde(default = "default_recording_directory")]
+    pub directory: PathBuf,
+    /// File extension, "wav" or "flac"
+    #[serde(default = "default_recording_format")]
+    pub format: String,
+    #[serde(default = "default_recording_bits")]
+    pub bits_per_sample: u16,
+    /// Tap point per channel, or None to leave the channel out
+    #[serde(default = "default_channel_taps")]
+    pub channel_taps: Vec<Option<TapPoint>>,
+    /// Record every output bus as well
+    #[serde(default = "default_true")]
+    pub record_buses: bool,
+    /// Refuse to start, and stop, when free space drops below this
+    #[serde(default = "default_min_free_mb")]
+    pub min_free_mb: u64,
+    /// Keep the last `replay_seconds` of audio in memory while the engine runs
+    #[serde(default)]
+    pub replay_enabled: bool,
+    #[serde(default = "default_replay_seconds")]
+    pub replay_seconds: u32,
+    /// Channel kept in the replay buffer next to the master bus
+    #[serde(default = "default_replay_channel")]
+    pub replay_channel: Option<usize>,
+}
+
+fn default_recording_directory() -> PathBuf {
+    dirs::audio_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("PhantomLink")
+}
+
+fn default_recording_format() -> String {
+    "wav".to_string()
+}
+
+fn default_recording_bits() -> u16 {
+    24
+}
+
+fn default_channel_taps() -> Vec<Option<TapPoint>> {
+    vec![Some(TapPoint::PostFx); 4]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_free_mb() -> u64 {
+    512
+}
+
+fn default_replay_seconds() -> u32 {
+    120
+}
+
+fn default_replay_channel() -> Option<usize> {
+    Some(0)
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            directory: default_recording_directory(),
+            format: default_recording_format(),
+            bits_per_sample: default_recording_bits(),
+            channel_taps: default_channel_taps(),
+            record_buses: true,
+            min_free_mb: default_min_free_mb(),
+            replay_enabled: false,
+            replay_seconds: default_replay_seconds(),
+            replay_channel: default_replay_channel(),
+        }
+    }
+}
+
+/// Sample layout of the running input stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamLayout {
+    pub sample_rate: u32,
+    pub input_channels: u16,
+}
+
+/// One callback's worth of audio for every tap point
+#[derive(Debug, Clone, Default)]
+pub struct RecordBlock {
+    /// Interleaved device input, as every channel strip receives it
+    pub input: Vec<f32>,
+    /// Stereo interleaved output of each channel strip
+    pub channels: Vec<Vec<f32>>,
+    /// Output of each bus, in `OUTPUT_BUSES` order
+    pub buses: Vec<Vec<f32>>,
+}
+
+/// Position of a marker within a recording
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordingMarker {
+    pub label: String,
+    pub frame: u64,
+    pub seconds: f64,
+}
+
+/// Progress of the current or last recording
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecorderStatus {
+    pub recording: bool,
+    pub session: Option<PathBuf>,
+    pub elapsed_secs: f64,
+    pub tracks: Vec<String>,
+    pub markers: usize,
+    /// Buffers the disk writer could not keep up with
+    pub dropped_buffers: u64,
+    pub free_space_mb: Option<u64>,
+    /// Why the last recording ended early, if it did
+    pub error: Option<String>,
+    /// Seconds held by the replay buffer, or None when it is off
+    pub replay_buffered_secs: Option<f64>,
+}
+
+/// A finished recording
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingSummary {
+    pub directory: PathBuf,
+    pub files: Vec<PathBuf>,
+    pub duration_secs: f64,
+    pub markers: Vec<RecordingMarker>,
+    pub dropped_buffers: u64,
+}
+
+/// Files written from the replay buffer
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplaySummary {
+    pub files: Vec<PathBuf>,
+    pub duration_secs: f64,
+}
+
+enum WriterMessage {
+    Start(Box<Session>),
+    Block(RecordBlock),
+    /// Label and how many frames back from the write position it belongs
+    Marker(Option<String>, u64),
+    Stop(Sender<Result<RecordingSummary>>),
+    Replay(Option<Box<ReplayBuffer>>),
+    SaveReplay(Sender<Result<ReplaySummary>>),
+}
+
+/// Flags shared between the audio callback, the control side and the writer
+#[derive(Debug, Default)]
+struct TapState {
+    recording: AtomicBool,
+    replay: AtomicBool,
+    dropped: AtomicU64,
+}
+
+/// The audio callback's handle on the recorder; never blocks
+pub struct RecorderTap {
+    sender: Sender<WriterMessage>,
+    state: Arc<TapState>,
+}
+
+impl RecorderTap {
+    pub fn is_recording(&self) -> bool {
+        self.state.recording.load(Ordering::Relaxed)
+    }
+
+    /// Whether the writer wants audio, for a recording or the replay buffer
+    pub fn is_active(&self) -> bool {
+        self.is_recording() || self.state.replay.load(Ordering::Relaxed)
+    }
+
+    /// Queue a buffer for the disk writer, dropping it if the queue is full
+    pub fn push(&self, block: RecordBlock) {
+        if self.sender.try_send(WriterMessage::Block(block)).is_err() {
+            self.state.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrackSource {
+    Channel { index: usize, tap: TapPoint },
+    Bus(usize),
+}
+
+impl TrackSource {
+    fn samples<'a>(&self, block: &'a RecordBlock) -> Option<&'a Vec<f32>> {
+        match *self {
+            TrackSource::Channel {
+                tap: TapPoint::PreFx,
+                ..
+            } => Some(&block.input),
+            TrackSource::Channel { index, .. } => block.channels.get(index),
+            TrackSource::Bus(index) => block.buses.get(index),
+        }
+    }
+
+    /// Interleaved channels this source carries
+    fn channels(&self, layout: StreamLayout) -> u16 {
+        match self {
+            TrackSource::Channel {
+                tap: TapPoint::PostFx,
+                ..
+            } => 2,
+            TrackSource::Bus(_) => BUS_CHANNELS as u16,
+            TrackSource::Channel { .. } => layout.input_channels.max(1),
+        }
+    }
+}
+
+/// Validate the configured format, returning the file extension to use
+fn recording_extension(config: &RecorderConfig) -> Result<String> {
+    let extension = config.format.to_ascii_lowercase();
+    let format = AudioFormat::from_path(Path::new(&format!("track.{}", extension)))?;
+    if !format.supports_bits(config.bits_per_sample) {
+        anyhow::bail!(
+            "{} recording does not support {}-bit samples",
+            format.name(),
+            config.bits_per_sample
+        );
+    }
+    Ok(extension)
+}
+
+/// Fail unless the filesystem holding `directory` has `min_free_mb` left
+fn check_free_space(directory: &Path, min_free_mb: u64) -> Result<Option<u64>> {
+    let free_space = free_space_bytes(directory);
+    if let Some(free) = free_space
+        && free < min_free_mb * 1024 * 1024
+    {
+        anyhow::bail!(
+            "Only {} MB free in {}, need at least {} MB to record",
+            free / (1024 * 1024),
+            directory.display(),
+            min_free_mb
+        );
+    }
+    Ok(free_space)
+}
+
+struct Track {
+    source: TrackSource,
+    path: PathBuf,
+    writer: AudioFileWriter,
+}
+
+/// An open recording, owned by the disk-writer thread
+struct Session {
+    directory: PathBuf,
+    sample_rate: u32,
+    input_channels: usize,
+    tracks: Vec<Track>,
+    markers: Vec<RecordingMarker>,
+    frames: u64,
+    min_free_bytes: u64,
+    free_space: Option<u64>,
+    last_flush: Instant,
+    last_space_check: Instant,
+}
+
+impl Session {
+    fn open(config: &RecorderConfig, layout: StreamLayout, bus_names: &[&str]) -> Result<Self> {
+        let extension = recording_extension(config)?;
+        fs::create_dir_all(&config.directory)
+            .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", config.directory.display(), e))?;
+        match recover_interrupted_sessions(&config.directory) {
+            Ok(repaired) if !repaired.is_empty() => {
+                log::warn!(
+                    "Repaired {} files from an interrupted recording",
+                    repaired.len()
+                )
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to check for interrupted recordings: {}", e),
+        }
+
+        let min_free_bytes = config.min_free_mb * 1024 * 1024;
+        let free_space = check_free_space(&config.directory, config.min_free_mb)?;
+
+        let mut sources: Vec<(TrackSource, String)> = config
+            .channel_taps
+            .iter()
+            .enumerate()
+            .filter_map(|(index, tap)| {
+                tap.map(|tap| {
+                    (
+                        TrackSource::Channel { index, tap },
+                        format!("channel-{}-{}", index + 1, tap.name()),
+                    )
+                })
+            })
+            .collect();
+        if config.record_buses {
+            sources.extend(
+                bus_names
+                    .iter()
+                    .enumerate()
+                    .map(|(index, name)| (TrackSource::Bus(index), name.to_string())),
+            );
+        }
+        if sources.is_empty() {
+            anyhow::bail!("No channels or buses are armed for recording");
+        }
+
+        let directory = unique_session_directory(&config.directory, SystemTime::now());
+        fs::create_dir_all(&directory)
+            .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", directory.display(), e))?;
+        File::create(directory.join(LOCK_FILE_NAME))?;
+
+        let mut tracks = Vec::with_capacity(sources.len());
+        for (source, name) in sources {
+            let channels = source.channels(layout);
+            let path = directory.join(format!("{}.{}", name, extension));
+            let spec = AudioSpec {
+                sample_rate: layout.sample_rate,
+                channels,
+                bits_per_sample: config.bits_per_sample,
+            };
+            let writer = AudioFileWriter::create(&path, spec)?;
+            tracks.push(Track {
+                source,
+                path,
+                writer,
+            });
+        }
+
+        let now = Instant::now();
+        Ok(Self {
+            directory,
+            sample_rate: layout.sample_rate.max(1),
+            input_channels: layout.input_channels.max(1) as usize,
+            tracks,
+            markers: Vec::new(),
+            frames: 0,
+            min_free_bytes,
+            free_space,
+            last_flush: now,
+            last_space_check: now,
+        })
+    }
+
+    fn elapsed_secs(&self) -> f64 {
+        self.frames as f64 / self.sample_rate as f64
+    }
+
+    fn write(&mut self, block: &RecordBlock) -> Result<()> {
+        for track in &mut self.tracks {
+            if let Some(samples) = track.source.samples(block) {
+                track.writer.write(samples)?;
+            }
+        }
+        self.frames += (block.input.len() / self.input_channels) as u64;
+        Ok(())
+    }
+
+    fn add_marker(&mut self, label: Option<String>, offset: u64) -> Result<()> {
+        let frame = self.frames.saturating_sub(offset);
+        let marker = RecordingMarker {
+            label: label.unwrap_or_else(|| format!("Marker {}", self.markers.len() + 1)),
+            frame,
+            seconds: frame as f64 / self.sample_rate as f64,
+        };
+
+        // Appended right away so markers survive a crash along with the audio
+        let path = self.directory.join(MARKERS_FILE_NAME);
+        let is_new = !path.exists();
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        if is_new {
+            writeln!(file, "marker,seconds,frame,label")?;
+        }
+        writeln!(
+            file,
+            "{},{:.3},{},\"{}\"",
+            self.markers.len() + 1,
+            marker.seconds,
+            marker.frame,
+            marker.label.replace('"', "\"\"")
+        )?;
+
+        self.markers.push(marker);
+        Ok(())
+    }
+
+    /// Periodic header refresh and disk-space check; errors end the recording
+    fn maintain(&mut self) -> Result<()> {
+        if self.last_flush.elapsed() >= HEADER_FLUSH_INTERVAL {
+            for track in &mut self.tracks {
+                track.writer.flush()?;
+            }
+            self.last_flush = Instant::now();
+        }
+
+        if self.last_space_check.elapsed() >= SPACE_CHECK_INTERVAL {
+            self.free_space = free_space_bytes(&self.directory);
+            self.last_space_check = Instant::now();
+            if let Some(free) = self.free_space
+                && free < self.min_free_bytes
+            {
+                anyhow::bail!(
+                    "Recording stopped: only {} MB of disk space left",
+                    free / (1024 * 1024)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self, dropped_buffers: u64) -> Result<RecordingSummary> {
+        let duration_secs = self.elapsed_secs();
+        let mut files = Vec::with_capacity(self.tracks.len());
+        for track in self.tracks {
+            track.writer.finalize()?;
+            files.push(track.path);
+        }
+        fs::remove_file(self.directory.join(LOCK_FILE_NAME))?;
+
+        Ok(RecordingSummary {
+            directory: self.directory,
+            files,
+            duration_secs,
+            markers: self.markers,
+            dropped_buffers,
+        })
+    }
+
+    fn status(&self, state: &TapState) -> RecorderStatus {
+        RecorderStatus {
+            recording: true,
+            session: Some(self.directory.clone()),
+            elapsed_secs: self.elapsed_secs(),
+            tracks: self
+                .tracks
+                .iter()
+                .filter_map(|t| t.path.file_name())
+                .map(|n| n.to_string_lossy().into_owned())
+                .collect(),
+            markers: self.markers.len(),
+            dropped_buffers: state.dropped.load(Ordering::Relaxed),
+            free_space_mb: self.free_space.map(|b| b / (1024 * 1024)),
+            error: None,
+            replay_buffered_secs: None,
+        }
+    }
+}
+
+/// Rolling copy of one source for the replay buffer
+struct ReplaySource {
+    name: String,
+    source: TrackSource,
+    channels: u16,
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+/// The last few minutes of the master bus and a microphone, in memory
+struct ReplayBuffer {
+    directory: PathBuf,
+    extension: String,
+    bits_per_sample: u16,
+    min_free_mb: u64,
+    sample_rate: u32,
+    sources: Vec<ReplaySource>,
+}
+
+impl ReplayBuffer {
+    fn new(config: &RecorderConfig, layout: StreamLayout, bus_names: &[&str]) -> Result<Self> {
+        let extension = recording_extension(config)?;
+        let frames = config.replay_seconds.max(1) as usize * layout.sample_rate as usize;
+
+        let mut sources: Vec<(TrackSource, String)> = bus_names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (TrackSource::Bus(index), name.to_string()))
+            .collect();
+        if let Some(index) = config.replay_channel {
+            sources.push((
+                TrackSource::Channel {
+                    index,
+                    tap: TapPoint::PostFx,
+                },
+                format!("channel-{}", index + 1),
+            ));
+        }
+
+        Ok(Self {
+            directory: config.directory.clone(),
+            extension,
+            bits_per_sample: config.bits_per_sample,
+            min_free_mb: config.min_free_mb,
+            sample_rate: layout.sample_rate.max(1),
+            sources: sources
+                .into_iter()
+                .map(|(source, name)| {
+                    let channels = source.channels(layout);
+                    ReplaySource {
+                        name,
+                        source,
+                        channels,
+                        samples: VecDeque::new(),
+                        capacity: frames * channels as usize,
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    fn push(&mut self, block: &RecordBlock) {
+        for replay in &mut self.sources {
+            if let Some(samples) = replay.source.samples(block) {
+                replay.samples.extend(samples);
+                let excess = replay.samples.len().saturating_sub(replay.capacity);
+                replay.samples.drain(..excess);
+            }
+        }
+    }
+
+    fn buffered_secs(&self) -> f64 {
+        self.sources
+            .first()
+            .map_or(0, |r| r.samples.len() / r.channels.max(1) as usize) as f64
+            / self.sample_rate as f64
+    }
+
+    /// Write everything buffered so far, leaving the buffer running
+    fn save(&self) -> Result<ReplaySummary> {
+        fs::create_dir_all(&self.directory)
+            .map_err(|e| anyhow::anyhow!("Cannot create {}: {}", self.directory.display(), e))?;
+        check_free_space(&self.directory, self.min_free_mb)?;
+
+        let stamp = timestamp(SystemTime::now());
+        let mut files = Vec::with_capacity(self.sources.len());
+        for replay in &self.sources {
+            let path = self.directory.join(format!(
+                "replay-{}-{}.{}",
+                stamp, replay.name, self.extension
+            ));
+            let spec = AudioSpec {
+                sample_rate: self.sample_rate,
+                channels: replay.channels,
+                bits_per_sample: self.bits_per_sample,
+            };
+            let mut writer = AudioFileWriter::create(&path, spec)?;
+            let (head, tail) = replay.samples.as_slices();
+            writer.write(head)?;
+            writer.write(tail)?;
+            writer.finalize()?;
+            files.push(path);
+        }
+
+        log::info!("Saved {:.1} s replay", self.buffered_secs());
+        Ok(ReplaySummary {
+            files,
+            duration_secs: self.buffered_secs(),
+        })
+    }
+}
+
+/// Records channels and buses to disk through a background writer thread
+pub struct Recorder {
+    tap: Arc<RecorderTap>,
+    /// Handed to the writer thread when the first recording starts
+    receiver: Option<Receiver<WriterMessage>>,
+    writer_thread: Option<JoinHandle<()>>,
+    status: Arc<Mutex<RecorderStatus>>,
+    /// Frames markers are moved back by, so they land where the audio was
+    /// heard rather than where the writer had got to
+    marker_offset: u64,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded(QUEUE_CAPACITY);
+        Self {
+            tap: Arc::new(RecorderTap {
+                sender,
+                state: Arc::new(TapState::default()),
+            }),
+            receiver: Some(receiver),
+            writer_thread: None,
+            status: Arc::new(Mutex::new(RecorderStatus::default())),
+            marker_offset: 0,
+        }
+    }
+
+    /// Handle for the audio callback
+    pub fn tap(&self) -> Arc<RecorderTap> {
+        Arc::clone(&self.tap)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.tap.is_recording()
+    }
+
+    pub fn status(&self) -> RecorderStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Open one file per armed track in a new timestamped directory
+    pub fn start(
+        &mut self,
+        config: &RecorderConfig,
+        layout: StreamLayout,
+        bus_names: &[&str],
+    ) -> Result<PathBuf> {
+        if self.is_recording() {
+            anyhow::bail!("Already recording");
+        }
+
+        let session = Session::open(config, layout, bus_names)?;
+        let directory = session.directory.clone();
+        self.ensure_writer_thread()?;
+
+        self.tap.state.dropped.store(0, Ordering::Relaxed);
+        self.tap
+            .sender
+            .send(WriterMessage::Start(Box::new(session)))
+            .map_err(|_| anyhow::anyhow!("Recorder disk writer has stopped"))?;
+        self.tap.state.recording.store(true, Ordering::Relaxed);
+
+        log::info!("Recording to {}", directory.display());
+        Ok(directory)
+    }
+
+    /// Finish every file of the current recording
+    pub fn stop(&mut self) -> Result<RecordingSummary> {
+        if self.writer_thread.is_none() {
+            anyhow::bail!("Not recording");
+        }
+        self.tap.state.recording.store(false, Ordering::Relaxed);
+
+        let (reply, result) = crossbeam_channel::bounded(1);
+        self.tap
+            .sender
+            .send(WriterMessage::Stop(reply))
+            .map_err(|_| anyhow::anyhow!("Recorder disk writer has stopped"))?;
+        let summary = result
+            .recv_timeout(Duration::from_secs(10))
+            .map_err(|_| anyhow::anyhow!("Timed out waiting for the disk writer"))??;
+
+        log::info!(
+            "Recorded {:.1} s to {}",
+            summary.duration_secs,
+            summary.directory.display()
+        );
+        Ok(summary)
+    }
+
+    /// Start the replay buffer, replacing (and emptying) any running one
+    pub fn start_replay(
+        &mut self,
+        config: &RecorderConfig,
+        layout: StreamLayout,
+        bus_names: &[&str],
+    ) -> Result<()> {
+        let buffer = ReplayBuffer::new(config, layout, bus_names)?;
+        self.ensure_writer_thread()?;
+        self.tap
+            .sender
+            .send(WriterMessage::Replay(Some(Box::new(buffer))))
+            .map_err(|_| anyhow::anyhow!("Recorder disk writer has stopped"))?;
+        self.tap.state.replay.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Stop the replay buffer and free its memory
+    pub fn stop_replay(&mut self) {
+        if self.tap.state.replay.swap(false, Ordering::Relaxed) {
+            let _ = self.tap.sender.send(WriterMessage::Replay(None));
+        }
+    }
+
+    pub fn is_replay_enabled(&self) -> bool {
+        self.tap.state.replay.load(Ordering::Relaxed)
+    }
+
+    /// Save the replay buffer's contents next to the recordings
+    pub fn save_replay(&self) -> Result<ReplaySummary> {
+        if !self.is_replay_enabled() {
+            anyhow::bail!("Replay buffer is off");
+        }
+
+        let (reply, result) = crossbeam_channel::bounded(1);
+        self.tap
+            .sender
+            .send(WriterMessage::SaveReplay(reply))
+            .map_err(|_| anyhow::anyhow!("Recorder disk writer has stopped"))?;
+        result
+            .recv_timeout(Duration::from_secs(30))
+            .map_err(|_| anyhow::anyhow!("Timed out waiting for the disk writer"))?
+    }
+
+    /// Mark the current position, e.g. to find a flubbed take later
+    pub fn add_marker(&self, label: Option<String>) -> Result<()> {
+        if !self.is_recording() {
+            anyhow::bail!("Not recording");
+        }
+        self.tap
+            .sender
+            .send(WriterMessage::Marker(label, self.marker_offset))
+            .map_err(|_| anyhow::anyhow!("Recorder disk writer has stopped"))
+    }
+
+    /// Set the measured round trip, in frames, that markers are moved back by
+    pub fn set_marker_offset(&mut self, frames: u64) {
+        self.marker_offset = frames;
+    }
+
+    fn ensure_writer_thread(&mut self) -> Result<()> {
+        if let Some(receiver) = self.receiver.take() {
+            let state = Arc::clone(&self.tap.state);
+            let status = Arc::clone(&self.status);
+            let handle = std::thread::Builder::new()
+                .name("recorder-disk-writer".to_string())
+                .spawn(move || run_disk_writer(receiver, state, status))?;
+            self.writer_thread = Some(handle);
+        }
+        Ok(())
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if self.is_recording()
+            && let Err(e) = self.stop()
+        {
+            log::error!("Failed to finish recording: {}", e);
+        }
+    }
+}
+
+fn run_disk_writer(
+    receiver: Receiver<WriterMessage>,
+    state: Arc<TapState>,
+    status: Arc<Mutex<RecorderStatus>>,
+) {
+    let mut session: Option<Session> = None;
+    let mut replay: Option<Box<ReplayBuffer>> = None;
+    let mut last_error: Option<String> = None;
+
+    // Ends once the recorder and every audio callback holding its tap are gone
+    for message in receiver {
+        let result = match message {
+            WriterMessage::Start(new_session) => {
+                last_error = None;
+                session = Some(*new_session);
+                Ok(())
+            }
+            WriterMessage::Block(block) => {
+                if let Some(ref mut buffer) = replay {
+                    buffer.push(&block);
+                }
+                match session.as_mut() {
+                    Some(s) => s.write(&block).and_then(|_| s.maintain()),
+                    None => Ok(()),
+                }
+            }
+            WriterMessage::Replay(buffer) => {
+                replay = buffer;
+                Ok(())
+            }
+            WriterMessage::SaveReplay(reply) => {
+                let summary = match replay {
+                    Some(ref buffer) => buffer.save(),
+                    None => Err(anyhow::anyhow!("Replay buffer is off")),
+                };
+                let _ = reply.send(summary);
+                Ok(())
+            }
+            WriterMessage::Marker(label, offset) => match session.as_mut() {
+                Some(s) => s.add_marker(label, offset),
+                None => Ok(()),
+            },
+            WriterMessage::Stop(reply) => {
+                let summary = match session.take() {
+                    Some(s) => s.finish(state.dropped.load(Ordering::Relaxed)),
+                    None => Err(anyhow::anyhow!(
+                        last_error
+                            .clone()
+                            .unwrap_or_else(|| "Not recording".to_string())
+                    )),
+                };
+                let _ = reply.send(summary);
+                Ok(())
+            }
+        };
+
+        if let Err(e) = result
+            && let Some(s) = session.take()
+        {
+            log::error!("Recording to {} failed: {:#}", s.directory.display(), e);
+            state.recording.store(false, Ordering::Relaxed);
+            if let Err(close_error) = s.finish(state.dropped.load(Ordering::Relaxed)) {
+                log::error!("Failed to close recording: {:#}", close_error);
+            }
+            last_error = Some(format!("{:#}", e));
+        }
+
+        if let Ok(mut status) = status.lock() {
+            let replay_buffered_secs = replay.as_ref().map(|b| b.buffered_secs());
+            *status = match session {
+                Some(ref s) => s.status(&state),
+                None => RecorderStatus {
+                    session: status.session.take(),
+                    elapsed_secs: status.elapsed_secs,
+                    tracks: std::mem::take(&mut status.tracks),
+                    markers: status.markers,
+                    dropped_buffers: state.dropped.load(Ordering::Relaxed),
+                    error: last_error.clone(),
+                    ..RecorderStatus::default()
+                },
+            };
+            status.replay_buffered_secs = replay_buffered_secs;
+        }
+    }
+}
+
+/// Local time as `YYYYMMDD-HHMMSS`
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0) as libc::time_t;
+    // SAFETY: tm is plain data, and both pointers are valid for the call
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&secs, &mut tm) };
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+/// `phantomlink-<timestamp>` below `root`, suffixed if that already exists
+fn unique_session_directory(root: &Path, time: SystemTime) -> PathBuf {
+    let name = format!("phantomlink-{}", timestamp(time));
+    let mut directory = root.join(&name);
+    let mut suffix = 2;
+    while directory.exists() {
+        directory = root.join(format!("{}-{}", name, suffix));
+        suffix += 1;
+    }
+    directory
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`
+pub fn free_space_bytes(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statvfs is plain data and c_path is NUL-terminated
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)] // fsblkcnt_t is 32-bit on some targets
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+/// Repair every session under `root` that was never closed cleanly,
+/// returning the files whose headers were rewritten
+pub fn recover_interrupted_sessions(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut repaired = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let directory = entry?.path();
+        if !directory.join(LOCK_FILE_NAME).exists() {
+            continue;
+        }
+
+        // FLAC needs no repair: frames decode on their own and STREAMINFO
+        // reports an unknown length until the file is finalized
+        for file in fs::read_dir(&directory)? {
+            let path = file?.path();
+            let is_wav = AudioFormat::from_path(&path).is_ok_and(|f| f == AudioFormat::Wav);
+            if is_wav {
+                match repair_wav_header(&path) {
+                    Ok(true) => repaired.push(path),
+                    Ok(false) => {}
+                    Err(e) => log::warn!("Cannot repair {}: {}", path.display(), e),
+                }
+            }
+        }
+        fs::remove_file(directory.join(LOCK_FILE_NAME))?;
+    }
+    Ok(repaired)
+}
+
+/// Fix the RIFF and data chunk sizes of a WAV file that was cut off, dropping
+/// any trailing partial frame. Returns whether anything had to change.
+pub fn repair_wav_header(path: &Path) -> Result<bool> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let file_len = file.metadata()?.len();
+
+    let mut header = [0u8; 12];
+    file.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        anyhow::bail!("{} is not a WAV file", path.display());
+    }
+
+    // Walk the chunks up to "data"; its recorded size can't be trusted
+    let mut position = 12u64;
+    let mut block_align = 0u64;
+    let data_start = loop {
+        if position + 8 > file_len {
+            anyhow::bail!("{} has no data chunk", path.display());
+        }
+        let mut chunk = [0u8; 8];
+        file.seek(SeekFrom::Start(position))?;
+        file.read_exact(&mut chunk)?;
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+
+        if &chunk[0..4] == b"fmt " {
+            let mut format = [0u8; 14];
+            file.read_exact(&mut format)?;
+            block_align = u16::from_le_bytes([format[12], format[13]]) as u64;
+        } else if &chunk[0..4] == b"data" {
+            break position + 8;
+        }
+        position += 8 + size + (size & 1);
+    };
+    if block_align == 0 {
+        anyhow::bail!("{} has no usable fmt chunk", path.display());
+    }
+
+    let data_len = (file_len - data_start) / block_align * block_align;
+    let riff_len = data_start + data_len - 8;
+
+    let mut recorded = [0u8; 4];
+    file.seek(SeekFrom::Start(data_start - 4))?;
+    file.read_exact(&mut recorded)?;
+    let up_to_date = u32::from_le_bytes(header[4..8].try_into()?) as u64 == riff_len
+        && u32::from_le_bytes(recorded) as u64 == data_len
+        && data_start + data_len == file_len;
+    if up_to_date {
+        return Ok(false);
+    }
+
+    file.set_len(data_start + data_len)?;
+    file.seek(SeekFrom::Start(4))?;
+    file.write_all(&(riff_len as u32).to_le_bytes())?;
+    file.seek(SeekFrom::Start(data_start - 4))?;
+    file.write_all(&(data_len as u32).to_le_bytes())?;
+    file.flush()?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::ChannelProcessor;
+    use crate::audio_file::read_audio_file;
+
+    fn test_config(name: &str) -> RecorderConfig {
+        let directory = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&directory);
+        RecorderConfig {
+            directory,
+            channel_taps: vec![Some(TapPoint::PreFx), Some(TapPoint::PostFx), None, None],
+            min_free_mb: 0,
+            ..RecorderConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_records_tracks_and_markers() {
+        let config = test_config("phantomlink_test_recorder");
+        let layout = StreamLayout {
+            sample_rate: 48000,
+            input_channels: 1,
+        };
+        let mut recorder = Recorder::new();
+        let tap = recorder.tap();
+        assert!(!tap.is_recording());
+
+        let directory = recorder.start(&config, layout, &["master"]).unwrap();
+        assert!(recorder.start(&config, layout, &["master"]).is_err());
+        assert!(tap.is_recording());
+        for i in 0..10 {
+            if i == 5 {
+                recorder.add_marker(Some("Retake".to_string())).unwrap();
+            }
+            if i == 8 {
+                // A calibrated round trip moves the marker back to what was heard
+                recorder.set_marker_offset(960);
+                recorder.add_marker(None).unwrap();
+            }
+            tap.push(RecordBlock {
+                input: vec![0.25; 480],
+                channels: vec![vec![0.5; 960]; 4],
+                buses: vec![vec![-0.5; 960]],
+            });
+        }
+        let summary = recorder.stop().unwrap();
+
+        assert_eq!(summary.directory, directory);
+        assert_eq!(summary.files.len(), 3);
+        assert!((summary.duration_secs - 0.1).abs() < 1e-9);
+        assert_eq!(summary.markers.len(), 2);
+        assert_eq!(summary.markers[0].frame, 2400);
+        assert_eq!(summary.markers[1].frame, 3840 - 960);
+        assert!(!directory.join(LOCK_FILE_NAME).exists());
+
+        let pre = read_audio_file(&directory.join("channel-1-pre.wav")).unwrap();
+        let post = read_audio_file(&directory.join("channel-2-post.wav")).unwrap();
+        let master = read_audio_file(&directory.join("master.wav")).unwrap();
+        assert_eq!((pre.spec.channels, pre.frames()), (1, 4800));
+        assert_eq!((post.spec.channels, post.frames()), (2, 4800));
+        assert_eq!((master.spec.channels, master.frames()), (2, 4800));
+        assert!((master.samples[100] + 0.5).abs() < 1e-3);
+
+        let markers = fs::read_to_string(directory.join(MARKERS_FILE_NAME)).unwrap();
+        assert!(markers.contains("1,0.050,2400,\"Retake\""));
+
+        assert!(recorder.stop().is_err());
+        assert!(recorder.add_marker(None).is_err());
+        let _ = fs::remove_dir_all(&config.directory);
+    }
+
+    #[test]
+    fn test_replay_buffer_keeps_only_the_last_seconds() {
+        let mut config = test_config("phantomlink_test_recorder_replay");
+        config.replay_seconds = 1;
+        let layout = StreamLayout {
+            sample_rate: 4800,
+            input_channels: 1,
+        };
+        let mut recorder = Recorder::new();
+        assert!(recorder.save_replay().is_err());
+
+        recorder.start_replay(&config, layout, &["master"]).unwrap();
+        let tap = recorder.tap();
+        assert!(tap.is_active() && !tap.is_recording());
+        // Three seconds of audio, each second at a different level
+        for i in 0..30 {
+            let level = (i / 10) as f32 * 0.25;
+            tap.push(RecordBlock {
+                input: vec![0.0; 480],
+                channels: vec![vec![level; 960]; 4],
+                buses: vec![vec![level; 960]],
+            });
+        }
+
+        let summary = recorder.save_replay().unwrap();
+        assert_eq!(summary.files.len(), 2);
+        assert!((summary.duration_secs - 1.0).abs() < 1e-9);
+        let master = read_audio_file(&summary.files[0]).unwrap();
+        let mic = read_audio_file(&summary.files[1]).unwrap();
+        assert_eq!((master.spec.channels, master.frames()), (2, 4800));
+        assert_eq!((mic.spec.channels, mic.frames()), (2, 4800));
+        assert!(master.samples.iter().all(|s| (s - 0.5).abs() < 1e-3));
+
+        recorder.stop_replay();
+        assert!(!tap.is_active());
+        assert!(recorder.save_replay().is_err());
+        let _ = fs::remove_dir_all(&config.directory);
+    }
+
+    #[test]
+    fn test_stereo_device_tracks_stay_in_sync() {
+        let mut config = test_config("phantomlink_test_recorder_stereo");
+        config.channel_taps = vec![
+            Some(TapPoint::PreFx),
+            Some(TapPoint::PostFx),
+            Some(TapPoint::PostFx),
+            None,
+        ];
+        config.replay_seconds = 1;
+        config.replay_channel = Some(1);
+        let layout = StreamLayout {
+            sample_rate: 4800,
+            input_channels: 2,
+        };
+        let mut strips: Vec<ChannelProcessor> = (0..4)
+            .map(|_| {
+                let mut strip = ChannelProcessor::new();
+                strip.set_input_channels(2);
+                strip
+            })
+            .collect();
+
+        let mut recorder = Recorder::new();
+        recorder.start(&config, layout, &["master"]).unwrap();
+        recorder.start_replay(&config, layout, &["master"]).unwrap();
+        let tap = recorder.tap();
+        // Half a second of stereo device audio, through the strips as the
+        // input callback runs them
+        for _ in 0..5 {
+            let input: Vec<f32> = (0..480).flat_map(|_| [0.1, -0.2]).collect();
+            let channels = strips
+                .iter_mut()
+                .map(|strip| strip.process(&input, 0.01).0)
+                .collect();
+            tap.push(RecordBlock {
+                input,
+                channels,
+                buses: vec![vec![0.0; 960]],
+            });
+        }
+        recorder.add_marker(None).unwrap();
+        let summary = recorder.stop().unwrap();
+        let replay = recorder.save_replay().unwrap();
+        recorder.stop_replay();
+
+        // Every file, recorded or replayed, is as long as the input and the
+        // marker sits at its end
+        assert_eq!(summary.files.len(), 4);
+        assert_eq!(replay.files.len(), 2);
+        assert_eq!(summary.markers[0].frame, 2400);
+        for file in summary.files.iter().chain(&replay.files) {
+            let data = read_audio_file(file).unwrap();
+            assert_eq!(data.frames(), 2400, "{}", file.display());
+        }
+        let _ = fs::remove_dir_all(&config.directory);
+    }
+
+    #[test]
+    fn test_refuses_to_start_without_disk_space_or_tracks() {
+        let layout = StreamLayout {
+            sample_rate: 48000,
+            input_channels: 2,
+        };
+        let mut recorder = Recorder::new();
+
+        let mut config = test_config("phantomlink_test_recorder_space");
+        config.min_free_mb = u64::MAX / (1024 * 1024);
+        let error = recorder.start(&config, layout, &["master"]).unwrap_err();
+        assert!(error.to_string().contains("free"), "{}", error);
+
+        let mut config = test_config("phantomlink_test_recorder_empty");
+        config.channel_taps = vec![None; 4];
+        config.record_buses = false;
+        assert!(recorder.start(&config, layout, &["master"]).is_err());
+        assert!(!recorder.is_recording());
+
+        let _ = fs::remove_dir_all(std::env::temp_dir().join("phantomlink_test_recorder_space"));
+        let _ = fs::remove_dir_all(&config.directory);
+    }
+
+    #[test]
+    fn test_repairs_interrupted_wav() {
+        let root = std::env::temp_dir().join("phantomlink_test_recorder_repair");
+        let session = root.join("phantomlink-20260101-120000");
+        fs::create_dir_all(&session).unwrap();
+        File::create(session.join(LOCK_FILE_NAME)).unwrap();
+
+        let path = session.join("master.wav");
+        let spec = AudioSpec {
+            sample_rate: 48000,
+            channels: 2,
+            bits_per_sample: 24,
+        };
+        let mut writer = AudioFileWriter::create(&path, spec).unwrap();
+        writer.write(&vec![0.1; 2000]).unwrap();
+        writer.finalize().unwrap();
+
+        // A crash before the header was refreshed: sizes are stale and the
+        // last frame is only half written
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(4)).unwrap();
+        file.write_all(&36u32.to_le_bytes()).unwrap();
+        file.seek(SeekFrom::End(0)).unwrap();
+        file.write_all(&[1, 2, 3, 4]).unwrap();
+        drop(file);
+
+        let repaired = recover_interrupted_sessions(&root).unwrap();
+        assert_eq!(repaired, vec![path.clone()]);
+        assert!(!session.join(LOCK_FILE_NAME).exists());
+        let data = read_audio_file(&path).unwrap();
+        assert_eq!(data.frames(), 1000);
+        assert!(!repair_wav_header(&path).unwrap());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
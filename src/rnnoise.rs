@@ -1,15 +1,162 @@
 use nnnoiseless::DenoiseState;
+use std::collections::VecDeque;
 use std::sync::Mutex;
 
+/// RNNoise operates on fixed 480-sample frames (10 ms at 48 kHz)
+const FRAME_SIZE: usize = DenoiseState::FRAME_SIZE;
+
+/// The only sample rate the RNNoise model was trained for
+const MODEL_SAMPLE_RATE: u32 = 48000;
+
+/// nnnoiseless expects samples in i16 range rather than ±1.0
+const I16_SCALE: f32 = 32768.0;
+
+/// Streaming linear-interpolation resampler that carries its phase across calls
+struct LinearResampler {
+    /// Input samples advanced per output sample
+    step: f64,
+    /// Read position relative to `prev` (0.0 = `prev`, 1.0 = first new sample)
+    pos: f64,
+    prev: f32,
+}
+
+impl LinearResampler {
+    fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            step: from_rate as f64 / to_rate as f64,
+            pos: 0.0,
+            prev: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: &[f32], output: &mut VecDeque<f32>) {
+        if input.is_empty() {
+            return;
+        }
+
+        let sample_at = |idx: usize, prev: f32| if idx == 0 { prev } else { input[idx - 1] };
+
+        while self.pos < input.len() as f64 {
+            let idx = self.pos.floor() as usize;
+            let frac = (self.pos - idx as f64) as f32;
+            let a = sample_at(idx, self.prev);
+            let b = sample_at(idx + 1, self.prev);
+            output.push_back(a + (b - a) * frac);
+            self.pos += self.step;
+        }
+
+        self.pos -= input.len() as f64;
+        self.prev = input[input.len() - 1];
+    }
+}
+
+/// Per-stream state: the model plus the FIFOs that decouple callback sizes from frames
+struct StreamState {
+    denoiser: Box<DenoiseState<'static>>,
+    /// Pending model-rate input that has not yet filled a whole frame
+    input_fifo: VecDeque<f32>,
+    /// Denoised audio at the device rate, waiting to be handed back
+    output_fifo: VecDeque<f32>,
+    /// Device rate → 48 kHz (None when the device already runs at 48 kHz)
+    downsampler: Option<LinearResampler>,
+    /// 48 kHz → device rate
+    upsampler: Option<LinearResampler>,
+    /// Scratch buffer for upsampled frames
+    upsampled: VecDeque<f32>,
+}
+
+impl StreamState {
+    fn new(sample_rate: u32) -> Self {
+        let resample = sample_rate != MODEL_SAMPLE_RATE;
+        let mut output_fifo = VecDeque::with_capacity(FRAME_SIZE * 4);
+        // Prime the output with silence so a full frame is always available
+        // before it is needed, no matter how callbacks line up with frames
+        output_fifo.extend(std::iter::repeat_n(0.0, prefill_samples(sample_rate)));
+
+        Self {
+            denoiser: DenoiseState::new(),
+            input_fifo: VecDeque::with_capacity(FRAME_SIZE * 2),
+            output_fifo,
+            downsampler: resample.then(|| LinearResampler::new(sample_rate, MODEL_SAMPLE_RATE)),
+            upsampler: resample.then(|| LinearResampler::new(MODEL_SAMPLE_RATE, sample_rate)),
+            upsampled: VecDeque::with_capacity(FRAME_SIZE * 2),
+        }
+    }
+
+    fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        match self.downsampler {
+            Some(ref mut down) => down.process(input, &mut self.input_fifo),
+            None => self.input_fifo.extend(input.iter().copied()),
+        }
+
+        let mut in_frame = [0.0f32; FRAME_SIZE];
+        let mut out_frame = [0.0f32; FRAME_SIZE];
+
+        while self.input_fifo.len() >= FRAME_SIZE {
+            for (dst, src) in in_frame.iter_mut().zip(self.input_fifo.drain(..FRAME_SIZE)) {
+                *dst = src * I16_SCALE;
+            }
+
+            let _vad_prob = self.denoiser.process_frame(&mut out_frame, &in_frame);
+
+            for sample in out_frame.iter_mut() {
+                *sample = (*sample / I16_SCALE).clamp(-1.0, 1.0);
+            }
+
+            match self.upsampler {
+                Some(ref mut up) => {
+                    up.process(&out_frame, &mut self.upsampled);
+                    self.output_fifo.extend(self.upsampled.drain(..));
+                }
+                None => self.output_fifo.extend(out_frame.iter().copied()),
+            }
+        }
+
+        // The prefill covers the worst case, but pad with silence rather than
+        // returning a short buffer if resampler rounding ever leaves us short
+        let available = self.output_fifo.len().min(input.len());
+        let mut output: Vec<f32> = self.output_fifo.drain(..available).collect();
+        output.resize(input.len(), 0.0);
+        output
+    }
+}
+
+/// Silence queued ahead of the first frame: one frame at the device rate, plus
+/// a little headroom for the resamplers' ±1 sample jitter
+fn prefill_samples(sample_rate: u32) -> usize {
+    if sample_rate == MODEL_SAMPLE_RATE {
+        FRAME_SIZE
+    } else {
+        frame_at_rate(sample_rate) + 2
+    }
+}
+
+/// Length of one 480-sample model frame expressed at `sample_rate`
+fn frame_at_rate(sample_rate: u32) -> usize {
+    (FRAME_SIZE * sample_rate as usize).div_ceil(MODEL_SAMPLE_RATE as usize)
+}
+
+/// Streaming RNNoise wrapper.
+///
+/// Accepts mono buffers of any length at any sample rate and always returns the
+/// same number of samples. Input is buffered into 480-sample frames across calls,
+/// so output is delayed by [`Rnnoise::latency_samples`].
 pub struct Rnnoise {
     enabled: bool,
-    denoiser: Mutex<Option<DenoiseState<'static>>>,
+    sample_rate: u32,
+    denoiser: Mutex<Option<StreamState>>,
 }
 
 impl Rnnoise {
     pub fn new() -> Self {
+        Self::with_sample_rate(MODEL_SAMPLE_RATE)
+    }
+
+    /// Create a wrapper for a stream running at `sample_rate`
+    pub fn with_sample_rate(sample_rate: u32) -> Self {
         Self {
             enabled: false,
+            sample_rate: sample_rate.max(1),
             denoiser: Mutex::new(None),
         }
     }
@@ -18,12 +165,11 @@ impl Rnnoise {
         self.enabled = true;
         // Initialize denoiser when enabled
         if let Ok(mut denoiser) = self.denoiser.lock() {
-            *denoiser = Some(*DenoiseState::new());
+            *denoiser = Some(StreamState::new(self.sample_rate));
         }
     }
 
     /// Disable noise suppression and clean up resources
-    #[allow(dead_code)] // API for toggling noise suppression
     pub fn disable(&mut self) {
         self.enabled = false;
         // Clean up denoiser when disabled
@@ -36,43 +182,120 @@ impl Rnnoise {
         self.enabled
     }
 
+    /// Change the stream sample rate, resetting any buffered audio
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        let sample_rate = sample_rate.max(1);
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        self.sample_rate = sample_rate;
+        if self.enabled {
+            self.reset();
+        }
+    }
+
+    #[allow(dead_code)] // API for status display
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Drop buffered audio and model state, e.g. after a stream restart
+    pub fn reset(&self) {
+        if let Ok(mut denoiser) = self.denoiser.lock()
+            && denoiser.is_some()
+        {
+            *denoiser = Some(StreamState::new(self.sample_rate));
+        }
+    }
+
+    /// Delay added by the wrapper, in samples at the stream rate.
+    ///
+    /// This is the FIFO prefill plus the model's own one-frame lookahead.
+    /// Returns 0 while disabled since audio then passes straight through.
+    pub fn latency_samples(&self) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        prefill_samples(self.sample_rate) + frame_at_rate(self.sample_rate)
+    }
+
+    /// Delay added by the wrapper in milliseconds
+    #[allow(dead_code)] // API for latency display
+    pub fn latency_ms(&self) -> f32 {
+        self.latency_samples() as f32 * 1000.0 / self.sample_rate as f32
+    }
+
     pub fn process(&self, input: &[f32]) -> Vec<f32> {
         if !self.enabled {
             return input.to_vec();
         }
 
         if let Ok(mut denoiser_guard) = self.denoiser.lock()
-            && let Some(ref mut denoiser) = *denoiser_guard
+            && let Some(ref mut state) = *denoiser_guard
         {
-            let mut output = vec![0.0f32; input.len()];
-
-            // Process in chunks of 480 samples (RNNoise frame size)
-            const FRAME_SIZE: usize = 480;
-
-            for (input_chunk, output_chunk) in
-                input.chunks(FRAME_SIZE).zip(output.chunks_mut(FRAME_SIZE))
-            {
-                if input_chunk.len() == FRAME_SIZE {
-                    // Convert f32 to the format expected by nnnoiseless
-                    let mut input_frame = [0.0f32; FRAME_SIZE];
-                    let mut output_frame = [0.0f32; FRAME_SIZE];
-                    input_frame[..input_chunk.len()].copy_from_slice(input_chunk);
-
-                    // Apply denoising
-                    let _vad_prob = denoiser.process_frame(&mut output_frame, &input_frame);
-
-                    // Copy back to output
-                    output_chunk.copy_from_slice(&output_frame[..output_chunk.len()]);
-                } else {
-                    // Handle partial frames
-                    output_chunk.copy_from_slice(input_chunk);
-                }
-            }
-
-            return output;
+            return state.process(input);
         }
 
         // Fallback if denoiser is not available
         input.to_vec()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_length_matches_unaligned_input() {
+        let mut rnnoise = Rnnoise::new();
+        rnnoise.enable();
+        for len in [1, 64, 256, 479, 480, 481, 1000, 1024] {
+            let input = vec![0.1f32; len];
+            assert_eq!(rnnoise.process(&input).len(), len);
+        }
+    }
+
+    #[test]
+    fn test_output_length_when_resampling() {
+        let mut rnnoise = Rnnoise::with_sample_rate(44100);
+        rnnoise.enable();
+        for _ in 0..50 {
+            let input = vec![0.1f32; 441];
+            let output = rnnoise.process(&input);
+            assert_eq!(output.len(), 441);
+            assert!(output.iter().all(|s| s.is_finite() && s.abs() <= 1.0));
+        }
+    }
+
+    #[test]
+    fn test_latency_reporting() {
+        let mut rnnoise = Rnnoise::new();
+        assert_eq!(rnnoise.latency_samples(), 0);
+        rnnoise.enable();
+        assert_eq!(rnnoise.latency_samples(), 2 * FRAME_SIZE);
+        assert!((rnnoise.latency_ms() - 20.0).abs() < 0.01);
+
+        rnnoise.set_sample_rate(96000);
+        assert!(rnnoise.latency_samples() >= 4 * FRAME_SIZE);
+    }
+
+    #[test]
+    fn test_resampler_preserves_duration() {
+        let mut down = LinearResampler::new(44100, 48000);
+        let mut out = VecDeque::new();
+        for _ in 0..100 {
+            down.process(&[0.5; 441], &mut out);
+        }
+        // One second in at 44.1 kHz is one second out at 48 kHz; the first two
+        // outputs still interpolate against the zero-initialized history
+        assert!((out.len() as i64 - 48000).abs() <= 1);
+        assert!(out.iter().skip(2).all(|&s| (s - 0.5).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_disabled_passthrough() {
+        let rnnoise = Rnnoise::new();
+        let input = vec![0.25f32, -0.5, 0.75];
+        assert_eq!(rnnoise.process(&input), input);
+    }
+}
@@ -0,0 +1,287 @@
+//! Test signal source for channel strips.
+//!
+//! A channel can take its input from a generator instead of the capture
+//! device, for checking routing, gain staging and meters without a mic.
+//! Tones are set by their peak level; noise is scaled to the RMS of a sine
+//! at the same level, so switching between them keeps meters comparable.
+
+#![allow(dead_code)] // Complete generator API, used by the GUI and IPC
+
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+
+/// Frequency of the alignment tone
+pub const REFERENCE_FREQUENCY_HZ: f32 = 1000.0;
+
+/// Level of the alignment tone, the EBU R68 line-up level
+pub const REFERENCE_LEVEL_DBFS: f32 = -18.0;
+
+/// RMS of Paul Kellet's pink filter fed with uniform white noise in ±1
+const PINK_RMS: f32 = 1.707;
+
+/// RMS of uniform white noise in ±1
+const WHITE_RMS: f32 = 0.577_350_3;
+
+/// Shape of the generated signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    WhiteNoise,
+    PinkNoise,
+    /// Logarithmic sweep that repeats
+    Sweep,
+    /// 1 kHz at -18 dBFS whatever the frequency and level settings
+    Reference,
+}
+
+impl Waveform {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Sine => "Sine",
+            Waveform::WhiteNoise => "White",
+            Waveform::PinkNoise => "Pink",
+            Waveform::Sweep => "Sweep",
+            Waveform::Reference => "Reference",
+        }
+    }
+
+    /// Parse a name as used by IPC (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|w| w.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn all() -> &'static [Waveform] {
+        &[
+            Waveform::Sine,
+            Waveform::WhiteNoise,
+            Waveform::PinkNoise,
+            Waveform::Sweep,
+            Waveform::Reference,
+        ]
+    }
+}
+
+/// Settings of a channel's generator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignalGeneratorConfig {
+    pub waveform: Waveform,
+    pub frequency_hz: f32,
+    /// Peak level of tones; noise matches a sine at this level in RMS
+    pub level_dbfs: f32,
+    pub sweep_start_hz: f32,
+    pub sweep_end_hz: f32,
+    pub sweep_secs: f32,
+}
+
+impl Default for SignalGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Sine,
+            frequency_hz: REFERENCE_FREQUENCY_HZ,
+            level_dbfs: REFERENCE_LEVEL_DBFS,
+            sweep_start_hz: 20.0,
+            sweep_end_hz: 20000.0,
+            sweep_secs: 10.0,
+        }
+    }
+}
+
+/// Produces a test signal, continuous across buffers
+#[derive(Debug, Clone)]
+pub struct SignalGenerator {
+    config: SignalGeneratorConfig,
+    sample_rate: u32,
+    /// Phase in cycles, kept in [0, 1)
+    phase: f64,
+    /// Position within the sweep, in samples
+    sweep_pos: u64,
+    seed: u32,
+    pink: [f32; 3],
+}
+
+impl SignalGenerator {
+    pub fn new(config: SignalGeneratorConfig, sample_rate: u32) -> Self {
+        Self {
+            config,
+            sample_rate: sample_rate.max(1),
+            phase: 0.0,
+            sweep_pos: 0,
+            seed: 0x1234_5678,
+            pink: [0.0; 3],
+        }
+    }
+
+    pub fn config(&self) -> &SignalGeneratorConfig {
+        &self.config
+    }
+
+    /// Change settings without restarting the phase, so tones don't click
+    pub fn set_config(&mut self, config: SignalGeneratorConfig) {
+        if config.waveform != self.config.waveform {
+            self.sweep_pos = 0;
+        }
+        self.config = config;
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate.max(1);
+        self.sweep_pos = 0;
+    }
+
+    /// Fill interleaved frames, the same signal on every channel
+    pub fn fill_interleaved(&mut self, output: &mut [f32], channels: usize) {
+        for frame in output.chunks_mut(channels.max(1)) {
+            let sample = self.next_sample();
+            frame.iter_mut().for_each(|s| *s = sample);
+        }
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        let level_dbfs = match self.config.waveform {
+            Waveform::Reference => REFERENCE_LEVEL_DBFS,
+            _ => self.config.level_dbfs.min(0.0),
+        };
+        let nyquist = self.sample_rate as f32 / 2.0;
+        let peak = 10.0_f32.powf(level_dbfs / 20.0);
+        let rms = peak * std::f32::consts::FRAC_1_SQRT_2;
+
+        let sample = match self.config.waveform {
+            Waveform::Sine => self.tone(self.config.frequency_hz.clamp(1.0, nyquist) as f64) * peak,
+            Waveform::Reference => self.tone(REFERENCE_FREQUENCY_HZ as f64) * peak,
+            Waveform::Sweep => {
+                let start = self.config.sweep_start_hz.clamp(1.0, nyquist) as f64;
+                let end = self.config.sweep_end_hz.clamp(1.0, nyquist) as f64;
+                let length = (self.config.sweep_secs.max(0.1) * self.sample_rate as f32) as u64;
+                let t = self.sweep_pos as f64 / length as f64;
+                self.sweep_pos = (self.sweep_pos + 1) % length;
+                self.tone(start * (end / start).powf(t)) * peak
+            }
+            Waveform::WhiteNoise => self.white() / WHITE_RMS * rms,
+            Waveform::PinkNoise => {
+                // Paul Kellet's economy filter, -3 dB/octave within 0.5 dB
+                let white = self.white();
+                self.pink[0] = 0.99765 * self.pink[0] + white * 0.099_046;
+                self.pink[1] = 0.963 * self.pink[1] + white * 0.296_516_4;
+                self.pink[2] = 0.57 * self.pink[2] + white * 1.052_691_3;
+                let pink = self.pink.iter().sum::<f32>() + white * 0.1848;
+                pink / PINK_RMS * rms
+            }
+        };
+        sample.clamp(-1.0, 1.0)
+    }
+
+    /// Advance the oscillator at `frequency` and return its sine
+    fn tone(&mut self, frequency: f64) -> f32 {
+        let sample = (self.phase * TAU).sin() as f32;
+        self.phase = (self.phase + frequency / self.sample_rate as f64).fract();
+        sample
+    }
+
+    /// Uniform noise in ±1
+    fn white(&mut self) -> f32 {
+        self.seed = self
+            .seed
+            .wrapping_mul(1_664_525)
+            .wrapping_add(1_013_904_223);
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn generate(config: SignalGeneratorConfig, frames: usize, channels: usize) -> Vec<f32> {
+        let mut generator = SignalGenerator::new(config, 48000);
+        let mut output = vec![0.0; frames * channels];
+        // Odd block sizes check continuity across buffers
+        for block in output.chunks_mut(333 * channels) {
+            generator.fill_interleaved(block, channels);
+        }
+        output
+    }
+
+    #[test]
+    fn test_reference_tone_level_and_frequency() {
+        let config = SignalGeneratorConfig {
+            waveform: Waveform::Reference,
+            frequency_hz: 440.0,
+            level_dbfs: 0.0,
+            ..Default::default()
+        };
+        let output = generate(config, 48000, 2);
+        assert!(output.chunks(2).all(|frame| frame[0] == frame[1]));
+        let left: Vec<f32> = output.iter().step_by(2).copied().collect();
+
+        let peak = left.iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+        assert!((20.0 * peak.log10() - REFERENCE_LEVEL_DBFS).abs() < 0.05);
+
+        // 1 kHz crosses zero upward once per cycle
+        let crossings = left
+            .windows(2)
+            .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+            .count();
+        assert!((999..=1001).contains(&crossings));
+    }
+
+    #[test]
+    fn test_noise_matches_sine_rms() {
+        let sine = generate(SignalGeneratorConfig::default(), 96000, 1);
+        let sine_rms = rms(&sine);
+        for waveform in [Waveform::WhiteNoise, Waveform::PinkNoise] {
+            let config = SignalGeneratorConfig {
+                waveform,
+                ..Default::default()
+            };
+            let noise = generate(config, 96000, 1);
+            let ratio_db = 20.0 * (rms(&noise) / sine_rms).log10();
+            assert!(
+                ratio_db.abs() < 0.5,
+                "{} is {:.2} dB off",
+                waveform.name(),
+                ratio_db
+            );
+        }
+    }
+
+    #[test]
+    fn test_sweep_rises_and_repeats() {
+        let config = SignalGeneratorConfig {
+            waveform: Waveform::Sweep,
+            sweep_start_hz: 100.0,
+            sweep_end_hz: 4000.0,
+            sweep_secs: 1.0,
+            ..Default::default()
+        };
+        let output = generate(config, 96000, 1);
+        let crossings = |samples: &[f32]| {
+            samples
+                .windows(2)
+                .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+                .count()
+        };
+        let first_tenth = crossings(&output[..4800]);
+        let last_tenth = crossings(&output[43200..48000]);
+        assert!(first_tenth < 20 && last_tenth > 300);
+        // The second pass starts low again
+        assert!(crossings(&output[48000..52800]) < 20);
+    }
+
+    #[test]
+    fn test_waveform_names_round_trip() {
+        for waveform in Waveform::all() {
+            assert_eq!(Waveform::from_name(waveform.name()), Some(*waveform));
+        }
+        assert_eq!(Waveform::from_name("pink"), Some(Waveform::PinkNoise));
+        assert_eq!(Waveform::from_name("square"), None);
+    }
+}
@@ -0,0 +1,415 @@
+//! Host transport: the musical timeline plugins are given.
+//!
+//! Tempo-synced delays, LFOs and arpeggiators ask the host where it is. The
+//! transport holds a sample position, tempo, time signature and playing flag:
+//! - The audio callback advances the position while playing
+//! - With the JACK backend active and "Follow JACK transport" on, the JACK
+//!   transport master drives it instead
+//! - Every hosted format reads the same snapshot: VST2 `get_time_info`, the
+//!   CLAP process transport event and LV2 `time:Position` atoms. LADSPA has
+//!   no transport API.
+
+#![allow(dead_code)] // Complete transport API, used by the GUI, IPC and plugin hosts
+
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const MIN_TEMPO: f64 = 20.0;
+pub const MAX_TEMPO: f64 = 999.0;
+pub const MAX_BEATS_PER_BAR: u32 = 32;
+/// Note values a time signature may count in
+pub const BEAT_UNITS: [u32; 6] = [1, 2, 4, 8, 16, 32];
+
+static TRANSPORT: Mutex<Transport> = Mutex::new(Transport::new());
+/// Frames the audio callback processed while the transport was locked
+static PENDING_FRAMES: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Last snapshot this thread read, returned while the transport is locked
+    static LAST_SEEN: Cell<TransportInfo> = const { Cell::new(TransportInfo::new()) };
+}
+
+/// Saved transport settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransportConfig {
+    /// Beats per minute, counted in quarter notes
+    pub tempo: f64,
+    pub beats_per_bar: u32,
+    /// Note value of one beat: 4 for x/4, 8 for x/8
+    pub beat_unit: u32,
+    /// Take position, tempo and meter from the JACK transport when JACK is running
+    pub sync_jack: bool,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            tempo: 120.0,
+            beats_per_bar: 4,
+            beat_unit: 4,
+            sync_jack: true,
+        }
+    }
+}
+
+/// Bar and beat published by a JACK timebase master
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MusicalPosition {
+    /// Bar counted from zero
+    pub bar: i64,
+    /// Beats since the start of the bar in the beat unit, ticks included
+    pub beat: f64,
+}
+
+/// Where the transport is at the start of a block
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TransportInfo {
+    pub playing: bool,
+    pub sample_position: i64,
+    pub sample_rate: f64,
+    pub tempo: f64,
+    pub beats_per_bar: u32,
+    pub beat_unit: u32,
+    /// Bar/beat/tick from JACK. Positions derived from samples assume the
+    /// tempo never changed, so this wins whenever it is known.
+    #[serde(default)]
+    pub musical: Option<MusicalPosition>,
+}
+
+impl Default for TransportInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransportInfo {
+    const fn new() -> Self {
+        Self {
+            playing: false,
+            sample_position: 0,
+            sample_rate: 48000.0,
+            tempo: 120.0,
+            beats_per_bar: 4,
+            beat_unit: 4,
+            musical: None,
+        }
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.sample_position as f64 / self.sample_rate
+    }
+
+    /// Position in quarter notes (VST2 "PPQ", CLAP beats)
+    pub fn quarter_notes(&self) -> f64 {
+        match self.musical {
+            Some(musical) => self.bar_start() + musical.beat * 4.0 / self.beat_unit as f64,
+            None => self.seconds() * self.tempo / 60.0,
+        }
+    }
+
+    /// Length of a bar in quarter notes: 4 for 4/4, 3 for 6/8
+    pub fn bar_length(&self) -> f64 {
+        self.beats_per_bar as f64 * 4.0 / self.beat_unit as f64
+    }
+
+    /// Bar the position falls in, counted from zero
+    pub fn bar(&self) -> i64 {
+        match self.musical {
+            Some(musical) => musical.bar,
+            None => (self.quarter_notes() / self.bar_length()).floor() as i64,
+        }
+    }
+
+    /// Start of the current bar in quarter notes
+    pub fn bar_start(&self) -> f64 {
+        self.bar() as f64 * self.bar_length()
+    }
+
+    /// Beats since the start of the bar, in the time signature's beat unit
+    pub fn beat_in_bar(&self) -> f64 {
+        match self.musical {
+            Some(musical) => musical.beat,
+            None => (self.quarter_notes() - self.bar_start()) * self.beat_unit as f64 / 4.0,
+        }
+    }
+
+    /// The same transport `frames` samples later, at the current tempo
+    pub fn offset(&self, frames: usize) -> Self {
+        let mut info = *self;
+        if info.playing {
+            info.sample_position += frames as i64;
+            if let Some(musical) = info.musical.as_mut() {
+                musical.beat +=
+                    frames as f64 / self.samples_per_quarter() * self.beat_unit as f64 / 4.0;
+                let beats_per_bar = self.beats_per_bar as f64;
+                while musical.beat >= beats_per_bar {
+                    musical.beat -= beats_per_bar;
+                    musical.bar += 1;
+                }
+            }
+        }
+        info
+    }
+
+    /// Samples in one quarter note
+    pub fn samples_per_quarter(&self) -> f64 {
+        self.sample_rate * 60.0 / self.tempo
+    }
+}
+
+/// Transport state shared by the audio callback, the JACK client and the GUI
+#[derive(Debug, Clone)]
+pub struct Transport {
+    info: TransportInfo,
+    sync_jack: bool,
+    /// The JACK backend is running and reporting its transport
+    jack_active: bool,
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport {
+    pub const fn new() -> Self {
+        Self {
+            info: TransportInfo::new(),
+            sync_jack: true,
+            jack_active: false,
+        }
+    }
+
+    pub fn info(&self) -> TransportInfo {
+        self.info
+    }
+
+    pub fn config(&self) -> TransportConfig {
+        TransportConfig {
+            tempo: self.info.tempo,
+            beats_per_bar: self.info.beats_per_bar,
+            beat_unit: self.info.beat_unit,
+            sync_jack: self.sync_jack,
+        }
+    }
+
+    pub fn configure(&mut self, config: &TransportConfig) {
+        self.set_tempo(config.tempo);
+        self.set_time_signature(config.beats_per_bar, config.beat_unit);
+        self.sync_jack = config.sync_jack;
+    }
+
+    pub fn set_tempo(&mut self, tempo: f64) {
+        if tempo.is_finite() {
+            self.info.tempo = tempo.clamp(MIN_TEMPO, MAX_TEMPO);
+        }
+    }
+
+    /// Beat units other than a power of two up to 32 are ignored
+    pub fn set_time_signature(&mut self, beats_per_bar: u32, beat_unit: u32) {
+        self.info.beats_per_bar = beats_per_bar.clamp(1, MAX_BEATS_PER_BAR);
+        if BEAT_UNITS.contains(&beat_unit) {
+            self.info.beat_unit = beat_unit;
+        }
+    }
+
+    pub fn set_playing(&mut self, playing: bool) {
+        self.info.playing = playing;
+    }
+
+    pub fn locate(&mut self, sample_position: i64) {
+        self.info.sample_position = sample_position.max(0);
+        PENDING_FRAMES.store(0, Ordering::Relaxed);
+    }
+
+    pub fn set_sync_jack(&mut self, sync: bool) {
+        self.sync_jack = sync;
+    }
+
+    /// Whether position, tempo and meter currently come from JACK
+    pub fn following_jack(&self) -> bool {
+        self.sync_jack && self.jack_active
+    }
+
+    pub fn set_jack_active(&mut self, active: bool) {
+        self.jack_active = active;
+    }
+
+    /// Move past a processed block; while following JACK the position comes
+    /// from [`Transport::follow_jack`] instead
+    pub fn advance(&mut self, frames: usize, sample_rate: f64) {
+        if self.following_jack() {
+            return;
+        }
+        if sample_rate > 0.0 {
+            self.info.sample_rate = sample_rate;
+        }
+        if self.info.playing {
+            self.info.sample_position += frames as i64;
+        }
+    }
+
+    /// Take the JACK transport's state for this cycle, when following it
+    pub fn follow_jack(&mut self, info: TransportInfo) {
+        self.jack_active = true;
+        if self.sync_jack {
+            self.info = info;
+        }
+    }
+
+    /// Replace the whole state; plugin bridge children use this to mirror
+    /// the transport of the process that feeds them
+    pub fn publish(&mut self, info: TransportInfo) {
+        self.info = info;
+    }
+}
+
+/// The process-wide transport
+pub fn global() -> &'static Mutex<Transport> {
+    &TRANSPORT
+}
+
+/// Snapshot for the block about to be processed. Audio threads call this,
+/// so it never waits: while the GUI or IPC holds the transport, the last
+/// snapshot this thread saw is returned.
+pub fn current() -> TransportInfo {
+    match TRANSPORT.try_lock() {
+        Ok(transport) => {
+            let info = transport.info();
+            LAST_SEEN.with(|last| last.set(info));
+            info
+        }
+        Err(_) => LAST_SEEN.with(Cell::get),
+    }
+}
+
+/// Called by the audio callback after each block. Blocks that find the
+/// transport locked are added on the next call rather than waited for.
+pub fn advance(frames: usize, sample_rate: f64) {
+    let Ok(mut transport) = TRANSPORT.try_lock() else {
+        PENDING_FRAMES.fetch_add(frames, Ordering::Relaxed);
+        return;
+    };
+    let frames = frames + PENDING_FRAMES.swap(0, Ordering::Relaxed);
+    transport.advance(frames, sample_rate);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(sample_position: i64, beats_per_bar: u32, beat_unit: u32) -> TransportInfo {
+        TransportInfo {
+            playing: true,
+            sample_position,
+            sample_rate: 48000.0,
+            tempo: 120.0,
+            beats_per_bar,
+            beat_unit,
+            musical: None,
+        }
+    }
+
+    #[test]
+    fn test_musical_position() {
+        // 120 BPM: a quarter note every 24000 samples
+        let info = at(24000 * 9, 4, 4);
+        assert_eq!(info.samples_per_quarter(), 24000.0);
+        assert_eq!(info.quarter_notes(), 9.0);
+        assert_eq!(info.bar(), 2);
+        assert_eq!(info.bar_start(), 8.0);
+        assert_eq!(info.beat_in_bar(), 1.0);
+
+        // 6/8: three quarter notes per bar, counted in eighths
+        let info = at(24000 * 4, 6, 8);
+        assert_eq!(info.bar_length(), 3.0);
+        assert_eq!(info.bar(), 1);
+        assert_eq!(info.beat_in_bar(), 2.0);
+    }
+
+    #[test]
+    fn test_jack_bar_and_beat_survive_tempo_changes() {
+        // Bar 3, beat 2 half way through, but at 90 BPM after a tempo change:
+        // the sample position alone would put it elsewhere
+        let info = TransportInfo {
+            tempo: 90.0,
+            musical: Some(MusicalPosition { bar: 2, beat: 1.5 }),
+            ..at(24000 * 9, 4, 4)
+        };
+        assert_eq!(info.bar(), 2);
+        assert_eq!(info.bar_start(), 8.0);
+        assert_eq!(info.beat_in_bar(), 1.5);
+        assert_eq!(info.quarter_notes(), 9.5);
+
+        // Sub-blocks carry the beat on and roll over into the next bar
+        let later = TransportInfo {
+            musical: Some(MusicalPosition { bar: 2, beat: 3.5 }),
+            ..info
+        }
+        .offset(20000);
+        assert_eq!(later.bar(), 3);
+        assert!((later.beat_in_bar() - 0.125).abs() < 1e-9);
+
+        // 6/8 counts beats in eighths
+        let info = TransportInfo {
+            musical: Some(MusicalPosition { bar: 1, beat: 2.0 }),
+            ..at(0, 6, 8)
+        };
+        assert_eq!(info.quarter_notes(), 4.0);
+    }
+
+    #[test]
+    fn test_transport_advances_only_while_playing() {
+        let mut transport = Transport::new();
+        transport.advance(512, 44100.0);
+        assert_eq!(transport.info().sample_position, 0);
+        assert_eq!(transport.info().sample_rate, 44100.0);
+
+        transport.set_playing(true);
+        transport.advance(512, 44100.0);
+        transport.advance(512, 44100.0);
+        assert_eq!(transport.info().sample_position, 1024);
+
+        transport.locate(-5);
+        assert_eq!(transport.info().sample_position, 0);
+    }
+
+    #[test]
+    fn test_jack_drives_transport_when_followed() {
+        let mut transport = Transport::new();
+        transport.set_playing(true);
+        transport.follow_jack(at(96000, 3, 4));
+        assert!(transport.following_jack());
+        assert_eq!(transport.info().beats_per_bar, 3);
+
+        // The audio callback no longer moves it
+        transport.advance(512, 48000.0);
+        assert_eq!(transport.info().sample_position, 96000);
+
+        // Not following: JACK's state is ignored and the callback advances it
+        transport.set_sync_jack(false);
+        transport.follow_jack(at(0, 4, 4));
+        transport.advance(512, 48000.0);
+        assert_eq!(transport.info().sample_position, 96512);
+    }
+
+    #[test]
+    fn test_transport_settings_are_validated() {
+        let mut transport = Transport::new();
+        transport.configure(&TransportConfig {
+            tempo: 5000.0,
+            beats_per_bar: 0,
+            beat_unit: 3,
+            sync_jack: false,
+        });
+        let config = transport.config();
+        assert_eq!(config.tempo, MAX_TEMPO);
+        assert_eq!(config.beats_per_bar, 1);
+        assert_eq!(config.beat_unit, 4);
+        assert!(!config.sync_jack);
+    }
+}
@@ -8,18 +8,31 @@
 
 #![allow(dead_code)] // Complete VST hosting API for plugin management
 
+use crate::plugin_bridge;
+use crate::plugin_host::{
+    self, BlockPipeline, ChannelLayout, ParameterChange, ParameterQuery, PluginFormat,
+    PluginProcessor, ProgramList,
+};
+use crate::transport::{self, TransportInfo};
+use crate::vst_preset::{BankData, PluginIdentity, PresetFile, Program, ProgramData};
 use crossbeam_channel::{Receiver, Sender, bounded};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use vst::api::Events;
+use std::time::Duration;
+use vst::api::{Events, SmpteFrameRate, TimeInfo, TimeInfoFlags};
 use vst::host::{Host, PluginInstance, PluginLoader};
-use vst::plugin::{Category, Plugin};
+use vst::plugin::{Category, Info, Plugin, PluginParameters};
 
 pub struct VstHost {
     plugin_id: i32,
     sample_rate: f32,
     buffer_size: usize,
+    /// Where parameter changes made by the plugin itself go
+    automation: Option<Sender<ParameterChange>>,
+    /// Transport at the start of the block being processed
+    transport: Arc<Mutex<TransportInfo>>,
 }
 
 impl VstHost {
@@ -28,13 +41,57 @@ impl VstHost {
             plugin_id: 1000,
             sample_rate: 48000.0,
             buffer_size: 1024,
+            automation: None,
+            transport: Arc::new(Mutex::new(TransportInfo::default())),
         }
     }
+
+    /// Report the plugin's own parameter changes to `sender`
+    pub fn with_automation(mut self, sender: Sender<ParameterChange>) -> Self {
+        self.automation = Some(sender);
+        self
+    }
+
+    /// Answer time info queries from `transport`, updated before each block
+    pub fn with_transport(mut self, transport: Arc<Mutex<TransportInfo>>) -> Self {
+        self.transport = transport;
+        self
+    }
+}
+
+/// VST2 time info for a transport snapshot
+fn time_info(info: &TransportInfo) -> TimeInfo {
+    let mut flags = TimeInfoFlags::PPQ_POS_VALID.bits()
+        | TimeInfoFlags::TEMPO_VALID.bits()
+        | TimeInfoFlags::BARS_VALID.bits()
+        | TimeInfoFlags::TIME_SIG_VALID.bits();
+    if info.playing {
+        flags |= TimeInfoFlags::TRANSPORT_PLAYING.bits();
+    }
+    TimeInfo {
+        sample_pos: info.sample_position as f64,
+        sample_rate: info.sample_rate,
+        nanoseconds: 0.0,
+        ppq_pos: info.quarter_notes(),
+        tempo: info.tempo,
+        bar_start_pos: info.bar_start(),
+        cycle_start_pos: 0.0,
+        cycle_end_pos: 0.0,
+        time_sig_numerator: info.beats_per_bar as i32,
+        time_sig_denominator: info.beat_unit as i32,
+        smpte_offset: 0,
+        smpte_frame_rate: SmpteFrameRate::Smpte24fps,
+        samples_to_next_clock: 0,
+        flags,
+    }
 }
 
 impl Host for VstHost {
     fn automate(&self, index: i32, value: f32) {
-        println!("Parameter {} automated to {}", index, value);
+        // Called from the plugin's editor or audio thread; never block it
+        if let Some(ref sender) = self.automation {
+            let _ = sender.try_send(ParameterChange { index, value });
+        }
     }
 
     fn get_plugin_id(&self) -> i32 {
@@ -53,8 +110,9 @@ impl Host for VstHost {
         // Process MIDI events
     }
 
-    fn get_time_info(&self, _mask: i32) -> Option<vst::api::TimeInfo> {
-        None
+    fn get_time_info(&self, _mask: i32) -> Option<TimeInfo> {
+        let info = *self.transport.lock().ok()?;
+        Some(time_info(&info))
     }
 
     fn get_block_size(&self) -> isize {
@@ -62,13 +120,18 @@ impl Host for VstHost {
     }
 }
 
+/// How long preset and program requests may take; chunk-based plugins
+/// can be slow to serialise
+const PRESET_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Message types for VST processor thread communication
 #[derive(Debug)]
 enum VstMessage {
-    /// Process audio buffer and return result
+    /// Process planar audio and return the output channels
     ProcessAudio {
-        input: Vec<f32>,
-        response: Sender<Vec<f32>>,
+        input: Vec<Vec<f32>>,
+        transport: TransportInfo,
+        response: Sender<Vec<Vec<f32>>>,
     },
     /// Set a parameter value
     SetParameter { index: i32, value: f32 },
@@ -77,8 +140,31 @@ enum VstMessage {
         index: i32,
         response: Sender<Option<ParameterInfo>>,
     },
+    /// Get the info of every parameter at once
+    GetAllParameterInfo {
+        response: Sender<Vec<ParameterInfo>>,
+    },
     /// Request all parameter values
     GetAllParameters { response: Sender<HashMap<i32, f32>> },
+    /// Built-in program names and the current program
+    GetPrograms { response: Sender<ProgramList> },
+    /// Switch program; answers with the new parameter values
+    SetProgram {
+        index: i32,
+        response: Sender<HashMap<i32, f32>>,
+    },
+    /// Current program (or with `bank`, every program) as an .fxp/.fxb file
+    ExportPreset {
+        bank: bool,
+        response: Sender<Vec<u8>>,
+    },
+    /// Apply an .fxp/.fxb file; answers with the new parameter values
+    ImportPreset {
+        data: Vec<u8>,
+        response: Sender<Result<HashMap<i32, f32>, String>>,
+    },
+    /// Suspend, switch sample rate and resume
+    SetSampleRate { sample_rate: f32 },
     /// Set enabled state
     SetEnabled { enabled: bool },
     /// Shutdown the processor
@@ -86,7 +172,7 @@ enum VstMessage {
 }
 
 /// Parameter information returned from VST plugin
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ParameterInfo {
     pub index: i32,
     pub name: String,
@@ -101,6 +187,15 @@ pub struct VstProcessor {
     enabled: bool,
     parameters: HashMap<i32, f32>,
     parameter_count: i32,
+    /// Delay the plugin reports it adds, in samples
+    initial_delay: usize,
+    layout: ChannelLayout,
+    /// Keeps the processing thread one buffer behind the callback
+    pipeline: BlockPipeline,
+    /// Answer to the block submitted last callback
+    pending: Option<Receiver<Vec<Vec<f32>>>>,
+    /// Parameter changes reported by the plugin through `automate`
+    automation: Receiver<ParameterChange>,
     // Message channel to processing thread
     message_sender: Option<Sender<VstMessage>>,
     processing_thread: Option<std::thread::JoinHandle<()>>,
@@ -110,24 +205,24 @@ pub struct VstProcessor {
 
 // Thread-safe VST processor that handles audio in a separate thread
 impl VstProcessor {
-    pub fn load(plugin_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn load(plugin_path: &Path, sample_rate: f32) -> Result<Self, Box<dyn std::error::Error>> {
         let plugin_name = plugin_path
             .file_name()
             .unwrap_or_default()
             .to_string_lossy()
             .to_string();
 
-        let sample_rate = 48000.0;
         let buffer_size = 1024;
 
         // Create message channel with reasonable buffer
         let (message_sender, message_receiver) = bounded::<VstMessage>(64);
+        let (automation_sender, automation) = bounded(plugin_host::AUTOMATION_QUEUE);
 
         // Clone the path for the thread
         let plugin_path_clone = plugin_path.to_path_buf();
 
-        // Get parameter count by loading plugin temporarily
-        let parameter_count = Self::query_parameter_count(&plugin_path_clone)?;
+        // Get parameter count, delay and ports by loading plugin temporarily
+        let (parameter_count, initial_delay, layout) = Self::query_plugin_info(&plugin_path_clone)?;
 
         // Start the processing thread
         let processing_thread = std::thread::spawn(move || {
@@ -136,35 +231,71 @@ impl VstProcessor {
                 sample_rate,
                 buffer_size,
                 message_receiver,
+                automation_sender,
             );
         });
 
-        // Initialize default parameter values
-        let mut parameters = HashMap::new();
-        for i in 0..parameter_count {
-            parameters.insert(i, 0.5); // Default to 50%
-        }
-
-        Ok(Self {
+        let mut processor = Self {
             plugin_name,
             plugin_path: plugin_path.to_path_buf(),
             enabled: true,
-            parameters,
+            parameters: HashMap::new(),
             parameter_count,
+            initial_delay,
+            layout,
+            pipeline: BlockPipeline::new(),
+            pending: None,
+            automation,
             message_sender: Some(message_sender),
             processing_thread: Some(processing_thread),
             sample_rate,
             buffer_size,
-        })
+        };
+
+        // Start from the plugin's own defaults, once its thread has loaded it
+        processor.parameters = processor
+            .request(
+                |response| VstMessage::GetAllParameters { response },
+                PRESET_TIMEOUT,
+            )
+            .unwrap_or_else(|| (0..parameter_count).map(|i| (i, 0.5)).collect());
+
+        Ok(processor)
     }
 
-    /// Get parameter count from plugin without keeping it loaded
-    fn query_parameter_count(plugin_path: &Path) -> Result<i32, Box<dyn std::error::Error>> {
+    /// Send a request to the processing thread and wait for the answer
+    fn request<T>(
+        &self,
+        message: impl FnOnce(Sender<T>) -> VstMessage,
+        timeout: Duration,
+    ) -> Option<T> {
+        let sender = self.message_sender.as_ref()?;
+        let (response_sender, response_receiver) = bounded(1);
+        sender.try_send(message(response_sender)).ok()?;
+        response_receiver.recv_timeout(timeout).ok()
+    }
+
+    /// Get parameter count, initial delay and ports from plugin without keeping it loaded
+    fn query_plugin_info(
+        plugin_path: &Path,
+    ) -> Result<(i32, usize, ChannelLayout), Box<dyn std::error::Error>> {
         let host = Arc::new(Mutex::new(VstHost::new()));
         let mut loader = PluginLoader::load(plugin_path, host)?;
         let plugin_instance = loader.instance()?;
         let info = plugin_instance.get_info();
-        Ok(info.parameters)
+        Ok((
+            info.parameters,
+            info.initial_delay.max(0) as usize,
+            Self::channel_layout_of(&info),
+        ))
+    }
+
+    /// VST2 has no sidechain ports; by convention inputs beyond the first
+    /// stereo pair carry the key signal
+    fn channel_layout_of(info: &Info) -> ChannelLayout {
+        let inputs = info.inputs.max(0) as usize;
+        let main = inputs.min(2);
+        ChannelLayout::new(main, info.outputs.max(0) as usize).with_sidechain(inputs - main)
     }
 
     /// Main loop for the processor thread
@@ -173,15 +304,22 @@ impl VstProcessor {
         sample_rate: f32,
         buffer_size: usize,
         message_receiver: Receiver<VstMessage>,
+        automation: Sender<ParameterChange>,
     ) {
         // Load the VST plugin in the processing thread
-        let plugin_result = Self::load_plugin_instance(&plugin_path, sample_rate, buffer_size);
+        let transport = Arc::new(Mutex::new(TransportInfo::default()));
+        let host = VstHost::new()
+            .with_automation(automation)
+            .with_transport(Arc::clone(&transport));
+        let plugin_result =
+            Self::load_plugin_instance(&plugin_path, host, sample_rate, buffer_size);
 
         match plugin_result {
             Ok(mut plugin_instance) => {
                 let mut enabled = true;
                 let info = plugin_instance.get_info();
                 let param_count = info.parameters;
+                let layout = Self::channel_layout_of(&info);
 
                 // Get parameter object for accessing/setting parameters
                 let params = plugin_instance.get_parameter_object();
@@ -189,15 +327,24 @@ impl VstProcessor {
                 // Process messages in the thread
                 while let Ok(message) = message_receiver.recv() {
                     match message {
-                        VstMessage::ProcessAudio { input, response } => {
+                        VstMessage::ProcessAudio {
+                            input,
+                            transport: block_transport,
+                            response,
+                        } => {
+                            if let Ok(mut shared) = transport.lock() {
+                                *shared = block_transport;
+                            }
                             let output = if enabled {
                                 Self::process_audio_with_plugin(
                                     &mut plugin_instance,
                                     &input,
-                                    buffer_size,
+                                    layout,
                                 )
                             } else {
-                                input
+                                let inputs: Vec<&[f32]> = input.iter().map(Vec::as_slice).collect();
+                                let frames = input.first().map_or(0, Vec::len);
+                                plugin_host::passthrough(layout, &inputs, frames)
                             };
                             let _ = response.send(output);
                         }
@@ -220,12 +367,50 @@ impl VstProcessor {
                                 let _ = response.send(None);
                             }
                         }
+                        VstMessage::GetAllParameterInfo { response } => {
+                            let _ = response.send(
+                                (0..param_count)
+                                    .map(|index| ParameterInfo {
+                                        index,
+                                        name: params.get_parameter_name(index),
+                                        label: params.get_parameter_label(index),
+                                        value: params.get_parameter(index),
+                                        display: params.get_parameter_text(index),
+                                    })
+                                    .collect(),
+                            );
+                        }
                         VstMessage::GetAllParameters { response } => {
-                            let mut param_values = HashMap::new();
-                            for i in 0..param_count {
-                                param_values.insert(i, params.get_parameter(i));
+                            let _ = response.send(Self::parameter_values(&*params, param_count));
+                        }
+                        VstMessage::GetPrograms { response } => {
+                            let _ = response.send(ProgramList {
+                                names: (0..info.presets)
+                                    .map(|i| params.get_preset_name(i))
+                                    .collect(),
+                                current: params.get_preset_num().max(0) as usize,
+                            });
+                        }
+                        VstMessage::SetProgram { index, response } => {
+                            if index >= 0 && index < info.presets {
+                                params.change_preset(index);
                             }
-                            let _ = response.send(param_values);
+                            let _ = response.send(Self::parameter_values(&*params, param_count));
+                        }
+                        VstMessage::ExportPreset { bank, response } => {
+                            let preset = Self::export_preset_file(&*params, &info, bank);
+                            let _ = response.send(preset.to_bytes());
+                        }
+                        VstMessage::ImportPreset { data, response } => {
+                            let result = Self::import_preset_file(&*params, &info, &data)
+                                .map(|()| Self::parameter_values(&*params, param_count))
+                                .map_err(|e| format!("{:#}", e));
+                            let _ = response.send(result);
+                        }
+                        VstMessage::SetSampleRate { sample_rate } => {
+                            plugin_instance.suspend();
+                            plugin_instance.set_sample_rate(sample_rate);
+                            plugin_instance.resume();
                         }
                         VstMessage::SetEnabled { enabled: e } => {
                             enabled = e;
@@ -241,7 +426,9 @@ impl VstProcessor {
                 // If plugin loading failed, just handle audio passthrough
                 while let Ok(message) = message_receiver.recv() {
                     match message {
-                        VstMessage::ProcessAudio { input, response } => {
+                        VstMessage::ProcessAudio {
+                            input, response, ..
+                        } => {
                             let _ = response.send(input);
                         }
                         VstMessage::Shutdown => break,
@@ -252,12 +439,105 @@ impl VstProcessor {
         }
     }
 
+    fn parameter_values(params: &dyn PluginParameters, count: i32) -> HashMap<i32, f32> {
+        (0..count).map(|i| (i, params.get_parameter(i))).collect()
+    }
+
+    /// The current program, as a chunk if the plugin saves its own state
+    fn export_program(params: &dyn PluginParameters, info: &Info) -> Program {
+        Program {
+            name: params.get_preset_name(params.get_preset_num()),
+            data: if info.preset_chunks {
+                ProgramData::Chunk(params.get_preset_data())
+            } else {
+                ProgramData::Parameters(
+                    (0..info.parameters)
+                        .map(|i| params.get_parameter(i))
+                        .collect(),
+                )
+            },
+        }
+    }
+
+    fn export_preset_file(params: &dyn PluginParameters, info: &Info, bank: bool) -> PresetFile {
+        let plugin = PluginIdentity {
+            unique_id: info.unique_id,
+            version: info.version,
+        };
+        if !bank {
+            return PresetFile::Program {
+                plugin,
+                program: Self::export_program(params, info),
+            };
+        }
+
+        let current = params.get_preset_num();
+        let data = if info.preset_chunks {
+            BankData::Chunk(params.get_bank_data())
+        } else {
+            // Visit each program to read its values, then go back
+            let programs = (0..info.presets)
+                .map(|i| {
+                    params.change_preset(i);
+                    Self::export_program(params, info)
+                })
+                .collect();
+            params.change_preset(current);
+            BankData::Programs(programs)
+        };
+        PresetFile::Bank {
+            plugin,
+            current,
+            programs: info.presets,
+            data,
+        }
+    }
+
+    fn import_preset_file(
+        params: &dyn PluginParameters,
+        info: &Info,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        let preset = PresetFile::parse(data)?;
+        preset.check_plugin(info.unique_id)?;
+
+        let apply = |program: &Program| match &program.data {
+            ProgramData::Chunk(chunk) => params.load_preset_data(chunk),
+            ProgramData::Parameters(values) => {
+                for (i, &value) in values.iter().take(info.parameters as usize).enumerate() {
+                    params.set_parameter(i as i32, value.clamp(0.0, 1.0));
+                }
+                params.set_preset_name(program.name.clone());
+            }
+        };
+        match preset {
+            PresetFile::Program { program, .. } => apply(&program),
+            PresetFile::Bank {
+                data: BankData::Chunk(chunk),
+                ..
+            } => params.load_bank_data(&chunk),
+            PresetFile::Bank {
+                data: BankData::Programs(programs),
+                current,
+                ..
+            } => {
+                for (i, program) in programs.iter().take(info.presets as usize).enumerate() {
+                    params.change_preset(i as i32);
+                    apply(program);
+                }
+                params.change_preset(current.clamp(0, (info.presets - 1).max(0)));
+            }
+        }
+        Ok(())
+    }
+
     fn load_plugin_instance(
         plugin_path: &Path,
+        host: VstHost,
         sample_rate: f32,
         buffer_size: usize,
     ) -> Result<PluginInstance, Box<dyn std::error::Error>> {
-        let host = Arc::new(Mutex::new(VstHost::new()));
+        let host = Arc::new(Mutex::new(host));
         let mut loader = PluginLoader::load(plugin_path, host)?;
         let mut plugin_instance = loader.instance()?;
 
@@ -269,41 +549,30 @@ impl VstProcessor {
         Ok(plugin_instance)
     }
 
+    /// Run planar audio through the plugin; channels missing from `input`
+    /// are fed silence
     fn process_audio_with_plugin(
         plugin: &mut PluginInstance,
-        input: &[f32],
-        _buffer_size: usize,
-    ) -> Vec<f32> {
-        if input.is_empty() {
-            return Vec::new();
-        }
-
-        // Get plugin channel configuration
-        let info = plugin.get_info();
-        let num_inputs = info.inputs.max(1) as usize;
-        let num_outputs = info.outputs.max(1) as usize;
-
-        // Determine actual frame count
-        let frame_count = input.len() / num_inputs.max(1);
-
-        // Create input buffers (deinterleaved)
-        let input_buffers: Vec<Vec<f32>> = (0..num_inputs)
-            .map(|ch| {
-                (0..frame_count)
-                    .map(|frame| {
-                        let idx = frame * num_inputs + ch;
-                        if idx < input.len() { input[idx] } else { 0.0 }
-                    })
-                    .collect()
-            })
-            .collect();
-
-        // Create output buffers
+        input: &[Vec<f32>],
+        layout: ChannelLayout,
+    ) -> Vec<Vec<f32>> {
+        let frame_count = input.first().map_or(0, Vec::len);
+        let num_inputs = layout.inputs + layout.sidechain;
+        let num_outputs = layout.outputs;
         let mut output_buffers: Vec<Vec<f32>> =
             (0..num_outputs).map(|_| vec![0.0; frame_count]).collect();
+        if frame_count == 0 {
+            return output_buffers;
+        }
 
         // Convert to raw pointers for VST AudioBuffer API
-        let input_ptrs: Vec<*const f32> = input_buffers.iter().map(|b| b.as_ptr()).collect();
+        let silence = vec![0.0; frame_count];
+        let input_ptrs: Vec<*const f32> = (0..num_inputs)
+            .map(|ch| match input.get(ch) {
+                Some(buffer) if buffer.len() == frame_count => buffer.as_ptr(),
+                _ => silence.as_ptr(),
+            })
+            .collect();
         let mut output_ptrs: Vec<*mut f32> =
             output_buffers.iter_mut().map(|b| b.as_mut_ptr()).collect();
 
@@ -320,43 +589,49 @@ impl VstProcessor {
             plugin.process(&mut audio_buffer);
         }
 
-        // Interleave output back to single buffer
-        let mut output = Vec::with_capacity(frame_count * num_outputs);
-        for frame in 0..frame_count {
-            for buf in output_buffers.iter().take(num_outputs) {
-                output.push(buf[frame]);
-            }
-        }
-
-        output
+        output_buffers
     }
 
-    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
-        if !self.enabled || input.is_empty() {
-            return input.to_vec();
+    /// Process planar audio laid out as [`VstProcessor::channel_layout`].
+    /// The processing thread works one buffer behind: this returns the
+    /// previous block's result, or that block dry (an xrun) if it is late.
+    pub fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+        let frames = inputs.first().map_or(0, |input| input.len());
+        if !self.enabled || frames == 0 {
+            self.pipeline.reset();
+            self.pending = None;
+            return plugin_host::passthrough(self.layout, inputs, frames);
         }
 
+        // Collect the block submitted last time
+        let pending = self.pending.take();
+        let previous = self
+            .pipeline
+            .wait_time(frames, self.sample_rate)
+            .and_then(|grace| pending?.recv_timeout(grace).ok());
+
+        // Submit this one
+        let mut submitted = false;
         if let Some(ref sender) = self.message_sender {
             let (response_sender, response_receiver) = bounded(1);
-
             let message = VstMessage::ProcessAudio {
-                input: input.to_vec(),
+                input: inputs.iter().map(|input| input.to_vec()).collect(),
+                transport: transport::current(),
                 response: response_sender,
             };
-
-            // Send audio for processing
             if sender.try_send(message).is_ok() {
-                // Try to get the result with a timeout
-                if let Ok(processed_audio) =
-                    response_receiver.recv_timeout(std::time::Duration::from_millis(10))
-                {
-                    return processed_audio;
-                }
+                self.pending = Some(response_receiver);
+                submitted = true;
             }
         }
 
-        // Fallback: return original audio if processing fails or times out
-        input.to_vec()
+        self.pipeline
+            .advance(self.layout, inputs, previous, submitted)
+    }
+
+    /// Ports from the plugin's reported input and output counts
+    pub fn channel_layout(&self) -> ChannelLayout {
+        self.layout
     }
 
     pub fn get_plugin_name(&self) -> String {
@@ -403,6 +678,34 @@ impl VstProcessor {
         None
     }
 
+    /// Info for every parameter, fetched from the processing thread in one
+    /// request when the query runs. Falls back to generic names from the
+    /// cached values if the thread does not answer.
+    pub fn parameter_query(&self) -> ParameterQuery {
+        let sender = self.message_sender.clone();
+        let cached: Vec<f32> = (0..self.parameter_count)
+            .map(|index| self.get_parameter(index))
+            .collect();
+        Box::new(move || {
+            let (response_sender, response_receiver) = bounded(1);
+            let message = VstMessage::GetAllParameterInfo {
+                response: response_sender,
+            };
+            if let Some(sender) = sender
+                && sender.try_send(message).is_ok()
+                && let Ok(parameters) =
+                    response_receiver.recv_timeout(std::time::Duration::from_millis(100))
+            {
+                return parameters;
+            }
+            cached
+                .iter()
+                .enumerate()
+                .map(|(index, &value)| plugin_host::generic_parameter(index as i32, value))
+                .collect()
+        })
+    }
+
     /// Get all parameter values from the VST plugin (blocking)
     pub fn get_all_parameters(&self) -> HashMap<i32, f32> {
         if let Some(ref sender) = self.message_sender {
@@ -428,11 +731,74 @@ impl VstProcessor {
         self.parameters = params;
     }
 
+    /// Built-in programs, empty if the plugin has none or does not answer
+    pub fn programs(&self) -> ProgramList {
+        self.request(
+            |response| VstMessage::GetPrograms { response },
+            PRESET_TIMEOUT,
+        )
+        .unwrap_or_default()
+    }
+
+    /// Switch to a built-in program
+    pub fn set_program(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let parameters = self
+            .request(
+                |response| VstMessage::SetProgram {
+                    index: index as i32,
+                    response,
+                },
+                PRESET_TIMEOUT,
+            )
+            .ok_or("Plugin did not answer")?;
+        self.parameters = parameters;
+        Ok(())
+    }
+
+    /// The current program, or with `bank` all of them, as .fxp/.fxb bytes
+    pub fn export_preset(&self, bank: bool) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(self
+            .request(
+                |response| VstMessage::ExportPreset { bank, response },
+                PRESET_TIMEOUT,
+            )
+            .ok_or("Plugin did not answer")?)
+    }
+
+    /// Apply an .fxp or .fxb file
+    pub fn import_preset(&mut self, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let parameters = self
+            .request(
+                |response| VstMessage::ImportPreset {
+                    data: data.to_vec(),
+                    response,
+                },
+                PRESET_TIMEOUT,
+            )
+            .ok_or("Plugin did not answer")??;
+        self.parameters = parameters;
+        Ok(())
+    }
+
     /// Get number of parameters
     pub fn get_parameter_count(&self) -> i32 {
         self.parameter_count
     }
 
+    /// Switch the plugin to a new stream rate; blocks already queued at the
+    /// old rate are dropped
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        self.sample_rate = sample_rate;
+        self.pipeline.reset();
+        self.pending = None;
+        if let Some(ref sender) = self.message_sender {
+            let _ = sender.send(VstMessage::SetSampleRate { sample_rate });
+        }
+    }
+
     pub fn set_enabled(&mut self, enabled: bool) {
         self.enabled = enabled;
 
@@ -446,6 +812,32 @@ impl VstProcessor {
         self.enabled
     }
 
+    /// Delay the plugin adds (its `initial_delay` plus the buffer the
+    /// processing thread runs behind), or 0 while bypassed
+    pub fn latency_samples(&self) -> usize {
+        if self.enabled {
+            self.initial_delay + self.pipeline.latency()
+        } else {
+            0
+        }
+    }
+
+    /// Blocks the processing thread did not finish in time
+    pub fn xruns(&self) -> u64 {
+        self.pipeline.xruns()
+    }
+
+    /// Parameter changes the plugin reported since the last call, applied
+    /// to the cache
+    pub fn take_automation(&mut self) -> Vec<ParameterChange> {
+        let changes: Vec<ParameterChange> = self.automation.try_iter().collect();
+        for change in &changes {
+            self.parameters
+                .insert(change.index, change.value.clamp(0.0, 1.0));
+        }
+        changes
+    }
+
     /// Get the sample rate
     pub fn get_sample_rate(&self) -> f32 {
         self.sample_rate
@@ -472,13 +864,101 @@ impl Drop for VstProcessor {
     }
 }
 
+impl PluginProcessor for VstProcessor {
+    fn format(&self) -> PluginFormat {
+        PluginFormat::Vst2
+    }
+
+    fn channel_layout(&self) -> ChannelLayout {
+        VstProcessor::channel_layout(self)
+    }
+
+    fn process(&mut self, inputs: &[&[f32]]) -> Vec<Vec<f32>> {
+        VstProcessor::process(self, inputs)
+    }
+
+    fn get_plugin_name(&self) -> String {
+        VstProcessor::get_plugin_name(self)
+    }
+
+    fn set_parameter(&mut self, index: i32, value: f32) {
+        VstProcessor::set_parameter(self, index, value)
+    }
+
+    fn get_parameter(&self, index: i32) -> f32 {
+        VstProcessor::get_parameter(self, index)
+    }
+
+    fn get_parameter_info(&self, index: i32) -> Option<ParameterInfo> {
+        VstProcessor::get_parameter_info(self, index)
+    }
+
+    fn parameter_query(&self) -> ParameterQuery {
+        VstProcessor::parameter_query(self)
+    }
+
+    fn get_parameter_count(&self) -> i32 {
+        VstProcessor::get_parameter_count(self)
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        VstProcessor::set_enabled(self, enabled)
+    }
+
+    fn is_enabled(&self) -> bool {
+        VstProcessor::is_enabled(self)
+    }
+
+    fn latency_samples(&self) -> usize {
+        VstProcessor::latency_samples(self)
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) -> anyhow::Result<()> {
+        VstProcessor::set_sample_rate(self, sample_rate);
+        Ok(())
+    }
+
+    fn xruns(&self) -> u64 {
+        VstProcessor::xruns(self)
+    }
+
+    fn take_automation(&mut self) -> Vec<ParameterChange> {
+        VstProcessor::take_automation(self)
+    }
+
+    /// The current program as an .fxp file
+    fn save_state(&self) -> anyhow::Result<Vec<u8>> {
+        VstProcessor::export_preset(self, false).map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    fn load_state(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        VstProcessor::import_preset(self, data).map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    fn programs(&self) -> ProgramList {
+        VstProcessor::programs(self)
+    }
+
+    fn set_program(&mut self, index: usize) -> anyhow::Result<()> {
+        VstProcessor::set_program(self, index).map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    fn export_preset(&self, bank: bool) -> anyhow::Result<Vec<u8>> {
+        VstProcessor::export_preset(self, bank).map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    fn import_preset(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        VstProcessor::import_preset(self, data).map_err(|e| anyhow::anyhow!("{}", e))
+    }
+}
+
 // VST plugin scanner to find and catalog available plugins
 pub struct VstScanner {
     plugins: Vec<VstPluginInfo>,
     scan_paths: Vec<PathBuf>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VstPluginInfo {
     pub path: PathBuf,
     pub name: String,
@@ -490,9 +970,13 @@ pub struct VstPluginInfo {
     pub outputs: i32,
     pub parameters: i32,
     pub is_synth: bool,
+    /// Plugin standard, so catalogues can mix VST2 and CLAP entries
+    pub format: PluginFormat,
+    /// Plugin within a multi-plugin bundle (CLAP); None for VST2
+    pub plugin_id: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum VstCategory {
     Effect,
     Synth,
@@ -547,37 +1031,44 @@ impl VstScanner {
         }
     }
 
-    pub fn scan(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.plugins.clear();
+    pub fn add_scan_path(&mut self, path: PathBuf) {
+        self.scan_paths.push(path);
+    }
 
-        let scan_paths = self.scan_paths.clone();
-        for scan_path in &scan_paths {
-            if scan_path.exists() {
-                self.scan_directory(scan_path)?;
+    /// `.so` plugins directly inside the scan paths
+    pub fn plugin_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for dir in &self.scan_paths {
+            if let Ok(entries) = std::fs::read_dir(dir) {
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    if path.is_file() && path.extension().is_some_and(|ext| ext == "so") {
+                        files.push(path);
+                    }
+                }
             }
         }
-
-        Ok(())
+        files
     }
 
-    fn scan_directory(&mut self, dir: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
+    pub fn scan(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.plugins.clear();
 
-                if path.is_file()
-                    && path.extension().is_some_and(|ext| ext == "so")
-                    && let Ok(plugin_info) = self.scan_plugin(&path)
-                {
-                    self.plugins.push(plugin_info);
-                }
+        for path in self.plugin_files() {
+            // Loaded in a child process, so a crashing plugin is just skipped
+            match plugin_bridge::scan_sandboxed(PluginFormat::Vst2, &path) {
+                Ok(plugins) => self.plugins.extend(plugins),
+                Err(e) => log::warn!("Skipping VST plugin {}: {:#}", path.display(), e),
             }
         }
 
         Ok(())
     }
 
-    fn scan_plugin(&self, plugin_path: &Path) -> Result<VstPluginInfo, Box<dyn std::error::Error>> {
+    /// Load one plugin in this process and read its info
+    pub(crate) fn scan_plugin(
+        plugin_path: &Path,
+    ) -> Result<VstPluginInfo, Box<dyn std::error::Error>> {
         // Try to load the plugin and get its info
         let host = Arc::new(Mutex::new(VstHost::new()));
 
@@ -596,6 +1087,8 @@ impl VstScanner {
             outputs: info.outputs,
             parameters: info.parameters,
             is_synth: matches!(info.category, Category::Synth),
+            format: PluginFormat::Vst2,
+            plugin_id: None,
         })
     }
 
@@ -637,9 +1130,44 @@ mod tests {
     }
 
     #[test]
-    fn test_vst_host_time_info_none() {
-        let host = VstHost::new();
-        assert!(host.get_time_info(0).is_none());
+    fn test_vst_host_forwards_automation() {
+        let (sender, receiver) = bounded(4);
+        let host = VstHost::new().with_automation(sender);
+        host.automate(3, 0.25);
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            ParameterChange {
+                index: 3,
+                value: 0.25
+            }
+        );
+
+        // Nobody listening: the change is dropped
+        VstHost::new().automate(3, 0.25);
+    }
+
+    #[test]
+    fn test_vst_host_time_info_follows_transport() {
+        let transport = Arc::new(Mutex::new(TransportInfo::default()));
+        let host = VstHost::new().with_transport(Arc::clone(&transport));
+        *transport.lock().unwrap() = TransportInfo {
+            playing: true,
+            sample_position: 24000 * 5,
+            sample_rate: 48000.0,
+            tempo: 120.0,
+            beats_per_bar: 3,
+            beat_unit: 4,
+            musical: None,
+        };
+
+        let info = host.get_time_info(0).unwrap();
+        assert_eq!(info.sample_pos, 120000.0);
+        assert_eq!(info.ppq_pos, 5.0);
+        assert_eq!(info.bar_start_pos, 3.0);
+        assert_eq!(info.tempo, 120.0);
+        assert_eq!((info.time_sig_numerator, info.time_sig_denominator), (3, 4));
+        assert_ne!(info.flags & TimeInfoFlags::TRANSPORT_PLAYING.bits(), 0);
+        assert_ne!(info.flags & TimeInfoFlags::PPQ_POS_VALID.bits(), 0);
     }
 
     #[test]
@@ -745,6 +1273,8 @@ mod tests {
             outputs: 2,
             parameters: 10,
             is_synth: false,
+            format: PluginFormat::Vst2,
+            plugin_id: None,
         };
 
         assert_eq!(info.name, "TestPlugin");
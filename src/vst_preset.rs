@@ -0,0 +1,382 @@
+//! VST2 preset files.
+//!
+//! Reads and writes the standard `.fxp` (one program) and `.fxb` (bank of
+//! programs) formats, so presets move between PhantomLink and other hosts.
+//! Both are big-endian and come in two flavours: a list of normalised
+//! parameter values, or an opaque chunk for plugins that save their own
+//! state.
+
+#![allow(dead_code)] // Complete preset file API, used by the VST host
+
+use anyhow::{Result, bail, ensure};
+
+const CHUNK_MAGIC: &[u8; 4] = b"CcnK";
+const PROGRAM_PARAMS: &[u8; 4] = b"FxCk";
+const PROGRAM_CHUNK: &[u8; 4] = b"FPCh";
+const BANK_PARAMS: &[u8; 4] = b"FxBk";
+const BANK_CHUNK: &[u8; 4] = b"FBCh";
+const NAME_LEN: usize = 28;
+
+/// Plugin a preset was made with, from its `unique_id` and `version`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluginIdentity {
+    pub unique_id: i32,
+    pub version: i32,
+}
+
+/// Contents of one program
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgramData {
+    /// Normalised value per parameter
+    Parameters(Vec<f32>),
+    /// The plugin's own serialised state
+    Chunk(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub name: String,
+    pub data: ProgramData,
+}
+
+/// Contents of a bank
+#[derive(Debug, Clone, PartialEq)]
+pub enum BankData {
+    Programs(Vec<Program>),
+    Chunk(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetFile {
+    Program {
+        plugin: PluginIdentity,
+        program: Program,
+    },
+    Bank {
+        plugin: PluginIdentity,
+        /// Program selected when the bank was saved
+        current: i32,
+        /// Number of programs, also for chunk banks
+        programs: i32,
+        data: BankData,
+    },
+}
+
+impl PresetFile {
+    pub fn plugin(&self) -> PluginIdentity {
+        match self {
+            PresetFile::Program { plugin, .. } | PresetFile::Bank { plugin, .. } => *plugin,
+        }
+    }
+
+    /// Fail unless the preset was saved by `plugin` (or does not say)
+    pub fn check_plugin(&self, unique_id: i32) -> Result<()> {
+        let saved = self.plugin().unique_id;
+        ensure!(
+            saved == 0 || saved == unique_id,
+            "Preset is for another plugin (ID {}, expected {})",
+            fourcc(saved),
+            fourcc(unique_id)
+        );
+        Ok(())
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        Reader { bytes, pos: 0 }.preset()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            PresetFile::Program { plugin, program } => program_bytes(*plugin, program),
+            PresetFile::Bank {
+                plugin,
+                current,
+                programs,
+                data,
+            } => {
+                let mut body = Vec::new();
+                let magic = match data {
+                    BankData::Programs(_) => BANK_PARAMS,
+                    BankData::Chunk(_) => BANK_CHUNK,
+                };
+                body.extend_from_slice(magic);
+                put_i32(&mut body, 2);
+                put_i32(&mut body, plugin.unique_id);
+                put_i32(&mut body, plugin.version);
+                put_i32(&mut body, *programs);
+                put_i32(&mut body, *current);
+                body.extend_from_slice(&[0; 124]);
+                match data {
+                    BankData::Programs(list) => {
+                        for program in list {
+                            body.extend_from_slice(&program_bytes(*plugin, program));
+                        }
+                    }
+                    BankData::Chunk(chunk) => {
+                        put_i32(&mut body, chunk.len() as i32);
+                        body.extend_from_slice(chunk);
+                    }
+                }
+                wrap(body)
+            }
+        }
+    }
+}
+
+/// A unique ID as its four characters, the way plugin vendors quote them
+fn fourcc(id: i32) -> String {
+    let bytes = id.to_be_bytes();
+    if bytes.iter().all(|b| b.is_ascii_graphic()) {
+        String::from_utf8_lossy(&bytes).into_owned()
+    } else {
+        id.to_string()
+    }
+}
+
+fn put_i32(out: &mut Vec<u8>, value: i32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Prefix a body with the chunk magic and its size
+fn wrap(body: Vec<u8>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len() + 8);
+    out.extend_from_slice(CHUNK_MAGIC);
+    put_i32(&mut out, body.len() as i32);
+    out.extend(body);
+    out
+}
+
+fn program_bytes(plugin: PluginIdentity, program: &Program) -> Vec<u8> {
+    let mut body = Vec::new();
+    let (magic, count) = match &program.data {
+        ProgramData::Parameters(values) => (PROGRAM_PARAMS, values.len() as i32),
+        ProgramData::Chunk(_) => (PROGRAM_CHUNK, 0),
+    };
+    body.extend_from_slice(magic);
+    put_i32(&mut body, 1);
+    put_i32(&mut body, plugin.unique_id);
+    put_i32(&mut body, plugin.version);
+    put_i32(&mut body, count);
+
+    let mut name = [0u8; NAME_LEN];
+    // Leave room for the terminator
+    let bytes = program.name.as_bytes();
+    let len = bytes.len().min(NAME_LEN - 1);
+    name[..len].copy_from_slice(&bytes[..len]);
+    body.extend_from_slice(&name);
+
+    match &program.data {
+        ProgramData::Parameters(values) => {
+            for value in values {
+                body.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        ProgramData::Chunk(chunk) => {
+            put_i32(&mut body, chunk.len() as i32);
+            body.extend_from_slice(chunk);
+        }
+    }
+    wrap(body)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let Some(slice) = self.bytes.get(self.pos..self.pos.saturating_add(len)) else {
+            bail!("Preset file is truncated");
+        };
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    /// A length field, checked against what is left
+    fn len(&mut self) -> Result<usize> {
+        let len = self.i32()?;
+        ensure!(
+            len >= 0 && len as usize <= self.bytes.len() - self.pos,
+            "Preset file is truncated"
+        );
+        Ok(len as usize)
+    }
+
+    fn header(&mut self) -> Result<([u8; 4], PluginIdentity, i32)> {
+        ensure!(
+            self.take(4)? == CHUNK_MAGIC,
+            "Not a VST preset file (.fxp/.fxb)"
+        );
+        self.i32()?; // Size; some hosts get it wrong, so it is not trusted
+        let magic: [u8; 4] = self.take(4)?.try_into()?;
+        self.i32()?; // Format version
+        let plugin = PluginIdentity {
+            unique_id: self.i32()?,
+            version: self.i32()?,
+        };
+        let count = self.i32()?;
+        Ok((magic, plugin, count))
+    }
+
+    fn preset(&mut self) -> Result<PresetFile> {
+        let start = self.pos;
+        let (magic, plugin, count) = self.header()?;
+        match &magic {
+            PROGRAM_PARAMS | PROGRAM_CHUNK => {
+                self.pos = start;
+                let program = self.program()?;
+                Ok(PresetFile::Program { plugin, program })
+            }
+            BANK_PARAMS | BANK_CHUNK => {
+                let current = self.i32()?;
+                self.take(124)?;
+                let data = if &magic == BANK_CHUNK {
+                    let len = self.len()?;
+                    BankData::Chunk(self.take(len)?.to_vec())
+                } else {
+                    ensure!(count >= 0, "Preset bank has a negative program count");
+                    let programs = (0..count).map(|_| self.program()).collect::<Result<_>>()?;
+                    BankData::Programs(programs)
+                };
+                Ok(PresetFile::Bank {
+                    plugin,
+                    current,
+                    programs: count,
+                    data,
+                })
+            }
+            other => bail!("Unknown preset type {}", String::from_utf8_lossy(other)),
+        }
+    }
+
+    fn program(&mut self) -> Result<Program> {
+        let (magic, _, count) = self.header()?;
+        let name = self.take(NAME_LEN)?;
+        let name_len = name.iter().position(|&b| b == 0).unwrap_or(NAME_LEN);
+        let name = String::from_utf8_lossy(&name[..name_len]).into_owned();
+        let data = match &magic {
+            PROGRAM_PARAMS => {
+                ensure!(
+                    count >= 0 && count as usize * 4 <= self.bytes.len() - self.pos,
+                    "Preset file is truncated"
+                );
+                ProgramData::Parameters((0..count).map(|_| self.f32()).collect::<Result<_>>()?)
+            }
+            PROGRAM_CHUNK => {
+                let len = self.len()?;
+                ProgramData::Chunk(self.take(len)?.to_vec())
+            }
+            other => bail!(
+                "Expected a program, found {}",
+                String::from_utf8_lossy(other)
+            ),
+        };
+        Ok(Program { name, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLUGIN: PluginIdentity = PluginIdentity {
+        unique_id: i32::from_be_bytes(*b"PhLk"),
+        version: 1200,
+    };
+
+    #[test]
+    fn test_program_layout() {
+        let file = PresetFile::Program {
+            plugin: PLUGIN,
+            program: Program {
+                name: "Warm".to_string(),
+                data: ProgramData::Parameters(vec![0.25, 1.0]),
+            },
+        };
+        let bytes = file.to_bytes();
+        // Magic, size, type, version, id, plugin version, count, name, values
+        assert_eq!(bytes.len(), 7 * 4 + NAME_LEN + 2 * 4);
+        assert_eq!(&bytes[..4], b"CcnK");
+        assert_eq!(
+            i32::from_be_bytes(bytes[4..8].try_into().unwrap()),
+            bytes.len() as i32 - 8
+        );
+        assert_eq!(&bytes[8..12], b"FxCk");
+        assert_eq!(&bytes[16..20], b"PhLk");
+        assert_eq!(&bytes[28..32], b"Warm");
+        assert_eq!(&bytes[bytes.len() - 4..], &1.0f32.to_be_bytes());
+        assert_eq!(PresetFile::parse(&bytes).unwrap(), file);
+    }
+
+    #[test]
+    fn test_bank_round_trip() {
+        let programs = (0..3)
+            .map(|i| Program {
+                name: format!("Program {}", i),
+                data: ProgramData::Parameters(vec![i as f32 / 4.0; 4]),
+            })
+            .collect();
+        let bank = PresetFile::Bank {
+            plugin: PLUGIN,
+            current: 1,
+            programs: 3,
+            data: BankData::Programs(programs),
+        };
+        assert_eq!(PresetFile::parse(&bank.to_bytes()).unwrap(), bank);
+
+        let chunk = PresetFile::Bank {
+            plugin: PLUGIN,
+            current: 0,
+            programs: 16,
+            data: BankData::Chunk(vec![1, 2, 3, 4, 5]),
+        };
+        let bytes = chunk.to_bytes();
+        assert_eq!(&bytes[8..12], b"FBCh");
+        assert_eq!(PresetFile::parse(&bytes).unwrap(), chunk);
+    }
+
+    #[test]
+    fn test_long_names_are_truncated() {
+        let file = PresetFile::Program {
+            plugin: PLUGIN,
+            program: Program {
+                name: "A program name far longer than the format allows".to_string(),
+                data: ProgramData::Chunk(vec![9; 10]),
+            },
+        };
+        let PresetFile::Program { program, .. } = PresetFile::parse(&file.to_bytes()).unwrap()
+        else {
+            panic!("expected a program");
+        };
+        assert_eq!(program.name.len(), NAME_LEN - 1);
+        assert_eq!(program.data, ProgramData::Chunk(vec![9; 10]));
+    }
+
+    #[test]
+    fn test_rejects_bad_files() {
+        assert!(PresetFile::parse(b"RIFF\0\0\0\0WAVE").is_err());
+        let bytes = PresetFile::Program {
+            plugin: PLUGIN,
+            program: Program {
+                name: String::new(),
+                data: ProgramData::Chunk(vec![0; 64]),
+            },
+        }
+        .to_bytes();
+        assert!(PresetFile::parse(&bytes[..bytes.len() - 1]).is_err());
+
+        let file = PresetFile::parse(&bytes).unwrap();
+        assert!(file.check_plugin(PLUGIN.unique_id).is_ok());
+        let error = file.check_plugin(42).unwrap_err().to_string();
+        assert!(error.contains("PhLk"), "{}", error);
+    }
+}